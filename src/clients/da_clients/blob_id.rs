@@ -0,0 +1,98 @@
+use crate::clients::da_clients::{
+    celestia::CelestiaBlobId,
+    in_memory::{HashBlobId, ShareBlobId},
+    types::DAError,
+};
+
+/// `BlobIdCodec` is the blob_id scheme of a backend, the handlers and the index treat the encoded
+/// blob_ids as opaque strings.
+///
+/// A blob_id is `hex([version (1 byte) | payload])`, the version byte identifies the scheme and
+/// its layout so new schemes can be introduced without breaking the blob_ids already handed out.
+pub trait BlobIdCodec: Sized {
+    /// The version byte of the blob_ids, unique across the schemes.
+    const VERSION: u8;
+
+    fn to_payload(&self) -> Vec<u8>;
+
+    fn from_payload(payload: &[u8]) -> anyhow::Result<Self>;
+
+    /// Decodes a blob_id issued before the version byte, if the scheme had one.
+    fn from_unversioned(_bytes: &[u8]) -> Option<Self> {
+        None
+    }
+
+    /// Returns what the blob_id tells about the blob location.
+    fn locator(&self) -> BlobLocator;
+
+    fn encode(&self) -> String {
+        let mut blob_id = vec![Self::VERSION];
+        blob_id.extend_from_slice(&self.to_payload());
+        hex::encode(blob_id)
+    }
+
+    fn decode(blob_id: &str) -> Result<Self, DAError> {
+        let invalid = |reason: String| DAError::InvalidBlobId {
+            blob_id: blob_id.to_string(),
+            reason,
+        };
+
+        let bytes = hex::decode(blob_id).map_err(|error| invalid(error.to_string()))?;
+        if let Some(id) = Self::from_unversioned(&bytes) {
+            return Ok(id);
+        }
+        match bytes.split_first() {
+            Some((&version, payload)) if version == Self::VERSION => {
+                Self::from_payload(payload).map_err(|error| invalid(error.to_string()))
+            }
+            Some((version, _)) => Err(invalid(format!("Unsupported blob_id version {version}"))),
+            None => Err(invalid("Empty blob_id".to_string())),
+        }
+    }
+}
+
+/// `BlobLocator` is the location of a blob decoded from its blob_id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobLocator {
+    /// The DA height the blob was included at, for the schemes with a chain.
+    pub height: Option<u64>,
+    pub commitment: Vec<u8>,
+}
+
+/// Decodes a blob_id of any known scheme.
+pub fn locate(blob_id: &str) -> Option<BlobLocator> {
+    CelestiaBlobId::decode(blob_id)
+        .map(|id| id.locator())
+        .or_else(|_| HashBlobId::decode(blob_id).map(|id| id.locator()))
+        .or_else(|_| ShareBlobId::decode(blob_id).map(|id| id.locator()))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_ids_are_versioned() {
+        let id = CelestiaBlobId {
+            height: 42,
+            commitment: [7u8; 32],
+        };
+        let blob_id = id.encode();
+        assert_eq!(&blob_id[..2], "01");
+        assert_eq!(CelestiaBlobId::decode(&blob_id).unwrap(), id);
+        assert!(HashBlobId::decode(&blob_id).is_err());
+
+        let locator = locate(&blob_id).unwrap();
+        assert_eq!(locator.height, Some(42));
+        assert_eq!(locator.commitment, vec![7u8; 32]);
+
+        // The blob_ids without a version byte are still decoded.
+        let unversioned = hex::encode([&42u64.to_be_bytes()[..], &[7u8; 32]].concat());
+        assert_eq!(CelestiaBlobId::decode(&unversioned).unwrap(), id);
+
+        let err = CelestiaBlobId::decode(&format!("ff{}", &blob_id[2..])).unwrap_err();
+        assert!(matches!(err, DAError::InvalidBlobId { .. }));
+        assert!(locate("not hex").is_none());
+    }
+}
@@ -1,33 +1,289 @@
 use std::{
     fmt::{Debug, Formatter},
-    sync::Arc,
+    ops::Range,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use anyhow::anyhow;
 use async_trait::async_trait;
-use celestia_rpc::{BlobClient, Client, HeaderClient, P2PClient, TxConfig};
+use celestia_rpc::{BlobClient, Client, HeaderClient, P2PClient, StateClient, TxConfig};
 use celestia_types::{
-    AppVersion, Blob, Commitment, consts::appconsts::SHARE_VERSION_ZERO, nmt::Namespace,
+    AppVersion, Blob, Commitment, ExtendedHeader, SyncState,
+    consts::appconsts::{
+        CONTINUATION_SPARSE_SHARE_CONTENT_SIZE, FIRST_SPARSE_SHARE_CONTENT_SIZE, SHARE_SIZE,
+        SHARE_VERSION_ZERO, gas_per_blob_byte, max_tx_size, square_size_upper_bound,
+        subtree_root_threshold,
+    },
+    nmt::Namespace,
+    state::RawTxResponse,
 };
+use chrono::{DateTime, Utc};
 use hex;
+use jsonrpsee_core::client::Error as RpcError;
+use tokio::time::Instant;
+use tracing::Instrument;
 
-use crate::clients::da_clients::{
-    DataAvailabilityClient,
-    types::{DAError, DispatchResponse, InclusionData, ViaDaBlob, deserialize_blob_ids},
+use crate::{
+    clients::da_clients::{
+        DataAvailabilityClient,
+        blob_id::{BlobIdCodec, BlobLocator},
+        types::{
+            CallContext, DAError, DispatchResponse, InclusionData, InclusionProof, InclusionRange,
+            ScannedBlob, ViaDaBlob, read_chunk_range,
+        },
+    },
+    config::DaBackend,
+    services::{cpu::offload, metrics::DA_METRICS},
+    types::dispatch::{DispatchFee, DispatchReceipt},
 };
 
 /// If no value is provided for GasPrice, then this will be serialized to `-1.0` which means the node that
 /// receives the request will calculate the GasPrice for given blob.
 const GAS_PRICE: f64 = -1.0;
 
+/// The gas of a PayForBlobs transaction besides its blobs.
+const PFB_GAS_FIXED_COST: u64 = 75_000;
+
+/// The bytes of a PayForBlobs transaction besides its blob, kept out of the max transaction size.
+const PFB_TX_OVERHEAD: usize = 4 * 1024;
+
+/// The error message of `blob.Get` when there is no blob for the commitment at the height.
+const BLOB_NOT_FOUND: &str = "blob: not found";
+
+/// The default retention of the light nodes, the blobs older than the sampling window are pruned.
+pub const DEFAULT_SAMPLING_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The app version of the commitments and the limits until the network parameters are discovered.
+pub const DEFAULT_APP_VERSION: AppVersion = AppVersion::V5;
+
+/// A failed query of the network parameters is retried by the dispatches after the delay, the
+/// last parameters are used meanwhile.
+const PARAMS_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// The earliest available height is searched again at most once per interval.
+const EARLIEST_HEIGHT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The JSON-RPC error codes of the throttled calls: the HTTP status used by the RPC gateways and
+/// the "limit exceeded" code of the Ethereum JSON-RPC convention.
+const RATE_LIMITED_CODES: [i32; 2] = [429, -32005];
+
+fn is_rate_limited(code: i32, message: &str) -> bool {
+    let message = message.to_lowercase();
+    RATE_LIMITED_CODES.contains(&code)
+        || message.contains("rate limit")
+        || message.contains("too many requests")
+}
+
+/// Returns the backoff requested by a throttled call, from the `retry_after` seconds of the error
+/// data.
+fn retry_after(data: &str) -> Option<Duration> {
+    let data: serde_json::Value = serde_json::from_str(data).ok()?;
+    let seconds = data
+        .get("retry_after")
+        .or_else(|| data.get("retryAfter"))
+        .unwrap_or(&data)
+        .as_f64()?;
+
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// Returns the gas and the fee of a submission, the fee is read from the `fee` attribute of the
+/// `tx` event, e.g. `2000utia`.
+fn tx_fee(response: &RawTxResponse) -> DispatchFee {
+    let fee = response
+        .events
+        .iter()
+        .filter(|event| event.r#type == "tx")
+        .flat_map(|event| &event.attributes)
+        .find(|attribute| attribute.key == "fee")
+        // Only the first coin is kept, blobs are paid in a single denom.
+        .and_then(|attribute| attribute.value.split(',').next())
+        .and_then(|coin| {
+            let split = coin.find(|c: char| !c.is_ascii_digit())?;
+            let (amount, denom) = coin.split_at(split);
+            Some((amount.parse::<u64>().ok()?, denom.to_string()))
+        });
+
+    DispatchFee {
+        tx_hash: response.txhash.clone(),
+        gas_wanted: response.gas_wanted.max(0) as u64,
+        gas_used: response.gas_used.max(0) as u64,
+        amount: fee.as_ref().map(|(amount, _)| *amount),
+        denom: fee.map(|(_, denom)| denom),
+    }
+}
+
+/// Classifies the errors of the light node RPC, `on_call` classifies the errors returned by the
+/// node itself from their message.
+fn rpc_error(error: RpcError, on_call: impl FnOnce(String) -> DAError) -> DAError {
+    match error {
+        RpcError::Call(error) if is_rate_limited(error.code(), error.message()) => {
+            DAError::RateLimited {
+                message: error.message().to_string(),
+                retry_after: error.data().and_then(|data| retry_after(data.get())),
+            }
+        }
+        RpcError::Call(error) => on_call(error.message().to_string()),
+        // The HTTP transport reports the throttled requests as rejected with a 429 status.
+        RpcError::Transport(error) if error.to_string().contains("429") => DAError::RateLimited {
+            message: error.to_string(),
+            retry_after: None,
+        },
+        RpcError::Transport(_)
+        | RpcError::RestartNeeded(_)
+        | RpcError::RequestTimeout
+        | RpcError::ServiceDisconnect => DAError::ConnectionError {
+            message: error.to_string(),
+        },
+        error => DAError::Internal(error.into()),
+    }
+}
+
+/// `CelestiaBlobId` is the blob_id of a blob included at `height` with the commitment hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CelestiaBlobId {
+    pub height: u64,
+    pub commitment: [u8; 32],
+}
+
+impl BlobIdCodec for CelestiaBlobId {
+    const VERSION: u8 = 1;
+
+    fn to_payload(&self) -> Vec<u8> {
+        // [block_height (8 bytes) | commitment hash (32 bytes)]
+        let mut payload = Vec::with_capacity(8 + 32);
+        payload.extend_from_slice(&self.height.to_be_bytes());
+        payload.extend_from_slice(&self.commitment);
+        payload
+    }
+
+    fn from_payload(payload: &[u8]) -> anyhow::Result<Self> {
+        if payload.len() != 40 {
+            anyhow::bail!("Invalid blob_id length {}", payload.len() + 1);
+        }
+
+        Ok(Self {
+            height: u64::from_be_bytes(payload[..8].try_into()?),
+            commitment: payload[8..].try_into()?,
+        })
+    }
+
+    /// The blob_ids issued before the version byte have the same layout as the payload.
+    fn from_unversioned(bytes: &[u8]) -> Option<Self> {
+        Self::from_payload(bytes).ok()
+    }
+
+    fn locator(&self) -> BlobLocator {
+        BlobLocator {
+            height: Some(self.height),
+            commitment: self.commitment.to_vec(),
+        }
+    }
+}
+
+/// Returns the Celestia namespace of the blobs, `VIA` or the namespace id of a namespaced client.
+pub fn celestia_namespace(namespace: Option<&str>) -> anyhow::Result<Namespace> {
+    let Some(namespace) = namespace else {
+        let mut namespace_bytes = [0u8; 8];
+        namespace_bytes[..3].copy_from_slice(b"VIA");
+        return Ok(Namespace::new_v0(&namespace_bytes)?);
+    };
+
+    Namespace::new_v0(namespace.as_bytes())
+        .map_err(|error| anyhow!("Invalid Celestia namespace {}: {}", namespace, error))
+}
+
+/// Returns the share commitment of the data in the namespace under the app version, the
+/// commitment of its blob_id once included in Celestia.
+pub fn share_commitment(
+    namespace: Namespace,
+    data: &[u8],
+    app_version: AppVersion,
+) -> anyhow::Result<[u8; 32]> {
+    let commitment = Commitment::from_blob(namespace, data, SHARE_VERSION_ZERO, None, app_version)?;
+    Ok(*commitment.hash())
+}
+
+/// Whether the data is the blob of the commitment in the namespace, the blobs read from the
+/// fallback nodes are not trusted. The blob may have been committed before a network upgrade, so
+/// the commitment is matched under every known app version, once per subtree root threshold as
+/// the commitments only depend on it.
+fn matches_commitment(namespace: Namespace, data: &[u8], commitment: &[u8; 32]) -> bool {
+    let mut versions = (1..=AppVersion::latest().as_u64())
+        .rev()
+        .filter_map(AppVersion::from_u64)
+        .collect::<Vec<_>>();
+    versions.dedup_by_key(|version| subtree_root_threshold(*version));
+    versions.into_iter().any(|app_version| {
+        share_commitment(namespace, data, app_version).is_ok_and(|hash| &hash == commitment)
+    })
+}
+
+/// Whether the blob may be served by the fallback nodes, e.g. pruned by the light node or read
+/// while it is offline. A blob not found is only read from them when the light node may have
+/// missed it.
+fn is_unavailable(error: &DAError) -> bool {
+    matches!(
+        error,
+        DAError::ConnectionError { .. }
+            | DAError::RateLimited { .. }
+            | DAError::Pruned { .. }
+            | DAError::Internal(_)
+    )
+}
+
+/// `NetworkParams` are the limits of the app version the network runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct NetworkParams {
+    app_version: AppVersion,
+    blob_size_limit: usize,
+}
+
+impl NetworkParams {
+    /// The largest blob fits in a PayForBlobs transaction of the max transaction size, and in the
+    /// largest square besides the share of the transaction.
+    fn of(app_version: AppVersion) -> Self {
+        let square_shares = square_size_upper_bound(app_version).pow(2) - 1;
+        let square_bytes = FIRST_SPARSE_SHARE_CONTENT_SIZE
+            + (square_shares - 1) * CONTINUATION_SPARSE_SHARE_CONTENT_SIZE;
+        // The transactions were only bounded by the square before v3.
+        let tx_bytes = match app_version {
+            AppVersion::V1 | AppVersion::V2 => usize::MAX,
+            _ => max_tx_size(app_version) as usize - PFB_TX_OVERHEAD,
+        };
+
+        Self {
+            app_version,
+            blob_size_limit: square_bytes.min(tx_bytes),
+        }
+    }
+}
+
 /// An implementation of the `DataAvailabilityClient` trait that stores the pubdata in Celestia DA.
 #[derive(Clone)]
 pub struct CelestiaClient {
     light_node_url: String,
     client: Arc<Client>,
-    blob_size_limit: usize,
     namespace: Namespace,
+    /// The blob size limit and the app version until the network parameters are discovered.
+    blob_size_limit: usize,
     app_version: AppVersion,
+    /// The interval between two queries of the network parameters, they are static without.
+    params_refresh_interval: Option<Duration>,
+    /// The last network parameters and when they were queried, shared by the namespaced clients.
+    network_params: Arc<Mutex<Option<(NetworkParams, Instant)>>>,
+    /// When the last query of the network parameters failed, if it did.
+    params_failed_at: Arc<Mutex<Option<Instant>>>,
+    sampling_window: Duration,
+    /// The node serving the blobs pruned by the light node, if any.
+    archival_client: Option<Arc<Client>>,
+    /// The public nodes the blobs the other nodes can't serve are read from, by url. Their blobs
+    /// are only returned once their commitment is verified.
+    fallback_clients: Vec<(String, Arc<Client>)>,
+    /// The last earliest available height and when it was searched, shared by the namespaced
+    /// clients.
+    earliest_height: Arc<Mutex<Option<(u64, Instant)>>>,
 }
 
 impl CelestiaClient {
@@ -43,160 +299,556 @@ impl CelestiaClient {
         // Ensure connectivity by calling P2P info
         client.p2p_info().await?;
 
-        let mut namespace_bytes = [0u8; 8];
-        namespace_bytes[..3].copy_from_slice(b"VIA");
-
-        let namespace = Namespace::new_v0(&namespace_bytes).map_err(|error| DAError {
-            error: error.into(),
-            is_retriable: false,
-        })?;
+        let namespace = celestia_namespace(None)?;
 
         Ok(Self {
             light_node_url: node_url,
             client: Arc::new(client),
-            blob_size_limit,
             namespace,
-            app_version: AppVersion::V5,
+            blob_size_limit,
+            app_version: DEFAULT_APP_VERSION,
+            params_refresh_interval: None,
+            network_params: Arc::default(),
+            params_failed_at: Arc::default(),
+            sampling_window: DEFAULT_SAMPLING_WINDOW,
+            archival_client: None,
+            fallback_clients: vec![],
+            earliest_height: Arc::default(),
         })
     }
 
-    fn parse_blob_id(&self, blob_id: &str) -> anyhow::Result<(Commitment, u64)> {
-        // [8]byte block height ++ [32]byte commitment
-        let blob_id_bytes = hex::decode(blob_id).map_err(|error| DAError {
-            error: error.into(),
-            is_retriable: false,
-        })?;
+    /// Sets the retention of the light node, the blobs older than the window are pruned.
+    pub fn with_sampling_window(mut self, sampling_window: Duration) -> Self {
+        self.sampling_window = sampling_window;
+        self
+    }
+
+    /// Queries the network parameters every `interval`, the blob size limit follows the app
+    /// version of the network from then on.
+    pub async fn with_params_refresh(mut self, interval: Duration) -> Self {
+        self.params_refresh_interval = Some(interval);
+        if let Err(error) = self.refresh_params().await {
+            tracing::warn!(
+                "Failed to query the network parameters, the blob size limit is {} until then: {}",
+                self.blob_size_limit,
+                error
+            );
+        }
+        self
+    }
+
+    /// Reads the blobs pruned by the light node from an archival node.
+    pub async fn with_archival_node(
+        mut self,
+        node_url: &str,
+        auth_token: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let client = Client::new(node_url, auth_token)
+            .await
+            .map_err(|error| anyhow!("Failed to create the archival node client: {error}"))?;
+        self.archival_client = Some(Arc::new(client));
+        Ok(self)
+    }
+
+    /// Reads the blobs the light node and the archival node can't serve from public nodes, tried
+    /// in order.
+    pub async fn with_fallback_nodes(mut self, node_urls: &[String]) -> anyhow::Result<Self> {
+        for node_url in node_urls {
+            let client = Client::new(node_url, None).await.map_err(|error| {
+                anyhow!("Failed to create the fallback node client {node_url}: {error}")
+            })?;
+            self.fallback_clients
+                .push((node_url.clone(), Arc::new(client)));
+        }
+        Ok(self)
+    }
+
+    fn parse_blob_id(&self, blob_id: &str) -> Result<(Commitment, u64), DAError> {
+        let id = CelestiaBlobId::decode(blob_id)?;
+        Ok((Commitment::new(id.commitment), id.height))
+    }
+
+    async fn network_head(&self) -> Result<ExtendedHeader, DAError> {
+        self.client.header_network_head().await.map_err(|error| {
+            rpc_error(error, |message| {
+                DAError::Internal(anyhow!("Error to get the network head: {}", message))
+            })
+        })
+    }
+
+    /// Returns the last network parameters, the configured ones until they are discovered.
+    fn params(&self) -> NetworkParams {
+        match *self.network_params.lock().unwrap() {
+            Some((params, _)) => params,
+            None => NetworkParams {
+                app_version: self.app_version,
+                blob_size_limit: self.blob_size_limit,
+            },
+        }
+    }
 
-        let block_height =
-            u64::from_be_bytes(blob_id_bytes[..8].try_into().map_err(|_| DAError {
-                error: anyhow!("Failed to convert block height"),
-                is_retriable: false,
-            })?);
+    /// Returns the network parameters, queried again once the last ones are older than the
+    /// refresh interval and the last failed query older than `PARAMS_RETRY_DELAY`.
+    async fn refresh_params(&self) -> Result<NetworkParams, DAError> {
+        let Some(interval) = self.params_refresh_interval else {
+            return Ok(self.params());
+        };
+        if let Some((params, queried_at)) = *self.network_params.lock().unwrap()
+            && queried_at.elapsed() < interval
+        {
+            return Ok(params);
+        }
+        if let Some(failed_at) = *self.params_failed_at.lock().unwrap()
+            && failed_at.elapsed() < PARAMS_RETRY_DELAY
+        {
+            return Ok(self.params());
+        }
+
+        self.query_params().await
+    }
 
-        let commitment_data: [u8; 32] = blob_id_bytes[8..40].try_into().map_err(|_| DAError {
-            error: anyhow!("Failed to convert commitment"),
-            is_retriable: false,
+    /// Queries the network parameters of the app version of the network head, called every
+    /// refresh interval by the `ParamsRefreshSvc`.
+    pub(crate) async fn query_params(&self) -> Result<NetworkParams, DAError> {
+        let head = self.network_head().await.inspect_err(|_| {
+            *self.params_failed_at.lock().unwrap() = Some(Instant::now());
         })?;
-        let commitment = Commitment::new(commitment_data);
+        let version = head.header.version.app;
+        let app_version = AppVersion::from_u64(version).unwrap_or_else(|| {
+            tracing::warn!(
+                "Unknown app version {}, using the limits of the latest known one",
+                version
+            );
+            AppVersion::latest()
+        });
+        let params = NetworkParams::of(app_version);
+        let cached = self
+            .network_params
+            .lock()
+            .unwrap()
+            .map(|(cached, _)| cached);
+        if cached != Some(params) {
+            tracing::info!(
+                "The blob size limit is {} bytes with app version {}",
+                params.blob_size_limit,
+                version
+            );
+        }
+        DA_METRICS
+            .blob_size_limit
+            .set(params.blob_size_limit as u64);
+        DA_METRICS.app_version.set(version);
+        *self.network_params.lock().unwrap() = Some((params, Instant::now()));
+        *self.params_failed_at.lock().unwrap() = None;
+
+        Ok(params)
+    }
+
+    /// Returns the earliest height within the sampling window, searched again once the last
+    /// result is older than `EARLIEST_HEIGHT_REFRESH_INTERVAL`.
+    async fn earliest_height(&self) -> Result<u64, DAError> {
+        if let Some((height, searched_at)) = *self.earliest_height.lock().unwrap()
+            && searched_at.elapsed() < EARLIEST_HEIGHT_REFRESH_INTERVAL
+        {
+            return Ok(height);
+        }
+
+        self.update_earliest_height().await
+    }
+
+    /// Searches the earliest height within the sampling window again.
+    async fn update_earliest_height(&self) -> Result<u64, DAError> {
+        let last = self
+            .earliest_height
+            .lock()
+            .unwrap()
+            .map(|(height, _)| height);
+        let height = self.search_earliest_height(last).await?;
+        *self.earliest_height.lock().unwrap() = Some((height, Instant::now()));
+        DA_METRICS.earliest_available_height.set(height);
+
+        Ok(height)
+    }
 
-        Ok((commitment, block_height))
+    /// Binary searches the first header within the sampling window of the network head, the
+    /// earliest height only moves forward so the search starts from the `last` one.
+    async fn search_earliest_height(&self, last: Option<u64>) -> Result<u64, DAError> {
+        let head = self.network_head().await?;
+        let Some(cutoff) = head.time().checked_sub(self.sampling_window) else {
+            return Ok(1);
+        };
+
+        let mut low = last.unwrap_or(1);
+        let mut high = head.height().value();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let in_window = match self.client.header_get_by_height(middle).await {
+                Ok(header) => header.time() >= cutoff,
+                // The headers out of the retention of the node are pruned as well.
+                Err(RpcError::Call(_)) => false,
+                Err(error) => {
+                    return Err(rpc_error(error, |message| {
+                        DAError::Internal(anyhow!("Error to get the header: {}", message))
+                    }));
+                }
+            };
+            if in_window {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        Ok(low)
+    }
+
+    /// Returns the node serving the blobs at `height`, the archival node for the blobs older than
+    /// the sampling window. They are reported as pruned when there is none.
+    async fn node_at(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        height: u64,
+    ) -> Result<&Arc<Client>, DAError> {
+        let earliest_available_height = ctx.run(self.earliest_height()).await?;
+        if height >= earliest_available_height {
+            Ok(&self.client)
+        } else if let Some(archival_client) = &self.archival_client {
+            DA_METRICS.archival_reads.inc();
+            Ok(archival_client)
+        } else {
+            Err(DAError::Pruned {
+                blob_id: blob_id.to_string(),
+                height,
+                earliest_available_height,
+            })
+        }
+    }
+
+    async fn header_at(&self, client: &Client, height: u64) -> Result<ExtendedHeader, DAError> {
+        client.header_get_by_height(height).await.map_err(|error| {
+            rpc_error(error, |message| {
+                DAError::Internal(anyhow!("Error to get the header: {}", message))
+            })
+        })
+    }
+
+    async fn blob_at(
+        &self,
+        ctx: &CallContext,
+        client: &Client,
+        blob_id: &str,
+        commitment: Commitment,
+        block_height: u64,
+    ) -> Result<Blob, DAError> {
+        ctx.run(async {
+            client
+                .blob_get(block_height, self.namespace, commitment)
+                .await
+                .map_err(|error| {
+                    rpc_error(error, |message| {
+                        if message.contains(BLOB_NOT_FOUND) {
+                            DAError::NotFound {
+                                blob_id: blob_id.to_string(),
+                            }
+                        } else {
+                            DAError::Internal(anyhow!("Error to get blob: {}", message))
+                        }
+                    })
+                })
+        })
+        .await
+    }
+
+    async fn get_blob(&self, ctx: &CallContext, blob_id: &str) -> Result<Blob, DAError> {
+        let (commitment, block_height) = self.parse_blob_id(blob_id)?;
+        let mut from_light_node = false;
+        let result = match self.node_at(ctx, blob_id, block_height).await {
+            Ok(client) => {
+                from_light_node = Arc::ptr_eq(client, &self.client);
+                self.blob_at(ctx, client, blob_id, commitment, block_height)
+                    .await
+            }
+            Err(error) => Err(error),
+        };
+
+        let error = match result {
+            Err(error) if !self.fallback_clients.is_empty() => error,
+            result => return result,
+        };
+        let unavailable = match &error {
+            DAError::NotFound { .. } => {
+                from_light_node && self.missed_by_light_node(ctx, block_height).await
+            }
+            error => is_unavailable(error),
+        };
+        if !unavailable {
+            return Err(error);
+        }
+        self.get_fallback_blob(ctx, blob_id, commitment, block_height)
+            .await
+            .ok_or(error)
+    }
+
+    /// Whether the light node may have missed a blob it didn't find at `height`: the node didn't
+    /// sync the height yet, or pruned it since the earliest available height was searched. The
+    /// blob doesn't exist otherwise.
+    async fn missed_by_light_node(&self, ctx: &CallContext, height: u64) -> bool {
+        match ctx.run(self.sync_state()).await {
+            Ok(state) if state.height < height => {
+                tracing::debug!(
+                    "The light node synced up to height {}, reading {} from the fallback nodes",
+                    state.height,
+                    height
+                );
+                return true;
+            }
+            Ok(_) => {}
+            Err(error) => {
+                tracing::warn!("Failed to get the sync state of the light node: {}", error);
+                return false;
+            }
+        }
+
+        match ctx.run(self.update_earliest_height()).await {
+            Ok(earliest_available_height) => height < earliest_available_height,
+            Err(error) => {
+                tracing::warn!("Failed to search the earliest available height: {}", error);
+                false
+            }
+        }
+    }
+
+    async fn sync_state(&self) -> Result<SyncState, DAError> {
+        self.client.header_sync_state().await.map_err(|error| {
+            rpc_error(error, |message| {
+                DAError::Internal(anyhow!("Error to get the sync state: {}", message))
+            })
+        })
+    }
+
+    /// Reads a blob from the fallback nodes in order, the first blob matching the commitment of
+    /// the blob_id is returned.
+    async fn get_fallback_blob(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        commitment: Commitment,
+        block_height: u64,
+    ) -> Option<Blob> {
+        for (node_url, client) in &self.fallback_clients {
+            let blob = match self
+                .blob_at(ctx, client, blob_id, commitment, block_height)
+                .await
+            {
+                Ok(blob) => blob,
+                Err(error) => {
+                    tracing::warn!(
+                        "Failed to read {} from the fallback node {}: {}",
+                        blob_id,
+                        node_url,
+                        error
+                    );
+                    continue;
+                }
+            };
+
+            if matches_commitment(self.namespace, &blob.data, commitment.hash()) {
+                DA_METRICS.fallback_reads.inc();
+                return Some(blob);
+            }
+            DA_METRICS.fallback_mismatches.inc();
+            tracing::warn!(
+                "The fallback node {} returned a blob not matching the commitment of {}",
+                node_url,
+                blob_id
+            );
+        }
+        None
+    }
+
+    /// Fetches a chunk of a chunked blob, a missing chunk is an integrity error.
+    async fn fetch_chunk(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        index: usize,
+        chunks: usize,
+        chunk_id: &str,
+    ) -> Result<Vec<u8>, DAError> {
+        let span = tracing::info_span!("chunk", blob_id, index, chunks, chunk_id);
+        let start = Instant::now();
+        let result = self.get_blob(ctx, chunk_id).instrument(span.clone()).await;
+        DA_METRICS.chunk_fetch_latency.observe(start.elapsed());
+
+        result.map(|blob| blob.data).map_err(|error| {
+            DA_METRICS.failed_chunk_fetches.inc();
+            span.in_scope(|| tracing::warn!("Failed to fetch the chunk: {}", error));
+            match error {
+                DAError::NotFound { .. } => DAError::IntegrityMismatch {
+                    blob_id: blob_id.to_string(),
+                    reason: format!("Chunk {} not found", chunk_id),
+                },
+                error => error,
+            }
+        })
     }
 }
 
 #[async_trait]
 impl DataAvailabilityClient for CelestiaClient {
+    /// A dispatch abandoned at the deadline or on cancellation can still be included, the
+    /// submission can't be recalled once sent.
     async fn dispatch_blob(
         &self,
+        ctx: &CallContext,
         _batch_number: u32,
         data: Vec<u8>,
     ) -> Result<DispatchResponse, DAError> {
-        let blob =
-            Blob::new(self.namespace, data.clone(), None, self.app_version).map_err(|error| {
-                DAError {
-                    error: error.into(),
-                    is_retriable: false,
+        let size = data.len();
+        // A failed query keeps the last parameters, the node rejects the blobs too large anyway.
+        let params = match ctx.run(self.refresh_params()).await {
+            Ok(params) => params,
+            Err(error) => {
+                tracing::warn!("Failed to refresh the network parameters: {}", error);
+                self.params()
+            }
+        };
+        let (namespace, app_version) = (self.namespace, params.app_version);
+        let (blob, commitment) = offload(size, move || {
+            let commitment =
+                Commitment::from_blob(namespace, &data, SHARE_VERSION_ZERO, None, app_version)
+                    .map_err(|error| DAError::SubmitRejected {
+                        reason: format!("Error to create commitment: {}", error),
+                    })?;
+            let blob = Blob::new(namespace, data, None, app_version).map_err(|error| {
+                DAError::SubmitRejected {
+                    reason: error.to_string(),
                 }
             })?;
-
-        let commitment = Commitment::from_blob(
-            self.namespace,
-            &data,
-            SHARE_VERSION_ZERO,
-            None,
-            self.app_version,
-        )
-        .map_err(|error| DAError {
-            error: anyhow!("Error to create commitment: {}", error.to_string()),
-            is_retriable: false,
-        })?;
+            Ok::<_, DAError>((blob, commitment))
+        })
+        .await?;
 
         let tx_config = TxConfig {
             gas_price: Some(GAS_PRICE),
             ..Default::default()
         };
 
-        let block_height = self
-            .client
-            .blob_submit(&[blob], tx_config)
-            .await
-            .map_err(|error| DAError {
-                error: anyhow!("Error to submit blob: {}", error.to_string()),
-                is_retriable: true,
-            })?;
+        let response = ctx
+            .run(async {
+                self.client
+                    .state_submit_pay_for_blob(&[blob.into()], tx_config)
+                    .await
+                    .map_err(|error| {
+                        rpc_error(error, |message| {
+                            if message.contains("too large") {
+                                DAError::BlobTooLarge {
+                                    size,
+                                    limit: params.blob_size_limit,
+                                }
+                            } else {
+                                DAError::SubmitFailed { reason: message }
+                            }
+                        })
+                    })
+            })
+            .await?;
+        if response.code != 0 {
+            return Err(DAError::SubmitFailed {
+                reason: format!(
+                    "Transaction {} failed with code {}: {}",
+                    response.txhash, response.code, response.raw_log
+                ),
+            });
+        }
+        let block_height = response.height as u64;
 
-        // Construct blob_id = [block_height (8 bytes) | commitment hash (32 bytes)]
-        let mut blob_id = Vec::with_capacity(8 + 32);
-        blob_id.extend_from_slice(&block_height.to_be_bytes());
-        blob_id.extend_from_slice(commitment.hash());
+        Ok(DispatchResponse::from(
+            CelestiaBlobId {
+                height: block_height,
+                commitment: *commitment.hash(),
+            }
+            .encode(),
+        )
+        .with_receipt(DispatchReceipt {
+            height: Some(block_height),
+            commitment: hex::encode(commitment.hash()),
+            namespace: Some(hex::encode(self.namespace.as_bytes())),
+            size: size as u64,
+            backend: DaBackend::Celestia,
+            submitted_at: Utc::now(),
+            fee: Some(tx_fee(&response)),
+            chunks: vec![],
+        }))
+    }
 
-        Ok(DispatchResponse {
-            blob_id: hex::encode(blob_id),
-        })
+    /// A blob missing at its height is reported as `None`, while a missing chunk of a chunked blob
+    /// is an integrity error.
+    async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        Ok(self
+            .get_inclusion_range(ctx, blob_id, 0..u64::MAX)
+            .await?
+            .map(|range| InclusionData { data: range.data }))
     }
 
-    async fn get_inclusion_data(&self, blob_id: &str) -> Result<Option<InclusionData>, DAError> {
-        let (commitment, block_height) = self.parse_blob_id(&blob_id).map_err(|error| DAError {
-            error: error.into(),
-            is_retriable: true,
-        })?;
+    async fn get_inclusion_range(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        range: Range<u64>,
+    ) -> Result<Option<InclusionRange>, DAError> {
+        let blob = match self.get_blob(ctx, blob_id).await {
+            Ok(blob) => blob,
+            Err(DAError::NotFound { .. }) => return Ok(None),
+            Err(error) => return Err(error),
+        };
 
-        let blob = self
-            .client
-            .blob_get(block_height, self.namespace, commitment)
-            .await
-            .map_err(|error| DAError {
-                error: anyhow!("Error to get blob: {}", error.to_string()),
-                is_retriable: true,
-            })?;
+        let via_blob = match ViaDaBlob::from_bytes(&blob.data) {
+            Some(via_blob) if via_blob.chunks != 1 => via_blob,
+            Some(via_blob) => return Ok(Some(InclusionRange::slice(&via_blob.data, range))),
+            None => return Ok(Some(InclusionRange::slice(&blob.data, range))),
+        };
+        let chunk_ids = via_blob.chunk_ids(blob_id)?;
 
-        let data = match ViaDaBlob::from_bytes(&blob.data) {
-            Some(blob) => {
-                if blob.chunks == 1 {
-                    blob.data
-                } else {
-                    let blob_ids = deserialize_blob_ids(&blob.data).map_err(|_| DAError {
-                        error: anyhow!("Failed to deserialize blob ids"),
-                        is_retriable: false,
-                    })?;
-                    if blob_ids.len() != blob.chunks {
-                        return Err(DAError {
-                            error: anyhow!(
-                                "Mismatch, blob ids len [{}] != chunk size [{}]",
-                                blob_ids.len(),
-                                blob.chunks
-                            ),
-                            is_retriable: false,
-                        });
-                    }
-
-                    let mut batch_blob = vec![];
-
-                    for blob_id in blob_ids {
-                        let (commitment, block_height) =
-                            self.parse_blob_id(&blob_id).map_err(|error| DAError {
-                                error: error.into(),
-                                is_retriable: true,
-                            })?;
-
-                        let blob = self
-                            .client
-                            .blob_get(block_height, self.namespace, commitment)
-                            .await
-                            .map_err(|error| DAError {
-                                error: error.into(),
-                                is_retriable: true,
-                            })?;
-
-                        batch_blob.extend_from_slice(&blob.data);
-                    }
-
-                    batch_blob
-                }
-            }
-            None => blob.data,
+        let range = read_chunk_range(&chunk_ids, range, |index, chunk_id| {
+            self.fetch_chunk(ctx, blob_id, index, chunk_ids.len(), chunk_id)
+        })
+        .await?;
+
+        Ok(Some(range))
+    }
+
+    async fn get_chunk_ids(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        let blob = match self.get_blob(ctx, blob_id).await {
+            Ok(blob) => blob,
+            Err(DAError::NotFound { .. }) => return Ok(None),
+            Err(error) => return Err(error),
         };
 
-        Ok(Some(InclusionData { data }))
+        match ViaDaBlob::from_bytes(&blob.data) {
+            Some(via_blob) if via_blob.chunks != 1 => via_blob.chunk_ids(blob_id).map(Some),
+            _ => Ok(Some(vec![])),
+        }
+    }
+
+    async fn get_chunk(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        chunk_ids: &[String],
+        index: usize,
+    ) -> Result<Vec<u8>, DAError> {
+        self.fetch_chunk(ctx, blob_id, index, chunk_ids.len(), &chunk_ids[index])
+            .await
     }
 
     fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
@@ -204,7 +856,16 @@ impl DataAvailabilityClient for CelestiaClient {
     }
 
     fn blob_size_limit(&self) -> Option<usize> {
-        Some(self.blob_size_limit)
+        Some(self.params().blob_size_limit)
+    }
+
+    /// The gas of the blob shares and the fixed cost of the PayForBlobs transaction, as estimated
+    /// by the node.
+    fn estimate_gas(&self, blob_size: usize) -> Option<u64> {
+        let app_version = self.params().app_version;
+        let blob = Blob::new(self.namespace, vec![0; blob_size], None, app_version).ok()?;
+        let blob_gas = (blob.shares_len() * SHARE_SIZE) as u64 * gas_per_blob_byte(app_version);
+        Some(blob_gas + PFB_GAS_FIXED_COST)
     }
 
     async fn ping(&self) -> anyhow::Result<bool> {
@@ -213,12 +874,229 @@ impl DataAvailabilityClient for CelestiaClient {
             Err(_) => Ok(false),
         }
     }
+
+    /// The namespace id is the namespace bytes, up to 10 bytes.
+    fn namespaced(
+        &self,
+        namespace: &str,
+    ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        Ok(Arc::new(Self {
+            namespace: celestia_namespace(Some(namespace))?,
+            ..self.clone()
+        }))
+    }
+
+    async fn confirmations(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<u64>, DAError> {
+        let (_, block_height) = self.parse_blob_id(blob_id)?;
+        let head = ctx.run(self.network_head()).await?;
+
+        Ok(Some(head.height().value().saturating_sub(block_height)))
+    }
+
+    async fn head_height(&self) -> Result<Option<u64>, DAError> {
+        Ok(Some(self.network_head().await?.height().value()))
+    }
+
+    /// The light node is synced once it reached the last height of its current sync, without
+    /// error.
+    async fn is_synced(&self) -> Result<Option<bool>, DAError> {
+        let state = self.sync_state().await?;
+
+        Ok(Some(
+            state.error.is_none() && state.height >= state.to_height,
+        ))
+    }
+
+    /// The proofs of the rows spanned by the blob, against the data root of its block. Only the
+    /// manifest of a chunked blob is proven.
+    async fn get_inclusion_proof(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        let (commitment, block_height) = self.parse_blob_id(blob_id)?;
+        let client = self.node_at(ctx, blob_id, block_height).await?;
+
+        let result = ctx
+            .run(async {
+                client
+                    .blob_get_proof(block_height, self.namespace, commitment)
+                    .await
+                    .map_err(|error| {
+                        rpc_error(error, |message| {
+                            if message.contains(BLOB_NOT_FOUND) {
+                                DAError::NotFound {
+                                    blob_id: blob_id.to_string(),
+                                }
+                            } else {
+                                DAError::Internal(anyhow!("Error to get the proof: {}", message))
+                            }
+                        })
+                    })
+            })
+            .await;
+        let proofs = match result {
+            Ok(proofs) => proofs,
+            Err(DAError::NotFound { .. }) => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let header = ctx.run(self.header_at(client, block_height)).await?;
+
+        Ok(Some(InclusionProof {
+            blob_id: blob_id.to_string(),
+            height: block_height,
+            data_root: hex::encode(header.dah.hash().as_bytes()),
+            namespace: hex::encode(self.namespace.as_bytes()),
+            commitment: hex::encode(commitment.hash()),
+            proofs: serde_json::to_value(proofs).map_err(anyhow::Error::from)?,
+        }))
+    }
+
+    async fn data_root(&self, ctx: &CallContext, height: u64) -> Result<String, DAError> {
+        let earliest_available_height = ctx.run(self.earliest_height()).await?;
+        let client = match &self.archival_client {
+            Some(archival_client) if height < earliest_available_height => archival_client,
+            _ => &self.client,
+        };
+        let header = ctx.run(self.header_at(client, height)).await?;
+
+        Ok(hex::encode(header.dah.hash().as_bytes()))
+    }
+
+    /// The blobs are read from the archival node below the sampling window, as the data roots.
+    async fn scan_blobs(
+        &self,
+        ctx: &CallContext,
+        height: u64,
+    ) -> Result<Vec<ScannedBlob>, DAError> {
+        let earliest_available_height = ctx.run(self.earliest_height()).await?;
+        let client = match &self.archival_client {
+            Some(archival_client) if height < earliest_available_height => archival_client,
+            _ => &self.client,
+        };
+
+        let result = ctx
+            .run(async {
+                client
+                    .blob_get_all(height, &[self.namespace])
+                    .await
+                    .map_err(|error| {
+                        rpc_error(error, |message| {
+                            if message.contains(BLOB_NOT_FOUND) {
+                                DAError::NotFound {
+                                    blob_id: String::new(),
+                                }
+                            } else {
+                                DAError::Internal(anyhow!("Error to scan the blobs: {}", message))
+                            }
+                        })
+                    })
+            })
+            .await;
+        // The nodes report a height without blobs either way.
+        let blobs = match result {
+            Ok(blobs) => blobs.unwrap_or_default(),
+            Err(DAError::NotFound { .. }) => vec![],
+            Err(error) => return Err(error),
+        };
+        if blobs.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let header = ctx.run(self.header_at(client, height)).await?;
+        let included_at = DateTime::from_timestamp(header.time().unix_timestamp(), 0)
+            .ok_or_else(|| anyhow!("Invalid time of the block {}", height))?;
+        Ok(blobs
+            .into_iter()
+            .map(|blob| {
+                let commitment = *blob.commitment.hash();
+                ScannedBlob {
+                    blob_id: CelestiaBlobId { height, commitment }.encode(),
+                    receipt: DispatchReceipt {
+                        height: Some(height),
+                        commitment: hex::encode(commitment),
+                        namespace: Some(hex::encode(self.namespace.as_bytes())),
+                        size: blob.data.len() as u64,
+                        backend: DaBackend::Celestia,
+                        submitted_at: included_at,
+                        fee: None,
+                        chunks: vec![],
+                    },
+                    data: blob.data,
+                }
+            })
+            .collect())
+    }
+
+    async fn earliest_available_height(&self) -> Result<Option<u64>, DAError> {
+        self.earliest_height().await.map(Some)
+    }
+
+    /// The sampling window of the light node, the archival node keeps the blobs forever.
+    fn retention(&self) -> Option<Duration> {
+        self.archival_client
+            .is_none()
+            .then_some(self.sampling_window)
+    }
+
+    /// The time of the block including the blob plus the sampling window.
+    async fn expiry_for(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DAError> {
+        let Some(retention) = self.retention() else {
+            return Ok(None);
+        };
+        let (_, block_height) = self.parse_blob_id(blob_id)?;
+        let header = ctx.run(self.header_at(&self.client, block_height)).await?;
+
+        let included_at = DateTime::from_timestamp(header.time().unix_timestamp(), 0)
+            .ok_or_else(|| anyhow!("Invalid time of the block {}", block_height))?;
+        let retention = chrono::Duration::from_std(retention).map_err(anyhow::Error::from)?;
+        Ok(Some(included_at + retention))
+    }
 }
 
 impl Debug for CelestiaClient {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("CelestiaClient")
             .field("light_node_url", &self.light_node_url)
+            .field("sampling_window", &self.sampling_window)
+            .field("archival", &self.archival_client.is_some())
+            .field("fallbacks", &self.fallback_clients.len())
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_blobs_must_match_the_commitment() {
+        let namespace = celestia_namespace(None).unwrap();
+        let commitment = share_commitment(namespace, b"pubdata", DEFAULT_APP_VERSION).unwrap();
+
+        assert!(matches_commitment(namespace, b"pubdata", &commitment));
+        assert!(!matches_commitment(namespace, b"tampered", &commitment));
+        let other = celestia_namespace(Some("other")).unwrap();
+        assert!(!matches_commitment(other, b"pubdata", &commitment));
+    }
+
+    #[test]
+    fn test_fallback_blobs_match_the_commitment_of_a_previous_app_version() {
+        let namespace = celestia_namespace(None).unwrap();
+        // Spans more than a subtree root, so the commitment depends on the threshold.
+        let data = vec![7; 200 * SHARE_SIZE];
+        for version in 1..=AppVersion::latest().as_u64() {
+            let app_version = AppVersion::from_u64(version).unwrap();
+            let commitment = share_commitment(namespace, &data, app_version).unwrap();
+            assert!(matches_commitment(namespace, &data, &commitment));
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    ops::Range,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+
+use crate::{
+    clients::da_clients::{
+        DataAvailabilityClient,
+        types::{
+            CallContext, DAError, DispatchResponse, InclusionData, InclusionProof, InclusionRange,
+            ScannedBlob,
+        },
+    },
+    services::metrics::DA_METRICS,
+    types::admin::{ChaosError, ChaosSettings},
+};
+
+/// An implementation of the `DataAvailabilityClient` trait injecting latency and errors into the
+/// calls serving the requests, per settings changed at runtime. The staging game days exercise
+/// the retries and the fallbacks of the callers with it, the health checks are not affected.
+#[derive(Clone, Debug)]
+pub struct ChaosClient {
+    inner: Arc<dyn DataAvailabilityClient + Send + Sync>,
+    settings: Arc<RwLock<ChaosSettings>>,
+}
+
+impl ChaosClient {
+    /// Wraps a client, without faults until they are set.
+    pub fn new(inner: Arc<dyn DataAvailabilityClient + Send + Sync>) -> Self {
+        Self {
+            inner,
+            settings: Arc::default(),
+        }
+    }
+
+    pub fn settings(&self) -> ChaosSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Replaces the injected faults, returns the previous ones.
+    pub fn set(&self, settings: ChaosSettings) -> ChaosSettings {
+        tracing::warn!("Injecting faults into the DA calls: {:?}", settings);
+        std::mem::replace(&mut self.settings.write().unwrap(), settings)
+    }
+
+    /// Delays the call, then fails it at the error rate.
+    async fn inject(&self, ctx: &CallContext) -> Result<(), DAError> {
+        let settings = self.settings();
+        if settings.latency_ms > 0 {
+            ctx.run(async {
+                tokio::time::sleep(Duration::from_millis(settings.latency_ms)).await;
+                Ok(())
+            })
+            .await?;
+        }
+
+        if settings.error_rate > 0.0 && rand::thread_rng().gen_bool(settings.error_rate) {
+            DA_METRICS.injected_faults.inc();
+            let message = "Fault injected by the chaos settings".to_string();
+            return Err(match settings.error {
+                ChaosError::Connection => DAError::ConnectionError { message },
+                ChaosError::RateLimited => DAError::RateLimited {
+                    message,
+                    retry_after: None,
+                },
+                ChaosError::Internal => DAError::Internal(anyhow::anyhow!(message)),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DataAvailabilityClient for ChaosClient {
+    async fn dispatch_blob(
+        &self,
+        ctx: &CallContext,
+        batch_number: u32,
+        data: Vec<u8>,
+    ) -> Result<DispatchResponse, DAError> {
+        self.inject(ctx).await?;
+        self.inner.dispatch_blob(ctx, batch_number, data).await
+    }
+
+    async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        self.inject(ctx).await?;
+        self.inner.get_inclusion_data(ctx, blob_id).await
+    }
+
+    async fn get_inclusion_range(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        range: Range<u64>,
+    ) -> Result<Option<InclusionRange>, DAError> {
+        self.inject(ctx).await?;
+        self.inner.get_inclusion_range(ctx, blob_id, range).await
+    }
+
+    async fn get_chunk_ids(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        self.inject(ctx).await?;
+        self.inner.get_chunk_ids(ctx, blob_id).await
+    }
+
+    async fn get_chunk(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        chunk_ids: &[String],
+        index: usize,
+    ) -> Result<Vec<u8>, DAError> {
+        self.inject(ctx).await?;
+        self.inner.get_chunk(ctx, blob_id, chunk_ids, index).await
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+        Box::new(self.clone())
+    }
+
+    fn blob_size_limit(&self) -> Option<usize> {
+        self.inner.blob_size_limit()
+    }
+
+    fn estimate_gas(&self, blob_size: usize) -> Option<u64> {
+        self.inner.estimate_gas(blob_size)
+    }
+
+    async fn ping(&self) -> anyhow::Result<bool> {
+        self.inner.ping().await
+    }
+
+    async fn confirmations(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<u64>, DAError> {
+        self.inject(ctx).await?;
+        self.inner.confirmations(ctx, blob_id).await
+    }
+
+    async fn head_height(&self) -> Result<Option<u64>, DAError> {
+        self.inner.head_height().await
+    }
+
+    async fn earliest_available_height(&self) -> Result<Option<u64>, DAError> {
+        self.inner.earliest_available_height().await
+    }
+
+    fn retention(&self) -> Option<Duration> {
+        self.inner.retention()
+    }
+
+    async fn expiry_for(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DAError> {
+        self.inject(ctx).await?;
+        self.inner.expiry_for(ctx, blob_id).await
+    }
+
+    async fn is_synced(&self) -> Result<Option<bool>, DAError> {
+        self.inner.is_synced().await
+    }
+
+    async fn get_inclusion_proof(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        self.inject(ctx).await?;
+        self.inner.get_inclusion_proof(ctx, blob_id).await
+    }
+
+    async fn data_root(&self, ctx: &CallContext, height: u64) -> Result<String, DAError> {
+        self.inject(ctx).await?;
+        self.inner.data_root(ctx, height).await
+    }
+
+    async fn scan_blobs(
+        &self,
+        ctx: &CallContext,
+        height: u64,
+    ) -> Result<Vec<ScannedBlob>, DAError> {
+        self.inject(ctx).await?;
+        self.inner.scan_blobs(ctx, height).await
+    }
+
+    /// The namespaced client shares the settings.
+    fn namespaced(
+        &self,
+        namespace: &str,
+    ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        Ok(Arc::new(Self {
+            inner: self.inner.namespaced(namespace)?,
+            ..self.clone()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::clients::da_clients::in_memory::InMemoryClient;
+
+    #[tokio::test]
+    async fn test_faults_are_injected_per_the_settings() {
+        let client = ChaosClient::new(Arc::new(InMemoryClient::new(1024)));
+        let namespaced = client.namespaced("tenant").unwrap();
+        let ctx = CallContext::background();
+        assert!(namespaced.dispatch_blob(&ctx, 1, vec![1]).await.is_ok());
+
+        client.set(ChaosSettings {
+            latency_ms: 50,
+            error_rate: 1.0,
+            error: ChaosError::RateLimited,
+        });
+        let started_at = Instant::now();
+        let error = namespaced
+            .dispatch_blob(&ctx, 1, vec![1])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DAError::RateLimited { .. }));
+        assert!(started_at.elapsed() >= Duration::from_millis(50));
+        assert!(client.ping().await.unwrap());
+
+        let previous = client.set(ChaosSettings::default());
+        assert_eq!(previous.error, ChaosError::RateLimited);
+        assert!(namespaced.dispatch_blob(&ctx, 1, vec![1]).await.is_ok());
+    }
+}
@@ -1,20 +1,77 @@
 use std::collections::HashMap;
+use std::future::ready;
+use std::ops::Range;
 use std::sync::{Arc, Mutex};
 
-use anyhow::anyhow;
 use async_trait::async_trait;
+use chrono::Utc;
 use sha2::{Digest, Sha256};
 
-use crate::clients::da_clients::types::{ViaDaBlob, deserialize_blob_ids};
+use crate::clients::da_clients::blob_id::{BlobIdCodec, BlobLocator};
+use crate::clients::da_clients::celestia::{
+    DEFAULT_APP_VERSION, celestia_namespace, share_commitment,
+};
+use crate::clients::da_clients::types::{InclusionRange, ViaDaBlob, read_chunk_range};
 use crate::clients::da_clients::{
     DataAvailabilityClient,
-    types::{DAError, DispatchResponse, InclusionData},
+    types::{CallContext, DAError, DispatchResponse, InclusionData},
 };
+use crate::config::{CommitmentScheme, DaBackend};
+use crate::types::dispatch::DispatchReceipt;
+
+/// `HashBlobId` is the blob_id of a content-addressed blob, the SHA-256 hash of its data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashBlobId(pub [u8; 32]);
+
+impl BlobIdCodec for HashBlobId {
+    const VERSION: u8 = 2;
+
+    fn to_payload(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self(payload.try_into()?))
+    }
+
+    fn locator(&self) -> BlobLocator {
+        BlobLocator {
+            height: None,
+            commitment: self.0.to_vec(),
+        }
+    }
+}
+
+/// `ShareBlobId` is the blob_id of a blob committed to with its Celestia share commitment in the
+/// namespace of the client, the commitment the blob has once included in Celestia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareBlobId(pub [u8; 32]);
+
+impl BlobIdCodec for ShareBlobId {
+    const VERSION: u8 = 3;
+
+    fn to_payload(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self(payload.try_into()?))
+    }
+
+    fn locator(&self) -> BlobLocator {
+        BlobLocator {
+            height: None,
+            commitment: self.0.to_vec(),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct InMemoryClient {
     storage: Arc<Mutex<HashMap<String, Vec<u8>>>>,
     blob_size_limit: usize,
+    namespace: Option<String>,
+    commitment: CommitmentScheme,
 }
 
 impl InMemoryClient {
@@ -22,81 +79,174 @@ impl InMemoryClient {
         Self {
             storage: Arc::new(Mutex::new(HashMap::new())),
             blob_size_limit,
+            namespace: None,
+            commitment: CommitmentScheme::default(),
         }
     }
+
+    /// Sets the commitment of the issued blob_ids, the blob_ids issued with another scheme are
+    /// still verified with theirs.
+    pub fn with_commitment(mut self, commitment: CommitmentScheme) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    /// Returns the commitment of the data under the scheme.
+    fn commit(&self, scheme: CommitmentScheme, data: &[u8]) -> anyhow::Result<[u8; 32]> {
+        match scheme {
+            CommitmentScheme::Sha256 => Ok(Sha256::digest(data).into()),
+            CommitmentScheme::Share => share_commitment(
+                celestia_namespace(self.namespace.as_deref())?,
+                data,
+                DEFAULT_APP_VERSION,
+            ),
+        }
+    }
+
+    /// Checks the stored data of a blob against the commitment of its blob_id.
+    fn verify(&self, blob_id: &str, data: &[u8]) -> Result<(), DAError> {
+        let (scheme, commitment) = match HashBlobId::decode(blob_id) {
+            Ok(id) => (CommitmentScheme::Sha256, id.0),
+            Err(_) => (CommitmentScheme::Share, ShareBlobId::decode(blob_id)?.0),
+        };
+
+        if self.commit(scheme, data)? != commitment {
+            return Err(DAError::IntegrityMismatch {
+                blob_id: blob_id.to_string(),
+                reason: format!("The data doesn't match the {scheme:?} commitment of the blob_id"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the storage key of the blob in the client namespace.
+    fn key(&self, blob_id: &str) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{namespace}/{blob_id}"),
+            None => blob_id.to_string(),
+        }
+    }
+
+    /// Returns the stored data of a blob, checked against its blob_id.
+    fn stored(&self, blob_id: &str) -> Result<Option<Vec<u8>>, DAError> {
+        let data = self
+            .storage
+            .lock()
+            .unwrap()
+            .get(&self.key(blob_id))
+            .cloned();
+        if let Some(data) = &data {
+            self.verify(blob_id, data)?;
+        }
+        Ok(data)
+    }
+
+    /// Returns a chunk of a chunked blob, a missing chunk is an integrity error.
+    fn chunk(&self, blob_id: &str, chunk_id: &str) -> Result<Vec<u8>, DAError> {
+        self.stored(chunk_id)?
+            .ok_or_else(|| DAError::IntegrityMismatch {
+                blob_id: blob_id.to_string(),
+                reason: format!("Chunk {} not found", chunk_id),
+            })
+    }
 }
 
 #[async_trait]
 impl DataAvailabilityClient for InMemoryClient {
     async fn dispatch_blob(
         &self,
+        _ctx: &CallContext,
         _batch_number: u32,
         data: Vec<u8>,
     ) -> Result<DispatchResponse, DAError> {
-        let mut hasher = Sha256::new();
-        hasher.update(&data);
-        let result = hasher.finalize();
-
-        let blob_id = hex::encode(&result);
+        let commitment =
+            self.commit(self.commitment, &data)
+                .map_err(|error| DAError::SubmitRejected {
+                    reason: format!("Error to create commitment: {}", error),
+                })?;
+
+        let blob_id = match self.commitment {
+            CommitmentScheme::Sha256 => HashBlobId(commitment).encode(),
+            CommitmentScheme::Share => ShareBlobId(commitment).encode(),
+        };
+        let receipt = DispatchReceipt {
+            height: None,
+            commitment: hex::encode(commitment),
+            namespace: self.namespace.as_ref().map(hex::encode),
+            size: data.len() as u64,
+            backend: DaBackend::InMemory,
+            submitted_at: Utc::now(),
+            fee: None,
+            chunks: vec![],
+        };
 
-        self.storage.lock().unwrap().insert(blob_id.clone(), data);
+        self.storage
+            .lock()
+            .unwrap()
+            .insert(self.key(&blob_id), data);
 
-        Ok(DispatchResponse { blob_id })
+        Ok(DispatchResponse::from(blob_id).with_receipt(receipt))
     }
 
-    async fn get_inclusion_data(&self, blob_id: &str) -> Result<Option<InclusionData>, DAError> {
-        let storage = self.storage.lock().unwrap();
+    async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        Ok(self
+            .get_inclusion_range(ctx, blob_id, 0..u64::MAX)
+            .await?
+            .map(|range| InclusionData { data: range.data }))
+    }
 
-        let Some(blob) = storage
-            .get(blob_id)
-            .map(|data| InclusionData { data: data.clone() })
-        else {
+    async fn get_inclusion_range(
+        &self,
+        _ctx: &CallContext,
+        blob_id: &str,
+        range: Range<u64>,
+    ) -> Result<Option<InclusionRange>, DAError> {
+        let Some(data) = self.stored(blob_id)? else {
             return Ok(None);
         };
 
-        let data = match ViaDaBlob::from_bytes(&blob.data) {
-            Some(blob) => {
-                if blob.chunks == 1 {
-                    blob.data
-                } else {
-                    let blob_ids = deserialize_blob_ids(&blob.data).map_err(|_| DAError {
-                        error: anyhow!("Failed to deserialize blob ids"),
-                        is_retriable: false,
-                    })?;
-                    if blob_ids.len() != blob.chunks {
-                        return Err(DAError {
-                            error: anyhow!(
-                                "Mismatch, blob ids len [{}] != chunk size [{}]",
-                                blob_ids.len(),
-                                blob.chunks
-                            ),
-                            is_retriable: false,
-                        });
-                    }
-
-                    let mut batch_blob = vec![];
-
-                    for blob_id in blob_ids {
-                        let Some(blob) = storage
-                            .get(&blob_id)
-                            .map(|data| InclusionData { data: data.clone() })
-                        else {
-                            return Err(DAError {
-                                error: anyhow!("Failed to get blob"),
-                                is_retriable: false,
-                            });
-                        };
-
-                        batch_blob.extend_from_slice(&blob.data);
-                    }
-
-                    batch_blob
-                }
-            }
-            None => blob.data,
+        let via_blob = match ViaDaBlob::from_bytes(&data) {
+            Some(via_blob) if via_blob.chunks != 1 => via_blob,
+            Some(via_blob) => return Ok(Some(InclusionRange::slice(&via_blob.data, range))),
+            None => return Ok(Some(InclusionRange::slice(&data, range))),
+        };
+        let chunk_ids = via_blob.chunk_ids(blob_id)?;
+
+        let range = read_chunk_range(&chunk_ids, range, |_, chunk_id| {
+            ready(self.chunk(blob_id, chunk_id))
+        })
+        .await?;
+
+        Ok(Some(range))
+    }
+
+    async fn get_chunk_ids(
+        &self,
+        _ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        let Some(data) = self.stored(blob_id)? else {
+            return Ok(None);
         };
 
-        Ok(Some(InclusionData { data }))
+        match ViaDaBlob::from_bytes(&data) {
+            Some(via_blob) if via_blob.chunks != 1 => via_blob.chunk_ids(blob_id).map(Some),
+            _ => Ok(Some(vec![])),
+        }
+    }
+
+    async fn get_chunk(
+        &self,
+        _ctx: &CallContext,
+        blob_id: &str,
+        chunk_ids: &[String],
+        index: usize,
+    ) -> Result<Vec<u8>, DAError> {
+        self.chunk(blob_id, &chunk_ids[index])
     }
 
     fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
@@ -110,11 +260,27 @@ impl DataAvailabilityClient for InMemoryClient {
     async fn ping(&self) -> anyhow::Result<bool> {
         Ok(true)
     }
+
+    fn namespaced(
+        &self,
+        namespace: &str,
+    ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        // The share commitments need a valid Celestia namespace.
+        if self.commitment == CommitmentScheme::Share {
+            celestia_namespace(Some(namespace))?;
+        }
+
+        Ok(Arc::new(Self {
+            namespace: Some(namespace.to_string()),
+            ..self.clone()
+        }))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clients::da_clients::types::serialize_blob_ids;
     use hex;
     use sha2::{Digest, Sha256};
 
@@ -123,6 +289,10 @@ mod tests {
         InMemoryClient::new(1024)
     }
 
+    fn ctx() -> CallContext {
+        CallContext::background()
+    }
+
     #[tokio::test]
     async fn test_dispatch_and_retrieve_blob() {
         let client = new_client();
@@ -130,25 +300,31 @@ mod tests {
         let data = b"hello world".to_vec();
 
         // Dispatch blob
-        let response = client.dispatch_blob(1, data.clone()).await.unwrap();
+        let response = client.dispatch_blob(&ctx(), 1, data.clone()).await.unwrap();
 
         // Compute expected SHA256 hash manually
         let mut hasher = Sha256::new();
         hasher.update(&data);
-        let expected_blob_id = hex::encode(hasher.finalize());
+        let expected_blob_id = format!("02{}", hex::encode(hasher.finalize()));
 
         // Check that blob_id matches expected hash
         assert_eq!(response.blob_id, expected_blob_id);
 
         // Retrieve blob and verify data matches
-        let inclusion = client.get_inclusion_data(&response.blob_id).await.unwrap();
+        let inclusion = client
+            .get_inclusion_data(&ctx(), &response.blob_id)
+            .await
+            .unwrap();
         assert_eq!(inclusion, Some(InclusionData { data: data.clone() }));
     }
 
     #[tokio::test]
     async fn test_nonexistent_blob_returns_none() {
         let client = new_client();
-        let inclusion = client.get_inclusion_data("does_not_exist").await.unwrap();
+        let inclusion = client
+            .get_inclusion_data(&ctx(), "does_not_exist")
+            .await
+            .unwrap();
         assert!(inclusion.is_none());
     }
 
@@ -158,10 +334,13 @@ mod tests {
         let boxed = client.clone_boxed();
 
         let data = b"clone test".to_vec();
-        let resp = boxed.dispatch_blob(2, data.clone()).await.unwrap();
+        let resp = boxed.dispatch_blob(&ctx(), 2, data.clone()).await.unwrap();
 
         // Ensure data is accessible from the original client too (shared storage)
-        let inclusion = client.get_inclusion_data(&resp.blob_id).await.unwrap();
+        let inclusion = client
+            .get_inclusion_data(&ctx(), &resp.blob_id)
+            .await
+            .unwrap();
         assert_eq!(inclusion, Some(InclusionData { data: data.clone() }));
     }
 
@@ -185,15 +364,181 @@ mod tests {
         let data1 = b"first blob".to_vec();
         let data2 = b"second blob".to_vec();
 
-        let resp1 = client.dispatch_blob(1, data1.clone()).await.unwrap();
-        let resp2 = client.dispatch_blob(2, data2.clone()).await.unwrap();
+        let resp1 = client
+            .dispatch_blob(&ctx(), 1, data1.clone())
+            .await
+            .unwrap();
+        let resp2 = client
+            .dispatch_blob(&ctx(), 2, data2.clone())
+            .await
+            .unwrap();
 
         assert_ne!(resp1.blob_id, resp2.blob_id);
 
-        let retrieved1 = client.get_inclusion_data(&resp1.blob_id).await.unwrap();
-        let retrieved2 = client.get_inclusion_data(&resp2.blob_id).await.unwrap();
+        let retrieved1 = client
+            .get_inclusion_data(&ctx(), &resp1.blob_id)
+            .await
+            .unwrap();
+        let retrieved2 = client
+            .get_inclusion_data(&ctx(), &resp2.blob_id)
+            .await
+            .unwrap();
 
         assert_eq!(retrieved1, Some(InclusionData { data: data1 }));
         assert_eq!(retrieved2, Some(InclusionData { data: data2 }));
     }
+
+    #[tokio::test]
+    async fn test_blob_ids_commit_to_the_data() {
+        let client = new_client().with_commitment(CommitmentScheme::Share);
+        let data = b"pubdata".to_vec();
+
+        // The blob_id commits to the data as the blob would on Celestia.
+        let resp = client.dispatch_blob(&ctx(), 1, data.clone()).await.unwrap();
+        let commitment = share_commitment(
+            celestia_namespace(None).unwrap(),
+            &data,
+            DEFAULT_APP_VERSION,
+        )
+        .unwrap();
+        assert_eq!(resp.blob_id, ShareBlobId(commitment).encode());
+        assert_eq!(&resp.blob_id[..2], "03");
+
+        // The blob_ids issued with the previous scheme are still verified with theirs.
+        let sha256 = new_client();
+        let previous = sha256.dispatch_blob(&ctx(), 2, data.clone()).await.unwrap();
+        let client = InMemoryClient {
+            storage: sha256.storage.clone(),
+            ..client
+        };
+        assert_eq!(
+            client
+                .get_inclusion_data(&ctx(), &previous.blob_id)
+                .await
+                .unwrap(),
+            Some(InclusionData { data })
+        );
+
+        client
+            .storage
+            .lock()
+            .unwrap()
+            .insert(previous.blob_id.clone(), b"tampered".to_vec());
+        assert!(matches!(
+            client.get_inclusion_data(&ctx(), &previous.blob_id).await,
+            Err(DAError::IntegrityMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_missing_chunk_is_an_integrity_mismatch() {
+        let client = new_client();
+
+        let chunk_ids = vec![hex::encode([1u8; 32]), hex::encode([2u8; 32])];
+        let manifest = ViaDaBlob::new(2, serialize_blob_ids(&chunk_ids).unwrap());
+        let resp = client
+            .dispatch_blob(&ctx(), 1, manifest.to_bytes())
+            .await
+            .unwrap();
+
+        let error = client
+            .get_inclusion_data(&ctx(), &resp.blob_id)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DAError::IntegrityMismatch { .. }));
+        assert!(!error.is_retriable());
+    }
+
+    #[tokio::test]
+    async fn test_range_only_fetches_the_chunks_it_overlaps() {
+        let client = new_client();
+
+        let mut chunk_ids = vec![];
+        for chunk in [b"first-", b"second"] {
+            chunk_ids.push(
+                client
+                    .dispatch_blob(&ctx(), 1, chunk.to_vec())
+                    .await
+                    .unwrap()
+                    .blob_id,
+            );
+        }
+        // The last chunk is missing, only a full read notices it.
+        chunk_ids.push(hex::encode([3u8; 32]));
+        let manifest = ViaDaBlob::new(3, serialize_blob_ids(&chunk_ids).unwrap());
+        let resp = client
+            .dispatch_blob(&ctx(), 1, manifest.to_bytes())
+            .await
+            .unwrap();
+
+        let range = client
+            .get_inclusion_range(&ctx(), &resp.blob_id, 3..9)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(range.data, b"st-sec".to_vec());
+        assert_eq!(range.size, None);
+
+        assert!(
+            client
+                .get_inclusion_data(&ctx(), &resp.blob_id)
+                .await
+                .is_err()
+        );
+
+        let range = client
+            .get_inclusion_range(&ctx(), &chunk_ids[0], 4..100)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((range.data, range.size), (b"t-".to_vec(), Some(6)));
+    }
+
+    #[tokio::test]
+    async fn test_chunks_are_read_one_by_one() {
+        let client = new_client();
+
+        let mut chunk_ids = vec![];
+        for chunk in [b"first-", b"second"] {
+            chunk_ids.push(
+                client
+                    .dispatch_blob(&ctx(), 1, chunk.to_vec())
+                    .await
+                    .unwrap()
+                    .blob_id,
+            );
+        }
+        let manifest = ViaDaBlob::new(2, serialize_blob_ids(&chunk_ids).unwrap());
+        let resp = client
+            .dispatch_blob(&ctx(), 1, manifest.to_bytes())
+            .await
+            .unwrap();
+
+        let ids = client
+            .get_chunk_ids(&ctx(), &resp.blob_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ids, chunk_ids);
+        assert_eq!(
+            client
+                .get_chunk(&ctx(), &resp.blob_id, &ids, 1)
+                .await
+                .unwrap(),
+            b"second".to_vec()
+        );
+
+        // A blob that is not chunked has no chunks.
+        assert_eq!(
+            client.get_chunk_ids(&ctx(), &chunk_ids[0]).await.unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(
+            client
+                .get_chunk_ids(&ctx(), &hex::encode([3u8; 32]))
+                .await
+                .unwrap(),
+            None
+        );
+    }
 }
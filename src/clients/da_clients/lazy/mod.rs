@@ -0,0 +1,310 @@
+use std::{
+    fmt::{Debug, Formatter},
+    ops::Range,
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use tokio::sync::OnceCell;
+
+use crate::{
+    clients::da_clients::{
+        DataAvailabilityClient,
+        types::{
+            CallContext, DAError, DispatchResponse, InclusionData, InclusionProof, InclusionRange,
+            ScannedBlob,
+        },
+    },
+    config::DaBackend,
+};
+
+type Client = Arc<dyn DataAvailabilityClient + Send + Sync>;
+
+type Factory = Arc<dyn Fn() -> BoxFuture<'static, anyhow::Result<Client>> + Send + Sync>;
+
+/// An implementation of the `DataAvailabilityClient` trait creating the client of a backend on
+/// its first use, so the service starts while the DA node is unreachable. The calls fail with a
+/// connection error until the client is created, by a call or by the warm-up.
+#[derive(Clone)]
+pub struct LazyClient {
+    backend: DaBackend,
+    factory: Factory,
+    client: Arc<OnceCell<Client>>,
+    /// The namespace of a namespaced client, applied to the client once created.
+    namespace: Option<String>,
+    blob_size_limit: usize,
+}
+
+impl Debug for LazyClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyClient")
+            .field("backend", &self.backend)
+            .field("initialized", &self.is_initialized())
+            .field("namespace", &self.namespace)
+            .finish()
+    }
+}
+
+impl LazyClient {
+    /// Creates the client of `backend` with `factory` on its first use, `blob_size_limit` is the
+    /// limit of the backend until then.
+    pub fn new<F, Fut>(backend: DaBackend, blob_size_limit: usize, factory: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<Client>> + Send + 'static,
+    {
+        Self {
+            backend,
+            factory: Arc::new(move || Box::pin(factory())),
+            client: Arc::default(),
+            namespace: None,
+            blob_size_limit,
+        }
+    }
+
+    pub fn backend(&self) -> DaBackend {
+        self.backend
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.client.initialized()
+    }
+
+    /// Creates the client unless it was already, a failed attempt is tried again on the next
+    /// call.
+    pub async fn init(&self) -> anyhow::Result<()> {
+        self.client
+            .get_or_try_init(|| async {
+                let client = (self.factory)().await?;
+                tracing::info!("The {} backend is initialized", self.backend.as_str());
+                Ok::<_, anyhow::Error>(client)
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn inner(&self) -> Result<Client, DAError> {
+        if let Err(error) = self.init().await {
+            return Err(DAError::ConnectionError {
+                message: format!(
+                    "The {} backend is not initialized: {}",
+                    self.backend.as_str(),
+                    error
+                ),
+            });
+        }
+        let client = self.client.get().expect("The client is initialized");
+        match &self.namespace {
+            Some(namespace) => Ok(client.namespaced(namespace)?),
+            None => Ok(client.clone()),
+        }
+    }
+
+    /// Returns the client once created, without creating it.
+    fn created(&self) -> Option<Client> {
+        let client = self.client.get()?;
+        match &self.namespace {
+            Some(namespace) => client.namespaced(namespace).ok(),
+            None => Some(client.clone()),
+        }
+    }
+}
+
+#[async_trait]
+impl DataAvailabilityClient for LazyClient {
+    async fn dispatch_blob(
+        &self,
+        ctx: &CallContext,
+        batch_number: u32,
+        data: Vec<u8>,
+    ) -> Result<DispatchResponse, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.dispatch_blob(ctx, batch_number, data).await
+    }
+
+    async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.get_inclusion_data(ctx, blob_id).await
+    }
+
+    async fn get_inclusion_range(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        range: Range<u64>,
+    ) -> Result<Option<InclusionRange>, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.get_inclusion_range(ctx, blob_id, range).await
+    }
+
+    async fn get_chunk_ids(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.get_chunk_ids(ctx, blob_id).await
+    }
+
+    async fn get_chunk(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        chunk_ids: &[String],
+        index: usize,
+    ) -> Result<Vec<u8>, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.get_chunk(ctx, blob_id, chunk_ids, index).await
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+        Box::new(self.clone())
+    }
+
+    fn blob_size_limit(&self) -> Option<usize> {
+        match self.created() {
+            Some(client) => client.blob_size_limit(),
+            None => Some(self.blob_size_limit),
+        }
+    }
+
+    fn estimate_gas(&self, blob_size: usize) -> Option<u64> {
+        self.created()?.estimate_gas(blob_size)
+    }
+
+    /// An unreachable backend reports the DA layer as down rather than failing.
+    async fn ping(&self) -> anyhow::Result<bool> {
+        match self.inner().await {
+            Ok(client) => client.ping().await,
+            Err(_) => Ok(false),
+        }
+    }
+
+    async fn confirmations(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<u64>, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.confirmations(ctx, blob_id).await
+    }
+
+    async fn head_height(&self) -> Result<Option<u64>, DAError> {
+        self.inner().await?.head_height().await
+    }
+
+    async fn earliest_available_height(&self) -> Result<Option<u64>, DAError> {
+        self.inner().await?.earliest_available_height().await
+    }
+
+    fn retention(&self) -> Option<Duration> {
+        self.created()?.retention()
+    }
+
+    async fn expiry_for(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.expiry_for(ctx, blob_id).await
+    }
+
+    async fn is_synced(&self) -> Result<Option<bool>, DAError> {
+        self.inner().await?.is_synced().await
+    }
+
+    async fn get_inclusion_proof(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.get_inclusion_proof(ctx, blob_id).await
+    }
+
+    async fn data_root(&self, ctx: &CallContext, height: u64) -> Result<String, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.data_root(ctx, height).await
+    }
+
+    async fn scan_blobs(
+        &self,
+        ctx: &CallContext,
+        height: u64,
+    ) -> Result<Vec<ScannedBlob>, DAError> {
+        let client = ctx.run(self.inner()).await?;
+        client.scan_blobs(ctx, height).await
+    }
+
+    /// The namespaced client shares the client of the backend, created once for both.
+    fn namespaced(
+        &self,
+        namespace: &str,
+    ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        Ok(Arc::new(Self {
+            namespace: Some(namespace.to_string()),
+            ..self.clone()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::clients::da_clients::in_memory::InMemoryClient;
+
+    #[tokio::test]
+    async fn test_the_client_is_created_once_the_backend_is_reachable() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let client = LazyClient::new(DaBackend::InMemory, 1024, {
+            let attempts = attempts.clone();
+            move || {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    anyhow::ensure!(attempt > 0, "The DA node is not up yet");
+                    Ok(Arc::new(InMemoryClient::new(1024)) as Client)
+                }
+            }
+        });
+        let namespaced = client.namespaced("tenant").unwrap();
+        let ctx = CallContext::background();
+
+        assert!(!client.ping().await.unwrap());
+        assert_eq!(client.blob_size_limit(), Some(1024));
+
+        let blob_id = namespaced
+            .dispatch_blob(&ctx, 1, b"pubdata".to_vec())
+            .await
+            .unwrap()
+            .blob_id;
+        assert!(client.is_initialized());
+        assert!(client.ping().await.unwrap());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        // Both share the client, the blob is in the namespace of the tenant.
+        assert!(
+            namespaced
+                .get_inclusion_data(&ctx, &blob_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            client
+                .get_inclusion_data(&ctx, &blob_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
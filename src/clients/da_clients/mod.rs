@@ -1,47 +1,211 @@
+pub mod blob_id;
 pub mod celestia;
+pub mod chaos;
 pub mod in_memory;
+pub mod lazy;
+#[cfg(test)]
+pub mod proving;
+pub mod quorum;
+pub mod retrying;
+pub mod switchable;
 pub mod types;
 
-use std::{fmt, sync::Arc};
+use std::{fmt, ops::Range, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use types::{DAError, DispatchResponse, InclusionData};
+use chrono::{DateTime, Utc};
+use types::{
+    CallContext, DAError, DispatchResponse, InclusionData, InclusionProof, InclusionRange,
+    ScannedBlob,
+};
 
 use crate::{
-    clients::da_clients::{celestia::CelestiaClient, in_memory::InMemoryClient},
+    clients::da_clients::{
+        celestia::CelestiaClient, in_memory::InMemoryClient, lazy::LazyClient,
+        quorum::QuorumClient, retrying::RetryingClient, switchable::SwitchableClient,
+    },
     config::{Config, DaBackend},
+    services::{params_refresh::ParamsRefreshSvc, warmup::WarmupSvc},
 };
 
+/// Creates the client of a backend, the Celestia clients are refreshed by `params_refresh`.
 pub async fn make_da_client(
-    config: Config,
+    backend: DaBackend,
+    config: &Config,
+    params_refresh: &ParamsRefreshSvc,
 ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
-    match config.da_backend {
+    match backend {
         DaBackend::Celestia => {
-            let client = CelestiaClient::new(
-                config.da_node_url.unwrap(),
-                config.da_auth_token.unwrap(),
-                config.da_blob_size_limit,
-            )
-            .await?;
-            Ok(Arc::new(client))
+            let mut nodes: Vec<Arc<dyn DataAvailabilityClient + Send + Sync>> = vec![];
+            for node_url in std::iter::once(config.da_node_url.as_ref().unwrap())
+                .chain(&config.da_read_quorum_node_urls)
+            {
+                let mut client = CelestiaClient::new(
+                    node_url.clone(),
+                    config.da_auth_token.clone().unwrap(),
+                    config.da_blob_size_limit,
+                )
+                .await?
+                .with_sampling_window(config.da_sampling_window);
+                if let Some(interval) = config.da_params_refresh_interval {
+                    client = client.with_params_refresh(interval).await;
+                    params_refresh.add(client.clone());
+                }
+                if let Some(url) = &config.da_archival_node_url {
+                    client = client
+                        .with_archival_node(url, config.da_archival_auth_token.as_deref())
+                        .await?;
+                }
+                client = client
+                    .with_fallback_nodes(&config.da_fallback_node_urls)
+                    .await?;
+                nodes.push(Arc::new(client));
+            }
+
+            if nodes.len() == 1 {
+                return Ok(nodes.remove(0));
+            }
+            let quorum = config
+                .da_read_quorum
+                .unwrap_or_else(|| QuorumClient::majority(nodes.len()));
+            tracing::info!(
+                "Reading the Celestia blobs from {} light nodes with a quorum of {}",
+                nodes.len(),
+                quorum
+            );
+            Ok(Arc::new(QuorumClient::new(nodes, quorum)?))
         }
 
-        DaBackend::InMemory => Ok(Arc::new(InMemoryClient::new(config.da_blob_size_limit))),
+        DaBackend::InMemory => Ok(Arc::new(
+            InMemoryClient::new(config.da_blob_size_limit).with_commitment(config.da_commitment),
+        )),
+    }
+}
+
+/// Creates the client of a backend on its first use or by the warm-up, so the service starts
+/// while the DA node is unreachable.
+pub fn make_lazy_da_client(
+    backend: DaBackend,
+    config: &Config,
+    warmup: &WarmupSvc,
+    params_refresh: &Arc<ParamsRefreshSvc>,
+) -> LazyClient {
+    let client = LazyClient::new(backend, config.da_blob_size_limit, {
+        let config = config.clone();
+        let params_refresh = params_refresh.clone();
+        move || {
+            let (config, params_refresh) = (config.clone(), params_refresh.clone());
+            async move { make_da_client(backend, &config, &params_refresh).await }
+        }
+    });
+    warmup.add(client.clone());
+    client
+}
+
+/// Creates the switchable client over the active and the standby backends of the config.
+pub fn make_switchable_da_client(
+    config: &Config,
+    warmup: &WarmupSvc,
+    params_refresh: &Arc<ParamsRefreshSvc>,
+) -> anyhow::Result<SwitchableClient> {
+    let mut backends = vec![];
+    for backend in std::iter::once(config.da_backend).chain(config.da_standby_backends.clone()) {
+        let client = RetryingClient::new(
+            backend,
+            Arc::new(make_lazy_da_client(backend, config, warmup, params_refresh)),
+            config.retry_policy(backend),
+        );
+        backends.push((
+            backend,
+            Arc::new(client) as Arc<dyn DataAvailabilityClient + Send + Sync>,
+        ));
+    }
+
+    SwitchableClient::new(backends)
+}
+
+/// Creates the client of the shadow dispatches, None when the shadow mode is disabled.
+pub fn make_shadow_da_client(
+    config: &Config,
+    warmup: &WarmupSvc,
+    params_refresh: &Arc<ParamsRefreshSvc>,
+) -> anyhow::Result<Option<Arc<dyn DataAvailabilityClient + Send + Sync>>> {
+    if config.shadow_percent == 0 {
+        return Ok(None);
+    }
+
+    let backend = config.shadow_backend.unwrap_or(config.da_backend);
+    let client: Arc<dyn DataAvailabilityClient + Send + Sync> = Arc::new(RetryingClient::new(
+        backend,
+        Arc::new(make_lazy_da_client(backend, config, warmup, params_refresh)),
+        config.retry_policy(backend),
+    ));
+    match &config.shadow_namespace {
+        Some(namespace) => Ok(Some(client.namespaced(namespace)?)),
+        None => Ok(Some(client)),
     }
 }
 
 /// Trait that defines the interface for the data availability layer clients.
+///
+/// The calls serving a request take its `CallContext`, the clients stop working on a call once its
+/// deadline passed or the request is cancelled.
 #[async_trait]
 pub trait DataAvailabilityClient: Sync + Send + fmt::Debug {
     /// Dispatches a blob to the data availability layer.
     async fn dispatch_blob(
         &self,
+        ctx: &CallContext,
         batch_number: u32,
         data: Vec<u8>,
     ) -> Result<DispatchResponse, DAError>;
 
     /// Fetches the inclusion data for a given blob_id.
-    async fn get_inclusion_data(&self, blob_id: &str) -> Result<Option<InclusionData>, DAError>;
+    async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError>;
+
+    /// Fetches `range` of the payload of a blob, the backends storing the chunked blobs only fetch
+    /// the chunks up to the end of the range.
+    async fn get_inclusion_range(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        range: Range<u64>,
+    ) -> Result<Option<InclusionRange>, DAError> {
+        Ok(self
+            .get_inclusion_data(ctx, blob_id)
+            .await?
+            .map(|data| InclusionRange::slice(&data.data, range)))
+    }
+
+    /// Returns the blob_ids of the chunks of a chunked blob, empty for a blob that is not chunked
+    /// and None when the blob is not found.
+    async fn get_chunk_ids(
+        &self,
+        _ctx: &CallContext,
+        _blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        Err(DAError::Unsupported {
+            operation: "chunk reads",
+        })
+    }
+
+    /// Fetches the chunk `index` of the chunked blob with the `chunk_ids`, as reassembled in its
+    /// payload. A missing chunk is an integrity error.
+    async fn get_chunk(
+        &self,
+        _ctx: &CallContext,
+        _blob_id: &str,
+        _chunk_ids: &[String],
+        _index: usize,
+    ) -> Result<Vec<u8>, DAError> {
+        Err(DAError::Unsupported {
+            operation: "chunk reads",
+        })
+    }
 
     /// Clones the client and wraps it in a Box.
     fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient>;
@@ -49,8 +213,97 @@ pub trait DataAvailabilityClient: Sync + Send + fmt::Debug {
     /// Returns the maximum size of the blob (in bytes) that can be dispatched. None means no limit.
     fn blob_size_limit(&self) -> Option<usize>;
 
+    /// Returns the gas a blob of `blob_size` bytes is expected to use, None for the backends
+    /// without fees.
+    fn estimate_gas(&self, _blob_size: usize) -> Option<u64> {
+        None
+    }
+
     /// Ping the DA layer.
     async fn ping(&self) -> anyhow::Result<bool>;
+
+    /// Returns the number of blocks built on top of the block including the blob, None when the
+    /// blob is not found. Backends without blocks report the included blobs as final (`u64::MAX`).
+    async fn confirmations(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<u64>, DAError> {
+        Ok(self
+            .get_inclusion_data(ctx, blob_id)
+            .await?
+            .map(|_| u64::MAX))
+    }
+
+    /// Returns the height of the head of the DA chain, None for the backends without blocks.
+    async fn head_height(&self) -> Result<Option<u64>, DAError> {
+        Ok(None)
+    }
+
+    /// Returns the earliest height the DA node still serves the blobs of, None for the backends
+    /// without pruning.
+    async fn earliest_available_height(&self) -> Result<Option<u64>, DAError> {
+        Ok(None)
+    }
+
+    /// Returns how long the blobs stay available after their inclusion, None for the backends
+    /// keeping them forever.
+    fn retention(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns when the blob stops being served, None for the backends keeping the blobs forever.
+    async fn expiry_for(
+        &self,
+        _ctx: &CallContext,
+        _blob_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DAError> {
+        Ok(None)
+    }
+
+    /// Whether the DA node caught up with the head of the chain, None for the backends without
+    /// sync.
+    async fn is_synced(&self) -> Result<Option<bool>, DAError> {
+        Ok(None)
+    }
+
+    /// Generates the inclusion proof of a blob, None when the blob is not found.
+    async fn get_inclusion_proof(
+        &self,
+        _ctx: &CallContext,
+        _blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        Err(DAError::Unsupported {
+            operation: "inclusion proofs",
+        })
+    }
+
+    /// Returns the hex data root of the block at `height`, the inclusion proofs are checked
+    /// against it.
+    async fn data_root(&self, _ctx: &CallContext, _height: u64) -> Result<String, DAError> {
+        Err(DAError::Unsupported {
+            operation: "inclusion proofs",
+        })
+    }
+
+    /// Returns the blobs of the namespace included in the block at `height`, submitted by any
+    /// account.
+    async fn scan_blobs(
+        &self,
+        _ctx: &CallContext,
+        _height: u64,
+    ) -> Result<Vec<ScannedBlob>, DAError> {
+        Err(DAError::Unsupported {
+            operation: "blob scans",
+        })
+    }
+
+    /// Returns a client of the same DA layer scoped to `namespace`, the blobs dispatched by a
+    /// namespaced client are only readable by a client of the same namespace.
+    fn namespaced(
+        &self,
+        namespace: &str,
+    ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>>;
 }
 
 impl Clone for Box<dyn DataAvailabilityClient> {
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::clients::da_clients::{
+    DataAvailabilityClient,
+    types::{CallContext, DAError, DispatchResponse, InclusionData, InclusionProof},
+};
+
+/// `ProvingClient` is the node of the tests proving the blobs `<height>` against the data root
+/// of their block, and counting the proofs it serves.
+#[derive(Debug, Clone, Default)]
+pub struct ProvingClient {
+    data_roots: Arc<Mutex<HashMap<u64, String>>>,
+    proofs: Arc<AtomicUsize>,
+}
+
+impl ProvingClient {
+    /// Sets the data root of the block at `height`, e.g. to simulate a reorg.
+    pub fn set_data_root(&self, height: u64, data_root: &str) {
+        self.data_roots
+            .lock()
+            .unwrap()
+            .insert(height, data_root.to_string());
+    }
+
+    /// Returns the number of inclusion proofs served.
+    pub fn proofs(&self) -> usize {
+        self.proofs.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl DataAvailabilityClient for ProvingClient {
+    async fn dispatch_blob(
+        &self,
+        _: &CallContext,
+        _: u32,
+        _: Vec<u8>,
+    ) -> Result<DispatchResponse, DAError> {
+        Ok(DispatchResponse::from("ab".to_string()))
+    }
+
+    async fn get_inclusion_data(
+        &self,
+        _: &CallContext,
+        _: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        Ok(None)
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+        Box::new(self.clone())
+    }
+
+    fn blob_size_limit(&self) -> Option<usize> {
+        None
+    }
+
+    async fn ping(&self) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+
+    async fn get_inclusion_proof(
+        &self,
+        _: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        let Ok(height) = blob_id.parse::<u64>() else {
+            return Ok(None);
+        };
+        let Some(data_root) = self.data_roots.lock().unwrap().get(&height).cloned() else {
+            return Ok(None);
+        };
+        self.proofs.fetch_add(1, Ordering::SeqCst);
+        Ok(Some(InclusionProof {
+            blob_id: blob_id.to_string(),
+            height,
+            data_root,
+            namespace: String::new(),
+            commitment: String::new(),
+            proofs: serde_json::Value::Null,
+        }))
+    }
+
+    async fn data_root(&self, _: &CallContext, height: u64) -> Result<String, DAError> {
+        self.data_roots
+            .lock()
+            .unwrap()
+            .get(&height)
+            .cloned()
+            .ok_or_else(|| DAError::ConnectionError {
+                message: format!("No block at height {height}"),
+            })
+    }
+
+    fn namespaced(&self, _: &str) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        Ok(Arc::new(self.clone()))
+    }
+}
@@ -0,0 +1,340 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    clients::da_clients::{
+        DataAvailabilityClient,
+        types::{
+            CallContext, DAError, DispatchResponse, InclusionData, InclusionProof, ScannedBlob,
+        },
+    },
+    services::metrics::DA_METRICS,
+};
+
+type Node = Arc<dyn DataAvailabilityClient + Send + Sync>;
+
+/// An implementation of the `DataAvailabilityClient` trait that reads every blob from several DA
+/// nodes concurrently and only returns the data a quorum of them agrees on, so a single malicious
+/// or corrupted node can't serve forged data.
+///
+/// The nodes agree when they return the same payload, compared by SHA-256. A blob is missing when
+/// a quorum of nodes doesn't know it. The dispatches and the other calls go to the first node.
+#[derive(Clone, Debug)]
+pub struct QuorumClient {
+    nodes: Vec<Node>,
+    quorum: usize,
+}
+
+impl QuorumClient {
+    /// Creates the client, `quorum` is the number of nodes that must agree.
+    pub fn new(nodes: Vec<Node>, quorum: usize) -> anyhow::Result<Self> {
+        if quorum == 0 || quorum > nodes.len() {
+            anyhow::bail!(
+                "The read quorum must be between 1 and the {} DA nodes, got {}",
+                nodes.len(),
+                quorum
+            );
+        }
+
+        Ok(Self { nodes, quorum })
+    }
+
+    /// The smallest majority of `nodes`.
+    pub fn majority(nodes: usize) -> usize {
+        nodes / 2 + 1
+    }
+
+    fn primary(&self) -> &Node {
+        &self.nodes[0]
+    }
+}
+
+#[async_trait]
+impl DataAvailabilityClient for QuorumClient {
+    async fn dispatch_blob(
+        &self,
+        ctx: &CallContext,
+        batch_number: u32,
+        data: Vec<u8>,
+    ) -> Result<DispatchResponse, DAError> {
+        self.primary().dispatch_blob(ctx, batch_number, data).await
+    }
+
+    /// Without a quorum the first error is returned when the failed nodes could have completed
+    /// one, otherwise the nodes disagree and the blob is reported as an integrity mismatch.
+    async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        let results = join_all(
+            self.nodes
+                .iter()
+                .map(|node| node.get_inclusion_data(ctx, blob_id)),
+        )
+        .await;
+
+        // (payload hash, data, votes), None is the vote of the nodes that don't know the blob.
+        let mut votes: Vec<(Option<[u8; 32]>, Option<InclusionData>, usize)> = vec![];
+        let mut errors = vec![];
+        for result in results {
+            let data = match result {
+                Ok(data) => data,
+                Err(error) => {
+                    errors.push(error);
+                    continue;
+                }
+            };
+            let hash = data.as_ref().map(|data| Sha256::digest(&data.data).into());
+            match votes
+                .iter_mut()
+                .find(|(candidate, _, _)| *candidate == hash)
+            {
+                Some((_, _, count)) => *count += 1,
+                None => votes.push((hash, data, 1)),
+            }
+        }
+
+        let answered = self.nodes.len() - errors.len();
+        if let Some(index) = votes.iter().position(|(_, _, count)| *count >= self.quorum) {
+            let disagreeing = answered - votes[index].2;
+            if disagreeing > 0 {
+                DA_METRICS.quorum_disagreements.inc_by(disagreeing as u64);
+                tracing::warn!(
+                    "{} of {} DA nodes disagree with the quorum on {}",
+                    disagreeing,
+                    self.nodes.len(),
+                    blob_id
+                );
+            }
+            return Ok(votes.swap_remove(index).1);
+        }
+
+        let agreeing = votes.iter().map(|(_, _, count)| *count).max().unwrap_or(0);
+        if agreeing + errors.len() >= self.quorum
+            && let Some(error) = errors.into_iter().next()
+        {
+            return Err(error);
+        }
+
+        DA_METRICS.quorum_failures.inc();
+        Err(DAError::IntegrityMismatch {
+            blob_id: blob_id.to_string(),
+            reason: format!(
+                "No read quorum, {} of {} DA nodes agree at most, {} required",
+                agreeing,
+                self.nodes.len(),
+                self.quorum
+            ),
+        })
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+        Box::new(self.clone())
+    }
+
+    fn blob_size_limit(&self) -> Option<usize> {
+        self.primary().blob_size_limit()
+    }
+
+    fn estimate_gas(&self, blob_size: usize) -> Option<u64> {
+        self.primary().estimate_gas(blob_size)
+    }
+
+    async fn ping(&self) -> anyhow::Result<bool> {
+        self.primary().ping().await
+    }
+
+    async fn confirmations(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<u64>, DAError> {
+        self.primary().confirmations(ctx, blob_id).await
+    }
+
+    async fn head_height(&self) -> Result<Option<u64>, DAError> {
+        self.primary().head_height().await
+    }
+
+    async fn earliest_available_height(&self) -> Result<Option<u64>, DAError> {
+        self.primary().earliest_available_height().await
+    }
+
+    fn retention(&self) -> Option<Duration> {
+        self.primary().retention()
+    }
+
+    async fn get_chunk_ids(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        self.primary().get_chunk_ids(ctx, blob_id).await
+    }
+
+    async fn get_chunk(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        chunk_ids: &[String],
+        index: usize,
+    ) -> Result<Vec<u8>, DAError> {
+        self.primary()
+            .get_chunk(ctx, blob_id, chunk_ids, index)
+            .await
+    }
+
+    async fn expiry_for(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DAError> {
+        self.primary().expiry_for(ctx, blob_id).await
+    }
+
+    async fn is_synced(&self) -> Result<Option<bool>, DAError> {
+        self.primary().is_synced().await
+    }
+
+    async fn get_inclusion_proof(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        self.primary().get_inclusion_proof(ctx, blob_id).await
+    }
+
+    async fn data_root(&self, ctx: &CallContext, height: u64) -> Result<String, DAError> {
+        self.primary().data_root(ctx, height).await
+    }
+
+    async fn scan_blobs(
+        &self,
+        ctx: &CallContext,
+        height: u64,
+    ) -> Result<Vec<ScannedBlob>, DAError> {
+        self.primary().scan_blobs(ctx, height).await
+    }
+
+    fn namespaced(&self, namespace: &str) -> anyhow::Result<Node> {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| node.namespaced(namespace))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Arc::new(Self {
+            nodes,
+            quorum: self.quorum,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A node answering every read with the same data.
+    #[derive(Debug, Clone)]
+    struct FixedClient(Option<Vec<u8>>);
+
+    #[async_trait]
+    impl DataAvailabilityClient for FixedClient {
+        async fn dispatch_blob(
+            &self,
+            _: &CallContext,
+            _: u32,
+            _: Vec<u8>,
+        ) -> Result<DispatchResponse, DAError> {
+            Ok(DispatchResponse::from("blob".to_string()))
+        }
+
+        async fn get_inclusion_data(
+            &self,
+            _: &CallContext,
+            _: &str,
+        ) -> Result<Option<InclusionData>, DAError> {
+            match &self.0 {
+                Some(data) if data.is_empty() => Err(DAError::ConnectionError {
+                    message: "down".to_string(),
+                }),
+                data => Ok(data.clone().map(|data| InclusionData { data })),
+            }
+        }
+
+        fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+            Box::new(self.clone())
+        }
+
+        fn blob_size_limit(&self) -> Option<usize> {
+            None
+        }
+
+        async fn ping(&self) -> anyhow::Result<bool> {
+            Ok(true)
+        }
+
+        fn namespaced(&self, _: &str) -> anyhow::Result<Node> {
+            Ok(Arc::new(self.clone()))
+        }
+    }
+
+    /// `b""` is a node that is down.
+    fn new_client(answers: &[Option<&[u8]>], quorum: usize) -> QuorumClient {
+        let nodes = answers
+            .iter()
+            .map(|answer| Arc::new(FixedClient(answer.map(<[u8]>::to_vec))) as Node)
+            .collect();
+        QuorumClient::new(nodes, quorum).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_quorum_outvotes_a_corrupted_node() {
+        let client = new_client(&[Some(b"forged"), Some(b"data"), Some(b"data")], 2);
+        let data = client
+            .get_inclusion_data(&CallContext::background(), "blob")
+            .await
+            .unwrap();
+        assert_eq!(data.unwrap().data, b"data".to_vec());
+
+        let client = new_client(&[None, None, Some(b"data")], 2);
+        assert!(
+            client
+                .get_inclusion_data(&CallContext::background(), "blob")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_quorum_is_an_error() {
+        let client = new_client(&[Some(b"forged"), Some(b"data"), None], 2);
+        let err = client
+            .get_inclusion_data(&CallContext::background(), "blob")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DAError::IntegrityMismatch { .. }));
+
+        // Too few nodes answered, the call can be retried.
+        let client = new_client(&[Some(b""), Some(b""), Some(b"data")], 2);
+        let err = client
+            .get_inclusion_data(&CallContext::background(), "blob")
+            .await
+            .unwrap_err();
+        assert!(err.is_retriable());
+    }
+
+    #[test]
+    fn test_quorum_is_validated() {
+        let nodes = vec![Arc::new(FixedClient(None)) as Node];
+        assert!(QuorumClient::new(nodes.clone(), 0).is_err());
+        assert!(QuorumClient::new(nodes.clone(), 2).is_err());
+        assert_eq!(QuorumClient::majority(3), 2);
+    }
+}
@@ -0,0 +1,392 @@
+use std::{ops::Range, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+
+use crate::{
+    clients::da_clients::{
+        DataAvailabilityClient,
+        types::{
+            CallContext, DAError, DispatchResponse, InclusionData, InclusionProof, InclusionRange,
+            ScannedBlob,
+        },
+    },
+    config::{DaBackend, RetryOn, RetryPolicy},
+    services::metrics::DA_METRICS,
+};
+
+/// An implementation of the `DataAvailabilityClient` trait that retries the dispatches and the
+/// inclusion queries of a backend with exponential backoff, per the backend retry policy.
+#[derive(Clone, Debug)]
+pub struct RetryingClient {
+    backend: DaBackend,
+    inner: Arc<dyn DataAvailabilityClient + Send + Sync>,
+    policy: RetryPolicy,
+}
+
+impl RetryingClient {
+    pub fn new(
+        backend: DaBackend,
+        inner: Arc<dyn DataAvailabilityClient + Send + Sync>,
+        policy: RetryPolicy,
+    ) -> Self {
+        Self {
+            backend,
+            inner,
+            policy,
+        }
+    }
+
+    fn should_retry(&self, error: &DAError) -> bool {
+        match self.policy.retry_on {
+            RetryOn::Retriable => error.is_retriable(),
+            RetryOn::All => true,
+            RetryOn::None => false,
+        }
+    }
+
+    /// Returns the delay before the `attempt`th retry, starting at 1.
+    fn delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .policy
+            .base_delay_ms
+            .saturating_mul(2u64.saturating_pow(attempt - 1))
+            .min(self.policy.max_delay_ms);
+        let jitter = (delay as f64 * self.policy.jitter) as u64;
+        let delay = if jitter > 0 {
+            delay - jitter + rand::thread_rng().gen_range(0..=2 * jitter)
+        } else {
+            delay
+        };
+
+        Duration::from_millis(delay)
+    }
+
+    /// Retries `f` while the retry policy allows it, a retry that can't start before the deadline
+    /// of the context or after its cancellation returns the last error instead.
+    async fn retry<T, F, Fut>(&self, ctx: &CallContext, operation: &str, f: F) -> Result<T, DAError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, DAError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(error) if attempt < self.policy.max_attempts && self.should_retry(&error) => {
+                    let mut delay = self.delay(attempt);
+                    if let DAError::RateLimited {
+                        retry_after: Some(retry_after),
+                        ..
+                    } = &error
+                    {
+                        delay = delay.max(*retry_after);
+                    }
+                    if ctx.is_cancelled() || ctx.remaining().is_some_and(|left| left <= delay) {
+                        return Err(error);
+                    }
+                    tracing::warn!(
+                        "{} {} failed with {} (attempt {}/{}), retrying in {:?}: {}",
+                        self.backend.as_str(),
+                        operation,
+                        error.code(),
+                        attempt,
+                        self.policy.max_attempts,
+                        delay,
+                        error
+                    );
+                    DA_METRICS.retries[&self.backend.as_str()].inc();
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DataAvailabilityClient for RetryingClient {
+    async fn dispatch_blob(
+        &self,
+        ctx: &CallContext,
+        batch_number: u32,
+        data: Vec<u8>,
+    ) -> Result<DispatchResponse, DAError> {
+        self.retry(ctx, "dispatch", || {
+            self.inner.dispatch_blob(ctx, batch_number, data.clone())
+        })
+        .await
+    }
+
+    async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        self.retry(ctx, "inclusion query", || {
+            self.inner.get_inclusion_data(ctx, blob_id)
+        })
+        .await
+    }
+
+    async fn get_inclusion_range(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        range: Range<u64>,
+    ) -> Result<Option<InclusionRange>, DAError> {
+        self.retry(ctx, "inclusion query", || {
+            self.inner.get_inclusion_range(ctx, blob_id, range.clone())
+        })
+        .await
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+        Box::new(self.clone())
+    }
+
+    fn blob_size_limit(&self) -> Option<usize> {
+        self.inner.blob_size_limit()
+    }
+
+    fn estimate_gas(&self, blob_size: usize) -> Option<u64> {
+        self.inner.estimate_gas(blob_size)
+    }
+
+    async fn ping(&self) -> anyhow::Result<bool> {
+        self.inner.ping().await
+    }
+
+    async fn confirmations(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<u64>, DAError> {
+        self.inner.confirmations(ctx, blob_id).await
+    }
+
+    async fn head_height(&self) -> Result<Option<u64>, DAError> {
+        self.inner.head_height().await
+    }
+
+    async fn is_synced(&self) -> Result<Option<bool>, DAError> {
+        self.inner.is_synced().await
+    }
+
+    async fn get_inclusion_proof(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        self.retry(ctx, "inclusion proof", || {
+            self.inner.get_inclusion_proof(ctx, blob_id)
+        })
+        .await
+    }
+
+    async fn data_root(&self, ctx: &CallContext, height: u64) -> Result<String, DAError> {
+        self.retry(ctx, "data root query", || self.inner.data_root(ctx, height))
+            .await
+    }
+
+    async fn scan_blobs(
+        &self,
+        ctx: &CallContext,
+        height: u64,
+    ) -> Result<Vec<ScannedBlob>, DAError> {
+        self.retry(ctx, "blob scan", || self.inner.scan_blobs(ctx, height))
+            .await
+    }
+
+    async fn earliest_available_height(&self) -> Result<Option<u64>, DAError> {
+        self.inner.earliest_available_height().await
+    }
+
+    fn retention(&self) -> Option<Duration> {
+        self.inner.retention()
+    }
+
+    async fn get_chunk_ids(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        self.inner.get_chunk_ids(ctx, blob_id).await
+    }
+
+    async fn get_chunk(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        chunk_ids: &[String],
+        index: usize,
+    ) -> Result<Vec<u8>, DAError> {
+        self.inner.get_chunk(ctx, blob_id, chunk_ids, index).await
+    }
+
+    async fn expiry_for(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DAError> {
+        self.inner.expiry_for(ctx, blob_id).await
+    }
+
+    fn namespaced(
+        &self,
+        namespace: &str,
+    ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        Ok(Arc::new(Self {
+            inner: self.inner.namespaced(namespace)?,
+            ..self.clone()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    /// Fails the dispatches until `failures` are consumed.
+    #[derive(Debug)]
+    struct FlakyClient {
+        failures: AtomicU32,
+        calls: AtomicU32,
+        is_retriable: bool,
+    }
+
+    #[async_trait]
+    impl DataAvailabilityClient for FlakyClient {
+        async fn dispatch_blob(
+            &self,
+            _: &CallContext,
+            _: u32,
+            _: Vec<u8>,
+        ) -> Result<DispatchResponse, DAError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(if self.is_retriable {
+                    DAError::ConnectionError {
+                        message: "flaky".to_string(),
+                    }
+                } else {
+                    DAError::SubmitRejected {
+                        reason: "flaky".to_string(),
+                    }
+                });
+            }
+            Ok(DispatchResponse::from("blob".to_string()))
+        }
+
+        async fn get_inclusion_data(
+            &self,
+            _: &CallContext,
+            _: &str,
+        ) -> Result<Option<InclusionData>, DAError> {
+            Ok(None)
+        }
+
+        fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+            Box::new(FlakyClient {
+                failures: AtomicU32::new(self.failures.load(Ordering::SeqCst)),
+                calls: AtomicU32::new(self.calls.load(Ordering::SeqCst)),
+                is_retriable: self.is_retriable,
+            })
+        }
+
+        fn blob_size_limit(&self) -> Option<usize> {
+            None
+        }
+
+        async fn ping(&self) -> anyhow::Result<bool> {
+            Ok(true)
+        }
+
+        fn namespaced(
+            &self,
+            namespace: &str,
+        ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+            anyhow::bail!("The flaky client has no namespace {}", namespace)
+        }
+    }
+
+    fn new_client(failures: u32, is_retriable: bool) -> (Arc<FlakyClient>, RetryingClient) {
+        new_client_with_delay(failures, is_retriable, 1)
+    }
+
+    fn new_client_with_delay(
+        failures: u32,
+        is_retriable: bool,
+        delay_ms: u64,
+    ) -> (Arc<FlakyClient>, RetryingClient) {
+        let inner = Arc::new(FlakyClient {
+            failures: AtomicU32::new(failures),
+            calls: AtomicU32::new(0),
+            is_retriable,
+        });
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            base_delay_ms: delay_ms,
+            max_delay_ms: 2 * delay_ms,
+            jitter: 0.0,
+            retry_on: RetryOn::Retriable,
+        };
+        (
+            inner.clone(),
+            RetryingClient::new(DaBackend::InMemory, inner, policy),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_retriable_errors_are_retried() {
+        let (inner, client) = new_client(2, true);
+        assert!(
+            client
+                .dispatch_blob(&CallContext::background(), 1, vec![])
+                .await
+                .is_ok()
+        );
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+
+        let (inner, client) = new_client(3, true);
+        assert!(
+            client
+                .dispatch_blob(&CallContext::background(), 1, vec![])
+                .await
+                .is_err()
+        );
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_fatal_errors_are_not_retried() {
+        let (inner, client) = new_client(1, false);
+        assert!(
+            client
+                .dispatch_blob(&CallContext::background(), 1, vec![])
+                .await
+                .is_err()
+        );
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_at_the_deadline() {
+        let (inner, client) = new_client_with_delay(2, true, 1_000);
+        let ctx = CallContext::with_timeout(Duration::from_millis(500));
+        let err = client.dispatch_blob(&ctx, 1, vec![]).await.unwrap_err();
+        assert!(matches!(err, DAError::ConnectionError { .. }));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+
+        let ctx = CallContext::background();
+        ctx.cancel();
+        let (inner, client) = new_client(2, true);
+        assert!(client.dispatch_blob(&ctx, 1, vec![]).await.is_err());
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,586 @@
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::watch;
+
+use crate::{
+    clients::da_clients::{
+        DataAvailabilityClient,
+        types::{
+            CallContext, DAError, DispatchResponse, InclusionData, InclusionProof, InclusionRange,
+            ScannedBlob,
+        },
+    },
+    config::DaBackend,
+};
+
+type Backend = (DaBackend, Arc<dyn DataAvailabilityClient + Send + Sync>);
+
+/// The longest a switch waits for the in-flight calls of the previous backend.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of in-flight calls of a backend, decremented when their `InFlightCall` drops.
+#[derive(Debug)]
+struct InFlight(watch::Sender<usize>);
+
+impl Default for InFlight {
+    fn default() -> Self {
+        Self(watch::Sender::new(0))
+    }
+}
+
+struct InFlightCall<'a>(&'a InFlight);
+
+impl Drop for InFlightCall<'_> {
+    fn drop(&mut self) {
+        self.0.0.send_modify(|calls| *calls -= 1);
+    }
+}
+
+/// An implementation of the `DataAvailabilityClient` trait that delegates to one of several
+/// pre-configured backends, the active backend can be switched at runtime.
+///
+/// The calls read the active backend without holding a lock while they run, the calls started
+/// after a switch go to the new backend right away. The switch waits for the in-flight calls of
+/// the previous backend to drain, up to the drain timeout. The namespaced clients share the active
+/// backend and the in-flight calls with their parent.
+#[derive(Clone, Debug)]
+pub struct SwitchableClient {
+    active: Arc<Mutex<DaBackend>>,
+    backends: Vec<Backend>,
+    /// The in-flight calls of each backend, in the order of `backends`.
+    in_flight: Arc<Vec<InFlight>>,
+    drain_timeout: Duration,
+}
+
+impl SwitchableClient {
+    /// Creates the client, the first backend is active.
+    pub fn new(backends: Vec<Backend>) -> anyhow::Result<Self> {
+        let (active, _) = backends
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("At least one DA backend is required"))?;
+
+        Ok(Self {
+            active: Arc::new(Mutex::new(*active)),
+            in_flight: Arc::new(backends.iter().map(|_| InFlight::default()).collect()),
+            backends,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        })
+    }
+
+    /// Sets the longest a switch waits for the in-flight calls of the previous backend.
+    pub fn with_drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    fn position(&self, backend: DaBackend) -> usize {
+        self.backends
+            .iter()
+            .position(|(candidate, _)| *candidate == backend)
+            .expect("The active backend is always configured")
+    }
+
+    fn client(&self, backend: DaBackend) -> &Arc<dyn DataAvailabilityClient + Send + Sync> {
+        &self.backends[self.position(backend)].1
+    }
+
+    /// Returns the active backend and its client, the call is in flight until the returned
+    /// `InFlightCall` drops.
+    fn start_call(
+        &self,
+    ) -> (
+        DaBackend,
+        &Arc<dyn DataAvailabilityClient + Send + Sync>,
+        InFlightCall<'_>,
+    ) {
+        let active = self.active_backend();
+        let position = self.position(active);
+        let in_flight = &self.in_flight[position];
+        in_flight.0.send_modify(|calls| *calls += 1);
+
+        (active, &self.backends[position].1, InFlightCall(in_flight))
+    }
+
+    /// Returns the active backend.
+    pub fn active_backend(&self) -> DaBackend {
+        *self.active.lock().unwrap()
+    }
+
+    /// Returns all the configured backends.
+    pub fn backends(&self) -> Vec<DaBackend> {
+        self.backends.iter().map(|(backend, _)| *backend).collect()
+    }
+
+    /// Returns the client of a configured backend, active or not.
+    pub fn backend(
+        &self,
+        backend: DaBackend,
+    ) -> Option<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        self.backends
+            .iter()
+            .find(|(candidate, _)| *candidate == backend)
+            .map(|(_, client)| client.clone())
+    }
+
+    /// Switches the active backend, then waits for the in-flight calls of the previous one to
+    /// drain up to the drain timeout. Returns the previous backend.
+    pub async fn switch_to(&self, backend: DaBackend) -> anyhow::Result<DaBackend> {
+        if !self.backends().contains(&backend) {
+            anyhow::bail!("DA backend {:?} is not configured", backend);
+        }
+
+        let previous = std::mem::replace(&mut *self.active.lock().unwrap(), backend);
+        tracing::info!("Switched DA backend from {:?} to {:?}", previous, backend);
+        if previous == backend {
+            return Ok(previous);
+        }
+
+        let mut calls = self.in_flight[self.position(previous)].0.subscribe();
+        let drained = tokio::time::timeout(self.drain_timeout, calls.wait_for(|calls| *calls == 0));
+        if drained.await.is_err() {
+            tracing::warn!(
+                "{} calls to {:?} are still in flight after {:?}",
+                *calls.borrow(),
+                previous,
+                self.drain_timeout
+            );
+        }
+
+        Ok(previous)
+    }
+}
+
+#[async_trait]
+impl DataAvailabilityClient for SwitchableClient {
+    async fn dispatch_blob(
+        &self,
+        ctx: &CallContext,
+        batch_number: u32,
+        data: Vec<u8>,
+    ) -> Result<DispatchResponse, DAError> {
+        let (_, client, _call) = self.start_call();
+        client.dispatch_blob(ctx, batch_number, data).await
+    }
+
+    /// Reads from the active backend first, then from the standby backends so the blobs
+    /// dispatched before a switch stay readable.
+    async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        let (active, client, _call) = self.start_call();
+        let result = client.get_inclusion_data(ctx, blob_id).await;
+        if let Ok(Some(_)) = result {
+            return result;
+        }
+
+        for (backend, client) in &self.backends {
+            if *backend == active {
+                continue;
+            }
+            if let Ok(Some(data)) = client.get_inclusion_data(ctx, blob_id).await {
+                return Ok(Some(data));
+            }
+        }
+
+        result
+    }
+
+    /// Falls back to the standby backends like `get_inclusion_data`.
+    async fn get_inclusion_range(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        range: Range<u64>,
+    ) -> Result<Option<InclusionRange>, DAError> {
+        let (active, client, _call) = self.start_call();
+        let result = client
+            .get_inclusion_range(ctx, blob_id, range.clone())
+            .await;
+        if let Ok(Some(_)) = result {
+            return result;
+        }
+
+        for (backend, client) in &self.backends {
+            if *backend == active {
+                continue;
+            }
+            if let Ok(Some(data)) = client
+                .get_inclusion_range(ctx, blob_id, range.clone())
+                .await
+            {
+                return Ok(Some(data));
+            }
+        }
+
+        result
+    }
+
+    fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+        Box::new(self.clone())
+    }
+
+    fn blob_size_limit(&self) -> Option<usize> {
+        self.client(self.active_backend()).blob_size_limit()
+    }
+
+    fn estimate_gas(&self, blob_size: usize) -> Option<u64> {
+        self.client(self.active_backend()).estimate_gas(blob_size)
+    }
+
+    async fn ping(&self) -> anyhow::Result<bool> {
+        let (_, client, _call) = self.start_call();
+        client.ping().await
+    }
+
+    /// Like `get_inclusion_data`, falls back to the standby backends when the active one doesn't
+    /// know the blob.
+    async fn confirmations(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<u64>, DAError> {
+        let (active, client, _call) = self.start_call();
+        let result = client.confirmations(ctx, blob_id).await;
+        if let Ok(Some(_)) = result {
+            return result;
+        }
+
+        for (backend, client) in &self.backends {
+            if *backend == active {
+                continue;
+            }
+            if let Ok(Some(confirmations)) = client.confirmations(ctx, blob_id).await {
+                return Ok(Some(confirmations));
+            }
+        }
+
+        result
+    }
+
+    async fn head_height(&self) -> Result<Option<u64>, DAError> {
+        let (_, client, _call) = self.start_call();
+        client.head_height().await
+    }
+
+    async fn earliest_available_height(&self) -> Result<Option<u64>, DAError> {
+        let (_, client, _call) = self.start_call();
+        client.earliest_available_height().await
+    }
+
+    /// Like `get_inclusion_data`, falls back to the standby backends when the active one doesn't
+    /// know the blob.
+    async fn get_chunk_ids(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        let (active, client, _call) = self.start_call();
+        let result = client.get_chunk_ids(ctx, blob_id).await;
+        if let Ok(Some(_)) = result {
+            return result;
+        }
+
+        for (backend, client) in &self.backends {
+            if *backend == active {
+                continue;
+            }
+            if let Ok(Some(chunk_ids)) = client.get_chunk_ids(ctx, blob_id).await {
+                return Ok(Some(chunk_ids));
+            }
+        }
+
+        result
+    }
+
+    /// The chunks are read from the backend knowing their chunked blob.
+    async fn get_chunk(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+        chunk_ids: &[String],
+        index: usize,
+    ) -> Result<Vec<u8>, DAError> {
+        let (active, client, _call) = self.start_call();
+        let result = client.get_chunk(ctx, blob_id, chunk_ids, index).await;
+        if result.is_ok() {
+            return result;
+        }
+
+        for (backend, client) in &self.backends {
+            if *backend == active {
+                continue;
+            }
+            if let Ok(chunk) = client.get_chunk(ctx, blob_id, chunk_ids, index).await {
+                return Ok(chunk);
+            }
+        }
+
+        result
+    }
+
+    fn retention(&self) -> Option<Duration> {
+        self.client(self.active_backend()).retention()
+    }
+
+    /// Falls back to the standby backends when the blob_id is not one of the active backend.
+    async fn expiry_for(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DAError> {
+        let (active, client, _call) = self.start_call();
+        let result = client.expiry_for(ctx, blob_id).await;
+        if !matches!(result, Err(DAError::InvalidBlobId { .. })) {
+            return result;
+        }
+
+        for (backend, client) in &self.backends {
+            if *backend == active {
+                continue;
+            }
+            if let Ok(expiry) = client.expiry_for(ctx, blob_id).await {
+                return Ok(expiry);
+            }
+        }
+
+        result
+    }
+
+    async fn is_synced(&self) -> Result<Option<bool>, DAError> {
+        let (_, client, _call) = self.start_call();
+        client.is_synced().await
+    }
+
+    async fn get_inclusion_proof(
+        &self,
+        ctx: &CallContext,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        let (_, client, _call) = self.start_call();
+        client.get_inclusion_proof(ctx, blob_id).await
+    }
+
+    async fn data_root(&self, ctx: &CallContext, height: u64) -> Result<String, DAError> {
+        let (_, client, _call) = self.start_call();
+        client.data_root(ctx, height).await
+    }
+
+    async fn scan_blobs(
+        &self,
+        ctx: &CallContext,
+        height: u64,
+    ) -> Result<Vec<ScannedBlob>, DAError> {
+        let (_, client, _call) = self.start_call();
+        client.scan_blobs(ctx, height).await
+    }
+
+    fn namespaced(
+        &self,
+        namespace: &str,
+    ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        let backends = self
+            .backends
+            .iter()
+            .map(|(backend, client)| Ok((*backend, client.namespaced(namespace)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Arc::new(Self {
+            active: self.active.clone(),
+            backends,
+            in_flight: self.in_flight.clone(),
+            drain_timeout: self.drain_timeout,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::da_clients::in_memory::InMemoryClient;
+
+    fn ctx() -> CallContext {
+        CallContext::background()
+    }
+
+    /// A backend whose dispatches never complete, like a DA node hanging during an outage.
+    #[derive(Debug, Clone)]
+    struct HangingClient;
+
+    #[async_trait]
+    impl DataAvailabilityClient for HangingClient {
+        async fn dispatch_blob(
+            &self,
+            _: &CallContext,
+            _: u32,
+            _: Vec<u8>,
+        ) -> Result<DispatchResponse, DAError> {
+            std::future::pending().await
+        }
+
+        async fn get_inclusion_data(
+            &self,
+            _: &CallContext,
+            _: &str,
+        ) -> Result<Option<InclusionData>, DAError> {
+            Ok(None)
+        }
+
+        fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+            Box::new(self.clone())
+        }
+
+        fn blob_size_limit(&self) -> Option<usize> {
+            None
+        }
+
+        async fn ping(&self) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+
+        fn namespaced(
+            &self,
+            _: &str,
+        ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+            Ok(Arc::new(self.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_switch_keeps_previous_blobs_readable() {
+        let first = Arc::new(InMemoryClient::new(1024));
+        let second = Arc::new(InMemoryClient::new(1024));
+        let client = SwitchableClient::new(vec![
+            (DaBackend::Celestia, first.clone()),
+            (DaBackend::InMemory, second.clone()),
+        ])
+        .unwrap();
+
+        let before = client
+            .dispatch_blob(&ctx(), 1, b"before".to_vec())
+            .await
+            .unwrap();
+        assert!(
+            first
+                .get_inclusion_data(&ctx(), &before.blob_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let previous = client.switch_to(DaBackend::InMemory).await.unwrap();
+        assert_eq!(previous, DaBackend::Celestia);
+        assert_eq!(client.active_backend(), DaBackend::InMemory);
+
+        let after = client
+            .dispatch_blob(&ctx(), 2, b"after".to_vec())
+            .await
+            .unwrap();
+        assert!(
+            second
+                .get_inclusion_data(&ctx(), &after.blob_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            first
+                .get_inclusion_data(&ctx(), &after.blob_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let data = client
+            .get_inclusion_data(&ctx(), &before.blob_id)
+            .await
+            .unwrap();
+        assert_eq!(data.unwrap().data, b"before".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_switch_does_not_wait_for_hung_calls_beyond_the_drain_timeout() {
+        let client = SwitchableClient::new(vec![
+            (DaBackend::Celestia, Arc::new(HangingClient)),
+            (DaBackend::InMemory, Arc::new(InMemoryClient::new(1024))),
+        ])
+        .unwrap()
+        .with_drain_timeout(Duration::from_millis(200));
+
+        let hung = tokio::spawn({
+            let client = client.clone();
+            async move { client.dispatch_blob(&ctx(), 1, b"hung".to_vec()).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let switch = tokio::spawn({
+            let client = client.clone();
+            async move { client.switch_to(DaBackend::InMemory).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // The calls started during the drain are served by the new backend right away.
+        assert_eq!(client.active_backend(), DaBackend::InMemory);
+        let served = tokio::time::timeout(Duration::from_millis(100), async {
+            assert!(client.ping().await.unwrap());
+            client.dispatch_blob(&ctx(), 2, b"after".to_vec()).await
+        })
+        .await;
+        assert!(served.unwrap().is_ok());
+        assert!(!switch.is_finished());
+
+        let switched = tokio::time::timeout(Duration::from_secs(1), switch).await;
+        assert_eq!(switched.unwrap().unwrap().unwrap(), DaBackend::Celestia);
+        hung.abort();
+    }
+
+    #[tokio::test]
+    async fn test_switch_to_unknown_backend_fails() {
+        let client = SwitchableClient::new(vec![(
+            DaBackend::InMemory,
+            Arc::new(InMemoryClient::new(1)),
+        )])
+        .unwrap();
+
+        assert!(client.switch_to(DaBackend::Celestia).await.is_err());
+        assert_eq!(client.active_backend(), DaBackend::InMemory);
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_clients_follow_the_switch() {
+        let client = SwitchableClient::new(vec![
+            (DaBackend::Celestia, Arc::new(InMemoryClient::new(1024))),
+            (DaBackend::InMemory, Arc::new(InMemoryClient::new(1024))),
+        ])
+        .unwrap();
+        let tenant = client.namespaced("tenant").unwrap();
+
+        client.switch_to(DaBackend::InMemory).await.unwrap();
+        let blob_id = tenant
+            .dispatch_blob(&ctx(), 1, b"tenant".to_vec())
+            .await
+            .unwrap()
+            .blob_id;
+
+        assert!(
+            tenant
+                .get_inclusion_data(&ctx(), &blob_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            client
+                .get_inclusion_data(&ctx(), &blob_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
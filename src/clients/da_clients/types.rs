@@ -1,43 +1,195 @@
-use std::{error, fmt::Display};
+use std::{future::Future, ops::Range, time::Duration};
 
 use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use tokio_util::sync::{CancellationToken, DropGuard};
 
-/// `DAError` is the error type returned by the DA clients.
-#[derive(Debug)]
-pub struct DAError {
-    pub error: anyhow::Error,
-    pub is_retriable: bool,
+use crate::types::{
+    attestation::Attestation,
+    dispatch::{DispatchReceipt, PartialDispatchReport},
+};
+
+/// `DAError` is the error type returned by the DA clients, each variant is a machine-readable
+/// class of failure and tells whether the call can be retried.
+#[derive(Debug, thiserror::Error)]
+pub enum DAError {
+    /// The DA node is unreachable, the call timed out or the connection was lost.
+    #[error("Connection to the DA layer failed: {message}")]
+    ConnectionError { message: String },
+    /// The DA node throttles the calls.
+    #[error("Rate limited by the DA layer: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The DA node accepted the blob but failed to submit it, e.g. an out of funds wallet.
+    #[error("Failed to submit the blob: {reason}")]
+    SubmitFailed { reason: String },
+    /// The blob can't be submitted as is.
+    #[error("Blob rejected by the DA layer: {reason}")]
+    SubmitRejected { reason: String },
+    #[error("Blob of {size} bytes exceeds the limit of {limit} bytes")]
+    BlobTooLarge { size: usize, limit: usize },
+    #[error("Blob {blob_id} not found")]
+    NotFound { blob_id: String },
+    /// The blob is older than the retention of the DA node and no archival node is configured.
+    #[error(
+        "Blob {blob_id} at height {height} is pruned, the earliest available height is {earliest_available_height}"
+    )]
+    Pruned {
+        blob_id: String,
+        height: u64,
+        earliest_available_height: u64,
+    },
+    /// The stored blob is inconsistent, e.g. a chunked blob with missing chunks.
+    #[error("Integrity mismatch for blob {blob_id}: {reason}")]
+    IntegrityMismatch { blob_id: String, reason: String },
+    #[error("Invalid blob_id {blob_id}: {reason}")]
+    InvalidBlobId { blob_id: String, reason: String },
+    /// The deadline of the request the call serves passed.
+    #[error("The DA call exceeded the deadline of the request")]
+    DeadlineExceeded,
+    /// The request the call serves was cancelled, e.g. the caller disconnected.
+    #[error("The DA call was cancelled with its request")]
+    Cancelled,
+    /// The backend doesn't support the operation, e.g. the inclusion proofs of the in-memory one.
+    #[error("The DA backend doesn't support {operation}")]
+    Unsupported { operation: &'static str },
+    /// Some chunks of a chunked blob were dispatched before the others failed.
+    #[error(
+        "Dispatched {} of the {} chunks of the blob",
+        .0.succeeded.len(),
+        .0.chunks
+    )]
+    PartialDispatch(Box<PartialDispatchReport>),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
 }
 
 impl DAError {
     pub fn is_retriable(&self) -> bool {
-        self.is_retriable
+        match self {
+            Self::ConnectionError { .. } | Self::RateLimited { .. } | Self::SubmitFailed { .. } => {
+                true
+            }
+            Self::PartialDispatch(report) => report.retriable,
+            _ => false,
+        }
+    }
+
+    /// Returns the machine-readable code of the error class.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ConnectionError { .. } => "DA_CONNECTION_ERROR",
+            Self::RateLimited { .. } => "DA_RATE_LIMITED",
+            Self::SubmitFailed { .. } => "DA_SUBMIT_FAILED",
+            Self::SubmitRejected { .. } => "DA_SUBMIT_REJECTED",
+            Self::BlobTooLarge { .. } => "DA_BLOB_TOO_LARGE",
+            Self::NotFound { .. } => "DA_NOT_FOUND",
+            Self::Pruned { .. } => "DA_PRUNED",
+            Self::IntegrityMismatch { .. } => "DA_INTEGRITY_MISMATCH",
+            Self::InvalidBlobId { .. } => "DA_INVALID_BLOB_ID",
+            Self::DeadlineExceeded => "DA_DEADLINE_EXCEEDED",
+            Self::Cancelled => "DA_CANCELLED",
+            Self::Unsupported { .. } => "DA_UNSUPPORTED",
+            Self::PartialDispatch(_) => "DA_PARTIAL_DISPATCH",
+            Self::Internal(_) => "DA_INTERNAL_ERROR",
+        }
     }
 }
 
-impl Display for DAError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let kind = if self.is_retriable {
-            "retriable"
-        } else {
-            "fatal"
+/// `CallContext` carries the deadline and the cancellation of the request a DA call serves, the
+/// clients abandon the call once the deadline passed or the request is cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CallContext {
+    deadline: Option<Instant>,
+    cancellation: CancellationToken,
+}
+
+impl CallContext {
+    /// The context of the background calls, without deadline and never cancelled.
+    pub fn background() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            deadline: Some(Instant::now() + timeout),
+            ..Self::default()
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The time left before the deadline, None without deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Returns a guard cancelling the context once dropped.
+    pub fn cancel_on_drop(&self) -> DropGuard {
+        self.cancellation.clone().drop_guard()
+    }
+
+    /// Runs a DA call until it completes, the deadline passes or the context is cancelled.
+    pub async fn run<T>(
+        &self,
+        call: impl Future<Output = Result<T, DAError>>,
+    ) -> Result<T, DAError> {
+        let deadline = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
         };
-        write!(f, "{kind} data availability client error: {}", self.error)
+
+        tokio::select! {
+            biased;
+            _ = self.cancellation.cancelled() => Err(DAError::Cancelled),
+            _ = deadline => Err(DAError::DeadlineExceeded),
+            result = call => result,
+        }
     }
 }
 
-impl error::Error for DAError {}
-
 /// `DispatchResponse` is the response received from the DA layer after dispatching a blob.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DispatchResponse {
     /// The blob_id is needed to fetch the inclusion data.
     pub blob_id: String,
+    /// The signed attestation of the dispatch, set when attestations are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Attestation>,
+    /// The submission metadata, set by the clients and returned from API version 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<DispatchReceipt>,
 }
 
 impl From<String> for DispatchResponse {
     fn from(blob_id: String) -> Self {
-        DispatchResponse { blob_id }
+        DispatchResponse {
+            blob_id,
+            attestation: None,
+            receipt: None,
+        }
+    }
+}
+
+impl DispatchResponse {
+    pub fn with_receipt(mut self, receipt: DispatchReceipt) -> Self {
+        self.receipt = Some(receipt);
+        self
     }
 }
 
@@ -49,6 +201,85 @@ pub struct InclusionData {
     pub data: Vec<u8>,
 }
 
+/// `ScannedBlob` is a blob found on the DA layer, whatever submitted it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedBlob {
+    /// The blob_id the blob is read with, as if this service had dispatched it.
+    pub blob_id: String,
+    pub data: Vec<u8>,
+    pub receipt: DispatchReceipt,
+}
+
+/// `InclusionProof` proves that a blob is included in the block at `height`, the proofs of the
+/// shares of the blob lead to the data root of the block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InclusionProof {
+    pub blob_id: String,
+    pub height: u64,
+    /// The hex data root of the block, the proof is only valid against this root.
+    pub data_root: String,
+    /// The hex namespace of the blob.
+    pub namespace: String,
+    /// The hex commitment of the blob.
+    pub commitment: String,
+    /// The namespace proofs of the rows spanned by the blob, as serialized by the DA node.
+    pub proofs: serde_json::Value,
+}
+
+/// `InclusionRange` is a byte range of the payload of a blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionRange {
+    /// The bytes of the payload within the range.
+    pub data: Vec<u8>,
+    /// The payload size, None when the chunks after the range were not fetched.
+    pub size: Option<u64>,
+}
+
+impl InclusionRange {
+    /// Returns the range of a whole payload.
+    pub fn slice(data: &[u8], range: Range<u64>) -> Self {
+        let size = data.len() as u64;
+        let start = range.start.min(size);
+        let end = range.end.clamp(start, size);
+        Self {
+            data: data[start as usize..end as usize].to_vec(),
+            size: Some(size),
+        }
+    }
+}
+
+/// Reads `range` of the payload of a chunked blob, the chunks after the end of the range are not
+/// fetched.
+pub async fn read_chunk_range<'a, F, Fut>(
+    chunk_ids: &'a [String],
+    range: Range<u64>,
+    mut fetch_chunk: F,
+) -> Result<InclusionRange, DAError>
+where
+    F: FnMut(usize, &'a str) -> Fut,
+    Fut: Future<Output = Result<Vec<u8>, DAError>>,
+{
+    let mut data = vec![];
+    let mut offset = 0u64;
+
+    for (index, chunk_id) in chunk_ids.iter().enumerate() {
+        if offset >= range.end {
+            return Ok(InclusionRange { data, size: None });
+        }
+
+        let chunk = fetch_chunk(index, chunk_id).await?;
+        let end = offset + chunk.len() as u64;
+        let (from, to) = (range.start.clamp(offset, end), range.end.clamp(offset, end));
+        data.extend_from_slice(&chunk[(from - offset) as usize..(to - offset) as usize]);
+        offset = end;
+    }
+
+    Ok(InclusionRange {
+        data,
+        size: Some(offset),
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ViaDaBlob {
     pub chunks: usize,
@@ -67,6 +298,27 @@ impl ViaDaBlob {
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
         bincode::deserialize(bytes).ok()
     }
+
+    /// Returns the blob_ids of the chunks of a chunked blob.
+    pub fn chunk_ids(&self, blob_id: &str) -> Result<Vec<String>, DAError> {
+        let chunk_ids =
+            deserialize_blob_ids(&self.data).map_err(|_| DAError::IntegrityMismatch {
+                blob_id: blob_id.to_string(),
+                reason: "Failed to deserialize blob ids".to_string(),
+            })?;
+        if chunk_ids.len() != self.chunks {
+            return Err(DAError::IntegrityMismatch {
+                blob_id: blob_id.to_string(),
+                reason: format!(
+                    "Mismatch, blob ids len [{}] != chunk size [{}]",
+                    chunk_ids.len(),
+                    self.chunks
+                ),
+            });
+        }
+
+        Ok(chunk_ids)
+    }
 }
 
 pub fn serialize_blob_ids(hex_vec: &[String]) -> anyhow::Result<Vec<u8>> {
@@ -84,18 +336,26 @@ pub fn serialize_blob_ids(hex_vec: &[String]) -> anyhow::Result<Vec<u8>> {
     Ok(result)
 }
 
+/// Parses the blob_ids serialized by `serialize_blob_ids`, the data can come from any blob of the
+/// namespace and a truncated one is an error.
 pub fn deserialize_blob_ids(data: &[u8]) -> anyhow::Result<Vec<String>> {
     let mut pos = 0;
     let mut result = Vec::new();
 
     while pos < data.len() {
         // Read the 4-byte length prefix
-        let len_bytes: [u8; 4] = data[pos..pos + 4].try_into()?;
+        let len_bytes: [u8; 4] = data
+            .get(pos..pos + 4)
+            .ok_or_else(|| anyhow::anyhow!("Truncated length prefix at {}", pos))?
+            .try_into()?;
         let len = u32::from_be_bytes(len_bytes) as usize;
         pos += 4;
 
         // Extract the chunk
-        let chunk = &data[pos..pos + len];
+        let chunk = pos
+            .checked_add(len)
+            .and_then(|end| data.get(pos..end))
+            .ok_or_else(|| anyhow::anyhow!("Truncated blob_id of {} bytes at {}", len, pos))?;
         pos += len;
 
         result.push(hex::encode(chunk));
@@ -103,3 +363,20 @@ pub fn deserialize_blob_ids(data: &[u8]) -> anyhow::Result<Vec<String>> {
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncated_blob_ids_are_rejected() {
+        let ids = vec!["abcd".to_string(), "ef".to_string()];
+        let data = serialize_blob_ids(&ids).unwrap();
+        assert_eq!(deserialize_blob_ids(&data).unwrap(), ids);
+
+        for len in [2, 5, data.len() - 1] {
+            assert!(deserialize_blob_ids(&data[..len]).is_err());
+        }
+        assert!(deserialize_blob_ids(&[0xff; 6]).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    fmt::{Debug, Formatter},
+    time::Duration,
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::clients::data_root_sources::DataRootSource;
+
+/// The timeout of the requests to the source.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+struct DataRootResponse {
+    data_root: String,
+}
+
+/// A data root source served over HTTP by a light client of the L1 contract, e.g. a Blobstream
+/// relayer. `GET <url>/data_root/<height>` returns `{"data_root": "<hex>"}` for the committed
+/// blocks and 404 for the blocks not committed yet.
+#[derive(Clone)]
+pub struct HttpDataRootSource {
+    http: reqwest::Client,
+    url: String,
+    token: Option<String>,
+}
+
+impl Debug for HttpDataRootSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HttpDataRootSource")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+impl HttpDataRootSource {
+    /// Creates the source, `token` is sent as a bearer token when set.
+    pub fn new(url: String, token: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+}
+
+#[async_trait]
+impl DataRootSource for HttpDataRootSource {
+    async fn data_root(&self, height: u64) -> anyhow::Result<Option<String>> {
+        let mut request = self
+            .http
+            .get(format!("{}/data_root/{}", self.url, height))
+            .timeout(REQUEST_TIMEOUT);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Data root request for height {height} failed"))?;
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Data root request for height {height} failed with {status}: {body}");
+        }
+
+        let data_root = response.json::<DataRootResponse>().await?.data_root;
+        Ok(Some(
+            data_root.trim_start_matches("0x").to_ascii_lowercase(),
+        ))
+    }
+}
@@ -0,0 +1,27 @@
+pub mod http;
+
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{clients::data_root_sources::http::HttpDataRootSource, config::Config};
+
+/// Creates the source of the data roots committed on L1, None when the blobs are not verified
+/// against L1.
+pub fn make_data_root_source(config: &Config) -> Option<Arc<dyn DataRootSource>> {
+    let url = config.data_root_source_url.as_ref()?;
+    Some(Arc::new(HttpDataRootSource::new(
+        url.clone(),
+        config.data_root_source_token.clone(),
+    )))
+}
+
+/// Trait that defines the interface for the sources of the Celestia data roots committed on L1,
+/// e.g. the Blobstream contract read through a light client. A blob is provable on L1 once the
+/// data root of its block is committed.
+#[async_trait]
+pub trait DataRootSource: Sync + Send + fmt::Debug {
+    /// Returns the hex data root committed on L1 for the Celestia block at `height`, None while
+    /// the block is not committed yet.
+    async fn data_root(&self, height: u64) -> anyhow::Result<Option<String>>;
+}
@@ -0,0 +1,322 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use ed25519_dalek::Signer as _;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    clients::key_providers::{EncryptionKey, KeyProvider, PublicKey, Signature},
+    config::{AttestationKeyType, Config},
+};
+
+/// A private key used to sign dispatch attestations.
+#[derive(Clone)]
+pub enum LocalSigningKey {
+    Ed25519(ed25519_dalek::SigningKey),
+    Secp256k1(k256::ecdsa::SigningKey),
+}
+
+impl LocalSigningKey {
+    /// Parses a hex encoded 32 bytes private key.
+    pub fn from_hex(key_type: AttestationKeyType, private_key: &str) -> anyhow::Result<Self> {
+        let bytes = parse_hex_key(private_key).context("Invalid attestation private key")?;
+
+        Ok(match key_type {
+            AttestationKeyType::Ed25519 => {
+                Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(&bytes))
+            }
+            AttestationKeyType::Secp256k1 => Self::Secp256k1(
+                k256::ecdsa::SigningKey::from_bytes(&bytes.into())
+                    .context("Invalid secp256k1 private key")?,
+            ),
+        })
+    }
+
+    pub fn key_type(&self) -> AttestationKeyType {
+        match self {
+            Self::Ed25519(_) => AttestationKeyType::Ed25519,
+            Self::Secp256k1(_) => AttestationKeyType::Secp256k1,
+        }
+    }
+
+    /// Returns the public key, compressed SEC1 for secp256k1.
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            Self::Ed25519(key) => key.verifying_key().to_bytes().to_vec(),
+            Self::Secp256k1(key) => key
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec(),
+        }
+    }
+
+    /// Derives a key id from the public key, stable across restarts.
+    pub fn default_key_id(&self) -> String {
+        hex::encode(&Sha256::digest(self.public_key())[..8])
+    }
+
+    /// Signs the message, secp256k1 signatures are 64 bytes `r | s` over the SHA-256 of the message.
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Ed25519(key) => key.sign(message).to_bytes().to_vec(),
+            Self::Secp256k1(key) => {
+                let signature: k256::ecdsa::Signature = key.sign(message);
+                signature.to_bytes().to_vec()
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LocalSigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalSigningKey")
+            .field("key_type", &self.key_type())
+            .field("public_key", &hex::encode(self.public_key()))
+            .finish()
+    }
+}
+
+/// A signing key of the rotation schedule.
+#[derive(Debug, Clone)]
+struct ScheduledKey {
+    key_id: String,
+    key: LocalSigningKey,
+    /// The unix timestamp (seconds) the key signs from.
+    active_from: u64,
+}
+
+/// A key provider holding the keys in memory, loaded from the env or from files.
+///
+/// The signing keys follow a rotation schedule: the key activated last signs the attestations, the
+/// others stay published so the attestations they signed can still be verified.
+#[derive(Debug, Clone, Default)]
+pub struct LocalKeyProvider {
+    /// The signing keys, by activation time.
+    signing_keys: Vec<ScheduledKey>,
+    encryption_keys: HashMap<String, [u8; 32]>,
+    active_encryption_key_id: Option<String>,
+}
+
+impl LocalKeyProvider {
+    pub fn new(
+        signing_key: Option<(String, LocalSigningKey)>,
+        encryption_keys: Vec<(String, [u8; 32])>,
+    ) -> Self {
+        Self {
+            signing_keys: vec![],
+            active_encryption_key_id: encryption_keys.first().map(|(key_id, _)| key_id.clone()),
+            encryption_keys: encryption_keys.into_iter().collect(),
+        }
+        .with_signing_keys(
+            signing_key
+                .into_iter()
+                .map(|(key_id, key)| (key_id, key, 0))
+                .collect(),
+        )
+    }
+
+    /// Adds signing keys to the rotation schedule, each signing from its activation timestamp.
+    pub fn with_signing_keys(mut self, keys: Vec<(String, LocalSigningKey, u64)>) -> Self {
+        self.signing_keys.extend(
+            keys.into_iter()
+                .map(|(key_id, key, active_from)| ScheduledKey {
+                    key_id,
+                    key,
+                    active_from,
+                }),
+        );
+        self.signing_keys.sort_by_key(|key| key.active_from);
+        self
+    }
+
+    /// Returns the key signing at `now`, the last one activated.
+    fn active_key_at(&self, now: u64) -> Option<&ScheduledKey> {
+        self.signing_keys
+            .iter()
+            .rev()
+            .find(|key| key.active_from <= now)
+    }
+
+    fn public_keys_at(&self, now: u64) -> Vec<PublicKey> {
+        let active_key_id = self.active_key_at(now).map(|key| key.key_id.as_str());
+        self.signing_keys
+            .iter()
+            .map(|key| PublicKey {
+                key_id: key.key_id.clone(),
+                key_type: key.key.key_type(),
+                public_key: key.key.public_key(),
+                active_from: Some(key.active_from),
+                active: Some(key.key_id.as_str()) == active_key_id,
+            })
+            .collect()
+    }
+
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let signing_key = config
+            .attestation_private_key
+            .as_deref()
+            .map(|key| LocalSigningKey::from_hex(config.attestation_key_type, key))
+            .transpose()?
+            .map(|key| {
+                let key_id = config
+                    .attestation_key_id
+                    .clone()
+                    .unwrap_or_else(|| key.default_key_id());
+                (key_id, key)
+            });
+        let signing_keys = config
+            .attestation_keys
+            .iter()
+            .map(|(key_id, key, active_from)| {
+                let key = LocalSigningKey::from_hex(config.attestation_key_type, key)
+                    .with_context(|| format!("Invalid attestation key [{key_id}]"))?;
+                Ok((key_id.clone(), key, *active_from))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let encryption_keys = config
+            .encryption_keys
+            .iter()
+            .map(|(key_id, key)| {
+                let key = parse_hex_key(key)
+                    .with_context(|| format!("Invalid encryption key [{key_id}]"))?;
+                Ok((key_id.clone(), key))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self::new(signing_key, encryption_keys).with_signing_keys(signing_keys))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[async_trait]
+impl KeyProvider for LocalKeyProvider {
+    fn can_sign(&self) -> bool {
+        !self.signing_keys.is_empty()
+    }
+
+    async fn sign(&self, message: &[u8]) -> anyhow::Result<Signature> {
+        if self.signing_keys.is_empty() {
+            anyhow::bail!("No signing key configured");
+        }
+        let ScheduledKey { key_id, key, .. } = self
+            .active_key_at(now())
+            .ok_or_else(|| anyhow!("No signing key is active yet"))?;
+
+        Ok(Signature {
+            key_id: key_id.clone(),
+            key_type: key.key_type(),
+            public_key: key.public_key(),
+            signature: key.sign(message),
+        })
+    }
+
+    async fn public_keys(&self) -> anyhow::Result<Vec<PublicKey>> {
+        Ok(self.public_keys_at(now()))
+    }
+
+    async fn encryption_key(&self) -> anyhow::Result<EncryptionKey> {
+        let key_id = self
+            .active_encryption_key_id
+            .as_deref()
+            .ok_or_else(|| anyhow!("No encryption key configured"))?;
+
+        self.encryption_key_by_id(key_id).await
+    }
+
+    async fn encryption_key_by_id(&self, key_id: &str) -> anyhow::Result<EncryptionKey> {
+        let key = self
+            .encryption_keys
+            .get(key_id)
+            .ok_or_else(|| anyhow!("Unknown encryption key [{key_id}]"))?;
+
+        Ok(EncryptionKey {
+            key_id: key_id.to_string(),
+            key: *key,
+        })
+    }
+}
+
+fn parse_hex_key(key: &str) -> anyhow::Result<[u8; 32]> {
+    hex::decode(key.trim().trim_start_matches("0x"))
+        .context("Key must be a hex string")?
+        .try_into()
+        .map_err(|_| anyhow!("Key must be 32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIVATE_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[tokio::test]
+    async fn test_sign_with_key_id() {
+        let key = LocalSigningKey::from_hex(AttestationKeyType::Ed25519, PRIVATE_KEY).unwrap();
+        let provider = LocalKeyProvider::new(Some(("key-1".to_string(), key.clone())), vec![]);
+
+        let signature = provider.sign(b"message").await.unwrap();
+
+        assert_eq!(signature.key_id, "key-1");
+        assert_eq!(signature.public_key, key.public_key());
+        assert_eq!(signature.signature, key.sign(b"message"));
+    }
+
+    #[test]
+    fn test_signing_keys_rotate_on_schedule() {
+        let key = |byte: u8| {
+            LocalSigningKey::from_hex(AttestationKeyType::Ed25519, &hex::encode([byte; 32]))
+                .unwrap()
+        };
+        let provider = LocalKeyProvider::default().with_signing_keys(vec![
+            ("next".to_string(), key(2), 200),
+            ("first".to_string(), key(1), 100),
+        ]);
+
+        assert!(provider.active_key_at(50).is_none());
+        assert_eq!(provider.active_key_at(150).unwrap().key_id, "first");
+        assert_eq!(provider.active_key_at(200).unwrap().key_id, "next");
+
+        // The rotated out key stays published.
+        let public_keys = provider.public_keys_at(250);
+        assert_eq!(public_keys.len(), 2);
+        assert_eq!(public_keys[0].key_id, "first");
+        assert!(!public_keys[0].active);
+        assert_eq!(public_keys[0].public_key, key(1).public_key());
+        assert!(public_keys[1].active);
+    }
+
+    #[tokio::test]
+    async fn test_rotated_encryption_keys_stay_resolvable() {
+        let provider = LocalKeyProvider::new(
+            None,
+            vec![
+                ("new".to_string(), [2u8; 32]),
+                ("old".to_string(), [1u8; 32]),
+            ],
+        );
+
+        let active = provider.encryption_key().await.unwrap();
+        assert_eq!(active.key_id, "new");
+        assert_eq!(active.key, [2u8; 32]);
+
+        let old = provider.encryption_key_by_id("old").await.unwrap();
+        assert_eq!(old.key, [1u8; 32]);
+
+        assert!(provider.encryption_key_by_id("unknown").await.is_err());
+        assert!(!provider.can_sign());
+        assert!(provider.sign(b"message").await.is_err());
+    }
+}
@@ -0,0 +1,76 @@
+pub mod local;
+pub mod vault;
+
+use std::{fmt, sync::Arc};
+
+use async_trait::async_trait;
+
+use crate::{
+    clients::key_providers::{local::LocalKeyProvider, vault::VaultKeyProvider},
+    config::{AttestationKeyType, Config, KeyProviderBackend},
+};
+
+/// `Signature` is a signature produced by a key provider, with the key needed to verify it.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    /// The id of the key that produced the signature.
+    pub key_id: String,
+    pub key_type: AttestationKeyType,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// `PublicKey` is a published signing key, the attestations it signed stay verifiable once it is
+/// rotated out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub key_id: String,
+    pub key_type: AttestationKeyType,
+    pub public_key: Vec<u8>,
+    /// The unix timestamp (seconds) the key signs from, if known.
+    pub active_from: Option<u64>,
+    /// Whether the key signs the new attestations.
+    pub active: bool,
+}
+
+/// `EncryptionKey` is a 256 bits data encryption key.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    /// The id to record in the blob envelope, used to resolve the key again on retrieval.
+    pub key_id: String,
+    pub key: [u8; 32],
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+pub fn make_key_provider(config: &Config) -> anyhow::Result<Arc<dyn KeyProvider>> {
+    match config.key_provider {
+        KeyProviderBackend::Local => Ok(Arc::new(LocalKeyProvider::from_config(config)?)),
+        KeyProviderBackend::Vault => Ok(Arc::new(VaultKeyProvider::from_config(config)?)),
+    }
+}
+
+/// Trait that defines the interface for the providers of the signing and encryption keys.
+#[async_trait]
+pub trait KeyProvider: Sync + Send + fmt::Debug {
+    /// Returns true when a signing key is configured.
+    fn can_sign(&self) -> bool;
+
+    /// Signs the message with the active signing key.
+    async fn sign(&self, message: &[u8]) -> anyhow::Result<Signature>;
+
+    /// Returns the public keys of the active, scheduled and rotated out signing keys.
+    async fn public_keys(&self) -> anyhow::Result<Vec<PublicKey>>;
+
+    /// Returns the active encryption key, used for new blobs.
+    async fn encryption_key(&self) -> anyhow::Result<EncryptionKey>;
+
+    /// Resolves the encryption key recorded in a blob envelope, including rotated out keys.
+    async fn encryption_key_by_id(&self, key_id: &str) -> anyhow::Result<EncryptionKey>;
+}
@@ -0,0 +1,293 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::DateTime;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    clients::key_providers::{EncryptionKey, KeyProvider, PublicKey, Signature},
+    config::{AttestationKeyType, Config},
+};
+
+/// Prefix of the ciphertexts and signatures returned by the Vault transit engine.
+const VAULT_PREFIX: &str = "vault:v";
+
+#[derive(Deserialize)]
+struct VaultResponse<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct SignData {
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct KeyData {
+    keys: HashMap<String, KeyVersion>,
+    latest_version: u64,
+}
+
+#[derive(Deserialize)]
+struct KeyVersion {
+    public_key: String,
+    /// The RFC 3339 creation time of the version.
+    creation_time: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DataKeyData {
+    plaintext: String,
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct DecryptData {
+    plaintext: String,
+}
+
+/// A key provider backed by the HashiCorp Vault transit secrets engine.
+///
+/// Attestations are signed by an `ed25519` transit key, the key id is `<key name>:v<version>` so
+/// rotating the key in Vault is reflected in new attestations. Encryption uses envelope keys: each
+/// data key is generated by Vault and its wrapped ciphertext is the key id, so any blob can be
+/// decrypted as long as the transit key version used to wrap it is not deleted.
+#[derive(Clone)]
+pub struct VaultKeyProvider {
+    http: reqwest::Client,
+    address: String,
+    token: String,
+    mount: String,
+    signing_key: Option<String>,
+    encryption_key: Option<String>,
+    public_keys: Arc<RwLock<HashMap<u64, Vec<u8>>>>,
+}
+
+impl VaultKeyProvider {
+    pub fn new(
+        address: String,
+        token: String,
+        mount: String,
+        signing_key: Option<String>,
+        encryption_key: Option<String>,
+    ) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            address: address.trim_end_matches('/').to_string(),
+            token,
+            mount,
+            signing_key,
+            encryption_key,
+            public_keys: Arc::default(),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        if config.attestation_key_type != AttestationKeyType::Ed25519 {
+            anyhow::bail!("Vault key provider only supports ed25519 attestation keys");
+        }
+
+        Ok(Self::new(
+            config
+                .vault_address
+                .clone()
+                .ok_or_else(|| anyhow!("VAULT_ADDRESS is required for the Vault key provider"))?,
+            config
+                .vault_token
+                .clone()
+                .ok_or_else(|| anyhow!("VAULT_TOKEN is required for the Vault key provider"))?,
+            config.vault_transit_mount.clone(),
+            config.vault_signing_key.clone(),
+            config.vault_encryption_key.clone(),
+        ))
+    }
+
+    async fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> anyhow::Result<T> {
+        let url = format!("{}/v1/{}/{}", self.address, self.mount, path);
+        let mut request = self
+            .http
+            .request(method, &url)
+            .header("X-Vault-Token", &self.token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Vault request to {path} failed"))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Vault request to {path} failed with {status}: {body}");
+        }
+
+        Ok(response.json::<VaultResponse<T>>().await?.data)
+    }
+
+    async fn public_key(&self, key_name: &str, version: u64) -> anyhow::Result<Vec<u8>> {
+        if let Some(public_key) = self.public_keys.read().unwrap().get(&version) {
+            return Ok(public_key.clone());
+        }
+
+        self.load_key(key_name).await?;
+        self.public_keys
+            .read()
+            .unwrap()
+            .get(&version)
+            .cloned()
+            .ok_or_else(|| anyhow!("Vault key {key_name} has no version {version}"))
+    }
+
+    /// Reads the versions of a transit key, their public keys are cached.
+    async fn load_key(&self, key_name: &str) -> anyhow::Result<KeyData> {
+        let data: KeyData = self
+            .call(reqwest::Method::GET, &format!("keys/{key_name}"), None)
+            .await?;
+        let mut public_keys = self.public_keys.write().unwrap();
+        for (key_version, key) in &data.keys {
+            public_keys.insert(key_version.parse()?, BASE64.decode(&key.public_key)?);
+        }
+        Ok(data)
+    }
+}
+
+/// Splits a `vault:v<version>:<base64>` value into the version and the decoded payload.
+fn parse_vault_value(value: &str) -> anyhow::Result<(u64, Vec<u8>)> {
+    let (version, payload) = value
+        .strip_prefix(VAULT_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .ok_or_else(|| anyhow!("Unexpected Vault value format"))?;
+
+    Ok((version.parse()?, BASE64.decode(payload)?))
+}
+
+#[async_trait]
+impl KeyProvider for VaultKeyProvider {
+    fn can_sign(&self) -> bool {
+        self.signing_key.is_some()
+    }
+
+    async fn sign(&self, message: &[u8]) -> anyhow::Result<Signature> {
+        let key_name = self
+            .signing_key
+            .as_deref()
+            .ok_or_else(|| anyhow!("No Vault signing key configured"))?;
+
+        let data: SignData = self
+            .call(
+                reqwest::Method::POST,
+                &format!("sign/{key_name}"),
+                Some(json!({ "input": BASE64.encode(message) })),
+            )
+            .await?;
+        let (version, signature) = parse_vault_value(&data.signature)?;
+
+        Ok(Signature {
+            key_id: format!("{key_name}:v{version}"),
+            key_type: AttestationKeyType::Ed25519,
+            public_key: self.public_key(key_name, version).await?,
+            signature,
+        })
+    }
+
+    /// The versions of the transit key, rotated per its Vault rotation period. The latest version
+    /// signs the attestations.
+    async fn public_keys(&self) -> anyhow::Result<Vec<PublicKey>> {
+        let Some(key_name) = self.signing_key.as_deref() else {
+            return Ok(vec![]);
+        };
+
+        let data = self.load_key(key_name).await?;
+        let mut public_keys = data
+            .keys
+            .into_iter()
+            .map(|(key_version, key)| {
+                let version: u64 = key_version.parse()?;
+                let public_key = PublicKey {
+                    key_id: format!("{key_name}:v{version}"),
+                    key_type: AttestationKeyType::Ed25519,
+                    public_key: BASE64.decode(key.public_key)?,
+                    active_from: key
+                        .creation_time
+                        .as_deref()
+                        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                        .and_then(|time| u64::try_from(time.timestamp()).ok()),
+                    active: version == data.latest_version,
+                };
+                Ok((version, public_key))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        public_keys.sort_by_key(|(version, _)| *version);
+        Ok(public_keys.into_iter().map(|(_, key)| key).collect())
+    }
+
+    async fn encryption_key(&self) -> anyhow::Result<EncryptionKey> {
+        let key_name = self
+            .encryption_key
+            .as_deref()
+            .ok_or_else(|| anyhow!("No Vault encryption key configured"))?;
+
+        let data: DataKeyData = self
+            .call(
+                reqwest::Method::POST,
+                &format!("datakey/plaintext/{key_name}"),
+                Some(json!({ "bits": 256 })),
+            )
+            .await?;
+
+        Ok(EncryptionKey {
+            key_id: data.ciphertext,
+            key: BASE64
+                .decode(data.plaintext)?
+                .try_into()
+                .map_err(|_| anyhow!("Vault data key must be 32 bytes"))?,
+        })
+    }
+
+    async fn encryption_key_by_id(&self, key_id: &str) -> anyhow::Result<EncryptionKey> {
+        let key_name = self
+            .encryption_key
+            .as_deref()
+            .ok_or_else(|| anyhow!("No Vault encryption key configured"))?;
+
+        let data: DecryptData = self
+            .call(
+                reqwest::Method::POST,
+                &format!("decrypt/{key_name}"),
+                Some(json!({ "ciphertext": key_id })),
+            )
+            .await?;
+
+        Ok(EncryptionKey {
+            key_id: key_id.to_string(),
+            key: BASE64
+                .decode(data.plaintext)?
+                .try_into()
+                .map_err(|_| anyhow!("Vault data key must be 32 bytes"))?,
+        })
+    }
+}
+
+impl Debug for VaultKeyProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultKeyProvider")
+            .field("address", &self.address)
+            .field("mount", &self.mount)
+            .field("signing_key", &self.signing_key)
+            .field("encryption_key", &self.encryption_key)
+            .finish()
+    }
+}
@@ -0,0 +1,40 @@
+pub mod redis;
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{clients::lock_stores::redis::RedisLockStore, config::Config};
+
+/// Creates the lock store shared by the replicas, None when the dispatches are not coordinated.
+pub fn make_lock_store(config: &Config) -> anyhow::Result<Option<Arc<dyn LockStore>>> {
+    match &config.batch_lock_url {
+        Some(url) => Ok(Some(Arc::new(RedisLockStore::new(url)?))),
+        None => Ok(None),
+    }
+}
+
+/// Trait that defines the interface for the stores of the locks shared by the replicas. The keys
+/// expire after their ttl, so a crashed replica can't hold a lock forever.
+#[async_trait]
+pub trait LockStore: Sync + Send + fmt::Debug {
+    /// Sets the key to the value unless it is set, returns the current value otherwise.
+    async fn set_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<Option<String>>;
+
+    /// Sets the key to the value if it is `expected`, returns false otherwise.
+    async fn replace(
+        &self,
+        key: &str,
+        expected: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<bool>;
+
+    /// Deletes the key if it is `expected`, returns false otherwise.
+    async fn delete(&self, key: &str, expected: &str) -> anyhow::Result<bool>;
+}
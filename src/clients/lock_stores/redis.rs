@@ -0,0 +1,247 @@
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream},
+    net::TcpStream,
+    sync::Mutex,
+};
+use url::Url;
+
+use crate::clients::lock_stores::LockStore;
+
+/// The calls to Redis fail after this timeout, connecting included.
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+const REPLACE_SCRIPT: &str = "if redis.call('GET', KEYS[1]) == ARGV[1] then \
+     redis.call('SET', KEYS[1], ARGV[2], 'PX', ARGV[3]) return 1 else return 0 end";
+
+const DELETE_SCRIPT: &str = "if redis.call('GET', KEYS[1]) == ARGV[1] then \
+     return redis.call('DEL', KEYS[1]) else return 0 end";
+
+/// A reply of Redis, the errors are returned as `Err`.
+#[derive(Debug, PartialEq, Eq)]
+enum Reply {
+    Simple(String),
+    Integer(i64),
+    Bulk(Option<String>),
+}
+
+/// Sends a command and reads its reply.
+async fn call(stream: &mut BufStream<TcpStream>, args: &[&str]) -> anyhow::Result<Reply> {
+    let mut command = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        command.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        command.extend_from_slice(arg.as_bytes());
+        command.extend_from_slice(b"\r\n");
+    }
+    stream.write_all(&command).await?;
+    stream.flush().await?;
+
+    let mut line = String::new();
+    stream.read_line(&mut line).await?;
+    let line = line.trim_end_matches("\r\n");
+    let (kind, value) = line
+        .split_at_checked(1)
+        .ok_or_else(|| anyhow!("Redis closed the connection"))?;
+    match kind {
+        "+" => Ok(Reply::Simple(value.to_string())),
+        "-" => Err(anyhow!("Redis error: {}", value)),
+        ":" => Ok(Reply::Integer(value.parse()?)),
+        "$" => {
+            let Ok(len) = usize::try_from(value.parse::<i64>()?) else {
+                return Ok(Reply::Bulk(None));
+            };
+            let mut data = vec![0; len + 2];
+            stream.read_exact(&mut data).await?;
+            data.truncate(len);
+            Ok(Reply::Bulk(Some(String::from_utf8(data)?)))
+        }
+        _ => Err(anyhow!("Unexpected Redis reply {}", line)),
+    }
+}
+
+/// A lock store backed by Redis 7 or later, over a single connection opened again after a failed
+/// call.
+#[derive(Clone)]
+pub struct RedisLockStore {
+    address: String,
+    username: Option<String>,
+    password: Option<String>,
+    db: Option<u32>,
+    connection: Arc<Mutex<Option<BufStream<TcpStream>>>>,
+}
+
+impl Debug for RedisLockStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisLockStore")
+            .field("address", &self.address)
+            .field("db", &self.db)
+            .finish()
+    }
+}
+
+impl RedisLockStore {
+    /// Creates the store of `redis://[[username]:password@]host[:port][/db]`, connecting on the
+    /// first call.
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        let url = Url::parse(url).context("Invalid Redis url")?;
+        if url.scheme() != "redis" {
+            anyhow::bail!("Unsupported Redis url scheme {}", url.scheme());
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("The Redis url has no host"))?;
+        let db = match url.path().trim_start_matches('/') {
+            "" => None,
+            db => Some(db.parse().context("Invalid Redis database")?),
+        };
+
+        Ok(Self {
+            address: format!("{}:{}", host, url.port().unwrap_or(6379)),
+            username: Some(url.username())
+                .filter(|username| !username.is_empty())
+                .map(str::to_string),
+            password: url.password().map(str::to_string),
+            db,
+            connection: Arc::default(),
+        })
+    }
+
+    async fn connect(&self) -> anyhow::Result<BufStream<TcpStream>> {
+        let mut stream = BufStream::new(TcpStream::connect(&self.address).await?);
+        match (&self.username, &self.password) {
+            (Some(username), Some(password)) => {
+                call(&mut stream, &["AUTH", username, password]).await?;
+            }
+            (None, Some(password)) => {
+                call(&mut stream, &["AUTH", password]).await?;
+            }
+            _ => {}
+        }
+        if let Some(db) = self.db {
+            call(&mut stream, &["SELECT", &db.to_string()]).await?;
+        }
+        Ok(stream)
+    }
+
+    async fn command(&self, args: &[&str]) -> anyhow::Result<Reply> {
+        let mut connection = self.connection.lock().await;
+        let result = tokio::time::timeout(CALL_TIMEOUT, async {
+            let stream = match &mut *connection {
+                Some(stream) => stream,
+                None => connection.insert(self.connect().await?),
+            };
+            call(stream, args).await
+        })
+        .await
+        .unwrap_or_else(|_| Err(anyhow!("Redis call timed out")));
+
+        // The connection may be left mid-reply.
+        if result.is_err() {
+            *connection = None;
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl LockStore for RedisLockStore {
+    async fn set_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<Option<String>> {
+        let ttl = ttl.as_millis().to_string();
+        match self
+            .command(&["SET", key, value, "NX", "GET", "PX", &ttl])
+            .await?
+        {
+            Reply::Bulk(current) => Ok(current),
+            reply => Err(anyhow!("Unexpected reply to SET: {:?}", reply)),
+        }
+    }
+
+    async fn replace(
+        &self,
+        key: &str,
+        expected: &str,
+        value: &str,
+        ttl: Duration,
+    ) -> anyhow::Result<bool> {
+        let ttl = ttl.as_millis().to_string();
+        match self
+            .command(&["EVAL", REPLACE_SCRIPT, "1", key, expected, value, &ttl])
+            .await?
+        {
+            Reply::Integer(replaced) => Ok(replaced == 1),
+            reply => Err(anyhow!("Unexpected reply to EVAL: {:?}", reply)),
+        }
+    }
+
+    async fn delete(&self, key: &str, expected: &str) -> anyhow::Result<bool> {
+        match self
+            .command(&["EVAL", DELETE_SCRIPT, "1", key, expected])
+            .await?
+        {
+            Reply::Integer(deleted) => Ok(deleted == 1),
+            reply => Err(anyhow!("Unexpected reply to EVAL: {:?}", reply)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_commands_are_sent_as_resp_arrays() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let store = RedisLockStore::new(&format!(
+            "redis://:secret@{}/2",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufStream::new(stream);
+            let mut received = vec![];
+            for reply in ["+OK\r\n", "+OK\r\n", "$-1\r\n", "$7\r\npending\r\n"] {
+                let mut command = String::new();
+                stream.read_line(&mut command).await.unwrap();
+                let args: usize = command[1..].trim().parse().unwrap();
+                let mut words = vec![];
+                for _ in 0..args * 2 {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    words.push(line.trim_end().to_string());
+                }
+                received.push(words.into_iter().skip(1).step_by(2).collect::<Vec<_>>());
+                stream.write_all(reply.as_bytes()).await.unwrap();
+                stream.flush().await.unwrap();
+            }
+            received
+        });
+
+        let ttl = Duration::from_secs(1);
+        assert_eq!(store.set_if_absent("k", "v", ttl).await.unwrap(), None);
+        assert_eq!(
+            store.set_if_absent("k", "v", ttl).await.unwrap(),
+            Some("pending".to_string())
+        );
+
+        let received = server.await.unwrap();
+        assert_eq!(received[0], ["AUTH", "secret"]);
+        assert_eq!(received[1], ["SELECT", "2"]);
+        assert_eq!(received[2], ["SET", "k", "v", "NX", "GET", "PX", "1000"]);
+    }
+}
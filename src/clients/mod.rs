@@ -1 +1,4 @@
 pub mod da_clients;
+pub mod data_root_sources;
+pub mod key_providers;
+pub mod lock_stores;
@@ -1,19 +1,195 @@
-use serde::Deserialize;
-use std::env;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, env, path::PathBuf, time::Duration};
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+use crate::{
+    clients::da_clients::celestia::DEFAULT_SAMPLING_WINDOW, middlewares::ip_allowlist::parse_cidrs,
+    types::routing::NamespaceRoute,
+};
+
+/// The callers allowed to call the /admin routes without `VIA_ADMIN_ALLOWED_CIDRS`.
+const DEFAULT_ADMIN_ALLOWED_CIDRS: &str = "127.0.0.0/8,::1/128";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum DaBackend {
     Celestia,
+    #[default]
     InMemory,
 }
 
-impl Default for DaBackend {
-    fn default() -> Self {
-        DaBackend::InMemory
+impl DaBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DaBackend::Celestia => "celestia",
+            DaBackend::InMemory => "inmemory",
+        }
+    }
+
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "celestia" => Ok(DaBackend::Celestia),
+            "inmemory" | "" => Ok(DaBackend::InMemory),
+            other => anyhow::bail!("Invalid DA_BACKEND value: {}", other),
+        }
     }
 }
 
+/// The DA errors retried by a retry policy.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryOn {
+    /// The errors flagged as retriable by the client.
+    #[default]
+    Retriable,
+    All,
+    None,
+}
+
+/// The retry policy of the DA calls of a backend.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled on every retry.
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// The random variation of the delays, as a ratio of the delay (0 to 1).
+    pub jitter: f64,
+    pub retry_on: RetryOn,
+}
+
+impl RetryPolicy {
+    /// The default policy of a backend, Celestia submissions can take a few blocks to go through.
+    pub fn default_for(backend: DaBackend) -> Self {
+        match backend {
+            DaBackend::Celestia => Self {
+                max_attempts: 5,
+                base_delay_ms: 2_000,
+                max_delay_ms: 30_000,
+                jitter: 0.2,
+                retry_on: RetryOn::Retriable,
+            },
+            DaBackend::InMemory => Self {
+                max_attempts: 1,
+                base_delay_ms: 0,
+                max_delay_ms: 0,
+                jitter: 0.0,
+                retry_on: RetryOn::Retriable,
+            },
+        }
+    }
+
+    /// Reads the `VIA_RETRY_<BACKEND>_*` overrides of the default policy of the backend.
+    fn from_env(backend: DaBackend) -> anyhow::Result<Self> {
+        let prefix = format!("VIA_RETRY_{}", backend.as_str().to_uppercase());
+        let var = |name: &str| env::var(format!("{prefix}_{name}")).ok();
+        let mut policy = Self::default_for(backend);
+
+        if let Some(value) = var("MAX_ATTEMPTS") {
+            policy.max_attempts = value.parse::<u32>()?.max(1);
+        }
+        if let Some(value) = var("BASE_DELAY_MS") {
+            policy.base_delay_ms = value.parse()?;
+        }
+        if let Some(value) = var("MAX_DELAY_MS") {
+            policy.max_delay_ms = value.parse()?;
+        }
+        if let Some(value) = var("JITTER") {
+            policy.jitter = value.parse::<f64>()?.clamp(0.0, 1.0);
+        }
+        if let Some(value) = var("RETRY_ON") {
+            policy.retry_on = match value.to_lowercase().as_str() {
+                "retriable" => RetryOn::Retriable,
+                "all" => RetryOn::All,
+                "none" => RetryOn::None,
+                other => anyhow::bail!("Invalid {prefix}_RETRY_ON value: {}", other),
+            };
+        }
+
+        Ok(policy)
+    }
+}
+
+/// A step of the pipeline transforming the payloads before their dispatch.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadTransform {
+    Compress,
+    /// Encrypts with the active encryption key of the key provider.
+    Encrypt,
+    /// Splits the payloads larger than the chunk size into chunked blobs.
+    Chunk,
+}
+
+impl PayloadTransform {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "compress" => Ok(PayloadTransform::Compress),
+            "encrypt" => Ok(PayloadTransform::Encrypt),
+            "chunk" => Ok(PayloadTransform::Chunk),
+            other => anyhow::bail!("Invalid VIA_PAYLOAD_TRANSFORMS value: {}", other),
+        }
+    }
+}
+
+/// The commitment of the blob_ids on the backends without native commitments.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitmentScheme {
+    #[default]
+    Sha256,
+    /// The Celestia share commitment of the blob in the namespace of the client, the blob_ids
+    /// commit to the data as they would on Celestia.
+    Share,
+}
+
+impl CommitmentScheme {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "sha256" | "" => Ok(CommitmentScheme::Sha256),
+            "share" => Ok(CommitmentScheme::Share),
+            other => anyhow::bail!("Invalid VIA_DA_CLIENT_COMMITMENT value: {}", other),
+        }
+    }
+}
+
+/// The format of the log lines.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "text" | "" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => anyhow::bail!("Invalid VIA_LOG_FORMAT value: {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AttestationKeyType {
+    #[default]
+    Ed25519,
+    Secp256k1,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyProviderBackend {
+    #[default]
+    Local,
+    Vault,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     /// The app port
@@ -31,17 +207,259 @@ pub struct Config {
     /// The DA backend
     pub da_backend: DaBackend,
 
+    /// The DA backends the active backend can be switched to at runtime
+    pub da_standby_backends: Vec<DaBackend>,
+
+    /// The share of the dispatches submitted to the shadow backend or namespace as well, in
+    /// percent, disabled when 0
+    pub shadow_percent: u8,
+
+    /// The backend of the shadow dispatches, the DA backend when not set
+    pub shadow_backend: Option<DaBackend>,
+
+    /// The namespace of the shadow dispatches
+    pub shadow_namespace: Option<String>,
+
     /// The DA client node url
     pub da_node_url: Option<String>,
 
     /// The DA client auth token
     pub da_auth_token: Option<String>,
 
-    /// The DA blob size limit
+    /// The DA blob size limit, until it is discovered from the network parameters
     pub da_blob_size_limit: usize,
+
+    /// The interval between two queries of the Celestia network parameters, the blob size limit
+    /// is static when not set
+    pub da_params_refresh_interval: Option<Duration>,
+
+    /// The commitment of the blob_ids on the inmemory backend, verified on read
+    pub da_commitment: CommitmentScheme,
+
+    /// The retention of the light node, older blobs are pruned
+    pub da_sampling_window: Duration,
+
+    /// The archival node url, serves the blobs pruned by the light node when set
+    pub da_archival_node_url: Option<String>,
+
+    /// The archival node auth token
+    pub da_archival_auth_token: Option<String>,
+
+    /// The public nodes serving the blobs the light node can't, verified against their blob_id
+    pub da_fallback_node_urls: Vec<String>,
+
+    /// The additional light nodes the blobs are read from, enables the read quorum when set
+    pub da_read_quorum_node_urls: Vec<String>,
+
+    /// The number of light nodes that must agree on a blob, defaults to the majority
+    pub da_read_quorum: Option<usize>,
+
+    /// The maximum number of concurrent dispatches, the others wait in a priority queue
+    pub da_max_concurrent_dispatches: usize,
+
+    /// The interval between two compactions of the dispatch queue, dropping the cancelled and
+    /// the expired waiters
+    pub da_queue_compaction_interval: Duration,
+
+    /// The longest a dispatch waits in the queue for a slot before it fails as rate limited,
+    /// unbounded when not set
+    pub da_queue_max_wait: Option<Duration>,
+
+    /// The longest an inclusion query can wait for its blob with `?wait=`
+    pub inclusion_max_wait: Duration,
+
+    /// The deadline of the DA calls of a request, only the caller sets one when not set
+    pub request_timeout: Option<Duration>,
+
+    /// The transforms applied to the dispatched payloads, in order
+    pub payload_transforms: Vec<PayloadTransform>,
+
+    /// The zstd level of the compress transform
+    pub payload_compression_level: i32,
+
+    /// The chunk size of the chunk transform, the blob size limit of the backend when not set
+    pub payload_chunk_size: Option<usize>,
+
+    /// The retry policies of the configured backends
+    pub da_retry_policies: HashMap<DaBackend, RetryPolicy>,
+
+    /// The provider of the signing and encryption keys
+    pub key_provider: KeyProviderBackend,
+
+    /// The attestation signing key type
+    pub attestation_key_type: AttestationKeyType,
+
+    /// The attestation signing private key (hex) for the local key provider
+    pub attestation_private_key: Option<String>,
+
+    /// The attestation signing key id for the local key provider, derived from the key when not set
+    pub attestation_key_id: Option<String>,
+
+    /// The rotation schedule of the attestation signing keys (key id, hex key, unix timestamp it
+    /// signs from) for the local key provider
+    pub attestation_keys: Vec<(String, String, u64)>,
+
+    /// The blob encryption keys (key id, hex key) for the local key provider, the first is active
+    pub encryption_keys: Vec<(String, String)>,
+
+    /// The Vault server address
+    pub vault_address: Option<String>,
+
+    /// The Vault token
+    pub vault_token: Option<String>,
+
+    /// The Vault transit engine mount path
+    pub vault_transit_mount: String,
+
+    /// The Vault transit key used to sign attestations
+    pub vault_signing_key: Option<String>,
+
+    /// The Vault transit key used to wrap the blob encryption keys
+    pub vault_encryption_key: Option<String>,
+
+    /// The CIDRs allowed to call the /da routes, all callers are allowed when empty
+    pub da_allowed_cidrs: Vec<IpNet>,
+
+    /// The CIDRs allowed to call the /admin routes, the loopback addresses by default
+    pub admin_allowed_cidrs: Vec<IpNet>,
+
+    /// The proxies trusted to set the X-Forwarded-For header
+    pub trusted_proxies: Vec<IpNet>,
+
+    /// The API keys (caller, key) allowed to call the /da routes, authentication is disabled when empty
+    pub api_keys: Vec<(String, String)>,
+
+    /// The secret of the HS256 JWTs accepted along the API keys, the caller is their subject
+    pub jwt_secret: Option<String>,
+
+    /// The monthly cap of dispatched bytes per caller
+    pub usage_monthly_byte_cap: Option<u64>,
+
+    /// The monthly byte caps overriding `usage_monthly_byte_cap` per caller
+    pub usage_caller_byte_caps: Vec<(String, u64)>,
+
+    /// The file the usage is persisted to, kept in memory only when not set
+    pub usage_path: Option<PathBuf>,
+
+    /// How often the usage is written to `usage_path`
+    pub usage_snapshot_interval: Duration,
+
+    /// The fees the dispatches can pay over `fee_budget_window`, in the smallest denom, e.g.
+    /// utia. Unlimited when not set
+    pub fee_budget: Option<u64>,
+
+    /// The rolling window of the fee budget
+    pub fee_budget_window: Duration,
+
+    /// How long the dispatches staged by `POST /da/prepare` can be committed
+    pub staging_ttl: Duration,
+
+    /// The Redis url of the batch locks shared by the replicas, so only one of them submits a
+    /// batch. The dispatches are not coordinated when not set
+    pub batch_lock_url: Option<String>,
+
+    /// How long the lock of a batch is held without being renewed, by a crashed replica
+    pub batch_lock_ttl: Duration,
+
+    /// How long the blob_id of a submitted batch is returned to the dispatches of the batch on the
+    /// other replicas
+    pub batch_lock_retention: Duration,
+
+    /// The callers isolated in their own DA namespace, as (caller, namespace) pairs
+    pub tenant_namespaces: Vec<(String, String)>,
+
+    /// The namespaces of the dispatches of the callers that are not tenants by batch range or
+    /// protocol version, the first matching route applies
+    pub namespace_routes: Vec<NamespaceRoute>,
+
+    /// Whether to run the dispatch roundtrip selftest on startup
+    pub selftest_on_startup: bool,
+
+    /// Whether the admin routes injecting latency and errors into the DA calls are served, for the
+    /// staging environments only
+    pub chaos_enabled: bool,
+
+    /// Whether the instance only serves reads, without the dispatch routes. The index is loaded
+    /// from the file appended by the dispatching instance
+    pub read_only: bool,
+
+    /// The webhooks receiving the dispatch lifecycle events of all the requests
+    pub webhook_urls: Vec<String>,
+
+    /// The secret signing the webhook events, webhooks are disabled when not set
+    pub webhook_secret: Option<String>,
+
+    /// The number of blocks on top of a blob before the confirmed event
+    pub webhook_confirmation_depth: u64,
+
+    /// The maximum number of delivery attempts of a webhook event
+    pub webhook_max_attempts: u32,
+
+    /// The file persisting the index of the dispatched blobs, the index is in memory when not set
+    pub index_path: Option<PathBuf>,
+
+    /// The directory caching the dispatched payloads for re-dispatches, disabled when not set
+    pub payload_cache_dir: Option<PathBuf>,
+
+    /// The directory caching the generated inclusion proofs, generated on every request when not
+    /// set
+    pub proof_cache_dir: Option<PathBuf>,
+
+    /// The interval between two re-verifications of the dispatched blobs, disabled when not set
+    pub verification_interval: Option<Duration>,
+
+    /// The number of dispatched blobs re-verified per run
+    pub verification_sample_size: usize,
+
+    /// The URL of the source of the data roots committed on L1, the blobs are not verified
+    /// against L1 when not set
+    pub data_root_source_url: Option<String>,
+
+    /// The bearer token of the data root source
+    pub data_root_source_token: Option<String>,
+
+    /// The number of blocks on top of a blob before it is confirmed in the index
+    pub confirmation_depth: u64,
+
+    /// The interval between two polls of the chain head tracking the confirmations, disabled when
+    /// not set
+    pub confirmation_poll_interval: Option<Duration>,
+
+    /// The archive mirroring the confirmed Celestia blobs, `s3://bucket/prefix`, `file:///path`
+    /// or a directory, disabled when not set
+    pub archive_url: Option<String>,
+
+    /// The interval between two mirroring runs
+    pub archive_interval: Duration,
+
+    /// The time given to the server and the background components to stop on shutdown
+    pub shutdown_timeout: Duration,
+
+    /// The format of the logs
+    pub log_format: LogFormat,
+
+    /// The initial log level directives, in the `RUST_LOG` syntax
+    pub log_level: String,
+
+    /// The file the logs are written to instead of stdout
+    pub log_file: Option<PathBuf>,
+
+    /// The size of the log file before it is rotated
+    pub log_file_max_bytes: u64,
+
+    /// The number of rotated log files kept
+    pub log_file_max_files: usize,
 }
 
 impl Config {
+    /// Returns the retry policy of the backend.
+    pub fn retry_policy(&self, backend: DaBackend) -> RetryPolicy {
+        self.da_retry_policies
+            .get(&backend)
+            .cloned()
+            .unwrap_or_else(|| RetryPolicy::default_for(backend))
+    }
+
     pub fn from_env() -> anyhow::Result<Self> {
         let port = env::var("PORT")?.parse::<u16>()?;
         let metrics_port = env::var("METRICS_PORT")?.parse::<u16>()?;
@@ -49,17 +467,49 @@ impl Config {
         let metrics_address = format!("0.0.0.0:{}", metrics_port);
 
         // Backend selection with safe default
-        let da_backend = match env::var("VIA_DA_CLIENT_DA_BACKEND")
+        let da_backend =
+            DaBackend::parse(&env::var("VIA_DA_CLIENT_DA_BACKEND").unwrap_or_default())?;
+
+        // Standby backends the active backend can be switched to at runtime
+        let mut da_standby_backends = vec![];
+        for backend in env::var("VIA_DA_CLIENT_STANDBY_BACKENDS")
             .unwrap_or_default()
-            .to_lowercase()
-            .as_str()
+            .split(',')
+            .filter(|value| !value.trim().is_empty())
         {
-            "celestia" => DaBackend::Celestia,
-            "inmemory" | "" => DaBackend::InMemory,
-            other => anyhow::bail!("Invalid DA_BACKEND value: {}", other),
-        };
+            let backend = DaBackend::parse(backend)?;
+            if backend != da_backend && !da_standby_backends.contains(&backend) {
+                da_standby_backends.push(backend);
+            }
+        }
+
+        // Shadow dispatches to a canary backend or namespace
+        let shadow_percent = env::var("VIA_SHADOW_PERCENT")
+            .ok()
+            .map(|v| v.parse::<u8>())
+            .transpose()?
+            .unwrap_or(0);
+        if shadow_percent > 100 {
+            anyhow::bail!("VIA_SHADOW_PERCENT must be between 0 and 100");
+        }
+        let shadow_backend = env::var("VIA_SHADOW_BACKEND")
+            .ok()
+            .map(|v| DaBackend::parse(&v))
+            .transpose()?;
+        let shadow_namespace = env::var("VIA_SHADOW_NAMESPACE")
+            .ok()
+            .filter(|namespace| !namespace.is_empty());
+        if shadow_percent > 0
+            && shadow_backend.is_none_or(|backend| backend == da_backend)
+            && shadow_namespace.is_none()
+        {
+            anyhow::bail!("VIA_SHADOW_PERCENT requires VIA_SHADOW_BACKEND or VIA_SHADOW_NAMESPACE");
+        }
 
-        tracing::info!("Start with DA backend {:?}", da_backend);
+        let da_retry_policies = std::iter::once(da_backend)
+            .chain(da_standby_backends.clone())
+            .map(|backend| Ok((backend, RetryPolicy::from_env(backend)?)))
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
 
         let da_node_url = env::var("VIA_DA_CLIENT_API_NODE_URL").ok();
         let da_auth_token = env::var("VIA_DA_CLIENT_AUTH_TOKEN").ok();
@@ -69,9 +519,108 @@ impl Config {
             .ok()
             .and_then(|v| v.parse::<usize>().ok())
             .unwrap_or(1024 * 1024);
+        let da_commitment =
+            CommitmentScheme::parse(&env::var("VIA_DA_CLIENT_COMMITMENT").unwrap_or_default())?;
+
+        let da_sampling_window = env::var("VIA_DA_CLIENT_SAMPLING_WINDOW_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_SAMPLING_WINDOW);
+        let da_params_refresh_interval = env::var("VIA_DA_CLIENT_PARAMS_REFRESH_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .unwrap_or(600);
+        let da_params_refresh_interval = (da_params_refresh_interval > 0)
+            .then(|| Duration::from_secs(da_params_refresh_interval));
+        let da_archival_node_url = env::var("VIA_DA_CLIENT_ARCHIVAL_NODE_URL").ok();
+        let da_archival_auth_token = env::var("VIA_DA_CLIENT_ARCHIVAL_AUTH_TOKEN").ok();
+        let da_fallback_node_urls = env::var("VIA_DA_CLIENT_FALLBACK_NODE_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let da_read_quorum_node_urls = env::var("VIA_DA_CLIENT_READ_QUORUM_NODE_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        let da_read_quorum = env::var("VIA_DA_CLIENT_READ_QUORUM")
+            .ok()
+            .map(|v| v.parse::<usize>())
+            .transpose()?;
+
+        let da_max_concurrent_dispatches = env::var("VIA_DA_MAX_CONCURRENT_DISPATCHES")
+            .ok()
+            .map(|v| v.parse::<usize>())
+            .transpose()?
+            .unwrap_or(16);
+        let da_queue_compaction_interval = Duration::from_secs(
+            env::var("VIA_DA_QUEUE_COMPACTION_SECS")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(5),
+        );
+        if da_queue_compaction_interval.is_zero() {
+            anyhow::bail!("VIA_DA_QUEUE_COMPACTION_SECS must be positive");
+        }
+        let da_queue_max_wait = env::var("VIA_DA_QUEUE_MAX_WAIT_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+        let inclusion_max_wait = env::var("VIA_INCLUSION_MAX_WAIT_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .map_or(Duration::from_secs(60), Duration::from_secs);
+        let request_timeout = env::var("VIA_REQUEST_TIMEOUT_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+
+        let mut payload_transforms = vec![];
+        for transform in env::var("VIA_PAYLOAD_TRANSFORMS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|value| !value.trim().is_empty())
+        {
+            let transform = PayloadTransform::parse(transform)?;
+            if payload_transforms.contains(&transform) {
+                anyhow::bail!("VIA_PAYLOAD_TRANSFORMS lists {:?} twice", transform);
+            }
+            if payload_transforms.contains(&PayloadTransform::Chunk) {
+                anyhow::bail!("VIA_PAYLOAD_TRANSFORMS must end with chunk");
+            }
+            payload_transforms.push(transform);
+        }
+        let payload_compression_level = env::var("VIA_PAYLOAD_COMPRESSION_LEVEL")
+            .ok()
+            .map(|v| v.parse::<i32>())
+            .transpose()?
+            .unwrap_or(3);
+        let payload_chunk_size = env::var("VIA_PAYLOAD_CHUNK_SIZE")
+            .ok()
+            .map(|v| v.parse::<usize>())
+            .transpose()?
+            .filter(|size| *size > 0);
 
         // Validate required Celestia settings
-        if da_backend == DaBackend::Celestia {
+        if da_backend == DaBackend::Celestia
+            || da_standby_backends.contains(&DaBackend::Celestia)
+            || (shadow_percent > 0 && shadow_backend == Some(DaBackend::Celestia))
+        {
             if da_node_url.is_none() {
                 anyhow::bail!("DA_NODE_URL is required for Celestia backend");
             }
@@ -80,15 +629,403 @@ impl Config {
             }
         }
 
+        let attestation_key_type = match env::var("VIA_ATTESTATION_KEY_TYPE")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "ed25519" | "" => AttestationKeyType::Ed25519,
+            "secp256k1" => AttestationKeyType::Secp256k1,
+            other => anyhow::bail!("Invalid ATTESTATION_KEY_TYPE value: {}", other),
+        };
+
+        let key_provider = match env::var("VIA_KEY_PROVIDER")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "local" | "" => KeyProviderBackend::Local,
+            "vault" => KeyProviderBackend::Vault,
+            other => anyhow::bail!("Invalid KEY_PROVIDER value: {}", other),
+        };
+
+        let attestation_private_key = env_or_file("VIA_ATTESTATION_PRIVATE_KEY")?;
+        let attestation_key_id = env::var("VIA_ATTESTATION_KEY_ID").ok();
+
+        // Signing keys as "key_id:hex_key[:active_from]" separated by commas or new lines
+        let attestation_keys = env_or_file("VIA_ATTESTATION_KEYS")?
+            .unwrap_or_default()
+            .split([',', '\n'])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let mut parts = entry.splitn(3, ':');
+                let (Some(key_id), Some(key)) = (parts.next(), parts.next()) else {
+                    anyhow::bail!(
+                        "Invalid ATTESTATION_KEYS entry, expected key_id:hex_key[:active_from]"
+                    );
+                };
+                let active_from = parts
+                    .next()
+                    .map(|v| v.parse::<u64>())
+                    .transpose()?
+                    .unwrap_or(0);
+                Ok((key_id.to_string(), key.to_string(), active_from))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // Encryption keys as "key_id:hex_key" separated by commas or new lines
+        let encryption_keys = env_or_file("VIA_ENCRYPTION_KEYS")?
+            .unwrap_or_default()
+            .split([',', '\n'])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry
+                    .split_once(':')
+                    .map(|(key_id, key)| (key_id.to_string(), key.to_string()))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Invalid ENCRYPTION_KEYS entry, expected key_id:hex_key")
+                    })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let vault_address = env::var("VIA_VAULT_ADDRESS").ok();
+        let vault_token = env_or_file("VIA_VAULT_TOKEN")?;
+        let vault_transit_mount =
+            env::var("VIA_VAULT_TRANSIT_MOUNT").unwrap_or_else(|_| "transit".to_string());
+        let vault_signing_key = env::var("VIA_VAULT_SIGNING_KEY").ok();
+        let vault_encryption_key = env::var("VIA_VAULT_ENCRYPTION_KEY").ok();
+
+        let da_allowed_cidrs = parse_cidrs(&env::var("VIA_DA_ALLOWED_CIDRS").unwrap_or_default())?;
+        // The admin routes are not authenticated otherwise, they are only open to the local
+        // callers by default and never to everyone.
+        let admin_allowed_cidrs = match env::var("VIA_ADMIN_ALLOWED_CIDRS") {
+            Ok(value) => parse_cidrs(&value)?,
+            Err(_) => parse_cidrs(DEFAULT_ADMIN_ALLOWED_CIDRS)?,
+        };
+        if admin_allowed_cidrs.is_empty() {
+            anyhow::bail!(
+                "VIA_ADMIN_ALLOWED_CIDRS is empty, the admin routes must be restricted to some CIDRs"
+            );
+        }
+        let trusted_proxies = parse_cidrs(&env::var("VIA_TRUSTED_PROXIES").unwrap_or_default())?;
+
+        // API keys as "caller:api_key" separated by commas or new lines
+        let api_keys = parse_pairs(
+            "API_KEYS",
+            &env_or_file("VIA_API_KEYS")?.unwrap_or_default(),
+        )?;
+        let jwt_secret = env_or_file("VIA_JWT_SECRET")?.filter(|secret| !secret.is_empty());
+        let usage_monthly_byte_cap = env::var("VIA_USAGE_MONTHLY_BYTE_CAP")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?;
+        // Caller caps as "caller:bytes" separated by commas or new lines
+        let usage_caller_byte_caps = parse_pairs(
+            "USAGE_CALLER_BYTE_CAPS",
+            &env::var("VIA_USAGE_CALLER_BYTE_CAPS").unwrap_or_default(),
+        )?
+        .into_iter()
+        .map(|(caller, cap)| Ok((caller, cap.parse::<u64>()?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+        let usage_path = env::var("VIA_USAGE_PATH").ok().map(PathBuf::from);
+        let usage_snapshot_interval = Duration::from_secs(
+            env::var("VIA_USAGE_SNAPSHOT_SECS")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(60),
+        );
+        if usage_snapshot_interval.is_zero() {
+            anyhow::bail!("VIA_USAGE_SNAPSHOT_SECS must be positive");
+        }
+
+        let fee_budget = env::var("VIA_FEE_BUDGET")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?;
+        let fee_budget_window = Duration::from_secs(
+            env::var("VIA_FEE_BUDGET_WINDOW_SECS")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(24 * 60 * 60),
+        );
+        if fee_budget_window.is_zero() {
+            anyhow::bail!("VIA_FEE_BUDGET_WINDOW_SECS must be positive");
+        }
+        let staging_ttl = Duration::from_secs(
+            env::var("VIA_STAGING_TTL_SECS")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(60 * 60),
+        );
+        if staging_ttl.is_zero() {
+            anyhow::bail!("VIA_STAGING_TTL_SECS must be positive");
+        }
+
+        let batch_lock_url = env::var("VIA_BATCH_LOCK_URL").ok();
+        let batch_lock_ttl = Duration::from_secs(
+            env::var("VIA_BATCH_LOCK_TTL_SECS")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(10 * 60),
+        );
+        let batch_lock_retention = Duration::from_secs(
+            env::var("VIA_BATCH_LOCK_RETENTION_SECS")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(24 * 60 * 60),
+        );
+        if batch_lock_ttl.is_zero() || batch_lock_retention.is_zero() {
+            anyhow::bail!(
+                "VIA_BATCH_LOCK_TTL_SECS and VIA_BATCH_LOCK_RETENTION_SECS must be positive"
+            );
+        }
+
+        // Tenants as "caller:namespace" separated by commas or new lines
+        let tenant_namespaces = parse_pairs(
+            "TENANT_NAMESPACES",
+            &env::var("VIA_TENANT_NAMESPACES").unwrap_or_default(),
+        )?;
+        for (tenant, namespace) in &tenant_namespaces {
+            if jwt_secret.is_none() && !api_keys.iter().any(|(caller, _)| caller == tenant) {
+                anyhow::bail!("Tenant {} has no API key", tenant);
+            }
+            if namespace.is_empty() || namespace.len() > 10 {
+                anyhow::bail!("Tenant {} namespace must be 1 to 10 bytes", tenant);
+            }
+        }
+
+        // Routes as "selector:namespace" separated by commas or new lines
+        let namespace_routes = parse_pairs(
+            "NAMESPACE_ROUTES",
+            &env::var("VIA_NAMESPACE_ROUTES").unwrap_or_default(),
+        )?
+        .into_iter()
+        .map(|(selector, namespace)| {
+            if namespace.is_empty() || namespace.len() > 10 {
+                anyhow::bail!("Route {} namespace must be 1 to 10 bytes", selector);
+            }
+            NamespaceRoute::parse(&selector, &namespace)
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let selftest_on_startup = env::var("VIA_SELFTEST_ON_STARTUP")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1"))
+            .unwrap_or(false);
+        let chaos_enabled = env::var("VIA_CHAOS_ENABLED")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1"))
+            .unwrap_or(false);
+        let read_only = env::var("VIA_READ_ONLY")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1"))
+            .unwrap_or(false);
+        if read_only && selftest_on_startup {
+            anyhow::bail!(
+                "VIA_SELFTEST_ON_STARTUP dispatches a blob, it can't be set with VIA_READ_ONLY"
+            );
+        }
+
+        let webhook_urls = env::var("VIA_WEBHOOK_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        let webhook_secret = env_or_file("VIA_WEBHOOK_SECRET")?;
+        let webhook_confirmation_depth = env::var("VIA_WEBHOOK_CONFIRMATION_DEPTH")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .unwrap_or(1);
+        let webhook_max_attempts = env::var("VIA_WEBHOOK_MAX_ATTEMPTS")
+            .ok()
+            .map(|v| v.parse::<u32>())
+            .transpose()?
+            .unwrap_or(5);
+
+        let index_path = env::var("VIA_INDEX_PATH").ok().map(PathBuf::from);
+        let payload_cache_dir = env::var("VIA_PAYLOAD_CACHE_DIR").ok().map(PathBuf::from);
+        let proof_cache_dir = env::var("VIA_PROOF_CACHE_DIR").ok().map(PathBuf::from);
+        let verification_interval = env::var("VIA_VERIFICATION_INTERVAL_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs);
+        let verification_sample_size = env::var("VIA_VERIFICATION_SAMPLE_SIZE")
+            .ok()
+            .map(|v| v.parse::<usize>())
+            .transpose()?
+            .unwrap_or(10);
+        let data_root_source_url = env::var("VIA_DATA_ROOT_SOURCE_URL").ok();
+        let data_root_source_token = env_or_file("VIA_DATA_ROOT_SOURCE_TOKEN")?;
+        let confirmation_depth = env::var("VIA_CONFIRMATION_DEPTH")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .unwrap_or(1);
+        let confirmation_poll_interval = env::var("VIA_CONFIRMATION_POLL_INTERVAL_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .map_or(Some(10), |secs| (secs > 0).then_some(secs))
+            .map(Duration::from_secs);
+        let archive_url = env::var("VIA_ARCHIVE_URL")
+            .ok()
+            .filter(|url| !url.is_empty());
+        let archive_interval = env::var("VIA_ARCHIVE_INTERVAL_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .filter(|secs| *secs > 0)
+            .map_or(Duration::from_secs(60), Duration::from_secs);
+        if archive_url.is_some() && confirmation_poll_interval.is_none() {
+            anyhow::bail!(
+                "VIA_ARCHIVE_URL mirrors the confirmed blobs, it requires VIA_CONFIRMATION_POLL_INTERVAL_SECS"
+            );
+        }
+
+        let shutdown_timeout = env::var("VIA_SHUTDOWN_TIMEOUT_SECS")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .map_or(Duration::from_secs(30), Duration::from_secs);
+
+        let log_format = LogFormat::parse(&env::var("VIA_LOG_FORMAT").unwrap_or_default())?;
+        let log_level = env::var("VIA_LOG_LEVEL")
+            .or_else(|_| env::var("RUST_LOG"))
+            .unwrap_or_else(|_| "info".to_string());
+        let log_file = env::var("VIA_LOG_FILE").ok().map(PathBuf::from);
+        let log_file_max_bytes = env::var("VIA_LOG_FILE_MAX_BYTES")
+            .ok()
+            .map(|v| v.parse::<u64>())
+            .transpose()?
+            .unwrap_or(100 * 1024 * 1024);
+        let log_file_max_files = env::var("VIA_LOG_FILE_MAX_FILES")
+            .ok()
+            .map(|v| v.parse::<usize>())
+            .transpose()?
+            .unwrap_or(5);
+
         Ok(Config {
             port,
             app_address,
             metrics_port,
             metrics_address,
             da_backend,
+            da_standby_backends,
+            shadow_percent,
+            shadow_backend,
+            shadow_namespace,
             da_node_url,
             da_auth_token,
             da_blob_size_limit,
+            da_commitment,
+            da_sampling_window,
+            da_params_refresh_interval,
+            da_archival_node_url,
+            da_archival_auth_token,
+            da_fallback_node_urls,
+            da_read_quorum_node_urls,
+            da_read_quorum,
+            da_max_concurrent_dispatches,
+            da_queue_compaction_interval,
+            da_queue_max_wait,
+            inclusion_max_wait,
+            request_timeout,
+            payload_transforms,
+            payload_compression_level,
+            payload_chunk_size,
+            da_retry_policies,
+            key_provider,
+            attestation_key_type,
+            attestation_private_key,
+            attestation_key_id,
+            attestation_keys,
+            encryption_keys,
+            vault_address,
+            vault_token,
+            vault_transit_mount,
+            vault_signing_key,
+            vault_encryption_key,
+            da_allowed_cidrs,
+            admin_allowed_cidrs,
+            trusted_proxies,
+            api_keys,
+            jwt_secret,
+            usage_monthly_byte_cap,
+            usage_caller_byte_caps,
+            usage_path,
+            usage_snapshot_interval,
+            fee_budget,
+            fee_budget_window,
+            staging_ttl,
+            batch_lock_url,
+            batch_lock_ttl,
+            batch_lock_retention,
+            tenant_namespaces,
+            namespace_routes,
+            selftest_on_startup,
+            chaos_enabled,
+            read_only,
+            webhook_urls,
+            webhook_secret,
+            webhook_confirmation_depth,
+            webhook_max_attempts,
+            index_path,
+            payload_cache_dir,
+            proof_cache_dir,
+            verification_interval,
+            verification_sample_size,
+            data_root_source_url,
+            data_root_source_token,
+            confirmation_depth,
+            confirmation_poll_interval,
+            archive_url,
+            archive_interval,
+            shutdown_timeout,
+            log_format,
+            log_level,
+            log_file,
+            log_file_max_bytes,
+            log_file_max_files,
+        })
+    }
+}
+
+/// Parses "name:value" pairs separated by commas or new lines.
+fn parse_pairs(name: &str, value: &str) -> anyhow::Result<Vec<(String, String)>> {
+    value
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .split_once(':')
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .ok_or_else(|| anyhow::anyhow!("Invalid {} entry, expected name:value", name))
         })
+        .collect()
+}
+
+/// Reads a secret from the `name` env variable, or from the file at the `{name}_FILE` env variable.
+fn env_or_file(name: &str) -> anyhow::Result<Option<String>> {
+    if let Ok(value) = env::var(name) {
+        return Ok(Some(value));
+    }
+
+    match env::var(format!("{name}_FILE")) {
+        Ok(path) => {
+            let value = std::fs::read_to_string(&path)
+                .map_err(|error| anyhow::anyhow!("Failed to read {name}_FILE {path}: {error}"))?;
+            Ok(Some(value.trim().to_string()))
+        }
+        Err(_) => Ok(None),
     }
 }
@@ -0,0 +1,328 @@
+use axum::{
+    Extension, Json,
+    body::Body,
+    extract::{
+        Path, Query, State,
+        rejection::{JsonRejection, QueryRejection},
+    },
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use futures_util::stream;
+use std::{convert::Infallible, sync::Arc};
+
+use crate::{
+    clients::da_clients::types::CallContext,
+    middlewares::auth::Caller,
+    services::{cpu::offload, metrics::encode_metrics},
+    state::AppState,
+    types::{
+        admin::{
+            BackendResponse, BackendStats, ChaosResponse, ChaosSettings, ExportFormat, ExportQuery,
+            LogLevelRequest, LogLevelResponse, MetricsQuery, StatsResponse, SwitchBackendRequest,
+        },
+        import::ImportRequest,
+        index::IndexEntry,
+        maintenance::PauseRequest,
+        sequence::GapsResponse,
+        usage::{TenantMetricsResponse, UsageResponse},
+    },
+};
+
+/// GET /admin/usage
+pub async fn usage_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(UsageResponse {
+        callers: svc.usage_svc.usage(),
+    })
+}
+
+/// GET /admin/usage/:tenant/metrics
+///
+/// The usage of a single tenant, so its rollup team sees its own dispatches without access to
+/// the metrics of the other tenants. The route is served outside the admin allowlist, to the
+/// tenant authenticated by its API key, and refused without API keys. The usage of all the
+/// callers is served by `/admin/usage`.
+pub async fn tenant_metrics_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Path(tenant): Path<String>,
+) -> impl IntoResponse {
+    if !svc.api_keys.is_enabled() {
+        return (
+            StatusCode::FORBIDDEN,
+            "The usage of the tenants is only served to the callers authenticated by an API key",
+        )
+            .into_response();
+    }
+    if caller.0 != tenant {
+        return (
+            StatusCode::FORBIDDEN,
+            format!("{} can't read the usage of {}", caller.0, tenant),
+        )
+            .into_response();
+    }
+    let Some(namespace) = svc.da_svc.tenant_namespace(&tenant) else {
+        return (StatusCode::NOT_FOUND, format!("Unknown tenant {tenant}")).into_response();
+    };
+
+    Json(TenantMetricsResponse {
+        namespace: namespace.to_string(),
+        usage: svc.usage_svc.caller_usage(&tenant),
+    })
+    .into_response()
+}
+
+/// GET /admin/metrics
+///
+/// The metrics in the Prometheus text format, only the series of a caller with `?tenant=`.
+pub async fn metrics_handler(
+    query: Result<Query<MetricsQuery>, QueryRejection>,
+) -> impl IntoResponse {
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.body_text()).into_response(),
+    };
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        encode_metrics(query.tenant.as_deref()),
+    )
+        .into_response()
+}
+
+/// GET /admin/gaps
+///
+/// Lists the batch numbers each caller skipped between its first and last dispatched batches.
+pub async fn gaps_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(GapsResponse {
+        callers: svc.sequence.sequences(),
+    })
+}
+
+/// GET /admin/backend
+pub async fn backend_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(BackendResponse {
+        active: svc.da_backends.active_backend(),
+        previous: None,
+        available: svc.da_backends.backends(),
+    })
+}
+
+/// POST /admin/backend
+pub async fn switch_backend_handler(
+    State(svc): State<Arc<AppState>>,
+    payload: Result<Json<SwitchBackendRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match payload {
+        Ok(Json(p)) => p,
+        Err(err) => {
+            tracing::error!("Invalid JSON: {}", err);
+            return (StatusCode::BAD_REQUEST, "Invalid JSON body").into_response();
+        }
+    };
+
+    match svc.da_backends.switch_to(payload.backend).await {
+        Ok(previous) => Json(BackendResponse {
+            active: payload.backend,
+            previous: Some(previous),
+            available: svc.da_backends.backends(),
+        })
+        .into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+/// GET /admin/log_level
+pub async fn log_level_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(LogLevelResponse {
+        level: svc.logging.level(),
+        previous: None,
+    })
+}
+
+/// PUT /admin/log_level
+pub async fn set_log_level_handler(
+    State(svc): State<Arc<AppState>>,
+    payload: Result<Json<LogLevelRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match payload {
+        Ok(Json(p)) => p,
+        Err(err) => {
+            tracing::error!("Invalid JSON: {}", err);
+            return (StatusCode::BAD_REQUEST, "Invalid JSON body").into_response();
+        }
+    };
+
+    match svc.logging.set_level(&payload.level) {
+        Ok(previous) => Json(LogLevelResponse {
+            level: payload.level,
+            previous: Some(previous),
+        })
+        .into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+/// GET /admin/chaos
+pub async fn chaos_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    let Some(chaos) = &svc.chaos else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    Json(ChaosResponse {
+        settings: chaos.settings(),
+        previous: None,
+    })
+    .into_response()
+}
+
+/// PUT /admin/chaos
+///
+/// Replaces the faults injected into the DA calls, `{}` stops injecting them.
+pub async fn set_chaos_handler(
+    State(svc): State<Arc<AppState>>,
+    payload: Result<Json<ChaosSettings>, JsonRejection>,
+) -> impl IntoResponse {
+    let Some(chaos) = &svc.chaos else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let payload = match payload {
+        Ok(Json(p)) => p,
+        Err(err) => {
+            tracing::error!("Invalid JSON: {}", err);
+            return (StatusCode::BAD_REQUEST, "Invalid JSON body").into_response();
+        }
+    };
+    if let Err(err) = payload.validate() {
+        return (StatusCode::BAD_REQUEST, err).into_response();
+    }
+
+    let previous = chaos.set(payload.clone());
+    Json(ChaosResponse {
+        settings: payload,
+        previous: Some(previous),
+    })
+    .into_response()
+}
+
+/// POST /admin/pause
+pub async fn pause_handler(
+    State(svc): State<Arc<AppState>>,
+    payload: Option<Json<PauseRequest>>,
+) -> impl IntoResponse {
+    let reason = payload.and_then(|Json(p)| p.reason);
+    Json(svc.maintenance.pause(reason))
+}
+
+/// POST /admin/import
+///
+/// Backfills the index with the blobs found in a range of DA heights, e.g. the blobs submitted
+/// before the migration of a rollup to this service.
+pub async fn import_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(ctx): Extension<CallContext>,
+    payload: Result<Json<ImportRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match payload {
+        Ok(Json(p)) => p,
+        Err(err) => {
+            tracing::error!("Invalid JSON: {}", err);
+            return (StatusCode::BAD_REQUEST, "Invalid JSON body").into_response();
+        }
+    };
+
+    match svc.import.import(&ctx, &payload).await {
+        // The blobs imported before the failure are kept.
+        Ok(response) if response.failure.is_some() => {
+            (StatusCode::MULTI_STATUS, Json(response)).into_response()
+        }
+        Ok(response) => Json(response).into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
+
+/// POST /admin/resume
+pub async fn resume_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(svc.maintenance.resume())
+}
+
+/// POST /admin/selftest
+pub async fn selftest_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    let result = svc.selftest.run().await;
+    if result.success {
+        Json(result).into_response()
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(result)).into_response()
+    }
+}
+
+/// GET /admin/verification
+pub async fn verification_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    match svc.verification.last_report() {
+        Some(report) => Json(report).into_response(),
+        None => (StatusCode::NOT_FOUND, "No verification run yet").into_response(),
+    }
+}
+
+/// GET /admin/stats
+pub async fn stats_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(StatsResponse {
+        active_backend: svc.da_backends.active_backend(),
+        backends: svc
+            .da_backends
+            .backends()
+            .into_iter()
+            .map(|backend| BackendStats {
+                backend,
+                retry_policy: svc.config.retry_policy(backend),
+            })
+            .collect(),
+    })
+}
+
+/// GET /admin/export?from=&to=&format=csv|parquet
+pub async fn export_handler(
+    State(svc): State<Arc<AppState>>,
+    query: Result<Query<ExportQuery>, QueryRejection>,
+) -> impl IntoResponse {
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.body_text()).into_response(),
+    };
+
+    let entries = svc.index.range(query.from, query.to);
+    if query.format == ExportFormat::Parquet {
+        // The footer of a Parquet file refers to its columns, the file is written before it is
+        // sent.
+        let file = offload(entries.len() * 256, move || {
+            IndexEntry::to_parquet(&entries)
+        })
+        .await;
+        return (
+            [
+                (header::CONTENT_TYPE, "application/vnd.apache.parquet"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"dispatches.parquet\"",
+                ),
+            ],
+            file,
+        )
+            .into_response();
+    }
+
+    let rows = std::iter::once(IndexEntry::CSV_HEADER.to_string())
+        .chain(entries.into_iter().map(|entry| entry.to_csv_row()))
+        .map(Ok::<_, Infallible>);
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"dispatches.csv\"",
+            ),
+        ],
+        Body::from_stream(stream::iter(rows)),
+    )
+        .into_response()
+}
@@ -0,0 +1,54 @@
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::sync::Arc;
+
+use crate::{middlewares::auth::Caller, state::AppState};
+
+/// GET /da/keys
+///
+/// Returns the public attestation keys as a JWK set, including the rotated out keys so the older
+/// attestations can still be verified.
+pub async fn keys_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    match svc.attestation_svc.key_set().await {
+        Ok(key_set) => Json(key_set).into_response(),
+        Err(err) => {
+            tracing::error!("Error to get the attestation keys: {}", err);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Error to get the attestation keys",
+            )
+                .into_response()
+        }
+    }
+}
+
+/// GET /attestation/:blob_id
+pub async fn attestation_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Path(blob_id): Path<String>,
+) -> impl IntoResponse {
+    if !svc.attestation_svc.is_enabled() {
+        return (StatusCode::NOT_FOUND, "Attestations are disabled").into_response();
+    }
+
+    // The attestations of the blobs dispatched by other callers are not disclosed.
+    let entry = svc.index.get(&blob_id);
+    if entry.as_ref().is_some_and(|entry| entry.caller != caller) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    // The older attestations are only kept in the index.
+    match svc
+        .attestation_svc
+        .get(&blob_id)
+        .or_else(|| entry?.attestation)
+    {
+        Some(attestation) => Json(attestation).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -1,30 +1,382 @@
 use axum::{
-    Json,
-    extract::{Path, State, rejection::JsonRejection},
-    http::StatusCode,
-    response::IntoResponse,
+    Extension, Json,
+    body::{Body, Bytes},
+    extract::{
+        Path, Query, State,
+        rejection::{JsonRejection, QueryRejection},
+    },
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
+use futures_util::stream;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{collections::BTreeMap, convert::Infallible, sync::Arc, time::Duration};
+use tokio::time::Instant;
 
-use crate::state::AppState;
+use crate::{
+    clients::da_clients::{
+        blob_id::{self, BlobIdCodec},
+        celestia::CelestiaBlobId,
+        types::{CallContext, DAError, DispatchResponse},
+    },
+    config::DaBackend,
+    middlewares::auth::Caller,
+    services::{
+        attestation::AttestationSvc, batch_lock::BatchClaim, cpu::offload,
+        fee_budget::FeeBudgetError, staging::StagedDispatch,
+    },
+    state::AppState,
+    types::{
+        dispatch::{DispatchPriority, PartialDispatchReport},
+        error::{
+            BATCH_LOCK_UNAVAILABLE_ERROR_CODE, BATCH_LOCKED_ERROR_CODE, ErrorResponse,
+            FEE_BUDGET_ERROR_CODE, MAINTENANCE_ERROR_CODE,
+        },
+        index::IndexEntry,
+        routing::PROTOCOL_VERSION_KEY,
+        staging::PrepareResponse,
+        verification::{VerifyRequest, VerifyResponse},
+    },
+};
+
+/// Header selecting the version of the API responses, defaults to 1.
+pub const API_VERSION_HEADER: &str = "X-Via-Api-Version";
+
+/// The latest API version, version 2 adds the receipts to the dispatch responses.
+pub const LATEST_API_VERSION: u32 = 2;
+
+/// Delay between the first two polls of a blob waited for, doubled after every poll.
+const INCLUSION_POLL_BASE_DELAY: Duration = Duration::from_millis(250);
+
+const INCLUSION_POLL_MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// The dispatches of a batch locked by another replica are retried after this delay.
+const BATCH_LOCKED_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// Size of the frames the raw blobs are streamed in.
+const RAW_FRAME_SIZE: usize = 64 * 1024;
+
+/// The limits of the metadata tags of a dispatch, they are kept in the index.
+const MAX_METADATA_TAGS: usize = 16;
+
+const MAX_METADATA_KEY_LEN: usize = 64;
+
+const MAX_METADATA_VALUE_LEN: usize = 256;
 
 #[derive(Deserialize)]
 pub struct DispatchRequest {
     pub batch_number: u32,
     pub data: String,
+    /// Defaults to normal, high priority dispatches are served first when the queue is saturated.
+    #[serde(default)]
+    pub priority: DispatchPriority,
+    /// A webhook receiving the lifecycle events of this dispatch, in addition to the global ones.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Key/value tags kept in the index, e.g. the protocol version or the batch hash, to
+    /// correlate the blob with the rollup state. They are not sent to the DA layer.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
 }
 
 #[derive(Serialize)]
 pub struct InclusionResponse {
     pub data: String,
+    /// The DA height of the blob, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// The commitment of the blob (hex), if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+    /// The namespace of the blob (hex), if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// The payload size in bytes.
+    pub size: u64,
+}
+
+/// The availability of a blob on the DA layer.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobStatus {
+    Available,
+    /// Older than the retention of the DA node, served by the archival node.
+    Archived,
+    /// Older than the retention of the DA node and no archival node is configured.
+    Pruned,
+    /// The blob never existed, or not in the namespace of the caller.
+    NotFound,
+}
+
+#[derive(Serialize)]
+pub struct BlobStatusResponse {
+    pub blob_id: String,
+    pub status: BlobStatus,
+    /// The DA height of the blob, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// The earliest height the DA node still serves, for the backends with pruning.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub earliest_available_height: Option<u64>,
+    /// How long the DA layer keeps the blobs after their inclusion, absent when forever.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention_secs: Option<u64>,
+    /// When the blob stops being served by the DA layer, absent when never or once archived.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The confirmations tracked for the dispatches of the caller, up to the confirmation depth.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    /// Whether the blob reached the confirmation depth, when its confirmations are tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmed: Option<bool>,
+    /// The metadata tags of the dispatch of the caller.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A blob of the DA footprint of a batch.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct FootprintBlob {
+    pub blob_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// The commitment of the blob (hex), if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+    /// The blob size in bytes, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// `BatchFootprintResponse` is what the last dispatch of a batch left on the DA layer, the
+/// pointers published on L1.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct BatchFootprintResponse {
+    pub batch_number: u32,
+    /// The blob the batch is read from, the manifest of the chunks of a chunked batch.
+    pub blob: FootprintBlob,
+    /// The chunks of a chunked batch, in order.
+    pub chunks: Vec<FootprintBlob>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<DaBackend>,
+    /// The namespace of the blobs (hex), if the backend has namespaces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// The size of the payload of the batch.
+    pub payload_size: u64,
+    /// The bytes submitted to the DA layer, the blob and its chunks, if their sizes are known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_size: Option<u64>,
+    /// The blob_id the batch was re-dispatched from, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redispatch_of: Option<String>,
+    /// The confirmations of the blob, when they are tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// `ChunkManifestResponse` lists the chunks of a chunked blob, read one by one from
+/// `/inclusion/:blob_id/chunks/:n`.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ChunkManifestResponse {
+    pub blob_id: String,
+    /// The chunks in order, empty when the blob is not chunked.
+    pub chunks: Vec<FootprintBlob>,
+    /// The size of the chunks, if their sizes are known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_size: Option<u64>,
+}
+
+/// The blobs dispatched before the receipts were indexed are located from their blob_id.
+impl From<IndexEntry> for BatchFootprintResponse {
+    fn from(entry: IndexEntry) -> Self {
+        let (blob, chunks, backend, namespace) = match entry.receipt {
+            Some(receipt) => (
+                FootprintBlob {
+                    blob_id: entry.blob_id,
+                    height: receipt.height,
+                    commitment: Some(receipt.commitment),
+                    size: Some(receipt.size),
+                },
+                receipt
+                    .chunks
+                    .into_iter()
+                    .map(|chunk| FootprintBlob {
+                        blob_id: chunk.blob_id,
+                        height: chunk.height,
+                        commitment: Some(chunk.commitment),
+                        size: Some(chunk.size),
+                    })
+                    .collect(),
+                Some(receipt.backend),
+                receipt.namespace,
+            ),
+            None => {
+                let locator = blob_id::locate(&entry.blob_id);
+                let blob = FootprintBlob {
+                    height: locator.as_ref().and_then(|locator| locator.height),
+                    commitment: locator.map(|locator| hex::encode(locator.commitment)),
+                    blob_id: entry.blob_id,
+                    size: None,
+                };
+                (blob, vec![], None, None)
+            }
+        };
+        let total_size = std::iter::once(&blob)
+            .chain(&chunks)
+            .map(|blob| blob.size)
+            .sum();
+
+        Self {
+            batch_number: entry.batch_number,
+            blob,
+            chunks,
+            backend,
+            namespace,
+            payload_size: entry.size,
+            total_size,
+            redispatch_of: entry.redispatch_of,
+            confirmations: entry.confirmations,
+            metadata: entry.metadata,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct RedispatchResponse {
+    #[serde(flatten)]
+    pub dispatch: DispatchResponse,
+    /// The blob_id of the re-dispatched blob.
+    pub redispatch_of: String,
+}
+
+/// Returns the 503 response of the dispatches while paused for maintenance.
+fn maintenance_response(svc: &AppState) -> Option<Response> {
+    let maintenance = svc.maintenance.status();
+    if !maintenance.paused {
+        return None;
+    }
+
+    let message = match maintenance.reason {
+        Some(reason) => format!("Dispatch is paused for maintenance: {}", reason),
+        None => "Dispatch is paused for maintenance".to_string(),
+    };
+    Some(
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(MAINTENANCE_ERROR_CODE, message)),
+        )
+            .into_response(),
+    )
+}
+
+/// Returns the 429 response of the dispatches while the fee budget is exhausted.
+fn fee_budget_response(err: &FeeBudgetError) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(
+            header::RETRY_AFTER,
+            err.retry_after.as_secs().max(1).to_string(),
+        )],
+        Json(ErrorResponse::new(FEE_BUDGET_ERROR_CODE, err.to_string())),
+    )
+        .into_response()
+}
+
+/// Returns the API version requested by the caller.
+fn api_version(headers: &HeaderMap) -> Result<u32, String> {
+    let Some(value) = headers.get(API_VERSION_HEADER) else {
+        return Ok(1);
+    };
+
+    match value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+    {
+        Some(version) if (1..=LATEST_API_VERSION).contains(&version) => Ok(version),
+        _ => Err(format!(
+            "Invalid {}, supported versions are 1 to {}",
+            API_VERSION_HEADER, LATEST_API_VERSION
+        )),
+    }
+}
+
+/// Drops the fields the requested API version doesn't know.
+fn versioned(mut resp: DispatchResponse, api_version: u32) -> DispatchResponse {
+    if api_version < 2 {
+        resp.receipt = None;
+    }
+    resp
+}
+
+/// Returns the response of a failed DA call, the body carries the code of the error class.
+fn da_error_response(err: &DAError, context: &str) -> Response {
+    let status = match err {
+        DAError::PartialDispatch(report) => {
+            return (StatusCode::MULTI_STATUS, Json(report)).into_response();
+        }
+        DAError::ConnectionError { .. } | DAError::SubmitFailed { .. } => StatusCode::BAD_GATEWAY,
+        DAError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        DAError::SubmitRejected { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        DAError::BlobTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        DAError::NotFound { .. } => StatusCode::NOT_FOUND,
+        DAError::Pruned { .. } => StatusCode::GONE,
+        DAError::InvalidBlobId { .. } => StatusCode::BAD_REQUEST,
+        DAError::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        DAError::Cancelled => StatusCode::SERVICE_UNAVAILABLE,
+        DAError::Unsupported { .. } => StatusCode::NOT_IMPLEMENTED,
+        DAError::IntegrityMismatch { .. } | DAError::Internal(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    let body = Json(ErrorResponse::new(
+        err.code(),
+        format!("{}: {}", context, err),
+    ));
+
+    match err {
+        DAError::RateLimited {
+            retry_after: Some(retry_after),
+            ..
+        } => (
+            status,
+            [(
+                header::RETRY_AFTER,
+                retry_after.as_secs().max(1).to_string(),
+            )],
+            body,
+        )
+            .into_response(),
+        _ => (status, body).into_response(),
+    }
 }
 
 /// POST /dispatch
+///
+/// The body can be sent compressed with `Content-Encoding: zstd`, `gzip` or `br`, it is
+/// decompressed before the hex decoding so the dispatched payload is unchanged. A chunked blob
+/// failed after some of its chunks were dispatched is reported with a 207, the retriable ones are
+/// staged to be resumed by `POST /da/commit/:staging_id`.
 pub async fn dispatch_handler(
     State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    headers: HeaderMap,
     payload: Result<Json<DispatchRequest>, JsonRejection>,
 ) -> impl IntoResponse {
+    if let Some(response) = maintenance_response(&svc) {
+        return response;
+    }
+    let api_version = match api_version(&headers) {
+        Ok(version) => version,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+
     let payload = match payload {
         Ok(Json(p)) => p,
         Err(err) => {
@@ -33,49 +385,1257 @@ pub async fn dispatch_handler(
         }
     };
 
-    let data = match hex::decode(payload.data) {
-        Ok(data) => data,
-        Err(_) => {
-            tracing::error!("Invalid data format");
-            return (
-                axum::http::StatusCode::BAD_REQUEST,
-                "Invalid data format, must be a hex string".to_string(),
-            )
-                .into_response();
+    let (data, payload) = match decode_dispatch(&svc, payload).await {
+        Ok(decoded) => decoded,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+    let mut dispatch = match stage_dispatch(&svc, &caller, payload, data).await {
+        Ok(dispatch) => dispatch,
+        Err(response) => return response,
+    };
+
+    match submit_dispatch(&svc, &ctx, &mut dispatch, api_version).await {
+        Ok(response) => response,
+        Err(SubmitFailure::Rejected(response)) => response,
+        // The dispatch is staged to be resumed from the dispatched chunks.
+        Err(SubmitFailure::Partial(mut report)) => {
+            if report.retriable {
+                let (staging_id, ttl) = svc.staging.stage(dispatch);
+                report.staging_id = Some(staging_id);
+                report.expires_at = Some(Utc::now() + ttl);
+            }
+            (StatusCode::MULTI_STATUS, Json(report)).into_response()
+        }
+    }
+}
+
+/// Decodes the payload of a dispatch request and validates its options, returns the reason of a
+/// bad request.
+async fn decode_dispatch(
+    svc: &AppState,
+    mut payload: DispatchRequest,
+) -> Result<(Vec<u8>, DispatchRequest), String> {
+    let encoded = std::mem::take(&mut payload.data);
+    let Ok(data) = offload(encoded.len(), move || hex::decode(encoded)).await else {
+        tracing::error!("Invalid data format");
+        return Err("Invalid data format, must be a hex string".to_string());
+    };
+
+    if let Some(url) = &payload.webhook_url
+        && let Err(err) = svc.webhooks.validate_url(url)
+    {
+        return Err(format!("Invalid webhook_url: {}", err));
+    }
+    validate_metadata(&payload.metadata)?;
+
+    Ok((data, payload))
+}
+
+/// Transforms the payload of a dispatch for its submission.
+async fn stage_dispatch(
+    svc: &AppState,
+    caller: &str,
+    payload: DispatchRequest,
+    data: Vec<u8>,
+) -> Result<StagedDispatch, Response> {
+    let size = data.len() as u64;
+    let (data, payload_hash) = offload(data.len(), move || {
+        let payload_hash = hex::encode(AttestationSvc::payload_hash(&data));
+        (data, payload_hash)
+    })
+    .await;
+    let cached = svc.payload_cache.is_enabled().then(|| data.clone());
+
+    match svc.da_svc.prepare(caller, data).await {
+        Ok(prepared) => Ok(StagedDispatch {
+            caller: caller.to_string(),
+            batch_number: payload.batch_number,
+            priority: payload.priority,
+            webhook_url: payload.webhook_url,
+            metadata: payload.metadata,
+            size,
+            payload_hash,
+            payload: cached,
+            prepared,
+        }),
+        Err(err) => {
+            svc.webhooks
+                .on_failed(payload.batch_number, &err.to_string(), payload.webhook_url);
+            tracing::error!("Error to prepare the blob data: {}", err);
+            Err(da_error_response(&err, "Error to prepare the blob data"))
+        }
+    }
+}
+
+/// A dispatch that was not submitted.
+enum SubmitFailure {
+    Rejected(Response),
+    /// Some chunks of the blob were dispatched, the dispatch is set to resume from them.
+    Partial(Box<PartialDispatchReport>),
+}
+
+impl From<Response> for SubmitFailure {
+    fn from(response: Response) -> Self {
+        Self::Rejected(response)
+    }
+}
+
+/// Submits a dispatch and records it, the failure is returned when it was not submitted.
+async fn submit_dispatch(
+    svc: &AppState,
+    ctx: &CallContext,
+    dispatch: &mut StagedDispatch,
+    api_version: u32,
+) -> Result<Response, SubmitFailure> {
+    let caller = &dispatch.caller;
+    if let Err(err) = svc.fee_budget.check() {
+        tracing::warn!("{}", err);
+        return Err(fee_budget_response(&err).into());
+    }
+    let reservation = match svc.usage_svc.reserve(caller, dispatch.size) {
+        Ok(reservation) => reservation,
+        Err(err) => {
+            tracing::warn!("{}", err);
+            return Err((StatusCode::TOO_MANY_REQUESTS, err.to_string())
+                .into_response()
+                .into());
+        }
+    };
+    let lock = match svc.batch_locks.claim(caller, dispatch.batch_number).await {
+        Ok(BatchClaim::Acquired(lock)) => lock,
+        claim => {
+            svc.usage_svc.release(reservation);
+            let submitted = matches!(claim, Ok(BatchClaim::Submitted { .. }));
+            let response = batch_claim_response(claim, caller, dispatch.batch_number, api_version);
+            return if submitted {
+                Ok(response)
+            } else {
+                Err(response.into())
+            };
         }
     };
 
-    match svc.da_svc.dispatch_blob(payload.batch_number, data).await {
-        Ok(resp) => Json(resp).into_response(),
+    match svc
+        .da_svc
+        .dispatch_prepared(
+            ctx,
+            caller,
+            dispatch.batch_number,
+            dispatch
+                .metadata
+                .get(PROTOCOL_VERSION_KEY)
+                .map(String::as_str),
+            dispatch.prepared.clone(),
+            dispatch.priority,
+        )
+        .await
+    {
+        Ok(resp) => {
+            svc.batch_locks.complete(lock, &resp.blob_id).await;
+            svc.usage_svc
+                .record_dispatch(caller, dispatch.size, resp.receipt.as_ref());
+            svc.fee_budget.record(resp.receipt.as_ref());
+            svc.index.record(IndexEntry {
+                blob_id: resp.blob_id.clone(),
+                batch_number: dispatch.batch_number,
+                caller: caller.clone(),
+                size: dispatch.size,
+                payload_hash: dispatch.payload_hash.clone(),
+                dispatched_at: Utc::now(),
+                redispatch_of: None,
+                receipt: resp.receipt.clone(),
+                confirmations: None,
+                archive: None,
+                metadata: dispatch.metadata.clone(),
+                attestation: resp.attestation.clone(),
+            });
+            svc.sequence.record(caller, dispatch.batch_number);
+            if let Some(data) = &dispatch.payload {
+                svc.payload_cache.put(&resp.blob_id, data).await;
+            }
+            svc.webhooks.on_submitted(
+                &resp.blob_id,
+                dispatch.batch_number,
+                dispatch.webhook_url.clone(),
+            );
+            Ok(Json(versioned(resp, api_version)).into_response())
+        }
         Err(err) => {
+            svc.batch_locks.release(lock).await;
+            svc.usage_svc.release(reservation);
+            svc.usage_svc.record_failure(caller);
+            svc.webhooks.on_failed(
+                dispatch.batch_number,
+                &err.to_string(),
+                dispatch.webhook_url.clone(),
+            );
             tracing::error!("Error to dispatch the blob data: {}", err);
+            match err {
+                DAError::PartialDispatch(report) => {
+                    dispatch.prepared.resume(&report);
+                    Err(SubmitFailure::Partial(report))
+                }
+                err => Err(da_error_response(&err, "Error to dispatch the blob data").into()),
+            }
+        }
+    }
+}
+
+/// Returns the response of a dispatch of a batch claimed by another replica, the blob_id of the
+/// submitted batch is returned as if it was dispatched.
+fn batch_claim_response(
+    claim: anyhow::Result<BatchClaim>,
+    caller: &str,
+    batch_number: u32,
+    api_version: u32,
+) -> Response {
+    match claim {
+        Ok(BatchClaim::Acquired(_)) => unreachable!("The acquired claims are submitted"),
+        Ok(BatchClaim::Submitted { blob_id }) => {
+            tracing::info!(
+                "Batch {} of {} was submitted by another replica as {}",
+                batch_number,
+                caller,
+                blob_id
+            );
+            Json(versioned(DispatchResponse::from(blob_id), api_version)).into_response()
+        }
+        Ok(BatchClaim::Locked) => (
+            StatusCode::CONFLICT,
+            [(
+                header::RETRY_AFTER,
+                BATCH_LOCKED_RETRY_AFTER.as_secs().to_string(),
+            )],
+            Json(ErrorResponse::new(
+                BATCH_LOCKED_ERROR_CODE,
+                format!(
+                    "Batch {} is being submitted by another replica",
+                    batch_number
+                ),
+            )),
+        )
+            .into_response(),
+        Err(err) => {
+            tracing::error!("Failed to lock batch {}: {}", batch_number, err);
             (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Error to dispatch the blob data: {}", err),
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::new(
+                    BATCH_LOCK_UNAVAILABLE_ERROR_CODE,
+                    format!("Failed to lock batch {}: {}", batch_number, err),
+                )),
             )
                 .into_response()
         }
     }
 }
 
-/// GET /inclusion/:blob_id
+/// POST /da/prepare
+///
+/// Stages a dispatch without submitting it, the response plans its chunks and estimates its fee.
+/// The staged dispatch is submitted by `POST /da/commit/:staging_id`.
+pub async fn prepare_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    payload: Result<Json<DispatchRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match payload {
+        Ok(Json(p)) => p,
+        Err(err) => {
+            tracing::error!("Invalid JSON: {}", err);
+            return (StatusCode::BAD_REQUEST, "Invalid JSON body").into_response();
+        }
+    };
+    let (data, payload) = match decode_dispatch(&svc, payload).await {
+        Ok(decoded) => decoded,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+    let dispatch = match stage_dispatch(&svc, &caller, payload, data).await {
+        Ok(dispatch) => dispatch,
+        Err(response) => return response,
+    };
+
+    let blob_sizes = dispatch.prepared.blob_sizes();
+    let estimate = svc.da_svc.estimate_fee(&caller, &dispatch.prepared);
+    let size = dispatch.size;
+    let blob_size = dispatch.prepared.data.len() as u64;
+    let (staging_id, ttl) = svc.staging.stage(dispatch);
+    tracing::debug!("Staged the dispatch {} of {}", staging_id, caller);
+
+    Json(PrepareResponse {
+        staging_id,
+        expires_at: Utc::now() + ttl,
+        size,
+        blob_size,
+        chunks: match blob_sizes.len() {
+            1 => vec![],
+            _ => blob_sizes.into_iter().map(|size| size as u64).collect(),
+        },
+        estimate,
+    })
+    .into_response()
+}
+
+/// POST /da/commit/:staging_id
+///
+/// Submits a dispatch staged by the caller, a failed submission can be committed again until the
+/// staged dispatch expires. A partially failed one resumes from its dispatched chunks.
+pub async fn commit_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    headers: HeaderMap,
+    Path(staging_id): Path<String>,
+) -> impl IntoResponse {
+    if let Some(response) = maintenance_response(&svc) {
+        return response;
+    }
+    let api_version = match api_version(&headers) {
+        Ok(version) => version,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+
+    let Some((mut dispatch, expires_at)) = svc.staging.take(&caller, &staging_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("No staged dispatch {}, it may have expired", staging_id),
+        )
+            .into_response();
+    };
+
+    match submit_dispatch(&svc, &ctx, &mut dispatch, api_version).await {
+        Ok(response) => response,
+        Err(failure) => {
+            svc.staging.restore(&staging_id, dispatch, expires_at);
+            match failure {
+                SubmitFailure::Rejected(response) => response,
+                SubmitFailure::Partial(mut report) => {
+                    report.staging_id = Some(staging_id);
+                    report.expires_at =
+                        Some(Utc::now() + expires_at.saturating_duration_since(Instant::now()));
+                    (StatusCode::MULTI_STATUS, Json(report)).into_response()
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct InclusionQuery {
+    pub height: u64,
+    /// The commitment hash (hex).
+    pub commitment: String,
+}
+
+#[derive(Deserialize)]
+pub struct InclusionWaitQuery {
+    /// How long to wait for a blob that is not retrievable yet, e.g. `30s`, `500ms` or `30`.
+    pub wait: Option<String>,
+}
+
+/// Checks the metadata tags against the limits, the keys are made of alphanumerics, `_`, `-` and
+/// `.`.
+fn validate_metadata(metadata: &BTreeMap<String, String>) -> Result<(), String> {
+    if metadata.len() > MAX_METADATA_TAGS {
+        return Err(format!("At most {} metadata tags", MAX_METADATA_TAGS));
+    }
+    for (key, value) in metadata {
+        if key.is_empty()
+            || key.len() > MAX_METADATA_KEY_LEN
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            return Err(format!(
+                "Invalid metadata key {:?}, must be 1 to {} alphanumerics, '_', '-' or '.'",
+                key, MAX_METADATA_KEY_LEN
+            ));
+        }
+        if value.len() > MAX_METADATA_VALUE_LEN {
+            return Err(format!(
+                "The value of metadata key {:?} exceeds {} bytes",
+                key, MAX_METADATA_VALUE_LEN
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses a wait duration, in seconds without a unit. The wait is capped by the handler, only a
+/// wait too long to be represented is invalid.
+fn parse_wait(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit) = value.split_at(
+        value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len()),
+    );
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "" | "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+/// GET /inclusion/:blob_id?wait=
+///
+/// With `wait`, a blob that is not retrievable yet is polled until it is or the wait, capped by
+/// the configured maximum, expires.
 pub async fn inclusion_handler(
     State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
     Path(blob_id): Path<String>,
+    headers: HeaderMap,
+    query: Result<Query<InclusionWaitQuery>, QueryRejection>,
 ) -> impl IntoResponse {
-    match svc.da_svc.get_inclusion_data(&blob_id).await {
-        Ok(Some(data)) => Json(InclusionResponse {
-            data: hex::encode(&data.data),
-        })
-        .into_response(),
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.body_text()).into_response(),
+    };
+    let wait = match query.wait.as_deref().map(parse_wait) {
+        None => Duration::ZERO,
+        Some(Some(wait)) => wait.min(svc.config.inclusion_max_wait),
+        Some(None) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Invalid wait, must be a duration like 30s or 500ms",
+            )
+                .into_response();
+        }
+    };
+
+    fetch_inclusion_data(&svc, &ctx, &caller, &blob_id, &headers, wait).await
+}
+
+/// GET /inclusion?height=&commitment=
+pub async fn inclusion_by_commitment_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    headers: HeaderMap,
+    query: Result<Query<InclusionQuery>, QueryRejection>,
+) -> impl IntoResponse {
+    let Query(query) = match query {
+        Ok(query) => query,
+        Err(err) => return (StatusCode::BAD_REQUEST, err.body_text()).into_response(),
+    };
+
+    if !svc.da_backends.backends().contains(&DaBackend::Celestia) {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Lookups by height and commitment require the Celestia backend",
+        )
+            .into_response();
+    }
+
+    let commitment = query.commitment.trim_start_matches("0x");
+    let commitment: [u8; 32] = match hex::decode(commitment).ok().and_then(|c| c.try_into().ok()) {
+        Some(commitment) => commitment,
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Invalid commitment, must be a 32 bytes hex string",
+            )
+                .into_response();
+        }
+    };
+
+    let blob_id = CelestiaBlobId {
+        height: query.height,
+        commitment,
+    }
+    .encode();
+    fetch_inclusion_data(&svc, &ctx, &caller, &blob_id, &headers, Duration::ZERO).await
+}
+
+/// Returns whether the `If-None-Match` header matches the ETag.
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|candidate| candidate.trim().trim_start_matches("W/"))
+        .any(|candidate| candidate == "*" || candidate == etag)
+}
+
+/// Answers a matching `If-None-Match` with a 304 once the blob is known to be readable by the
+/// caller, so the ETags don't reveal the blobs of the other callers. None when the request is
+/// served as usual.
+async fn not_modified(
+    svc: &AppState,
+    ctx: &CallContext,
+    caller: &str,
+    blob_id: &str,
+    headers: &HeaderMap,
+    etag: Option<&String>,
+) -> Option<Response> {
+    let etag = etag.filter(|etag| etag_matches(headers, etag))?;
+    match svc.da_svc.is_readable(ctx, caller, blob_id).await {
+        Ok(true) => {
+            Some((StatusCode::NOT_MODIFIED, [(header::ETAG, etag.clone())]).into_response())
+        }
+        Ok(false) => None,
+        Err(err) => {
+            tracing::error!("Error to fetch blob data: {}", err);
+            Some(da_error_response(&err, "Error to fetch blob data"))
+        }
+    }
+}
+
+/// Returns the height, commitment and namespace of a blob, from the index receipt of the caller
+/// dispatches or else from the blob_id.
+fn blob_metadata(
+    svc: &AppState,
+    caller: &str,
+    blob_id: &str,
+) -> (Option<u64>, Option<String>, Option<String>) {
+    let receipt = svc
+        .index
+        .get(blob_id)
+        .filter(|entry| entry.caller == caller)
+        .and_then(|entry| entry.receipt);
+    match receipt {
+        Some(receipt) => (receipt.height, Some(receipt.commitment), receipt.namespace),
+        None => match blob_id::locate(blob_id) {
+            Some(locator) => (locator.height, Some(hex::encode(locator.commitment)), None),
+            None => (None, None, None),
+        },
+    }
+}
+
+/// Serves the inclusion data, the blobs are immutable so the ETag is derived from the commitment
+/// and a matching `If-None-Match` is answered without the payload. A missing blob is polled for up
+/// to `wait`, within the deadline of the request.
+async fn fetch_inclusion_data(
+    svc: &AppState,
+    ctx: &CallContext,
+    caller: &str,
+    blob_id: &str,
+    headers: &HeaderMap,
+    wait: Duration,
+) -> Response {
+    let (height, commitment, namespace) = blob_metadata(svc, caller, blob_id);
+    let etag = commitment
+        .as_ref()
+        .map(|commitment| format!("\"{}\"", commitment));
+    if let Some(response) = not_modified(svc, ctx, caller, blob_id, headers, etag.as_ref()).await {
+        return response;
+    }
+
+    let deadline = Instant::now()
+        + ctx
+            .remaining()
+            .map_or(wait, |remaining| wait.min(remaining));
+    let mut delay = INCLUSION_POLL_BASE_DELAY;
+    let result = loop {
+        match svc.da_svc.get_inclusion_data(ctx, caller, blob_id).await {
+            Ok(None) if Instant::now() < deadline => {
+                tokio::time::sleep(delay.min(deadline.saturating_duration_since(Instant::now())))
+                    .await;
+                delay = (delay * 2).min(INCLUSION_POLL_MAX_DELAY);
+            }
+            result => break result,
+        }
+    };
+
+    match result {
+        Ok(Some(data)) => {
+            let size = data.data.len() as u64;
+            let response = Json(InclusionResponse {
+                data: offload(data.data.len(), move || hex::encode(data.data)).await,
+                height,
+                commitment,
+                namespace,
+                size,
+            });
+            match etag {
+                Some(etag) => ([(header::ETAG, etag)], response).into_response(),
+                None => response.into_response(),
+            }
+        }
         Ok(None) => axum::http::StatusCode::NOT_FOUND.into_response(),
         Err(err) => {
-            tracing::error!("Error to fetch blob data: {}", err.root_cause());
+            tracing::error!("Error to fetch blob data: {}", err);
+            da_error_response(&err, "Error to fetch blob data")
+        }
+    }
+}
+
+/// A byte range of a `Range` header.
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    /// `bytes=start-end`, the end is inclusive and open when None.
+    From { start: u64, end: Option<u64> },
+    /// `bytes=-length`, the last `length` bytes.
+    Suffix(u64),
+}
+
+/// Parses the `Range` header. Malformed headers and multiple ranges are ignored, per RFC 9110 the
+/// whole body is served then.
+fn byte_range(headers: &HeaderMap) -> Option<ByteRange> {
+    let value = headers.get(header::RANGE)?.to_str().ok()?;
+    let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    match (start, end) {
+        ("", "") => None,
+        ("", length) => Some(ByteRange::Suffix(length.parse().ok()?)),
+        (start, "") => Some(ByteRange::From {
+            start: start.parse().ok()?,
+            end: None,
+        }),
+        (start, end) => {
+            let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+            (start <= end).then_some(ByteRange::From {
+                start,
+                end: Some(end),
+            })
+        }
+    }
+}
+
+/// GET /blob/:blob_id/raw
+///
+/// Streams the payload as a binary body. A `Range` header selects a byte range of it, only the
+/// chunks of a chunked blob up to the end of the range are fetched, so the size in the
+/// `Content-Range` is `*` when the chunks after the range are skipped.
+pub async fn raw_blob_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    Path(blob_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (_, commitment, _) = blob_metadata(&svc, &caller, &blob_id);
+    let etag = commitment.map(|commitment| format!("\"{}\"", commitment));
+    if let Some(response) =
+        not_modified(&svc, &ctx, &caller, &blob_id, &headers, etag.as_ref()).await
+    {
+        return response;
+    }
+
+    let byte_range = byte_range(&headers);
+    // The suffix ranges need the payload size, the whole payload is fetched for them.
+    let fetched = match byte_range {
+        Some(ByteRange::From { start, end }) => {
+            start..end.map_or(u64::MAX, |end| end.saturating_add(1))
+        }
+        _ => 0..u64::MAX,
+    };
+    let part = match svc
+        .da_svc
+        .get_inclusion_range(&ctx, &caller, &blob_id, fetched)
+        .await
+    {
+        Ok(Some(part)) => part,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!("Error to fetch blob data: {}", err);
+            return da_error_response(&err, "Error to fetch blob data");
+        }
+    };
+
+    let size = part.size.map_or("*".to_string(), |size| size.to_string());
+    let (status, data, first) = match byte_range {
+        None => (StatusCode::OK, Bytes::from(part.data), 0),
+        Some(ByteRange::Suffix(length)) => {
+            let data = Bytes::from(part.data);
+            let first = data.len().saturating_sub(length as usize);
             (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Error to fetch blob data: {}", err),
+                StatusCode::PARTIAL_CONTENT,
+                data.slice(first..),
+                first as u64,
             )
-                .into_response()
         }
+        Some(ByteRange::From { start, .. }) => {
+            (StatusCode::PARTIAL_CONTENT, Bytes::from(part.data), start)
+        }
+    };
+    if status == StatusCode::PARTIAL_CONTENT && data.is_empty() {
+        return (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            [(header::CONTENT_RANGE, format!("bytes */{}", size))],
+        )
+            .into_response();
+    }
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, data.len())
+        .header(header::ACCEPT_RANGES, "bytes");
+    if status == StatusCode::PARTIAL_CONTENT {
+        response = response.header(
+            header::CONTENT_RANGE,
+            format!("bytes {}-{}/{}", first, first + data.len() as u64 - 1, size),
+        );
+    }
+    if let Some(etag) = etag {
+        response = response.header(header::ETAG, etag);
+    }
+
+    let frames = (0..data.len()).step_by(RAW_FRAME_SIZE).map(move |start| {
+        Ok::<_, Infallible>(data.slice(start..(start + RAW_FRAME_SIZE).min(data.len())))
+    });
+    response
+        .body(Body::from_stream(stream::iter(frames)))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// GET /status/:blob_id
+pub async fn status_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    Path(blob_id): Path<String>,
+) -> impl IntoResponse {
+    let earliest_available_height = match svc.da_svc.earliest_available_height(&ctx, &caller).await
+    {
+        Ok(height) => height,
+        Err(err) => {
+            tracing::error!("Error to get the earliest available height: {}", err);
+            return da_error_response(&err, "Error to get the earliest available height");
+        }
+    };
+    let height = blob_id::locate(&blob_id).and_then(|locator| locator.height);
+    let aged_out = matches!(
+        (height, earliest_available_height),
+        (Some(height), Some(earliest)) if height < earliest
+    );
+
+    let status = match svc.da_svc.get_inclusion_data(&ctx, &caller, &blob_id).await {
+        Ok(Some(_)) if aged_out => BlobStatus::Archived,
+        Ok(Some(_)) => BlobStatus::Available,
+        Ok(None) => BlobStatus::NotFound,
+        Err(DAError::Pruned { .. }) => BlobStatus::Pruned,
+        Err(err) => {
+            tracing::error!("Error to fetch blob data: {}", err);
+            return da_error_response(&err, "Error to fetch blob data");
+        }
+    };
+
+    // The expiry is informative, the status is returned without it.
+    let expires_at = match status {
+        BlobStatus::Available => svc
+            .da_svc
+            .expiry_for(&ctx, &caller, &blob_id)
+            .await
+            .unwrap_or_else(|err| {
+                tracing::warn!("Error to get the expiry of {}: {}", blob_id, err);
+                None
+            }),
+        _ => None,
+    };
+
+    let entry = svc
+        .index
+        .get(&blob_id)
+        .filter(|entry| entry.caller == caller);
+    let confirmations = entry.as_ref().and_then(|entry| entry.confirmations);
+    let depth = svc.confirmations.depth();
+
+    Json(BlobStatusResponse {
+        blob_id,
+        status,
+        height,
+        earliest_available_height,
+        retention_secs: svc
+            .da_svc
+            .retention(&caller)
+            .map(|retention| retention.as_secs()),
+        expires_at,
+        confirmations,
+        confirmed: confirmations.map(|confirmations| confirmations >= depth),
+        metadata: entry.map(|entry| entry.metadata).unwrap_or_default(),
+    })
+    .into_response()
+}
+
+/// GET /proof/:blob_id
+///
+/// Returns the inclusion proof of the blob against the data root of its block.
+pub async fn proof_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    Path(blob_id): Path<String>,
+) -> impl IntoResponse {
+    match svc
+        .proofs
+        .get_inclusion_proof(&ctx, &caller, &blob_id)
+        .await
+    {
+        Ok(Some(proof)) => Json(proof).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Blob not found").into_response(),
+        Err(err) => {
+            tracing::error!("Error to get the inclusion proof: {}", err);
+            da_error_response(&err, "Error to get the inclusion proof")
+        }
+    }
+}
+
+/// GET /verify_onchain/:blob_id
+///
+/// Returns whether the blob is provable on L1, the data root of its block being committed there.
+pub async fn verify_onchain_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    Path(blob_id): Path<String>,
+) -> impl IntoResponse {
+    let Some(onchain) = &svc.onchain else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match onchain.verify(&ctx, &caller, &blob_id).await {
+        Ok(Some(response)) => Json(response).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Blob not found").into_response(),
+        Err(err) => {
+            tracing::error!("Error to verify the blob against L1: {}", err);
+            da_error_response(&err, "Error to verify the blob against L1")
+        }
+    }
+}
+
+/// GET /batch/:batch_number
+///
+/// Returns the DA footprint of the last dispatch of the batch by the caller.
+pub async fn batch_footprint_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Path(batch_number): Path<u32>,
+) -> impl IntoResponse {
+    match svc.index.last_batch_dispatch(&caller, batch_number) {
+        Some(entry) => Json(BatchFootprintResponse::from(entry)).into_response(),
+        None => (StatusCode::NOT_FOUND, "No dispatch of the batch").into_response(),
+    }
+}
+
+/// GET /inclusion/:blob_id/chunks
+///
+/// Returns the chunks of a chunked blob, so the large payloads can be fetched one chunk at a time.
+pub async fn chunk_manifest_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    Path(blob_id): Path<String>,
+) -> impl IntoResponse {
+    let chunk_ids = match svc.da_svc.get_chunk_ids(&ctx, &caller, &blob_id).await {
+        Ok(Some(chunk_ids)) => chunk_ids,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!("Error to fetch the chunks of {}: {}", blob_id, err);
+            return da_error_response(&err, "Error to fetch the chunks");
+        }
+    };
+
+    // The receipts of the chunks are indexed with the receipt of the blob.
+    let receipts = svc
+        .index
+        .get(&blob_id)
+        .filter(|entry| entry.caller == caller)
+        .and_then(|entry| entry.receipt)
+        .map(|receipt| receipt.chunks)
+        .unwrap_or_default();
+    let chunks: Vec<FootprintBlob> = chunk_ids
+        .into_iter()
+        .map(
+            |chunk_id| match receipts.iter().find(|chunk| chunk.blob_id == chunk_id) {
+                Some(chunk) => FootprintBlob {
+                    blob_id: chunk_id,
+                    height: chunk.height,
+                    commitment: Some(chunk.commitment.clone()),
+                    size: Some(chunk.size),
+                },
+                None => {
+                    let locator = blob_id::locate(&chunk_id);
+                    FootprintBlob {
+                        height: locator.as_ref().and_then(|locator| locator.height),
+                        commitment: locator.map(|locator| hex::encode(locator.commitment)),
+                        blob_id: chunk_id,
+                        size: None,
+                    }
+                }
+            },
+        )
+        .collect();
+    let total_size = chunks.iter().map(|chunk| chunk.size).sum();
+
+    Json(ChunkManifestResponse {
+        blob_id,
+        chunks,
+        total_size,
+    })
+    .into_response()
+}
+
+/// GET /inclusion/:blob_id/chunks/:n
+///
+/// Returns the data of the chunk `n` of a chunked blob as stored, the chunks of an enveloped
+/// payload are decoded once concatenated.
+pub async fn chunk_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    Path((blob_id, index)): Path<(String, usize)>,
+) -> impl IntoResponse {
+    match svc.da_svc.get_chunk(&ctx, &caller, &blob_id, index).await {
+        Ok(Some(data)) => (
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            Bytes::from(data),
+        )
+            .into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!("Error to fetch chunk {} of {}: {}", index, blob_id, err);
+            da_error_response(&err, "Error to fetch the chunk")
+        }
+    }
+}
+
+/// POST /redispatch/:blob_id
+pub async fn redispatch_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    Path(blob_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Some(response) = maintenance_response(&svc) {
+        return response;
+    }
+    let api_version = match api_version(&headers) {
+        Ok(version) => version,
+        Err(err) => return (StatusCode::BAD_REQUEST, err).into_response(),
+    };
+
+    let Some(entry) = svc
+        .index
+        .get(&blob_id)
+        .filter(|entry| entry.caller == caller)
+    else {
+        return (StatusCode::NOT_FOUND, "Blob not found in the index").into_response();
+    };
+    let Some(data) = svc.payload_cache.get(&blob_id).await else {
+        return (
+            StatusCode::NOT_FOUND,
+            "The payload of the blob is not cached, it can't be re-dispatched",
+        )
+            .into_response();
+    };
+    let (data, payload_hash) = offload(data.len(), move || {
+        let payload_hash = hex::encode(AttestationSvc::payload_hash(&data));
+        (data, payload_hash)
+    })
+    .await;
+    if payload_hash != entry.payload_hash {
+        tracing::error!("The cached payload of {} is corrupted", blob_id);
+        return (
+            StatusCode::CONFLICT,
+            "The cached payload doesn't match the dispatched one",
+        )
+            .into_response();
+    }
+
+    let size = data.len() as u64;
+    if let Err(err) = svc.fee_budget.check() {
+        tracing::warn!("{}", err);
+        return fee_budget_response(&err);
+    }
+    let reservation = match svc.usage_svc.reserve(&caller, size) {
+        Ok(reservation) => reservation,
+        Err(err) => {
+            tracing::warn!("{}", err);
+            return (StatusCode::TOO_MANY_REQUESTS, err.to_string()).into_response();
+        }
+    };
+    let lock = match svc
+        .batch_locks
+        .claim_redispatch(&caller, entry.batch_number, &blob_id)
+        .await
+    {
+        Ok(BatchClaim::Acquired(lock)) => lock,
+        Ok(BatchClaim::Submitted {
+            blob_id: redispatched,
+        }) => {
+            svc.usage_svc.release(reservation);
+            tracing::info!(
+                "Batch {} of {} was re-dispatched by another replica as {}",
+                entry.batch_number,
+                caller,
+                redispatched
+            );
+            return Json(RedispatchResponse {
+                dispatch: versioned(DispatchResponse::from(redispatched), api_version),
+                redispatch_of: blob_id,
+            })
+            .into_response();
+        }
+        claim => {
+            svc.usage_svc.release(reservation);
+            return batch_claim_response(claim, &caller, entry.batch_number, api_version);
+        }
+    };
+
+    match svc
+        .da_svc
+        .dispatch_blob(
+            &ctx,
+            &caller,
+            entry.batch_number,
+            entry.metadata.get(PROTOCOL_VERSION_KEY).map(String::as_str),
+            data.clone(),
+            DispatchPriority::Normal,
+        )
+        .await
+    {
+        Ok(resp) => {
+            svc.batch_locks.complete(lock, &resp.blob_id).await;
+            svc.usage_svc
+                .record_dispatch(&caller, size, resp.receipt.as_ref());
+            svc.fee_budget.record(resp.receipt.as_ref());
+            svc.sequence.record(&caller, entry.batch_number);
+            svc.index.record(IndexEntry {
+                blob_id: resp.blob_id.clone(),
+                caller,
+                dispatched_at: Utc::now(),
+                // Content addressed backends return the same blob_id.
+                redispatch_of: (resp.blob_id != blob_id).then(|| blob_id.clone()),
+                receipt: resp.receipt.clone(),
+                confirmations: None,
+                archive: None,
+                attestation: resp.attestation.clone(),
+                ..entry
+            });
+            svc.payload_cache.put(&resp.blob_id, &data).await;
+            svc.webhooks
+                .on_submitted(&resp.blob_id, entry.batch_number, None);
+            tracing::info!("Re-dispatched {} as {}", blob_id, resp.blob_id);
+
+            Json(RedispatchResponse {
+                dispatch: versioned(resp, api_version),
+                redispatch_of: blob_id,
+            })
+            .into_response()
+        }
+        Err(err) => {
+            // The batch is still submitted as the blob, the lock is released back to it.
+            svc.batch_locks.complete(lock, &blob_id).await;
+            svc.usage_svc.release(reservation);
+            svc.usage_svc.record_failure(&caller);
+            tracing::error!("Error to re-dispatch {}: {}", blob_id, err);
+            da_error_response(&err, "Error to re-dispatch the blob data")
+        }
+    }
+}
+
+/// POST /verify
+pub async fn verify_handler(
+    State(svc): State<Arc<AppState>>,
+    Extension(Caller(caller)): Extension<Caller>,
+    Extension(ctx): Extension<CallContext>,
+    payload: Result<Json<VerifyRequest>, JsonRejection>,
+) -> impl IntoResponse {
+    let payload = match payload {
+        Ok(Json(p)) => p,
+        Err(err) => {
+            tracing::error!("Invalid JSON: {}", err);
+            return (StatusCode::BAD_REQUEST, "Invalid JSON body").into_response();
+        }
+    };
+
+    let expected_hash = match (payload.expected_hash, payload.data) {
+        (Some(hash), None) => hash.trim_start_matches("0x").to_lowercase(),
+        (None, Some(data)) => match offload(data.len(), move || {
+            hex::decode(data.trim_start_matches("0x"))
+                .map(|data| hex::encode(AttestationSvc::payload_hash(&data)))
+        })
+        .await
+        {
+            Ok(hash) => hash,
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    "Invalid data format, must be a hex string",
+                )
+                    .into_response();
+            }
+        },
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Exactly one of expected_hash or data is required",
+            )
+                .into_response();
+        }
+    };
+
+    let data = match svc
+        .da_svc
+        .get_inclusion_data(&ctx, &caller, &payload.blob_id)
+        .await
+    {
+        Ok(Some(data)) => data.data,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            tracing::error!("Error to fetch blob data: {}", err);
+            return da_error_response(&err, "Error to fetch blob data");
+        }
+    };
+
+    let size = data.len();
+    let payload_hash = offload(size, move || {
+        hex::encode(AttestationSvc::payload_hash(&data))
+    })
+    .await;
+    let (height, commitment) = match blob_id::locate(&payload.blob_id) {
+        Some(locator) => (locator.height, Some(hex::encode(locator.commitment))),
+        None => (None, None),
+    };
+
+    // The dispatch metadata of the other callers is not disclosed.
+    let entry = svc
+        .index
+        .get(&payload.blob_id)
+        .filter(|entry| entry.caller == caller);
+
+    Json(VerifyResponse {
+        verified: payload_hash == expected_hash,
+        expected_hash,
+        payload_hash,
+        size,
+        height,
+        commitment,
+        batch_number: entry.as_ref().map(|entry| entry.batch_number),
+        attestation: entry.and_then(|entry| {
+            svc.attestation_svc
+                .get(&payload.blob_id)
+                .or(entry.attestation)
+        }),
+        blob_id: payload.blob_id,
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::dispatch::{ChunkReceipt, DispatchReceipt};
+
+    fn range(value: &str) -> Option<ByteRange> {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, value.parse().unwrap());
+        byte_range(&headers)
+    }
+
+    #[test]
+    fn test_batch_footprint_sums_the_chunks() {
+        let receipt = |size| DispatchReceipt {
+            height: Some(7),
+            commitment: "aa".to_string(),
+            namespace: Some("bb".to_string()),
+            size,
+            backend: DaBackend::Celestia,
+            submitted_at: Utc::now(),
+            fee: None,
+            chunks: vec![],
+        };
+        let entry = IndexEntry {
+            blob_id: "manifest".to_string(),
+            batch_number: 3,
+            caller: "anonymous".to_string(),
+            size: 250,
+            payload_hash: String::new(),
+            dispatched_at: Utc::now(),
+            redispatch_of: None,
+            receipt: Some(DispatchReceipt {
+                chunks: vec![
+                    ChunkReceipt::new("chunk-0".to_string(), receipt(200)),
+                    ChunkReceipt::new("chunk-1".to_string(), receipt(60)),
+                ],
+                ..receipt(80)
+            }),
+            confirmations: None,
+            archive: None,
+            metadata: BTreeMap::new(),
+            attestation: None,
+        };
+
+        let footprint = BatchFootprintResponse::from(entry.clone());
+        assert_eq!(footprint.blob.blob_id, "manifest");
+        assert_eq!(footprint.chunks[1].blob_id, "chunk-1");
+        assert_eq!(footprint.namespace.as_deref(), Some("bb"));
+        assert_eq!(footprint.total_size, Some(340));
+
+        // Without a receipt, only the location encoded in the blob_id is known.
+        let blob_id = CelestiaBlobId {
+            height: 9,
+            commitment: [1; 32],
+        }
+        .encode();
+        let footprint = BatchFootprintResponse::from(IndexEntry {
+            blob_id,
+            receipt: None,
+            ..entry
+        });
+        assert_eq!(footprint.blob.height, Some(9));
+        assert_eq!(footprint.blob.commitment, Some(hex::encode([1; 32])));
+        assert_eq!(footprint.total_size, None);
+    }
+
+    #[test]
+    fn test_metadata_tags_are_limited() {
+        let tags = |tags: &[(&str, usize)]| {
+            tags.iter()
+                .map(|(key, len)| (key.to_string(), "x".repeat(*len)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        assert!(validate_metadata(&tags(&[("protocol_version", 2), ("l1.tx-hint", 66)])).is_ok());
+        assert!(validate_metadata(&tags(&[("batch hash", 1)])).is_err());
+        assert!(validate_metadata(&tags(&[("", 1)])).is_err());
+        assert!(validate_metadata(&tags(&[("hash", MAX_METADATA_VALUE_LEN + 1)])).is_err());
+
+        let keys: Vec<String> = (0..=MAX_METADATA_TAGS).map(|i| format!("k{i}")).collect();
+        let too_many = keys.iter().map(|key| (key.as_str(), 1)).collect::<Vec<_>>();
+        assert!(validate_metadata(&tags(&too_many)).is_err());
+    }
+
+    #[test]
+    fn test_parse_wait() {
+        assert_eq!(parse_wait("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_wait("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_wait("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_wait("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_wait("s"), None);
+        assert_eq!(parse_wait("1h"), None);
+        assert_eq!(parse_wait(&format!("{}m", u64::MAX)), None);
+    }
+
+    #[test]
+    fn test_byte_range() {
+        assert_eq!(
+            range("bytes=0-99"),
+            Some(ByteRange::From {
+                start: 0,
+                end: Some(99)
+            })
+        );
+        assert_eq!(
+            range("bytes=900-"),
+            Some(ByteRange::From {
+                start: 900,
+                end: None
+            })
+        );
+        assert_eq!(range("bytes=-100"), Some(ByteRange::Suffix(100)));
+
+        // Malformed and multiple ranges are ignored.
+        assert_eq!(range("bytes=5-1"), None);
+        assert_eq!(range("bytes=0-1,5-9"), None);
+        assert_eq!(range("items=0-1"), None);
     }
 }
@@ -14,3 +14,16 @@ pub async fn health_check_handler(State(svc): State<Arc<AppState>>) -> impl Into
             .into_response(),
     }
 }
+
+/// GET /health/ready
+pub async fn readiness_handler(State(svc): State<Arc<AppState>>) -> impl IntoResponse {
+    match svc.health_check.readiness().await {
+        Ok(resp) if resp.ready => Json(resp).into_response(),
+        Ok(resp) => (axum::http::StatusCode::SERVICE_UNAVAILABLE, Json(resp)).into_response(),
+        Err(err) => (
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            err.to_string(),
+        )
+            .into_response(),
+    }
+}
@@ -1,2 +1,4 @@
+pub mod admin;
+pub mod attestation;
 pub mod da;
 pub mod health_check;
@@ -1,6 +1,7 @@
 pub mod clients;
 pub mod config;
 pub mod handlers;
+pub mod middlewares;
 pub mod services;
 pub mod state;
 pub mod types;
@@ -1,23 +1,31 @@
+use std::{net::SocketAddr, sync::Arc};
+
+#[cfg(unix)]
+use tokio::signal::unix::{SignalKind, signal};
 use tokio::sync::watch;
+use tower::{Layer, util::MapResponse};
 use tower_http::trace::TraceLayer;
-use via_core_ext::{config::Config, state::AppState};
+use via_core_ext::{
+    config::Config,
+    services::{lifecycle::stopped, logging::LoggingSvc, metrics::track_connection},
+    state::AppState,
+};
 
-use axum::http::{Request, Response};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use vise_exporter::MetricsExporter;
+use axum::{
+    Extension,
+    http::{Request, Response},
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
 
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::from_default_env())
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     let config = Config::from_env()?;
+    let logging = Arc::new(LoggingSvc::init(&config)?);
+    tracing::info!("Start with DA backend {:?}", config.da_backend);
 
-    let state = AppState::new(config.clone()).await?;
+    let state = AppState::new(config.clone(), logging).await?;
+    let supervisor = state.supervisor.clone();
 
     let app = state.into_router().layer(
         TraceLayer::new_for_http()
@@ -53,18 +61,69 @@ async fn main() -> anyhow::Result<()> {
             ),
     );
 
-    let (shutdown_sender, mut shutdown_receiver) = watch::channel(());
-    let exporter = MetricsExporter::default().with_graceful_shutdown(async move {
-        shutdown_receiver.changed().await.ok();
-    });
-    tokio::spawn(exporter.start(config.metrics_address.parse().unwrap()));
-
     let listener = tokio::net::TcpListener::bind(&config.app_address).await?;
     tracing::info!("🚀 Server listening on {}", config.app_address);
 
-    axum::serve(listener, app).await?;
+    // Every connection service holds a guard, dropped once the connection is closed.
+    let make_service = MapResponse::new(
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+        |service| Extension(Arc::new(track_connection())).layer(service),
+    );
+    // A single signal stops the server, then the background components, the metrics last.
+    let (shutdown, _) = watch::channel(false);
+    let mut signal_received = shutdown.subscribe();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        shutdown.send_replace(true);
+    });
+
+    let mut server_stop = signal_received.clone();
+    let serve = async {
+        axum::serve(listener, make_service)
+            .with_graceful_shutdown(async move { stopped(&mut server_stop).await })
+            .await?;
+        supervisor.shutdown().await;
+        anyhow::Ok(())
+    };
+    tokio::pin!(serve);
+
+    tokio::select! {
+        result = &mut serve => return result,
+        _ = stopped(&mut signal_received) => {}
+    }
+    // The in-flight requests, e.g. the long-polls, and the components get a hard deadline.
+    match tokio::time::timeout(config.shutdown_timeout, serve).await {
+        Ok(result) => result,
+        Err(_) => {
+            tracing::error!(
+                "The shutdown didn't complete within {:?}, exiting",
+                config.shutdown_timeout
+            );
+            std::process::exit(1);
+        }
+    }
+}
 
-    shutdown_sender.send_replace(());
+/// Resolves on Ctrl+C or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    Ok(())
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("Shutting down");
 }
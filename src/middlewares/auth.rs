@@ -0,0 +1,189 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+const X_API_KEY: &str = "x-api-key";
+
+/// The caller identity used when no API keys are configured.
+pub const ANONYMOUS_CALLER: &str = "anonymous";
+
+/// The authenticated caller, stored in the request extensions by `api_key_middleware`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller(pub String);
+
+/// The header of the accepted JWTs.
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+}
+
+/// The claims of the accepted JWTs, the subject is the caller.
+#[derive(Debug, Deserialize)]
+struct JwtClaims {
+    sub: String,
+    exp: i64,
+    #[serde(default)]
+    nbf: Option<i64>,
+}
+
+/// `ApiKeys` maps the API keys to the caller names, and accepts the HS256 JWTs signed with the
+/// JWT secret as their subject. Authentication is disabled without keys and secret.
+#[derive(Clone, Default)]
+pub struct ApiKeys {
+    callers: HashMap<String, String>,
+    jwt_secret: Option<Vec<u8>>,
+}
+
+impl std::fmt::Debug for ApiKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKeys")
+            .field("callers", &self.callers.len())
+            .field("jwt", &self.jwt_secret.is_some())
+            .finish()
+    }
+}
+
+impl ApiKeys {
+    /// Creates the key set from (caller, api key) pairs.
+    pub fn new(keys: Vec<(String, String)>) -> Self {
+        Self {
+            callers: keys
+                .into_iter()
+                .map(|(caller, key)| (key, caller))
+                .collect(),
+            jwt_secret: None,
+        }
+    }
+
+    /// Accepts the JWTs signed with the secret, when set.
+    pub fn with_jwt_secret(self, secret: Option<String>) -> Self {
+        Self {
+            jwt_secret: secret.map(String::into_bytes),
+            ..self
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.callers.is_empty() || self.jwt_secret.is_some()
+    }
+
+    pub fn caller(&self, api_key: &str) -> Option<&str> {
+        self.callers.get(api_key).map(String::as_str)
+    }
+
+    /// Returns the subject of a JWT signed with the secret with HS256, unless it expired.
+    pub fn jwt_subject(&self, token: &str) -> Option<String> {
+        let secret = self.jwt_secret.as_ref()?;
+        let (signed, signature) = token.rsplit_once('.')?;
+        let (header, claims) = signed.split_once('.')?;
+
+        let header: JwtHeader = serde_json::from_slice(&BASE64_URL.decode(header).ok()?).ok()?;
+        if header.alg != "HS256" {
+            return None;
+        }
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).ok()?;
+        mac.update(signed.as_bytes());
+        mac.verify_slice(&BASE64_URL.decode(signature).ok()?).ok()?;
+
+        let claims: JwtClaims = serde_json::from_slice(&BASE64_URL.decode(claims).ok()?).ok()?;
+        let now = Utc::now().timestamp();
+        if claims.sub.is_empty() || claims.exp <= now || claims.nbf.is_some_and(|nbf| nbf > now) {
+            return None;
+        }
+        Some(claims.sub)
+    }
+}
+
+/// Resolves the caller from the API key or the JWT of the `Authorization: Bearer <key>` or
+/// `X-Api-Key` header, rejects unknown keys and invalid JWTs with 401 when authentication is
+/// enabled.
+pub async fn api_key_middleware(
+    State(api_keys): State<Arc<ApiKeys>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let caller = if api_keys.is_enabled() {
+        let headers = request.headers();
+        let api_key = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .or_else(|| headers.get(X_API_KEY).and_then(|value| value.to_str().ok()));
+
+        let caller = api_key.map(str::trim).and_then(|key| {
+            api_keys
+                .caller(key)
+                .map(str::to_string)
+                .or_else(|| api_keys.jwt_subject(key))
+        });
+        match caller {
+            Some(caller) => caller,
+            None => return (StatusCode::UNAUTHORIZED, "Invalid API key").into_response(),
+        }
+    } else {
+        ANONYMOUS_CALLER.to_string()
+    };
+
+    request.extensions_mut().insert(Caller(caller));
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn jwt(secret: &[u8], alg: &str, claims: serde_json::Value) -> String {
+        let signed = format!(
+            "{}.{}",
+            BASE64_URL.encode(json!({ "alg": alg, "typ": "JWT" }).to_string()),
+            BASE64_URL.encode(claims.to_string())
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(signed.as_bytes());
+        format!(
+            "{}.{}",
+            signed,
+            BASE64_URL.encode(mac.finalize().into_bytes())
+        )
+    }
+
+    #[test]
+    fn test_jwt_subjects_are_callers() {
+        let keys = ApiKeys::new(vec![("a".to_string(), "ka".to_string())])
+            .with_jwt_secret(Some("secret".to_string()));
+        let exp = Utc::now().timestamp() + 60;
+
+        let token = jwt(b"secret", "HS256", json!({ "sub": "b", "exp": exp }));
+        assert_eq!(keys.jwt_subject(&token).as_deref(), Some("b"));
+        assert_eq!(keys.caller("ka"), Some("a"));
+
+        // Other secrets, algorithms, expired and future tokens are rejected.
+        let other = jwt(b"other", "HS256", json!({ "sub": "b", "exp": exp }));
+        let none = jwt(b"secret", "none", json!({ "sub": "b", "exp": exp }));
+        let expired = jwt(b"secret", "HS256", json!({ "sub": "b", "exp": exp - 120 }));
+        let future = jwt(
+            b"secret",
+            "HS256",
+            json!({ "sub": "b", "exp": exp, "nbf": exp }),
+        );
+        let unbounded = jwt(b"secret", "HS256", json!({ "sub": "b" }));
+        for token in [other, none, expired, future, unbounded, "ka".to_string()] {
+            assert_eq!(keys.jwt_subject(&token), None);
+        }
+
+        // The JWTs are not accepted without a secret.
+        assert_eq!(ApiKeys::new(vec![]).jwt_subject(&token), None);
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::clients::da_clients::types::CallContext;
+
+/// The time the caller waits for the response, in seconds.
+pub const REQUEST_TIMEOUT_HEADER: &str = "x-request-timeout";
+
+/// Returns the timeout requested by the caller, if any.
+fn requested_timeout(request: &Request) -> Result<Option<Duration>, ()> {
+    let Some(value) = request.headers().get(REQUEST_TIMEOUT_HEADER) else {
+        return Ok(None);
+    };
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map(|secs| Some(Duration::from_secs_f64(secs)))
+        .ok_or(())
+}
+
+/// Stores the `CallContext` of the request in its extensions. The deadline is the
+/// `X-Request-Timeout` of the caller capped by the configured request timeout, and the context is
+/// cancelled once the request is dropped, e.g. when the caller disconnected.
+pub async fn call_context_middleware(
+    State(max_timeout): State<Option<Duration>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let timeout = match requested_timeout(&request) {
+        Ok(Some(timeout)) => Some(max_timeout.map_or(timeout, |max| timeout.min(max))),
+        Ok(None) => max_timeout,
+        Err(()) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Invalid X-Request-Timeout, must be a positive number of seconds",
+            )
+                .into_response();
+        }
+    };
+
+    let ctx = timeout.map_or_else(CallContext::background, CallContext::with_timeout);
+    let _cancel_on_drop = ctx.cancel_on_drop();
+    request.extensions_mut().insert(ctx);
+    next.run(request).await
+}
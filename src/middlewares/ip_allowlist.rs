@@ -0,0 +1,183 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ipnet::IpNet;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// The resolved IP of the caller, stored in the request extensions by `ip_allowlist_middleware`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// `IpAllowlist` restricts a group of routes to the callers in the allowed CIDRs.
+#[derive(Debug, Clone, Default)]
+pub struct IpAllowlist {
+    /// The allowed CIDRs, every caller is allowed when empty unless `deny_when_empty`.
+    allowed: Vec<IpNet>,
+    /// The proxies trusted to set the `X-Forwarded-For` header.
+    trusted_proxies: Vec<IpNet>,
+    deny_when_empty: bool,
+}
+
+impl IpAllowlist {
+    pub fn new(allowed: Vec<IpNet>, trusted_proxies: Vec<IpNet>) -> Self {
+        Self {
+            allowed,
+            trusted_proxies,
+            deny_when_empty: false,
+        }
+    }
+
+    /// The allowlist of the routes without other authentication, no caller is allowed when empty.
+    pub fn strict(allowed: Vec<IpNet>, trusted_proxies: Vec<IpNet>) -> Self {
+        Self {
+            deny_when_empty: true,
+            ..Self::new(allowed, trusted_proxies)
+        }
+    }
+
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        if self.allowed.is_empty() {
+            return !self.deny_when_empty;
+        }
+        self.allowed.iter().any(|net| net.contains(&ip))
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Resolves the client IP from the peer address and the `X-Forwarded-For` header.
+    ///
+    /// The header is only used when the peer is a trusted proxy. It is read from right to left and
+    /// the first address which is not a trusted proxy is the client, so a client can't spoof its IP
+    /// by prepending entries to the header.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted_proxy(peer) {
+            return peer;
+        }
+
+        let forwarded: Vec<IpAddr> = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|entry| entry.trim().parse().ok())
+            .collect();
+
+        let mut client = peer;
+        for ip in forwarded.into_iter().rev() {
+            client = ip;
+            if !self.is_trusted_proxy(ip) {
+                break;
+            }
+        }
+
+        client
+    }
+}
+
+/// Parses a comma separated list of CIDRs, plain IPs are accepted as single host networks.
+pub fn parse_cidrs(value: &str) -> anyhow::Result<Vec<IpNet>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| anyhow::anyhow!("Invalid CIDR: {}", entry))
+        })
+        .collect()
+}
+
+/// Rejects the callers outside of the allowlist with 403.
+pub async fn ip_allowlist_middleware(
+    State(allowlist): State<Arc<IpAllowlist>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(peer) = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+    else {
+        tracing::error!("Missing peer address, the server must be started with connect info");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let client_ip = allowlist.client_ip(peer, request.headers());
+    if !allowlist.is_allowed(client_ip) {
+        tracing::warn!("Rejected request from {} to {}", client_ip, request.uri());
+        return (StatusCode::FORBIDDEN, "Forbidden").into_response();
+    }
+
+    request.extensions_mut().insert(ClientIp(client_ip));
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn headers(forwarded_for: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            X_FORWARDED_FOR,
+            HeaderValue::from_str(forwarded_for).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let allowlist = IpAllowlist::new(parse_cidrs("10.0.0.0/8, 192.168.1.7").unwrap(), vec![]);
+
+        assert!(allowlist.is_allowed(ip("10.1.2.3")));
+        assert!(allowlist.is_allowed(ip("192.168.1.7")));
+        assert!(!allowlist.is_allowed(ip("192.168.1.8")));
+        assert!(IpAllowlist::default().is_allowed(ip("1.2.3.4")));
+        assert!(!IpAllowlist::strict(vec![], vec![]).is_allowed(ip("127.0.0.1")));
+        assert!(parse_cidrs("10.0.0.0/33").is_err());
+    }
+
+    #[test]
+    fn test_forwarded_for_ignored_from_untrusted_peer() {
+        let allowlist = IpAllowlist::new(vec![], parse_cidrs("10.0.0.0/8").unwrap());
+
+        let client = allowlist.client_ip(ip("1.2.3.4"), &headers("10.1.1.1"));
+
+        assert_eq!(client, ip("1.2.3.4"));
+    }
+
+    #[test]
+    fn test_forwarded_for_resolved_behind_trusted_proxies() {
+        let allowlist = IpAllowlist::new(vec![], parse_cidrs("10.0.0.0/8").unwrap());
+
+        // The spoofed leftmost entry is ignored, the client is the first untrusted hop.
+        let client = allowlist.client_ip(ip("10.0.0.1"), &headers("6.6.6.6, 1.2.3.4, 10.0.0.2"));
+        assert_eq!(client, ip("1.2.3.4"));
+
+        // Only trusted hops, the leftmost one is the client.
+        let client = allowlist.client_ip(ip("10.0.0.1"), &headers("10.0.0.3, 10.0.0.2"));
+        assert_eq!(client, ip("10.0.0.3"));
+
+        // No header, the proxy itself is the client.
+        let client = allowlist.client_ip(ip("10.0.0.1"), &HeaderMap::new());
+        assert_eq!(client, ip("10.0.0.1"));
+    }
+}
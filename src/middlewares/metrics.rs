@@ -0,0 +1,141 @@
+use std::time::Instant;
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use futures_util::StreamExt;
+
+use crate::services::metrics::{HTTP_METRICS, RouteLabels, RouteStatusLabels};
+
+/// The route label of the requests matching no route.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Calls `on_end` with the number of bytes read from the body once it is dropped.
+struct BodySize {
+    bytes: u64,
+    on_end: Option<Box<dyn FnOnce(u64) + Send>>,
+}
+
+impl BodySize {
+    fn add(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+    }
+}
+
+impl Drop for BodySize {
+    fn drop(&mut self) {
+        if let Some(on_end) = self.on_end.take() {
+            on_end(self.bytes);
+        }
+    }
+}
+
+/// Reports the size of a body to `on_end`, right away when the size is known or else once the
+/// body is streamed.
+fn observe_size(body: Body, on_end: impl FnOnce(u64) + Send + 'static) -> Body {
+    if let Some(size) = body.size_hint().exact() {
+        on_end(size);
+        return body;
+    }
+
+    let mut size = BodySize {
+        bytes: 0,
+        on_end: Some(Box::new(on_end)),
+    };
+    Body::from_stream(body.into_data_stream().inspect(move |chunk| {
+        if let Ok(chunk) = chunk {
+            size.add(chunk.len());
+        }
+    }))
+}
+
+/// Records the request and response body sizes and the latency of the requests per route, method
+/// and status, so the HTTP handling can be told apart from the DA calls.
+pub async fn http_metrics_middleware(request: Request, next: Next) -> Response {
+    let started_at = Instant::now();
+    let labels = RouteLabels {
+        method: request.method().to_string(),
+        route: request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or(UNMATCHED_ROUTE, MatchedPath::as_str)
+            .to_string(),
+    };
+
+    let request = request.map(|body| {
+        let labels = labels.clone();
+        observe_size(body, move |size| {
+            HTTP_METRICS.request_size[&labels].observe(size)
+        })
+    });
+    let response = next.run(request).await;
+
+    HTTP_METRICS.request_latency[&RouteStatusLabels {
+        method: labels.method.clone(),
+        route: labels.route.clone(),
+        status: response.status().as_u16(),
+    }]
+        .observe(started_at.elapsed());
+
+    response.map(|body| {
+        observe_size(body, move |size| {
+            HTTP_METRICS.response_size[&labels].observe(size)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, middleware, routing::get};
+    use futures_util::stream;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn labels(route: &str) -> RouteLabels {
+        RouteLabels {
+            method: "GET".to_string(),
+            route: route.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sizes_are_recorded_per_route() {
+        let app = Router::new()
+            .route("/blob/:id", get(|| async { "payload" }))
+            .route(
+                "/stream/:id",
+                get(|| async {
+                    Body::from_stream(stream::iter([Ok::<_, std::io::Error>("chunk")]))
+                }),
+            )
+            .layer(middleware::from_fn(http_metrics_middleware));
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/blob/1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        assert!(HTTP_METRICS.response_size.contains(&labels("/blob/:id")));
+        assert!(HTTP_METRICS.request_latency.contains(&RouteStatusLabels {
+            method: "GET".to_string(),
+            route: "/blob/:id".to_string(),
+            status: 200,
+        }));
+
+        // The size of a streamed body is known once it is read.
+        let response = app
+            .oneshot(Request::get("/stream/1").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(!HTTP_METRICS.response_size.contains(&labels("/stream/:id")));
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(HTTP_METRICS.response_size.contains(&labels("/stream/:id")));
+    }
+}
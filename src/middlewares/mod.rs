@@ -0,0 +1,4 @@
+pub mod auth;
+pub mod call_context;
+pub mod ip_allowlist;
+pub mod metrics;
@@ -0,0 +1,204 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use object_store::{ObjectStore, PutPayload, path::Path, prefix::PrefixStore};
+use url::Url;
+
+use crate::{
+    clients::da_clients::types::CallContext,
+    config::DaBackend,
+    services::{
+        da::DaSvc,
+        index::IndexSvc,
+        lifecycle::{Lifecycle, StopSignal, run_every},
+        metrics::DA_METRICS,
+    },
+};
+
+/// Keeps a copy of the blobs outside of the DA layer, so they stay retrievable once pruned.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveStore {
+    store: Option<Arc<dyn ObjectStore>>,
+    url: String,
+}
+
+impl ArchiveStore {
+    /// Opens the archive at `url`: `s3://bucket/prefix` with the credentials of the AWS_*
+    /// variables, `file:///path` or a directory. Disabled without a url.
+    pub fn new(url: Option<&str>) -> anyhow::Result<Self> {
+        let Some(url) = url else {
+            return Ok(Self::default());
+        };
+
+        let parsed = match Url::parse(url) {
+            Ok(parsed) => parsed,
+            Err(_) => Url::from_directory_path(std::path::absolute(url)?)
+                .map_err(|_| anyhow!("Invalid archive directory {}", url))?,
+        };
+        let options = std::env::vars()
+            .filter(|(key, _)| key.starts_with("AWS_"))
+            .map(|(key, value)| (key.to_lowercase(), value));
+        let (store, prefix) = object_store::parse_url_opts(&parsed, options)?;
+
+        Ok(Self {
+            store: Some(Arc::new(PrefixStore::new(store, prefix))),
+            url: url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Stores the payload of a blob, returns its location.
+    pub async fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<String> {
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| anyhow!("The archive is disabled"))?;
+        store.put(&Path::from(key), PutPayload::from(data)).await?;
+
+        Ok(format!("{}/{}", self.url, key))
+    }
+
+    /// Returns the archived payload of a blob, None when it was not archived.
+    pub async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(store) = &self.store else {
+            return Ok(None);
+        };
+
+        match store.get(&Path::from(key)).await {
+            Ok(result) => Ok(Some(result.bytes().await?.to_vec())),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Mirrors the confirmed Celestia blobs into the archive in the background, and records their
+/// location in the index.
+#[derive(Debug, Clone)]
+pub struct ArchiveSvc {
+    da_svc: Arc<DaSvc>,
+    index: Arc<IndexSvc>,
+    depth: u64,
+    interval: Duration,
+}
+
+impl ArchiveSvc {
+    pub fn new(da_svc: Arc<DaSvc>, index: Arc<IndexSvc>, depth: u64, interval: Duration) -> Self {
+        Self {
+            da_svc,
+            index,
+            depth,
+            interval,
+        }
+    }
+
+    /// Mirrors the confirmed blobs not archived yet, the failures are retried on the next run.
+    pub async fn run(&self) {
+        let ctx = CallContext::background();
+
+        for entry in self.index.pending_archive(DaBackend::Celestia, self.depth) {
+            match self
+                .da_svc
+                .archive(&ctx, &entry.caller, &entry.blob_id)
+                .await
+            {
+                Ok(location) => {
+                    tracing::debug!("Archived blob {} to {}", entry.blob_id, location);
+                    self.index.set_archive(&entry.blob_id, location);
+                    DA_METRICS.archived_blobs.inc();
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to archive blob {}: {}", entry.blob_id, err);
+                    DA_METRICS.failed_archivings.inc();
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Lifecycle for ArchiveSvc {
+    fn name(&self) -> &'static str {
+        "archive_mirror"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        run_every(self.interval, stop, || self.run()).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{
+        clients::da_clients::in_memory::InMemoryClient,
+        types::{dispatch::DispatchPriority, index::IndexEntry},
+    };
+
+    #[tokio::test]
+    async fn test_confirmed_blobs_are_mirrored() {
+        let dir = std::env::temp_dir().join(format!("via-archive-{}", std::process::id()));
+        let archive = Arc::new(ArchiveStore::new(dir.to_str()).unwrap());
+        let da_svc = Arc::new(
+            DaSvc::for_tests(Arc::new(InMemoryClient::new(1024))).with_archive(archive.clone()),
+        );
+        let index = Arc::new(IndexSvc::new(None).unwrap());
+
+        let ctx = CallContext::background();
+        for (batch_number, confirmations) in [(1, Some(2)), (2, Some(1))] {
+            let response = da_svc
+                .dispatch_blob(
+                    &ctx,
+                    "caller",
+                    batch_number,
+                    None,
+                    vec![batch_number as u8; 8],
+                    DispatchPriority::Normal,
+                )
+                .await
+                .unwrap();
+            let mut receipt = response.receipt.unwrap();
+            receipt.backend = DaBackend::Celestia;
+            index.record(IndexEntry {
+                blob_id: response.blob_id,
+                batch_number,
+                caller: "caller".to_string(),
+                size: 8,
+                payload_hash: String::new(),
+                dispatched_at: Utc::now(),
+                redispatch_of: None,
+                receipt: Some(receipt),
+                confirmations,
+                archive: None,
+                metadata: BTreeMap::new(),
+                attestation: None,
+            });
+        }
+
+        let svc = ArchiveSvc::new(da_svc, index.clone(), 2, Duration::from_secs(60));
+        svc.run().await;
+
+        // Only the blob reaching the confirmation depth is mirrored.
+        let pending = index.pending_archive(DaBackend::Celestia, 1);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].batch_number, 2);
+        let archived = index.range(None, None).remove(0);
+        let key = format!("blobs/{}", archived.blob_id);
+        assert_eq!(
+            archived.archive,
+            Some(format!("{}/{}", dir.to_str().unwrap(), key))
+        );
+        assert_eq!(archive.get(&key).await.unwrap(), Some(vec![1; 8]));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
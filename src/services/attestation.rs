@@ -0,0 +1,282 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL};
+use ed25519_dalek::Verifier as _;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    clients::key_providers::{KeyProvider, PublicKey},
+    config::AttestationKeyType,
+    types::attestation::{Attestation, Jwk, JwkSet},
+};
+
+/// Verifies the signature of an attestation against its own public key.
+pub fn verify_attestation(attestation: &Attestation) -> anyhow::Result<bool> {
+    let payload_hash: [u8; 32] = hex::decode(&attestation.payload_hash)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Payload hash must be 32 bytes"))?;
+    let message = Attestation::signing_message(
+        &attestation.blob_id,
+        attestation.batch_number,
+        &payload_hash,
+        attestation.timestamp,
+    );
+    let public_key = hex::decode(&attestation.public_key)?;
+    let signature = hex::decode(&attestation.signature)?;
+
+    let valid = match attestation.key_type {
+        AttestationKeyType::Ed25519 => {
+            let public_key = ed25519_dalek::VerifyingKey::try_from(public_key.as_slice())?;
+            let signature = ed25519_dalek::Signature::try_from(signature.as_slice())?;
+            public_key.verify(&message, &signature).is_ok()
+        }
+        AttestationKeyType::Secp256k1 => {
+            let public_key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&public_key)?;
+            let signature = k256::ecdsa::Signature::from_slice(&signature)?;
+            public_key.verify(&message, &signature).is_ok()
+        }
+    };
+
+    Ok(valid)
+}
+
+/// Converts a public signing key to a JSON Web Key.
+pub fn to_jwk(key: &PublicKey) -> anyhow::Result<Jwk> {
+    let (kty, crv, alg, x, y) = match key.key_type {
+        AttestationKeyType::Ed25519 => (
+            "OKP",
+            "Ed25519",
+            "EdDSA",
+            BASE64_URL.encode(&key.public_key),
+            None,
+        ),
+        AttestationKeyType::Secp256k1 => {
+            let point = k256::ecdsa::VerifyingKey::from_sec1_bytes(&key.public_key)?
+                .to_encoded_point(false);
+            let x = point
+                .x()
+                .ok_or_else(|| anyhow::anyhow!("Invalid public key"))?;
+            let y = point
+                .y()
+                .ok_or_else(|| anyhow::anyhow!("Invalid public key"))?;
+            (
+                "EC",
+                "secp256k1",
+                "ES256K",
+                BASE64_URL.encode(x),
+                Some(BASE64_URL.encode(y)),
+            )
+        }
+    };
+
+    Ok(Jwk {
+        kid: key.key_id.clone(),
+        kty: kty.to_string(),
+        crv: crv.to_string(),
+        alg: alg.to_string(),
+        key_use: "sig".to_string(),
+        x,
+        y,
+        active_from: key.active_from,
+        active: key.active,
+    })
+}
+
+/// The number of attestations kept in memory, the older ones are read from the index.
+const CACHED_ATTESTATIONS: usize = 10_000;
+
+/// The latest issued attestations by blob_id, in their issue order.
+#[derive(Debug, Default)]
+struct AttestationCache {
+    attestations: HashMap<String, Attestation>,
+    order: VecDeque<String>,
+}
+
+/// Signs dispatch responses and keeps the latest issued attestations by blob_id. The attestations
+/// are persisted with the index entries of the dispatches.
+#[derive(Debug, Clone)]
+pub struct AttestationSvc {
+    key_provider: Arc<dyn KeyProvider>,
+    cache: Arc<RwLock<AttestationCache>>,
+    capacity: usize,
+}
+
+impl AttestationSvc {
+    pub fn new(key_provider: Arc<dyn KeyProvider>) -> Self {
+        Self {
+            key_provider,
+            cache: Arc::default(),
+            capacity: CACHED_ATTESTATIONS,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.key_provider.can_sign()
+    }
+
+    /// Returns the SHA-256 of the payload, to be passed to `attest`.
+    pub fn payload_hash(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    /// Signs and stores an attestation for a dispatched blob. Returns None when signing is disabled
+    /// or fails, the blob is already dispatched at this point so the dispatch itself succeeds.
+    pub async fn attest(
+        &self,
+        blob_id: &str,
+        batch_number: u32,
+        payload_hash: &[u8; 32],
+    ) -> Option<Attestation> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let message = Attestation::signing_message(blob_id, batch_number, payload_hash, timestamp);
+
+        let signature = match self.key_provider.sign(&message).await {
+            Ok(signature) => signature,
+            Err(err) => {
+                tracing::error!("Failed to sign the attestation of {}: {}", blob_id, err);
+                return None;
+            }
+        };
+
+        let attestation = Attestation {
+            blob_id: blob_id.to_string(),
+            batch_number,
+            payload_hash: hex::encode(payload_hash),
+            timestamp,
+            key_id: signature.key_id,
+            key_type: signature.key_type,
+            public_key: hex::encode(signature.public_key),
+            signature: hex::encode(signature.signature),
+        };
+
+        let mut cache = self.cache.write().unwrap();
+        if cache
+            .attestations
+            .insert(blob_id.to_string(), attestation.clone())
+            .is_none()
+        {
+            cache.order.push_back(blob_id.to_string());
+        }
+        while cache.order.len() > self.capacity {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.attestations.remove(&oldest);
+            }
+        }
+
+        Some(attestation)
+    }
+
+    /// Returns the public keys of the signing keys, including the rotated out ones.
+    pub async fn key_set(&self) -> anyhow::Result<JwkSet> {
+        let keys = self.key_provider.public_keys().await?;
+        Ok(JwkSet {
+            keys: keys.iter().map(to_jwk).collect::<anyhow::Result<_>>()?,
+        })
+    }
+
+    /// Returns the attestation issued for the blob_id, if it is one of the latest ones.
+    pub fn get(&self, blob_id: &str) -> Option<Attestation> {
+        self.cache
+            .read()
+            .unwrap()
+            .attestations
+            .get(blob_id)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::key_providers::local::{LocalKeyProvider, LocalSigningKey};
+
+    const PRIVATE_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn new_svc(key_type: AttestationKeyType) -> AttestationSvc {
+        let key = LocalSigningKey::from_hex(key_type, PRIVATE_KEY).unwrap();
+        AttestationSvc::new(Arc::new(LocalKeyProvider::new(
+            Some(("key-1".to_string(), key)),
+            vec![],
+        )))
+    }
+
+    #[tokio::test]
+    async fn test_attestation_roundtrip() {
+        for key_type in [AttestationKeyType::Ed25519, AttestationKeyType::Secp256k1] {
+            let svc = new_svc(key_type);
+            let hash = AttestationSvc::payload_hash(b"pubdata");
+
+            let attestation = svc.attest("blob", 7, &hash).await.unwrap();
+
+            assert_eq!(attestation.key_id, "key-1");
+            assert_eq!(attestation.key_type, key_type);
+            assert_eq!(attestation.payload_hash, hex::encode(hash));
+            assert!(verify_attestation(&attestation).unwrap());
+            assert_eq!(svc.get("blob"), Some(attestation));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_only_the_latest_attestations_are_cached() {
+        let svc = AttestationSvc {
+            capacity: 2,
+            ..new_svc(AttestationKeyType::Ed25519)
+        };
+        let hash = AttestationSvc::payload_hash(b"pubdata");
+
+        for blob_id in ["a", "b", "a", "c"] {
+            svc.attest(blob_id, 7, &hash).await.unwrap();
+        }
+        assert_eq!(svc.get("a"), None);
+        assert!(svc.get("b").is_some() && svc.get("c").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_attestation_is_rejected() {
+        for key_type in [AttestationKeyType::Ed25519, AttestationKeyType::Secp256k1] {
+            let svc = new_svc(key_type);
+            let hash = AttestationSvc::payload_hash(b"pubdata");
+
+            let mut attestation = svc.attest("blob", 7, &hash).await.unwrap();
+            attestation.batch_number = 8;
+
+            assert!(!verify_attestation(&attestation).unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_set_publishes_the_signing_keys() {
+        for (key_type, kty, has_y) in [
+            (AttestationKeyType::Ed25519, "OKP", false),
+            (AttestationKeyType::Secp256k1, "EC", true),
+        ] {
+            let key_set = new_svc(key_type).key_set().await.unwrap();
+
+            assert_eq!(key_set.keys.len(), 1);
+            let jwk = &key_set.keys[0];
+            assert_eq!((jwk.kid.as_str(), jwk.kty.as_str()), ("key-1", kty));
+            assert_eq!(BASE64_URL.decode(&jwk.x).unwrap().len(), 32);
+            assert_eq!(jwk.y.is_some(), has_y);
+            assert!(jwk.active);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disabled_svc_does_not_attest() {
+        let svc = AttestationSvc::new(Arc::new(LocalKeyProvider::default()));
+        assert!(!svc.is_enabled());
+        assert!(svc.attest("blob", 1, &[0u8; 32]).await.is_none());
+        assert!(svc.get("blob").is_none());
+    }
+}
@@ -0,0 +1,377 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{clients::lock_stores::LockStore, services::metrics::DA_METRICS};
+
+/// The value of the lock of a batch being submitted, followed by the token of its holder.
+const PENDING_PREFIX: &str = "pending:";
+
+/// The value of the lock of a submitted batch, followed by its blob_id.
+const SUBMITTED_PREFIX: &str = "submitted:";
+
+/// `BatchLock` is the lock of a batch held by this replica. Its ttl is renewed until it is
+/// completed, released or dropped, so a dispatch can outlive the ttl.
+#[derive(Debug)]
+pub struct BatchLock {
+    key: String,
+    value: String,
+    stop: CancellationToken,
+    renewal: JoinHandle<()>,
+}
+
+impl BatchLock {
+    /// Stops the renewal of the lock, waiting for a renewal in progress.
+    async fn stop_renewal(&mut self) {
+        self.stop.cancel();
+        // The renewal doesn't panic, and isn't aborted.
+        let _ = (&mut self.renewal).await;
+    }
+}
+
+impl Drop for BatchLock {
+    fn drop(&mut self) {
+        self.stop.cancel();
+    }
+}
+
+/// `BatchClaim` is the outcome of claiming a batch before its submission.
+#[derive(Debug)]
+pub enum BatchClaim {
+    /// The batch can be submitted, under its lock when the dispatches are coordinated.
+    Acquired(Option<BatchLock>),
+    /// Another replica submitted the batch as this blob.
+    Submitted { blob_id: String },
+    /// Another replica is submitting the batch.
+    Locked,
+}
+
+/// Coordinates the replicas submitting the batches with locks in a shared store, so a batch
+/// dispatched to several replicas is only paid for once. The lock of a submitted batch keeps its
+/// blob_id, returned to the dispatches of the batch on the other replicas.
+#[derive(Debug, Clone, Default)]
+pub struct BatchLockSvc {
+    store: Option<Arc<dyn LockStore>>,
+    ttl: Duration,
+    retention: Duration,
+}
+
+impl BatchLockSvc {
+    /// Creates the service, the dispatches are not coordinated without a `store`.
+    pub fn new(store: Option<Arc<dyn LockStore>>, ttl: Duration, retention: Duration) -> Self {
+        Self {
+            store,
+            ttl,
+            retention,
+        }
+    }
+
+    /// Claims a batch of the caller for its submission.
+    pub async fn claim(&self, caller: &str, batch_number: u32) -> anyhow::Result<BatchClaim> {
+        self.claim_submitted_as(caller, batch_number, None).await
+    }
+
+    /// Claims a batch of the caller submitted as `blob_id` for its re-dispatch. The claim fails
+    /// when the batch is being submitted or was re-dispatched as another blob meanwhile.
+    pub async fn claim_redispatch(
+        &self,
+        caller: &str,
+        batch_number: u32,
+        blob_id: &str,
+    ) -> anyhow::Result<BatchClaim> {
+        self.claim_submitted_as(caller, batch_number, Some(blob_id))
+            .await
+    }
+
+    /// Claims a batch, taking over its lock when it was submitted as `submitted`.
+    async fn claim_submitted_as(
+        &self,
+        caller: &str,
+        batch_number: u32,
+        submitted: Option<&str>,
+    ) -> anyhow::Result<BatchClaim> {
+        let Some(store) = &self.store else {
+            return Ok(BatchClaim::Acquired(None));
+        };
+
+        let key = format!("via:batch:{caller}:{batch_number}");
+        let value = format!(
+            "{PENDING_PREFIX}{}",
+            hex::encode(rand::random::<[u8; 16]>())
+        );
+        let current = store
+            .set_if_absent(&key, &value, self.ttl)
+            .await
+            .inspect_err(|_| {
+                DA_METRICS.batch_lock_errors.inc();
+            })?;
+
+        let Some(current) = current else {
+            return Ok(BatchClaim::Acquired(Some(self.lock(store, key, value))));
+        };
+        if let Some(blob_id) = submitted
+            && current.strip_prefix(SUBMITTED_PREFIX) == Some(blob_id)
+            && store
+                .replace(&key, &current, &value, self.ttl)
+                .await
+                .inspect_err(|_| {
+                    DA_METRICS.batch_lock_errors.inc();
+                })?
+        {
+            return Ok(BatchClaim::Acquired(Some(self.lock(store, key, value))));
+        }
+        DA_METRICS.batch_lock_conflicts.inc();
+        match current.strip_prefix(SUBMITTED_PREFIX) {
+            Some(blob_id) => Ok(BatchClaim::Submitted {
+                blob_id: blob_id.to_string(),
+            }),
+            None => Ok(BatchClaim::Locked),
+        }
+    }
+
+    /// Holds the acquired lock, renewing its ttl every third of the ttl. The renewal stops once the
+    /// lock was lost, a failed renewal is tried again at the next one.
+    fn lock(&self, store: &Arc<dyn LockStore>, key: String, value: String) -> BatchLock {
+        let stop = CancellationToken::new();
+        let renewal = tokio::spawn({
+            let (store, key, value, stop, ttl) = (
+                store.clone(),
+                key.clone(),
+                value.clone(),
+                stop.clone(),
+                self.ttl,
+            );
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = stop.cancelled() => return,
+                        _ = tokio::time::sleep(ttl / 3) => {}
+                    }
+                    match store.replace(&key, &value, &value, ttl).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            DA_METRICS.batch_lock_losses.inc();
+                            tracing::error!("The lock {} expired before it was renewed", key);
+                            return;
+                        }
+                        Err(err) => {
+                            DA_METRICS.batch_lock_errors.inc();
+                            tracing::warn!("Failed to renew the lock {}: {}", key, err);
+                        }
+                    }
+                }
+            }
+        });
+        BatchLock {
+            key,
+            value,
+            stop,
+            renewal,
+        }
+    }
+
+    /// Records the blob_id of a submitted batch in its lock. A failure is logged as the batch was
+    /// submitted, its lock expires after the ttl.
+    pub async fn complete(&self, lock: Option<BatchLock>, blob_id: &str) {
+        let (Some(store), Some(mut lock)) = (&self.store, lock) else {
+            return;
+        };
+        lock.stop_renewal().await;
+
+        let submitted = format!("{SUBMITTED_PREFIX}{blob_id}");
+        match store
+            .replace(&lock.key, &lock.value, &submitted, self.retention)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!(
+                "The lock {} expired before the submission of {}",
+                lock.key,
+                blob_id
+            ),
+            Err(err) => {
+                DA_METRICS.batch_lock_errors.inc();
+                tracing::error!(
+                    "Failed to record {} in the lock {}: {}",
+                    blob_id,
+                    lock.key,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Releases the lock of a batch that was not submitted, so another dispatch can submit it.
+    pub async fn release(&self, lock: Option<BatchLock>) {
+        let (Some(store), Some(mut lock)) = (&self.store, lock) else {
+            return;
+        };
+        lock.stop_renewal().await;
+
+        if let Err(err) = store.delete(&lock.key, &lock.value).await {
+            DA_METRICS.batch_lock_errors.inc();
+            tracing::error!("Failed to release the lock {}: {}", lock.key, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use async_trait::async_trait;
+    use tokio::time::Instant;
+
+    use super::*;
+
+    /// A store shared by the replicas of the test, the keys expire after their ttl.
+    #[derive(Debug, Default)]
+    struct SharedStore {
+        keys: Mutex<HashMap<String, (String, Instant)>>,
+    }
+
+    impl SharedStore {
+        fn get(keys: &mut HashMap<String, (String, Instant)>, key: &str) -> Option<String> {
+            keys.retain(|_, (_, expiry)| *expiry > Instant::now());
+            keys.get(key).map(|(value, _)| value.clone())
+        }
+    }
+
+    #[async_trait]
+    impl LockStore for SharedStore {
+        async fn set_if_absent(
+            &self,
+            key: &str,
+            value: &str,
+            ttl: Duration,
+        ) -> anyhow::Result<Option<String>> {
+            let mut keys = self.keys.lock().unwrap();
+            if let Some(current) = Self::get(&mut keys, key) {
+                return Ok(Some(current));
+            }
+            keys.insert(key.to_string(), (value.to_string(), Instant::now() + ttl));
+            Ok(None)
+        }
+
+        async fn replace(
+            &self,
+            key: &str,
+            expected: &str,
+            value: &str,
+            ttl: Duration,
+        ) -> anyhow::Result<bool> {
+            let mut keys = self.keys.lock().unwrap();
+            if Self::get(&mut keys, key).as_deref() != Some(expected) {
+                return Ok(false);
+            }
+            keys.insert(key.to_string(), (value.to_string(), Instant::now() + ttl));
+            Ok(true)
+        }
+
+        async fn delete(&self, key: &str, expected: &str) -> anyhow::Result<bool> {
+            let mut keys = self.keys.lock().unwrap();
+            if Self::get(&mut keys, key).as_deref() != Some(expected) {
+                return Ok(false);
+            }
+            keys.remove(key);
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batches_are_submitted_by_one_replica() {
+        let store: Arc<dyn LockStore> = Arc::new(SharedStore::default());
+        let ttl = Duration::from_secs(60);
+        let first = BatchLockSvc::new(Some(store.clone()), ttl, ttl);
+        let second = BatchLockSvc::new(Some(store), ttl, ttl);
+
+        let BatchClaim::Acquired(lock) = first.claim("caller", 1).await.unwrap() else {
+            panic!("The batch is not locked yet");
+        };
+        assert!(matches!(
+            second.claim("caller", 1).await.unwrap(),
+            BatchClaim::Locked
+        ));
+
+        // A failed submission lets the other replica submit the batch.
+        first.release(lock).await;
+        let BatchClaim::Acquired(lock) = second.claim("caller", 1).await.unwrap() else {
+            panic!("The batch was released");
+        };
+        second.complete(lock, "ab").await;
+        assert!(matches!(
+            first.claim("caller", 1).await.unwrap(),
+            BatchClaim::Submitted { blob_id } if blob_id == "ab"
+        ));
+        assert!(matches!(
+            first.claim("other", 1).await.unwrap(),
+            BatchClaim::Acquired(Some(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_locks_are_renewed_while_the_batch_is_submitted() {
+        let store: Arc<dyn LockStore> = Arc::new(SharedStore::default());
+        let ttl = Duration::from_millis(300);
+        let first = BatchLockSvc::new(Some(store.clone()), ttl, ttl);
+        let second = BatchLockSvc::new(Some(store), ttl, ttl);
+
+        // The submission outlives the ttl of the lock.
+        let BatchClaim::Acquired(lock) = first.claim("caller", 1).await.unwrap() else {
+            panic!("The batch is not locked yet");
+        };
+        tokio::time::sleep(ttl * 3).await;
+        assert!(matches!(
+            second.claim("caller", 1).await.unwrap(),
+            BatchClaim::Locked
+        ));
+        first.complete(lock, "ab").await;
+        assert!(matches!(
+            second.claim("caller", 1).await.unwrap(),
+            BatchClaim::Submitted { blob_id } if blob_id == "ab"
+        ));
+
+        // The lock of a crashed dispatch expires after the ttl.
+        let BatchClaim::Acquired(lock) = first.claim("caller", 2).await.unwrap() else {
+            panic!("The batch is not locked yet");
+        };
+        drop(lock);
+        tokio::time::sleep(ttl * 2).await;
+        assert!(matches!(
+            second.claim("caller", 2).await.unwrap(),
+            BatchClaim::Acquired(Some(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_redispatches_take_over_the_lock_of_the_submitted_blob() {
+        let store: Arc<dyn LockStore> = Arc::new(SharedStore::default());
+        let ttl = Duration::from_secs(60);
+        let first = BatchLockSvc::new(Some(store.clone()), ttl, ttl);
+        let second = BatchLockSvc::new(Some(store), ttl, ttl);
+
+        let BatchClaim::Acquired(lock) = first.claim("caller", 1).await.unwrap() else {
+            panic!("The batch is not locked yet");
+        };
+        assert!(matches!(
+            second.claim_redispatch("caller", 1, "ab").await.unwrap(),
+            BatchClaim::Locked
+        ));
+        first.complete(lock, "ab").await;
+
+        let BatchClaim::Acquired(lock) = first.claim_redispatch("caller", 1, "ab").await.unwrap()
+        else {
+            panic!("The batch was submitted as ab");
+        };
+        assert!(matches!(
+            second.claim_redispatch("caller", 1, "ab").await.unwrap(),
+            BatchClaim::Locked
+        ));
+        first.complete(lock, "cd").await;
+        assert!(matches!(
+            second.claim_redispatch("caller", 1, "ab").await.unwrap(),
+            BatchClaim::Submitted { blob_id } if blob_id == "cd"
+        ));
+    }
+}
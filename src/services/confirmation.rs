@@ -0,0 +1,84 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{
+    clients::da_clients::switchable::SwitchableClient,
+    services::{
+        index::IndexSvc,
+        lifecycle::{Lifecycle, StopSignal, run_every},
+        metrics::DA_METRICS,
+    },
+};
+
+/// Tracks the confirmation depth of the dispatched blobs in the index, from the chain head of
+/// each backend polled in the background.
+#[derive(Debug, Clone)]
+pub struct ConfirmationSvc {
+    da_backends: Arc<SwitchableClient>,
+    index: Arc<IndexSvc>,
+    depth: u64,
+    poll_interval: Option<Duration>,
+}
+
+impl ConfirmationSvc {
+    /// Creates the service, the confirmations are not tracked without a `poll_interval`.
+    pub fn new(
+        da_backends: Arc<SwitchableClient>,
+        index: Arc<IndexSvc>,
+        depth: u64,
+        poll_interval: Option<Duration>,
+    ) -> Self {
+        Self {
+            da_backends,
+            index,
+            depth,
+            poll_interval,
+        }
+    }
+
+    /// The number of confirmations after which a blob is confirmed.
+    pub fn depth(&self) -> u64 {
+        self.depth
+    }
+
+    /// Updates the confirmations of the unconfirmed blobs of every backend with a chain.
+    pub async fn run(&self) {
+        for backend in self.da_backends.backends() {
+            let Some(client) = self.da_backends.backend(backend) else {
+                continue;
+            };
+            let head = match client.head_height().await {
+                Ok(Some(head)) => head,
+                Ok(None) => continue,
+                Err(err) => {
+                    tracing::warn!("Failed to get the {:?} chain head: {}", backend, err);
+                    continue;
+                }
+            };
+
+            for entry in self.index.update_confirmations(backend, head, self.depth) {
+                tracing::debug!(
+                    "Blob {} of batch {} is confirmed",
+                    entry.blob_id,
+                    entry.batch_number
+                );
+                DA_METRICS.confirmed_blobs.inc();
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Lifecycle for ConfirmationSvc {
+    fn name(&self) -> &'static str {
+        "confirmation_tracker"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        if let Some(interval) = self.poll_interval {
+            run_every(interval, stop, || self.run()).await;
+        }
+        Ok(())
+    }
+}
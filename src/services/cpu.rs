@@ -0,0 +1,56 @@
+use std::{sync::LazyLock, thread::available_parallelism, time::Instant};
+
+use tokio::sync::Semaphore;
+
+use crate::services::metrics::DA_METRICS;
+
+/// The inputs below this size are processed on the reactor, offloading them costs more than it
+/// saves.
+pub const OFFLOAD_THRESHOLD: usize = 64 * 1024;
+
+/// The offloaded jobs running at once, one per core so the large dispatches queue up for the CPU
+/// instead of oversubscribing it.
+static CPU_SLOTS: LazyLock<Semaphore> =
+    LazyLock::new(|| Semaphore::new(available_parallelism().map_or(1, |cores| cores.get())));
+
+/// Runs CPU-bound work on an input of `size` bytes, e.g. the hex encoding or the compression of
+/// a payload, on the blocking pool. The reactor keeps serving the health checks and the small
+/// reads meanwhile. The small inputs are processed inline.
+pub async fn offload<T, F>(size: usize, work: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    if size < OFFLOAD_THRESHOLD {
+        return work();
+    }
+
+    let queued_at = Instant::now();
+    let _permit = CPU_SLOTS
+        .acquire()
+        .await
+        .expect("The CPU slots are never closed");
+    DA_METRICS.cpu_offload_wait.observe(queued_at.elapsed());
+
+    match tokio::task::spawn_blocking(work).await {
+        Ok(output) => output,
+        Err(err) => std::panic::resume_unwind(err.into_panic()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_large_inputs_are_processed_off_the_reactor() {
+        let reactor = thread::current().id();
+
+        let small = offload(OFFLOAD_THRESHOLD - 1, || thread::current().id()).await;
+        assert_eq!(small, reactor);
+        let large = offload(OFFLOAD_THRESHOLD, || thread::current().id()).await;
+        assert_ne!(large, reactor);
+    }
+}
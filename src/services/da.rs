@@ -1,45 +1,918 @@
+use chrono::{DateTime, Utc};
 use tokio::time::Instant;
+use tracing::Instrument;
 
 use crate::{
     clients::da_clients::{
         DataAvailabilityClient,
-        types::{DispatchResponse, InclusionData},
+        types::{
+            CallContext, DAError, DispatchResponse, InclusionData, InclusionProof, InclusionRange,
+            ViaDaBlob, serialize_blob_ids,
+        },
     },
-    services::metrics::DA_METRICS,
+    services::{
+        archive::ArchiveStore, attestation::AttestationSvc, dispatch_queue::DispatchQueue,
+        metrics::DA_METRICS, shadow::ShadowSvc, transform::TransformSvc,
+    },
+    types::{
+        dispatch::{
+            ChunkReceipt, DispatchPriority, DispatchReceipt, DispatchedChunk, FailedChunk,
+            FeeEstimate, PartialDispatchReport,
+        },
+        envelope::{BlobEnvelope, ENVELOPE_HEADER_LEN},
+        routing::NamespaceRoute,
+    },
+};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
-use std::sync::Arc;
 
+/// `PreparedBlob` is a payload transformed for its dispatch, see `DaSvc::prepare`.
+#[derive(Debug, Clone, Default)]
+pub struct PreparedBlob {
+    /// The transformed payload.
+    pub data: Vec<u8>,
+    /// The hash of the payload of the caller, when the dispatches are attested.
+    payload_hash: Option<[u8; 32]>,
+    chunk_size: Option<usize>,
+    /// The chunk size and the chunks of a partially failed dispatch, not dispatched again.
+    resumed: Option<(usize, Vec<DispatchedChunk>)>,
+}
+
+impl PreparedBlob {
+    /// The sizes of the blobs the payload is dispatched as, the chunks of a chunked blob. The
+    /// chunk size is the one of the prepare, capped again by the blob size limit on dispatch.
+    pub fn blob_sizes(&self) -> Vec<usize> {
+        match self.chunk_size {
+            Some(chunk_size) if self.data.len() > chunk_size => {
+                self.data.chunks(chunk_size).map(<[u8]>::len).collect()
+            }
+            _ => vec![self.data.len()],
+        }
+    }
+
+    /// Resumes a partially failed dispatch, only its failed chunks are dispatched again.
+    pub fn resume(&mut self, report: &PartialDispatchReport) {
+        self.resumed = Some((report.chunk_size, report.succeeded.clone()));
+    }
+}
+
+/// Dispatches and reads the blobs of the callers, the callers configured as tenants use a client
+/// scoped to their own namespace and the dispatches of the others can be routed to other
+/// namespaces. The pruned blobs are read from the archive.
 #[derive(Debug, Clone)]
 pub struct DaSvc {
     da_client: Arc<dyn DataAvailabilityClient + Send + Sync>,
+    tenant_clients: HashMap<String, Arc<dyn DataAvailabilityClient + Send + Sync>>,
+    tenant_namespaces: HashMap<String, String>,
+    /// The routes of the dispatches of the callers that are not tenants, with the client of their
+    /// namespace.
+    namespace_routes: Vec<(
+        NamespaceRoute,
+        Arc<dyn DataAvailabilityClient + Send + Sync>,
+    )>,
+    attestation_svc: Arc<AttestationSvc>,
+    transform_svc: Arc<TransformSvc>,
+    archive: Arc<ArchiveStore>,
+    shadow_svc: Arc<ShadowSvc>,
+    queue: DispatchQueue,
+    /// The fee paid per gas and its denom, from the last dispatch with a fee.
+    gas_price: Arc<Mutex<Option<(f64, String)>>>,
 }
 
 impl DaSvc {
-    pub fn new(da_client: Arc<dyn DataAvailabilityClient + Send + Sync>) -> Self {
-        Self { da_client }
+    /// Creates the service, `tenants` are (caller, namespace) pairs.
+    pub fn new(
+        da_client: Arc<dyn DataAvailabilityClient + Send + Sync>,
+        attestation_svc: Arc<AttestationSvc>,
+        transform_svc: Arc<TransformSvc>,
+        archive: Arc<ArchiveStore>,
+        shadow_svc: Arc<ShadowSvc>,
+        max_concurrent_dispatches: usize,
+        tenants: &[(String, String)],
+    ) -> anyhow::Result<Self> {
+        let tenant_clients = tenants
+            .iter()
+            .map(|(tenant, namespace)| Ok((tenant.clone(), da_client.namespaced(namespace)?)))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            da_client,
+            tenant_clients,
+            tenant_namespaces: tenants.iter().cloned().collect(),
+            namespace_routes: vec![],
+            attestation_svc,
+            transform_svc,
+            archive,
+            shadow_svc,
+            queue: DispatchQueue::new(max_concurrent_dispatches),
+            gas_price: Arc::default(),
+        })
+    }
+
+    /// Compacts the dispatch queue every `interval`, the dispatches waiting longer than
+    /// `max_wait` for a slot fail then.
+    pub fn with_queue_compaction(mut self, interval: Duration, max_wait: Option<Duration>) -> Self {
+        self.queue = self.queue.with_compaction(interval, max_wait);
+        self
+    }
+
+    /// Routes the dispatches of the callers that are not tenants to the namespaces of `routes`,
+    /// the first matching route applies.
+    pub fn with_namespace_routes(mut self, routes: &[NamespaceRoute]) -> anyhow::Result<Self> {
+        let mut clients: HashMap<&str, Arc<dyn DataAvailabilityClient + Send + Sync>> =
+            HashMap::new();
+        for route in routes {
+            let client = match clients.get(route.namespace.as_str()) {
+                Some(client) => client.clone(),
+                None => {
+                    let client = self.da_client.namespaced(&route.namespace)?;
+                    clients.insert(&route.namespace, client.clone());
+                    client
+                }
+            };
+            self.namespace_routes.push((route.clone(), client));
+        }
+        Ok(self)
+    }
+
+    /// Returns the client of the caller namespace.
+    fn client(&self, caller: &str) -> &Arc<dyn DataAvailabilityClient + Send + Sync> {
+        self.tenant_clients.get(caller).unwrap_or(&self.da_client)
+    }
+
+    /// Returns the client of the namespace a dispatch of the caller is published under.
+    fn dispatch_client(
+        &self,
+        caller: &str,
+        batch_number: u32,
+        protocol_version: Option<&str>,
+    ) -> &Arc<dyn DataAvailabilityClient + Send + Sync> {
+        if let Some(client) = self.tenant_clients.get(caller) {
+            return client;
+        }
+        self.namespace_routes
+            .iter()
+            .find(|(route, _)| route.matches(batch_number, protocol_version))
+            .map_or(&self.da_client, |(_, client)| client)
+    }
+
+    /// Reads a blob of the caller from its namespace. The blobs of the callers that are not
+    /// tenants are searched in the namespaces of the routes too, in order, so the blobs dispatched
+    /// on both sides of a route change stay readable.
+    async fn read<'a, T, F>(
+        &'a self,
+        caller: &str,
+        read: impl Fn(&'a Arc<dyn DataAvailabilityClient + Send + Sync>) -> F,
+    ) -> Result<Option<T>, DAError>
+    where
+        F: Future<Output = Result<Option<T>, DAError>>,
+    {
+        let result = read(self.client(caller)).await;
+        if self.tenant_clients.contains_key(caller) || !matches!(result, Ok(None)) {
+            return result;
+        }
+
+        let mut searched = vec![&self.da_client];
+        for (_, client) in &self.namespace_routes {
+            if searched.iter().any(|known| Arc::ptr_eq(known, client)) {
+                continue;
+            }
+            searched.push(client);
+            if let Ok(Some(found)) = read(client).await {
+                return Ok(Some(found));
+            }
+        }
+        result
+    }
+
+    /// Returns the client of a namespace the blobs of the caller are read from, the caller
+    /// namespace by default. None when the blobs of the namespace wouldn't be readable by the
+    /// caller.
+    pub fn scan_client(
+        &self,
+        caller: &str,
+        namespace: Option<&str>,
+    ) -> Option<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+        let Some(namespace) = namespace else {
+            return Some(self.client(caller).clone());
+        };
+        match self.tenant_namespaces.get(caller) {
+            Some(tenant_namespace) => {
+                (tenant_namespace == namespace).then(|| self.client(caller).clone())
+            }
+            None => self
+                .namespace_routes
+                .iter()
+                .find(|(route, _)| route.namespace == namespace)
+                .map(|(_, client)| client.clone()),
+        }
+    }
+
+    /// Returns the namespace of the caller, None for the callers that are not tenants.
+    pub fn tenant_namespace(&self, caller: &str) -> Option<&str> {
+        self.tenant_namespaces.get(caller).map(String::as_str)
+    }
+
+    /// Returns the archive key of a blob, the blobs of the tenants are kept by namespace.
+    fn archive_key(&self, caller: &str, blob_id: &str) -> String {
+        match self.tenant_namespaces.get(caller) {
+            Some(namespace) => format!("tenants/{namespace}/{blob_id}"),
+            None => format!("blobs/{blob_id}"),
+        }
+    }
+
+    /// Fetches the payload of a blob as dispatched, from the archive once pruned from the DA
+    /// layer.
+    async fn fetch_payload(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<Option<Vec<u8>>, DAError> {
+        match self
+            .read(caller, |client| client.get_inclusion_data(ctx, blob_id))
+            .await
+        {
+            Ok(inclusion) => Ok(inclusion.map(|inclusion| inclusion.data)),
+            Err(err @ DAError::Pruned { .. }) => {
+                let Some(data) = self.archive.get(&self.archive_key(caller, blob_id)).await? else {
+                    return Err(err);
+                };
+                DA_METRICS.archive_reads.inc();
+                Ok(Some(data))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Copies the payload of a blob as dispatched into the archive, returns its location.
+    pub async fn archive(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<String, DAError> {
+        let inclusion = self
+            .read(caller, |client| client.get_inclusion_data(ctx, blob_id))
+            .await?
+            .ok_or_else(|| DAError::NotFound {
+                blob_id: blob_id.to_string(),
+            })?;
+
+        Ok(self
+            .archive
+            .put(&self.archive_key(caller, blob_id), inclusion.data)
+            .await?)
+    }
+
+    /// Transforms a payload for its dispatch in the caller namespace, the attestation covers the
+    /// payload of the caller.
+    pub async fn prepare(&self, caller: &str, data: Vec<u8>) -> Result<PreparedBlob, DAError> {
+        let payload_hash = self
+            .attestation_svc
+            .is_enabled()
+            .then(|| AttestationSvc::payload_hash(&data));
+
+        Ok(PreparedBlob {
+            data: self.transform_svc.encode(data).await?,
+            payload_hash,
+            chunk_size: self
+                .transform_svc
+                .chunk_size(self.client(caller).blob_size_limit()),
+            resumed: None,
+        })
+    }
+
+    /// Estimates the fee of the blobs of a prepared payload at the gas price of the last dispatch,
+    /// None for the backends without fees. The manifest of a chunked blob is not included.
+    pub fn estimate_fee(&self, caller: &str, prepared: &PreparedBlob) -> Option<FeeEstimate> {
+        let client = self.client(caller);
+        let gas = prepared
+            .blob_sizes()
+            .into_iter()
+            .map(|size| client.estimate_gas(size))
+            .sum::<Option<u64>>()?;
+
+        let gas_price = self.gas_price.lock().unwrap().clone();
+        Some(FeeEstimate {
+            gas,
+            amount: gas_price
+                .as_ref()
+                .map(|(price, _)| (gas as f64 * price).ceil() as u64),
+            denom: gas_price.map(|(_, denom)| denom),
+        })
     }
 
-    /// Dispatches a blob to the data availability layer.
+    /// Dispatches a blob to the data availability layer, see `dispatch_prepared`.
     pub async fn dispatch_blob(
         &self,
+        ctx: &CallContext,
+        caller: &str,
         batch_number: u32,
+        protocol_version: Option<&str>,
         data: Vec<u8>,
-    ) -> anyhow::Result<DispatchResponse> {
+        priority: DispatchPriority,
+    ) -> Result<DispatchResponse, DAError> {
+        let prepared = self.prepare(caller, data).await?;
+        self.dispatch_prepared(
+            ctx,
+            caller,
+            batch_number,
+            protocol_version,
+            prepared,
+            priority,
+        )
+        .await
+    }
+
+    /// Dispatches a prepared blob once a dispatch slot is available, the wait for the slot is
+    /// bounded by the deadline of the context too. The blob is published under the namespace of
+    /// the route matching the batch and the protocol version, if any.
+    pub async fn dispatch_prepared(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        batch_number: u32,
+        protocol_version: Option<&str>,
+        prepared: PreparedBlob,
+        priority: DispatchPriority,
+    ) -> Result<DispatchResponse, DAError> {
+        let PreparedBlob {
+            data,
+            payload_hash,
+            resumed,
+            ..
+        } = prepared;
+
+        let queued_at = Instant::now();
+        let _permit = ctx.run(self.queue.acquire(priority)).await?;
+        DA_METRICS
+            .dispatch_queue_latency
+            .observe(queued_at.elapsed());
+
+        let client = self.dispatch_client(caller, batch_number, protocol_version);
+        let shadowed = self.shadow_svc.sample().then(|| data.clone());
+
+        let _in_flight = DA_METRICS.in_flight_dispatches.inc_guard(1);
         let start = Instant::now();
-        let response = self.da_client.dispatch_blob(batch_number, data).await?;
+        let chunk_size = self.transform_svc.chunk_size(client.blob_size_limit());
+        // The chunks are only reused while the payload is split the same way.
+        let resumed = match &resumed {
+            Some((size, chunks)) if Some(*size) == chunk_size => chunks.as_slice(),
+            _ => &[],
+        };
+        let result = Self::submit(ctx, client, batch_number, data, chunk_size, resumed).await;
+        if let Some(data) = shadowed {
+            let chunk_size = self
+                .transform_svc
+                .chunk_size(self.shadow_svc.blob_size_limit());
+            self.shadow_svc
+                .dispatch(batch_number, data, chunk_size, result.is_ok());
+        }
+        match &result {
+            Ok(_) => self.queue.on_success(),
+            Err(DAError::RateLimited { .. }) => self.queue.on_rate_limited(),
+            Err(DAError::PartialDispatch(_)) => {
+                DA_METRICS.partial_dispatches.inc();
+            }
+            Err(_) => {}
+        }
+        let mut response = result?;
 
         DA_METRICS.dispatched_blobs.inc();
         DA_METRICS.dispatch_latency.observe(start.elapsed());
+        for fee in response.receipt.iter().flat_map(DispatchReceipt::fees) {
+            DA_METRICS.gas_used.inc_by(fee.gas_used);
+            DA_METRICS.dispatch_gas_used.observe(fee.gas_used);
+            if let (Some(amount), Some(denom)) = (fee.amount, &fee.denom) {
+                DA_METRICS.fees_paid[denom].inc_by(amount);
+                DA_METRICS.dispatch_fee.observe(amount);
+                if fee.gas_wanted > 0 {
+                    *self.gas_price.lock().unwrap() =
+                        Some((amount as f64 / fee.gas_wanted as f64, denom.clone()));
+                }
+            }
+        }
+
+        if let Some(payload_hash) = payload_hash {
+            response.attestation = self
+                .attestation_svc
+                .attest(&response.blob_id, batch_number, &payload_hash)
+                .await;
+        }
+
+        Ok(response)
+    }
+
+    /// Dispatches a payload, as a chunked blob when it is larger than the chunk size. The
+    /// `resumed` chunks of a partially failed dispatch are not dispatched again.
+    pub async fn submit(
+        ctx: &CallContext,
+        client: &Arc<dyn DataAvailabilityClient + Send + Sync>,
+        batch_number: u32,
+        data: Vec<u8>,
+        chunk_size: Option<usize>,
+        resumed: &[DispatchedChunk],
+    ) -> Result<DispatchResponse, DAError> {
+        match chunk_size {
+            Some(chunk_size) if data.len() > chunk_size => {
+                Self::dispatch_chunks(ctx, client, batch_number, data, chunk_size, resumed).await
+            }
+            _ => client.dispatch_blob(ctx, batch_number, data).await,
+        }
+    }
+
+    /// Dispatches the chunks of a payload, then the manifest of their blob_ids which identifies
+    /// the chunked blob. The receipts of the chunks are set on the receipt of the manifest. Every
+    /// chunk is attempted, the failures after a dispatched chunk are reported together.
+    async fn dispatch_chunks(
+        ctx: &CallContext,
+        client: &Arc<dyn DataAvailabilityClient + Send + Sync>,
+        batch_number: u32,
+        data: Vec<u8>,
+        chunk_size: usize,
+        resumed: &[DispatchedChunk],
+    ) -> Result<DispatchResponse, DAError> {
+        let mut results = vec![];
+        for (index, chunk) in data.chunks(chunk_size).enumerate() {
+            let result = match resumed.iter().find(|resumed| resumed.index == index) {
+                Some(resumed) => Ok(DispatchResponse {
+                    blob_id: resumed.blob_id.clone(),
+                    attestation: None,
+                    receipt: resumed.receipt.clone(),
+                }),
+                None => {
+                    Self::dispatch_chunk(ctx, client, batch_number, index, chunk.to_vec()).await
+                }
+            };
+            results.push(result);
+        }
+        if results.iter().any(Result::is_err) {
+            return Err(partial_dispatch(batch_number, chunk_size, results, None));
+        }
+
+        let chunks: Vec<DispatchResponse> = results.into_iter().flatten().collect();
+        let chunk_ids: Vec<String> = chunks.iter().map(|chunk| chunk.blob_id.clone()).collect();
+        let manifest = ViaDaBlob::new(chunk_ids.len(), serialize_blob_ids(&chunk_ids)?);
+        let mut response = match client
+            .dispatch_blob(ctx, batch_number, manifest.to_bytes())
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                let results = chunks.into_iter().map(Ok).collect();
+                return Err(partial_dispatch(
+                    batch_number,
+                    chunk_size,
+                    results,
+                    Some(err),
+                ));
+            }
+        };
+        if let Some(receipt) = &mut response.receipt {
+            receipt.chunks = chunks
+                .into_iter()
+                .filter_map(|chunk| Some(ChunkReceipt::new(chunk.blob_id, chunk.receipt?)))
+                .collect();
+        }
+
+        Ok(response)
+    }
+
+    /// Dispatches a chunk of a chunked blob in its span, with the blob_id once dispatched.
+    async fn dispatch_chunk(
+        ctx: &CallContext,
+        client: &Arc<dyn DataAvailabilityClient + Send + Sync>,
+        batch_number: u32,
+        index: usize,
+        chunk: Vec<u8>,
+    ) -> Result<DispatchResponse, DAError> {
+        let span = tracing::info_span!(
+            "chunk_dispatch",
+            batch_number,
+            index,
+            size = chunk.len(),
+            blob_id = tracing::field::Empty,
+        );
+        let start = Instant::now();
+        let result = client
+            .dispatch_blob(ctx, batch_number, chunk)
+            .instrument(span.clone())
+            .await;
+        DA_METRICS.chunk_dispatch_latency.observe(start.elapsed());
+
+        match &result {
+            Ok(response) => {
+                span.record("blob_id", response.blob_id.as_str());
+            }
+            Err(error) => {
+                DA_METRICS.chunk_dispatch_failures.inc();
+                span.in_scope(|| tracing::warn!("Failed to dispatch the chunk: {}", error));
+            }
+        }
+        result
+    }
+
+    /// Whether a blob is readable in the caller namespace, from the DA layer or from the archive
+    /// once pruned. Only the first part of the blob is fetched from the DA layer.
+    pub async fn is_readable(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<bool, DAError> {
+        match self
+            .read(caller, |client| {
+                client.get_inclusion_range(ctx, blob_id, 0..0)
+            })
+            .await
+        {
+            Ok(found) => Ok(found.is_some()),
+            Err(err @ DAError::Pruned { .. }) => {
+                match self.archive.get(&self.archive_key(caller, blob_id)).await? {
+                    Some(_) => Ok(true),
+                    None => Err(err),
+                }
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Fetches the inclusion data for a given blob_id in the caller namespace.
+    pub async fn get_inclusion_data(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<Option<InclusionData>, DAError> {
+        let response = match self.fetch_payload(ctx, caller, blob_id).await? {
+            Some(data) => Some(InclusionData {
+                data: self.transform_svc.decode(blob_id, data).await?,
+            }),
+            None => None,
+        };
+
+        DA_METRICS.inclusion_queries.inc();
 
         Ok(response)
     }
 
-    /// Fetches the inclusion data for a given blob_id.
-    pub async fn get_inclusion_data(&self, blob_id: &str) -> anyhow::Result<Option<InclusionData>> {
-        let response = self.da_client.get_inclusion_data(blob_id).await?;
+    /// Fetches a byte range of the payload of a blob in the caller namespace. The enveloped
+    /// payloads and the archived ones are fetched whole.
+    pub async fn get_inclusion_range(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+        range: Range<u64>,
+    ) -> Result<Option<InclusionRange>, DAError> {
+        let header = self
+            .read(caller, |client| async move {
+                let header = client
+                    .get_inclusion_range(ctx, blob_id, 0..ENVELOPE_HEADER_LEN as u64)
+                    .await?;
+                Ok(header.map(|header| (client, header)))
+            })
+            .await;
+        let (client, whole) = match header {
+            Ok(Some((client, header))) => (client, BlobEnvelope::is_enveloped(&header.data)),
+            Ok(None) => return Ok(None),
+            Err(DAError::Pruned { .. }) if self.archive.is_enabled() => (self.client(caller), true),
+            Err(err) => return Err(err),
+        };
+
+        let response = if whole {
+            match self.fetch_payload(ctx, caller, blob_id).await? {
+                Some(data) => {
+                    let data = self.transform_svc.decode(blob_id, data).await?;
+                    Some(InclusionRange::slice(&data, range))
+                }
+                None => None,
+            }
+        } else {
+            client.get_inclusion_range(ctx, blob_id, range).await?
+        };
 
         DA_METRICS.inclusion_queries.inc();
 
         Ok(response)
     }
+
+    /// Generates the inclusion proof of a blob in the caller namespace.
+    pub async fn get_inclusion_proof(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        self.read(caller, |client| client.get_inclusion_proof(ctx, blob_id))
+            .await
+    }
+
+    /// Returns the data root of the block at `height` on the DA layer of the caller.
+    pub async fn data_root(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        height: u64,
+    ) -> Result<String, DAError> {
+        self.client(caller).data_root(ctx, height).await
+    }
+
+    /// Returns the queue of the dispatches waiting for a slot.
+    pub fn queue(&self) -> &DispatchQueue {
+        &self.queue
+    }
+
+    /// Whether the dispatch queue serves the dispatches.
+    pub fn is_queue_healthy(&self) -> bool {
+        self.queue.is_healthy()
+    }
+
+    /// Returns how long the DA layer of the caller keeps the blobs, None when forever.
+    pub fn retention(&self, caller: &str) -> Option<Duration> {
+        self.client(caller).retention()
+    }
+
+    /// Returns when the blob stops being served by the DA layer of the caller, None when never.
+    pub async fn expiry_for(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DAError> {
+        self.client(caller).expiry_for(ctx, blob_id).await
+    }
+
+    /// Returns the blob_ids of the chunks of a blob of the caller, empty when the blob is not
+    /// chunked and None when it is not found.
+    pub async fn get_chunk_ids(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<Option<Vec<String>>, DAError> {
+        self.read(caller, |client| client.get_chunk_ids(ctx, blob_id))
+            .await
+    }
+
+    /// Fetches the chunk `index` of a blob of the caller as stored, the chunks of an enveloped
+    /// payload are decoded once reassembled. None when the blob is not found or has fewer chunks.
+    pub async fn get_chunk(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+        index: usize,
+    ) -> Result<Option<Vec<u8>>, DAError> {
+        let chunk_ids = self
+            .read(caller, |client| async move {
+                let chunk_ids = client.get_chunk_ids(ctx, blob_id).await?;
+                Ok(chunk_ids.map(|chunk_ids| (client, chunk_ids)))
+            })
+            .await?;
+        let Some((client, chunk_ids)) = chunk_ids else {
+            return Ok(None);
+        };
+        if index >= chunk_ids.len() {
+            return Ok(None);
+        }
+        client
+            .get_chunk(ctx, blob_id, &chunk_ids, index)
+            .await
+            .map(Some)
+    }
+
+    /// Returns the earliest height the DA node of the caller still serves, None without pruning.
+    pub async fn earliest_available_height(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+    ) -> Result<Option<u64>, DAError> {
+        ctx.run(self.client(caller).earliest_available_height())
+            .await
+    }
+}
+
+#[cfg(test)]
+impl DaSvc {
+    /// Creates the service of the tests over `da_client`, without tenants, transforms nor archive.
+    pub(crate) fn for_tests(da_client: Arc<dyn DataAvailabilityClient + Send + Sync>) -> Self {
+        let key_provider =
+            Arc::new(crate::clients::key_providers::local::LocalKeyProvider::default());
+        Self::new(
+            da_client,
+            Arc::new(AttestationSvc::new(key_provider.clone())),
+            Arc::new(TransformSvc::new(vec![], key_provider, 3, None)),
+            Arc::default(),
+            Arc::default(),
+            1,
+            &[],
+        )
+        .unwrap()
+    }
+
+    /// Scopes the callers of `tenants` to their namespace, as (caller, namespace) pairs.
+    pub(crate) fn with_tenants(mut self, tenants: &[(&str, &str)]) -> Self {
+        for (tenant, namespace) in tenants {
+            let client = self.da_client.namespaced(namespace).unwrap();
+            self.tenant_clients.insert(tenant.to_string(), client);
+            self.tenant_namespaces
+                .insert(tenant.to_string(), namespace.to_string());
+        }
+        self
+    }
+
+    /// Mirrors the confirmed blobs to `archive`.
+    pub(crate) fn with_archive(mut self, archive: Arc<ArchiveStore>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Applies the transforms of `transform_svc` to the payloads.
+    pub(crate) fn with_transforms(mut self, transform_svc: Arc<TransformSvc>) -> Self {
+        self.transform_svc = transform_svc;
+        self
+    }
+}
+
+/// Reports the failed chunks of a blob, or its failed `manifest` once the chunks are dispatched.
+/// When no chunk was dispatched, the error of the first one is returned as the blob failed whole.
+fn partial_dispatch(
+    batch_number: u32,
+    chunk_size: usize,
+    results: Vec<Result<DispatchResponse, DAError>>,
+    manifest: Option<DAError>,
+) -> DAError {
+    let chunks = results.len();
+    let mut succeeded = vec![];
+    let mut errors = vec![];
+    for (index, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(response) => succeeded.push(DispatchedChunk {
+                index,
+                blob_id: response.blob_id,
+                receipt: response.receipt,
+            }),
+            Err(err) => errors.push((Some(index), err)),
+        }
+    }
+    errors.extend(manifest.map(|err| (None, err)));
+    if succeeded.is_empty() && !errors.is_empty() {
+        return errors.remove(0).1;
+    }
+
+    let failed: Vec<FailedChunk> = errors
+        .iter()
+        .map(|(index, err)| FailedChunk::new(*index, err))
+        .collect();
+    tracing::warn!(
+        "Dispatched {} of the {} chunks of batch {}, {} failed",
+        succeeded.len(),
+        chunks,
+        batch_number,
+        failed.len()
+    );
+    DAError::PartialDispatch(Box::new(PartialDispatchReport {
+        batch_number,
+        chunks,
+        chunk_size,
+        retriable: failed.iter().all(|chunk| chunk.retriable),
+        succeeded,
+        failed,
+        staging_id: None,
+        expires_at: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::clients::da_clients::in_memory::InMemoryClient;
+
+    /// A node failing the second dispatch, and counting the dispatches.
+    #[derive(Debug, Clone)]
+    struct FlakyClient {
+        inner: InMemoryClient,
+        dispatches: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl DataAvailabilityClient for FlakyClient {
+        async fn dispatch_blob(
+            &self,
+            ctx: &CallContext,
+            batch_number: u32,
+            data: Vec<u8>,
+        ) -> Result<DispatchResponse, DAError> {
+            if self.dispatches.fetch_add(1, Ordering::SeqCst) == 1 {
+                return Err(DAError::ConnectionError {
+                    message: "The DA node is unreachable".to_string(),
+                });
+            }
+            self.inner.dispatch_blob(ctx, batch_number, data).await
+        }
+
+        async fn get_inclusion_data(
+            &self,
+            ctx: &CallContext,
+            blob_id: &str,
+        ) -> Result<Option<InclusionData>, DAError> {
+            self.inner.get_inclusion_data(ctx, blob_id).await
+        }
+
+        fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+            Box::new(self.clone())
+        }
+
+        fn blob_size_limit(&self) -> Option<usize> {
+            None
+        }
+
+        async fn ping(&self) -> anyhow::Result<bool> {
+            Ok(true)
+        }
+
+        fn namespaced(
+            &self,
+            _: &str,
+        ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+            Ok(Arc::new(self.clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_partial_dispatches_resume_from_the_dispatched_chunks() {
+        let client = FlakyClient {
+            inner: InMemoryClient::new(1024),
+            dispatches: Arc::default(),
+        };
+        let dispatches = client.dispatches.clone();
+        let client: Arc<dyn DataAvailabilityClient + Send + Sync> = Arc::new(client);
+        let ctx = CallContext::background();
+        let data = b"pubdata".to_vec();
+
+        let Err(DAError::PartialDispatch(report)) =
+            DaSvc::submit(&ctx, &client, 1, data.clone(), Some(3), &[]).await
+        else {
+            panic!("The second chunk failed");
+        };
+        let indexes: Vec<_> = report.succeeded.iter().map(|chunk| chunk.index).collect();
+        assert_eq!(indexes, [0, 2]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].index, Some(1));
+        assert_eq!(report.failed[0].code, "DA_CONNECTION_ERROR");
+        assert!(report.retriable);
+
+        // Only the failed chunk and the manifest are dispatched again.
+        let response = DaSvc::submit(&ctx, &client, 1, data, Some(3), &report.succeeded).await;
+        assert!(response.is_ok());
+        assert_eq!(dispatches.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn test_dispatches_are_routed_to_the_namespace_of_their_batch() {
+        let da_svc = DaSvc::for_tests(Arc::new(InMemoryClient::new(1024)))
+            .with_namespace_routes(&[
+                NamespaceRoute::parse("protocol_version=26", "V26").unwrap(),
+                NamespaceRoute::parse("batches=10-", "V2").unwrap(),
+            ])
+            .unwrap();
+
+        let ctx = CallContext::background();
+        let mut namespaces = vec![];
+        for (batch_number, protocol_version) in [(9, None), (10, None), (3, Some("26"))] {
+            let response = da_svc
+                .dispatch_blob(
+                    &ctx,
+                    "caller",
+                    batch_number,
+                    protocol_version,
+                    vec![batch_number as u8],
+                    DispatchPriority::Normal,
+                )
+                .await
+                .unwrap();
+            namespaces.push(response.receipt.unwrap().namespace);
+
+            // The blobs are read whatever their namespace.
+            let inclusion = da_svc
+                .get_inclusion_data(&ctx, "caller", &response.blob_id)
+                .await
+                .unwrap();
+            assert_eq!(inclusion.unwrap().data, vec![batch_number as u8]);
+        }
+        assert_eq!(
+            namespaces,
+            [None, Some(hex::encode("V2")), Some(hex::encode("V26"))]
+        );
+    }
 }
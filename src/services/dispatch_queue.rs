@@ -0,0 +1,430 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::{sync::oneshot, time::Instant};
+
+use crate::{
+    clients::da_clients::types::DAError,
+    services::{
+        lifecycle::{Lifecycle, StopSignal, run_every},
+        metrics::DA_METRICS,
+    },
+    types::dispatch::DispatchPriority,
+};
+
+/// The default interval between two compactions of the queue, the age of the oldest waiter is
+/// reported even when no dispatch is served.
+const DEFAULT_COMPACTION_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Waiter {
+    priority: DispatchPriority,
+    /// Arrival order, the waiters of a priority are served first in first out.
+    seq: u64,
+    queued_at: Instant,
+    sender: oneshot::Sender<DispatchPermit>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// The current concurrency limit, adapted between 1 and `max_limit`.
+    limit: usize,
+    max_limit: usize,
+    in_flight: usize,
+    /// Successful dispatches since the last increase of the limit.
+    successes: usize,
+    next_seq: u64,
+    waiting: BinaryHeap<Waiter>,
+    /// The waiters waiting longer are dropped by the compaction, if set.
+    max_wait: Option<Duration>,
+}
+
+impl QueueState {
+    /// Hands the free slots over to the waiters still waiting.
+    fn serve_waiters(&mut self, queue: &Arc<Mutex<QueueState>>) {
+        while self.in_flight < self.limit {
+            let Some(waiter) = self.waiting.pop() else {
+                break;
+            };
+
+            self.in_flight += 1;
+            let permit = DispatchPermit {
+                state: Some(queue.clone()),
+            };
+            // A waiter whose request was cancelled returns the permit, its slot is released here
+            // as the permit would lock the state again on drop.
+            if let Err(mut permit) = waiter.sender.send(permit) {
+                self.in_flight -= 1;
+                permit.state = None;
+                drop(permit);
+            }
+        }
+
+        self.compact();
+    }
+
+    /// Drops the waiters whose request was cancelled or waiting longer than `max_wait`, and
+    /// reports the depth of the queue.
+    fn compact(&mut self) {
+        let max_wait = self.max_wait;
+        self.waiting.retain(|waiter| {
+            !waiter.sender.is_closed()
+                && max_wait.is_none_or(|max_wait| waiter.queued_at.elapsed() < max_wait)
+        });
+
+        DA_METRICS.queued_dispatches.set(self.waiting.len());
+        DA_METRICS
+            .oldest_queued_dispatch_age
+            .set(self.oldest_wait().unwrap_or_default());
+    }
+
+    fn oldest_wait(&self) -> Option<Duration> {
+        self.waiting
+            .iter()
+            .map(|waiter| waiter.queued_at.elapsed())
+            .max()
+    }
+
+    fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.successes = 0;
+        DA_METRICS.dispatch_concurrency_limit.set(limit);
+    }
+}
+
+/// Limits the concurrent dispatches, the dispatches waiting for a slot are served by priority.
+///
+/// The limit adapts to the DA layer with AIMD: it is halved when the dispatches are rate limited
+/// and grows back by one slot every `limit` successful dispatches, up to the configured maximum.
+#[derive(Clone)]
+pub struct DispatchQueue {
+    state: Arc<Mutex<QueueState>>,
+    compaction_interval: Duration,
+}
+
+/// A dispatch slot, released on drop.
+pub struct DispatchPermit {
+    /// The queue the slot is released to, None once released.
+    state: Option<Arc<Mutex<QueueState>>>,
+}
+
+impl DispatchQueue {
+    pub fn new(max_concurrent: usize) -> Self {
+        let limit = max_concurrent.max(1);
+        DA_METRICS.dispatch_concurrency_limit.set(limit);
+
+        Self {
+            state: Arc::new(Mutex::new(QueueState {
+                limit,
+                max_limit: limit,
+                ..Default::default()
+            })),
+            compaction_interval: DEFAULT_COMPACTION_INTERVAL,
+        }
+    }
+
+    /// Compacts the queue every `interval`, the waiters waiting longer than `max_wait` are
+    /// dropped then.
+    pub fn with_compaction(mut self, interval: Duration, max_wait: Option<Duration>) -> Self {
+        self.compaction_interval = interval;
+        self.state.lock().unwrap().max_wait = max_wait;
+        self
+    }
+
+    /// Waits for a dispatch slot, a dispatch waiting longer than the max wait fails as rate
+    /// limited.
+    pub async fn acquire(&self, priority: DispatchPriority) -> Result<DispatchPermit, DAError> {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            if state.in_flight < state.limit && state.waiting.is_empty() {
+                state.in_flight += 1;
+                return Ok(DispatchPermit {
+                    state: Some(self.state.clone()),
+                });
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiting.push(Waiter {
+                priority,
+                seq,
+                queued_at: Instant::now(),
+                sender,
+            });
+            state.compact();
+            receiver
+        };
+
+        // The waiters are only dropped without a permit once expired.
+        receiver.await.map_err(|_| {
+            DA_METRICS.expired_queued_dispatches.inc();
+            DAError::RateLimited {
+                message: format!(
+                    "The dispatch waited more than {:?} for a slot",
+                    self.state.lock().unwrap().max_wait.unwrap_or_default()
+                ),
+                retry_after: None,
+            }
+        })
+    }
+
+    /// Returns the number of dispatches waiting for a slot.
+    pub fn len(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        state.compact();
+        state.waiting.len()
+    }
+
+    /// Returns how long the oldest dispatch has been waiting for a slot, None when none waits.
+    pub fn oldest_wait(&self) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        state.compact();
+        state.oldest_wait()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the current concurrency limit.
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    /// Whether the dispatches are served, the queue is unhealthy while dispatches wait with the
+    /// limit lowered by the rate limiting.
+    pub fn is_healthy(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.waiting.is_empty() || state.limit >= state.max_limit
+    }
+
+    /// Records a successful dispatch, the limit grows by one every `limit` successes.
+    pub fn on_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.limit >= state.max_limit {
+            return;
+        }
+
+        state.successes += 1;
+        if state.successes >= state.limit {
+            let limit = state.limit + 1;
+            state.set_limit(limit);
+            state.serve_waiters(&self.state);
+        }
+    }
+
+    /// Records a rate limited dispatch, the limit is halved.
+    pub fn on_rate_limited(&self) {
+        let mut state = self.state.lock().unwrap();
+        let limit = (state.limit / 2).max(1);
+        if limit < state.limit {
+            tracing::warn!(
+                "Dispatches are rate limited, lowering the concurrency limit to {}",
+                limit
+            );
+        }
+        state.set_limit(limit);
+    }
+}
+
+impl Drop for DispatchPermit {
+    fn drop(&mut self) {
+        let Some(queue) = self.state.take() else {
+            return;
+        };
+        let mut state = queue.lock().unwrap();
+        state.in_flight -= 1;
+        state.serve_waiters(&queue);
+    }
+}
+
+/// Compacts the queue periodically, so a stuck queue shows in the age of its oldest waiter.
+#[async_trait]
+impl Lifecycle for DispatchQueue {
+    fn name(&self) -> &'static str {
+        "dispatch_queue"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        run_every(self.compaction_interval, stop, || async {
+            self.state.lock().unwrap().compact();
+        })
+        .await;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for DispatchQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("DispatchQueue")
+            .field("limit", &state.limit)
+            .field("in_flight", &state.in_flight)
+            .field("waiting", &state.waiting.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_waiters_are_served_by_priority() {
+        let queue = DispatchQueue::new(1);
+        let permit = queue.acquire(DispatchPriority::Normal).await.unwrap();
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        for (name, priority) in [
+            ("low", DispatchPriority::Low),
+            ("normal-1", DispatchPriority::Normal),
+            ("high", DispatchPriority::High),
+            ("normal-2", DispatchPriority::Normal),
+        ] {
+            let queue = queue.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                let _permit = queue.acquire(priority).await.unwrap();
+                sender.send(name).unwrap();
+            });
+            // Let the task enqueue before the next one.
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(queue.len(), 4);
+
+        drop(permit);
+        let mut served = vec![];
+        for _ in 0..4 {
+            served.push(receiver.recv().await.unwrap());
+        }
+        assert_eq!(served, ["high", "normal-1", "normal-2", "low"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiters_do_not_leak_slots() {
+        let queue = DispatchQueue::new(1);
+        let permit = queue.acquire(DispatchPriority::Normal).await.unwrap();
+
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(10),
+            queue.acquire(DispatchPriority::High),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        assert_eq!(queue.len(), 0);
+
+        drop(permit);
+        let _permit = tokio::time::timeout(
+            Duration::from_millis(100),
+            queue.acquire(DispatchPriority::Low),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_waiters_fail_as_rate_limited() {
+        let queue = DispatchQueue::new(1)
+            .with_compaction(Duration::from_secs(5), Some(Duration::from_millis(20)));
+        let permit = queue.acquire(DispatchPriority::Normal).await.unwrap();
+
+        let waiter = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.acquire(DispatchPriority::Normal).await.map(drop) }
+        });
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(queue.len(), 0);
+        assert!(matches!(
+            waiter.await.unwrap(),
+            Err(DAError::RateLimited { .. })
+        ));
+
+        // The slot of the expired waiter was not taken.
+        drop(permit);
+        let _permit = queue.acquire(DispatchPriority::Normal).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oldest_wait_is_reported() {
+        let queue = DispatchQueue::new(1);
+        let _permit = queue.acquire(DispatchPriority::Normal).await.unwrap();
+        assert_eq!(queue.oldest_wait(), None);
+
+        let waiter = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                let _permit = queue.acquire(DispatchPriority::Normal).await.unwrap();
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(queue.oldest_wait().unwrap() >= Duration::from_millis(20));
+
+        waiter.abort();
+        let _ = waiter.await;
+        assert_eq!(queue.oldest_wait(), None);
+    }
+
+    #[tokio::test]
+    async fn test_limit_adapts_to_rate_limits() {
+        let queue = DispatchQueue::new(8);
+
+        queue.on_rate_limited();
+        queue.on_rate_limited();
+        assert_eq!(queue.limit(), 2);
+
+        // Only the slots within the lowered limit are handed out.
+        let _first = queue.acquire(DispatchPriority::Normal).await.unwrap();
+        let _second = queue.acquire(DispatchPriority::Normal).await.unwrap();
+        let waiter = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                let _permit = queue.acquire(DispatchPriority::Normal).await.unwrap();
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(queue.len(), 1);
+
+        // The limit grows by one every `limit` successes and serves the waiter.
+        queue.on_success();
+        queue.on_success();
+        assert_eq!(queue.limit(), 3);
+        tokio::time::timeout(Duration::from_millis(100), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+
+        for _ in 0..100 {
+            queue.on_success();
+        }
+        assert_eq!(queue.limit(), 8);
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::{services::metrics::DA_METRICS, types::dispatch::DispatchReceipt};
+
+/// `FeeBudgetError` is returned when the fees paid over the window exhausted the budget.
+#[derive(Debug, thiserror::Error)]
+#[error("Fee budget exhausted: {spent} paid in the last {window:?}, the budget is {budget}")]
+pub struct FeeBudgetError {
+    pub spent: u64,
+    pub budget: u64,
+    pub window: Duration,
+    /// The time before the oldest fee of the window leaves it.
+    pub retry_after: Duration,
+}
+
+/// Tracks the fees paid over a rolling window and rejects the dispatches once they exhaust the
+/// budget, so a runaway dispatcher can't drain the funding account.
+///
+/// The fee of a dispatch is only known once it is submitted, the dispatch exhausting the budget
+/// is let through and the following ones are rejected.
+#[derive(Debug, Clone, Default)]
+pub struct FeeBudgetSvc {
+    budget: Option<u64>,
+    window: Duration,
+    /// The fees paid within the window, oldest first.
+    fees: Arc<Mutex<VecDeque<(Instant, u64)>>>,
+}
+
+impl FeeBudgetSvc {
+    /// Creates the guard, the fees are not limited without a `budget`.
+    pub fn new(budget: Option<u64>, window: Duration) -> Self {
+        Self {
+            budget,
+            window,
+            fees: Arc::default(),
+        }
+    }
+
+    /// Drops the fees older than the window, returns the sum of the others.
+    fn spent_at(&self, fees: &mut VecDeque<(Instant, u64)>, now: Instant) -> u64 {
+        while let Some((paid_at, _)) = fees.front()
+            && now.duration_since(*paid_at) >= self.window
+        {
+            fees.pop_front();
+        }
+        let spent = fees.iter().map(|(_, amount)| amount).sum();
+        DA_METRICS.fee_budget_spent.set(spent);
+        spent
+    }
+
+    /// Checks that the budget allows another dispatch.
+    pub fn check(&self) -> Result<(), FeeBudgetError> {
+        self.check_at(Instant::now())
+    }
+
+    fn check_at(&self, now: Instant) -> Result<(), FeeBudgetError> {
+        let Some(budget) = self.budget else {
+            return Ok(());
+        };
+
+        let mut fees = self.fees.lock().unwrap();
+        let spent = self.spent_at(&mut fees, now);
+        if spent < budget {
+            return Ok(());
+        }
+
+        DA_METRICS.fee_budget_rejections.inc();
+        let retry_after = fees.front().map_or(Duration::ZERO, |(paid_at, _)| {
+            self.window.saturating_sub(now.duration_since(*paid_at))
+        });
+        Err(FeeBudgetError {
+            spent,
+            budget,
+            window: self.window,
+            retry_after,
+        })
+    }
+
+    /// Records the fees paid for a dispatch.
+    pub fn record(&self, receipt: Option<&DispatchReceipt>) {
+        self.record_at(receipt, Instant::now());
+    }
+
+    fn record_at(&self, receipt: Option<&DispatchReceipt>, now: Instant) {
+        if self.budget.is_none() {
+            return;
+        }
+
+        let amount: u64 = receipt
+            .into_iter()
+            .flat_map(DispatchReceipt::fees)
+            .filter_map(|fee| fee.amount)
+            .sum();
+        if amount > 0 {
+            let mut fees = self.fees.lock().unwrap();
+            fees.push_back((now, amount));
+            self.spent_at(&mut fees, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{config::DaBackend, types::dispatch::DispatchFee};
+
+    fn receipt(amount: u64) -> DispatchReceipt {
+        DispatchReceipt {
+            height: Some(1),
+            commitment: String::new(),
+            namespace: None,
+            size: 1,
+            backend: DaBackend::Celestia,
+            submitted_at: Utc::now(),
+            fee: Some(DispatchFee {
+                tx_hash: String::new(),
+                gas_wanted: 1,
+                gas_used: 1,
+                amount: Some(amount),
+                denom: Some("utia".to_string()),
+            }),
+            chunks: vec![],
+        }
+    }
+
+    #[test]
+    fn test_budget_is_enforced_over_the_window() {
+        let svc = FeeBudgetSvc::new(Some(100), Duration::from_secs(60));
+        let start = Instant::now();
+
+        svc.record_at(Some(&receipt(60)), start);
+        svc.check_at(start).unwrap();
+        svc.record_at(Some(&receipt(50)), start + Duration::from_secs(20));
+
+        let err = svc.check_at(start + Duration::from_secs(30)).unwrap_err();
+        assert_eq!(err.spent, 110);
+        assert_eq!(err.retry_after, Duration::from_secs(30));
+
+        // The first fee left the window.
+        svc.check_at(start + Duration::from_secs(60)).unwrap();
+
+        let unlimited = FeeBudgetSvc::new(None, Duration::from_secs(60));
+        unlimited.record_at(Some(&receipt(1000)), start);
+        unlimited.check_at(start).unwrap();
+    }
+}
@@ -1,26 +1,143 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
 
 use crate::{
     clients::da_clients::DataAvailabilityClient,
-    types::health_check::{HealthCheckResponse, ServiceStatus},
+    services::{
+        da::DaSvc,
+        index::IndexSvc,
+        lifecycle::{Lifecycle, StopSignal, Supervisor, run_every},
+        maintenance::MaintenanceSvc,
+        metrics::HEALTH_METRICS,
+        selftest::SelftestSvc,
+    },
+    types::health_check::{HealthCheckResponse, ReadinessResponse, ServiceStatus, SubsystemHealth},
 };
 
+/// The interval between two refreshes of the health gauges.
+const HEALTH_REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
 #[derive(Debug, Clone)]
 pub struct HealthCheckSvc {
     da_client: Arc<dyn DataAvailabilityClient + Send + Sync>,
+    da_svc: Arc<DaSvc>,
+    index: Arc<IndexSvc>,
+    maintenance: Arc<MaintenanceSvc>,
+    selftest: Arc<SelftestSvc>,
+    supervisor: Arc<Supervisor>,
 }
 
 impl HealthCheckSvc {
-    pub fn new(da_client: Arc<dyn DataAvailabilityClient + Send + Sync>) -> Self {
-        Self { da_client }
+    pub fn new(
+        da_client: Arc<dyn DataAvailabilityClient + Send + Sync>,
+        da_svc: Arc<DaSvc>,
+        index: Arc<IndexSvc>,
+        maintenance: Arc<MaintenanceSvc>,
+        selftest: Arc<SelftestSvc>,
+        supervisor: Arc<Supervisor>,
+    ) -> Self {
+        Self {
+            da_client,
+            da_svc,
+            index,
+            maintenance,
+            selftest,
+            supervisor,
+        }
+    }
+
+    /// Checks the subsystems and updates their gauges. A failed check reports its subsystem as
+    /// unhealthy.
+    pub async fn subsystems(&self) -> SubsystemHealth {
+        let celestia_connected = self.da_client.ping().await.unwrap_or(false);
+        let celestia_synced = match self.da_client.is_synced().await {
+            Ok(synced) => synced.unwrap_or(true),
+            Err(err) => {
+                tracing::warn!("Error to get the sync state of the DA node: {}", err);
+                false
+            }
+        };
+        let health = SubsystemHealth {
+            celestia_connected,
+            celestia_synced,
+            queue_healthy: self.da_svc.is_queue_healthy(),
+            index_db_healthy: self.index.is_healthy(),
+        };
+
+        HEALTH_METRICS
+            .celestia_connected
+            .set(health.celestia_connected as u64);
+        HEALTH_METRICS
+            .celestia_synced
+            .set(health.celestia_synced as u64);
+        HEALTH_METRICS
+            .queue_healthy
+            .set(health.queue_healthy as u64);
+        HEALTH_METRICS
+            .index_db_healthy
+            .set(health.index_db_healthy as u64);
+
+        health
     }
 
+    /// Reports the DA layer as unreachable rather than failing, e.g. while the DA node starts.
     pub async fn health_check(&self) -> anyhow::Result<HealthCheckResponse> {
+        let status = self.da_client.ping().await?;
         let da = ServiceStatus {
-            status: self.da_client.ping().await?,
-            message: "Data availability is healthy".to_string(),
+            status,
+            message: if status {
+                "Data availability is healthy".to_string()
+            } else {
+                "Data availability is degraded, the DA node is unreachable".to_string()
+            },
         };
 
-        Ok(HealthCheckResponse { da })
+        Ok(HealthCheckResponse {
+            da,
+            selftest: self.selftest.last_result(),
+        })
+    }
+
+    pub async fn readiness(&self) -> anyhow::Result<ReadinessResponse> {
+        let status = self.da_client.ping().await?;
+        let da = ServiceStatus {
+            status,
+            message: if status {
+                "Data availability is healthy".to_string()
+            } else {
+                "Data availability is unreachable".to_string()
+            },
+        };
+
+        let selftest = self.selftest.last_result();
+        let selftest_passed = selftest.as_ref().is_none_or(|result| result.success);
+        let components = self.supervisor.status();
+        let components_healthy = components.iter().all(|component| component.healthy);
+
+        Ok(ReadinessResponse {
+            ready: status && selftest_passed && components_healthy,
+            da,
+            maintenance: self.maintenance.status(),
+            selftest,
+            components,
+            subsystems: self.subsystems().await,
+        })
+    }
+}
+
+/// Refreshes the health gauges, so the alerts don't depend on the readiness probes.
+#[async_trait]
+impl Lifecycle for HealthCheckSvc {
+    fn name(&self) -> &'static str {
+        "health_monitor"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        run_every(HEALTH_REFRESH_INTERVAL, stop, || async {
+            self.subsystems().await;
+        })
+        .await;
+        Ok(())
     }
 }
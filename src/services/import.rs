@@ -0,0 +1,367 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    clients::da_clients::types::{CallContext, DAError, ViaDaBlob},
+    services::{
+        attestation::AttestationSvc, da::DaSvc, index::IndexSvc, metrics::DA_METRICS,
+        sequence::SequenceSvc,
+    },
+    types::{
+        dispatch::DispatchReceipt,
+        import::{IMPORTED_FROM_KEY, ImportFailure, ImportRequest, ImportResponse},
+        index::IndexEntry,
+    },
+};
+
+/// The most heights scanned by an import, a longer range is imported in several requests.
+pub const MAX_IMPORT_HEIGHTS: u64 = 10_000;
+
+/// `ImportError` is returned when an import can't scan the requested heights.
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error(
+        "Invalid height range {from}-{to}, at most {MAX_IMPORT_HEIGHTS} heights are scanned per import"
+    )]
+    InvalidRange { from: u64, to: u64 },
+    #[error("The blobs of {caller} are not read from the namespace {namespace}")]
+    UnreadableNamespace { caller: String, namespace: String },
+}
+
+/// Backfills the index with the blobs submitted to the DA layer by a previous system, so the
+/// history of a migrated rollup stays listed and readable through the blob_ids of this service.
+///
+/// Each batch is a top-level blob: the chunks referenced by the manifests of the range are not
+/// indexed, so the range has to end after the manifests of its chunks. The blobs are indexed
+/// with the hash of their decoded payload, as the dispatched ones, and the blobs that can't be
+/// decoded are skipped. The blobs already indexed are left as they are, an import can be run
+/// again over the same range.
+#[derive(Debug, Clone)]
+pub struct ImportSvc {
+    da_svc: Arc<DaSvc>,
+    index: Arc<IndexSvc>,
+    sequence: Arc<SequenceSvc>,
+}
+
+impl ImportSvc {
+    pub fn new(da_svc: Arc<DaSvc>, index: Arc<IndexSvc>, sequence: Arc<SequenceSvc>) -> Self {
+        Self {
+            da_svc,
+            index,
+            sequence,
+        }
+    }
+
+    /// Scans the heights of the request in order and indexes the blobs found for the caller. The
+    /// DA calls are bounded by the context of the request, a failed call stops the import and is
+    /// reported in the response with what was imported before it.
+    pub async fn import(
+        &self,
+        ctx: &CallContext,
+        request: &ImportRequest,
+    ) -> Result<ImportResponse, ImportError> {
+        let (from, to) = (request.from_height, request.to_height);
+        if from > to || to - from >= MAX_IMPORT_HEIGHTS {
+            return Err(ImportError::InvalidRange { from, to });
+        }
+        let client = self
+            .da_svc
+            .scan_client(&request.caller, request.namespace.as_deref())
+            .ok_or_else(|| ImportError::UnreadableNamespace {
+                caller: request.caller.clone(),
+                namespace: request.namespace.clone().unwrap_or_default(),
+            })?;
+
+        let mut response = ImportResponse {
+            scanned: 0,
+            imported: vec![],
+            already_indexed: 0,
+            skipped: 0,
+            failure: None,
+        };
+        // The chunks are submitted before their manifest, the range is scanned whole first.
+        let mut found = vec![];
+        let mut chunk_ids = HashSet::new();
+        for height in from..=to {
+            let blobs = match client.scan_blobs(ctx, height).await {
+                Ok(blobs) => blobs,
+                Err(err) => {
+                    tracing::error!("Failed to scan the blobs at {}: {}", height, err);
+                    response.failure = Some(ImportFailure::new(height, false, &err));
+                    return Ok(response);
+                }
+            };
+            for blob in blobs {
+                if let Some(manifest) = ViaDaBlob::from_bytes(&blob.data)
+                    && manifest.chunks != 1
+                    && let Ok(ids) = manifest.chunk_ids(&blob.blob_id)
+                {
+                    chunk_ids.extend(ids);
+                }
+                found.push((height, blob.blob_id, blob.receipt));
+            }
+            response.scanned += 1;
+        }
+
+        let mut batch_number = request.first_batch_number;
+        for (height, blob_id, receipt) in found {
+            if chunk_ids.contains(&blob_id) {
+                response.skipped += 1;
+            } else if self.index.get(&blob_id).is_some() {
+                response.already_indexed += 1;
+            } else {
+                match self
+                    .decode(ctx, &request.caller, height, blob_id, receipt, batch_number)
+                    .await
+                {
+                    Ok(Some(entry)) => {
+                        self.index.record(entry.clone());
+                        self.sequence.record(&request.caller, batch_number);
+                        DA_METRICS.imported_blobs.inc();
+                        response.imported.push(entry);
+                        batch_number = batch_number.saturating_add(1);
+                    }
+                    Ok(None) => response.skipped += 1,
+                    Err(err) => {
+                        tracing::error!("Failed to import the blobs at {}: {}", height, err);
+                        response.failure = Some(ImportFailure::new(height, true, &err));
+                        break;
+                    }
+                }
+            }
+        }
+
+        tracing::info!(
+            "Imported {} blobs of {} from the heights {}-{}",
+            response.imported.len(),
+            request.caller,
+            from,
+            to
+        );
+        Ok(response)
+    }
+
+    /// Reads the payload of a blob as the inclusion queries do, None when it can't be decoded.
+    async fn decode(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        height: u64,
+        blob_id: String,
+        receipt: DispatchReceipt,
+        batch_number: u32,
+    ) -> Result<Option<IndexEntry>, DAError> {
+        let data = match self.da_svc.get_inclusion_data(ctx, caller, &blob_id).await {
+            Ok(Some(inclusion)) => inclusion.data,
+            Ok(None)
+            | Err(DAError::IntegrityMismatch { .. })
+            | Err(DAError::InvalidBlobId { .. }) => {
+                tracing::warn!(
+                    "Skipping {} found at {}, it can't be decoded",
+                    blob_id,
+                    height
+                );
+                return Ok(None);
+            }
+            Err(error) => return Err(error),
+        };
+
+        Ok(Some(IndexEntry {
+            blob_id,
+            batch_number,
+            caller: caller.to_string(),
+            size: data.len() as u64,
+            payload_hash: hex::encode(AttestationSvc::payload_hash(&data)),
+            dispatched_at: receipt.submitted_at,
+            redispatch_of: None,
+            receipt: Some(receipt),
+            confirmations: None,
+            archive: None,
+            metadata: BTreeMap::from([(IMPORTED_FROM_KEY.to_string(), height.to_string())]),
+            attestation: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{
+        clients::da_clients::{
+            DataAvailabilityClient,
+            types::{DispatchResponse, InclusionData, ScannedBlob, serialize_blob_ids},
+        },
+        config::DaBackend,
+    };
+
+    /// A DA layer with a blob at each even height: the blobs `02` and `04` are the chunks of the
+    /// manifest `06`, `08` isn't readable, `0e` can't be read for now and the height 15 can't be
+    /// scanned.
+    #[derive(Debug, Clone)]
+    struct LegacyClient;
+
+    impl LegacyClient {
+        fn blob(height: u64) -> Vec<u8> {
+            match height {
+                6 => ViaDaBlob::new(
+                    2,
+                    serialize_blob_ids(&["02".to_string(), "04".to_string()]).unwrap(),
+                )
+                .to_bytes(),
+                height => vec![height as u8],
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DataAvailabilityClient for LegacyClient {
+        async fn dispatch_blob(
+            &self,
+            _: &CallContext,
+            _: u32,
+            _: Vec<u8>,
+        ) -> Result<DispatchResponse, DAError> {
+            Ok(DispatchResponse::from("ab".to_string()))
+        }
+
+        async fn get_inclusion_data(
+            &self,
+            _: &CallContext,
+            blob_id: &str,
+        ) -> Result<Option<InclusionData>, DAError> {
+            match blob_id {
+                "06" => Ok(Some(InclusionData { data: vec![2, 4] })),
+                "08" => Err(DAError::IntegrityMismatch {
+                    blob_id: blob_id.to_string(),
+                    reason: "Not a blob of this service".to_string(),
+                }),
+                "0e" => Err(DAError::ConnectionError {
+                    message: "The node is down".to_string(),
+                }),
+                blob_id => Ok(Some(InclusionData {
+                    data: hex::decode(blob_id).unwrap(),
+                })),
+            }
+        }
+
+        fn clone_boxed(&self) -> Box<dyn DataAvailabilityClient> {
+            Box::new(self.clone())
+        }
+
+        fn blob_size_limit(&self) -> Option<usize> {
+            None
+        }
+
+        async fn ping(&self) -> anyhow::Result<bool> {
+            Ok(true)
+        }
+
+        async fn scan_blobs(
+            &self,
+            _: &CallContext,
+            height: u64,
+        ) -> Result<Vec<ScannedBlob>, DAError> {
+            if height == 15 {
+                return Err(DAError::ConnectionError {
+                    message: "The node is down".to_string(),
+                });
+            }
+            if height % 2 == 1 {
+                return Ok(vec![]);
+            }
+            Ok(vec![ScannedBlob {
+                blob_id: format!("{height:02x}"),
+                data: Self::blob(height),
+                receipt: DispatchReceipt {
+                    height: Some(height),
+                    commitment: String::new(),
+                    namespace: None,
+                    size: 1,
+                    backend: DaBackend::Celestia,
+                    submitted_at: Utc::now(),
+                    fee: None,
+                    chunks: vec![],
+                },
+            }])
+        }
+
+        fn namespaced(
+            &self,
+            _: &str,
+        ) -> anyhow::Result<Arc<dyn DataAvailabilityClient + Send + Sync>> {
+            Ok(Arc::new(self.clone()))
+        }
+    }
+
+    fn request(from_height: u64, to_height: u64, namespace: Option<&str>) -> ImportRequest {
+        ImportRequest {
+            caller: "tenant".to_string(),
+            from_height,
+            to_height,
+            namespace: namespace.map(str::to_string),
+            first_batch_number: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batches_are_imported_once() {
+        let da_svc = DaSvc::for_tests(Arc::new(LegacyClient)).with_tenants(&[("tenant", "legacy")]);
+        let index = Arc::new(IndexSvc::default());
+        let svc = ImportSvc::new(
+            Arc::new(da_svc),
+            index.clone(),
+            Arc::new(SequenceSvc::new(&index)),
+        );
+
+        let ctx = CallContext::background();
+        let response = svc
+            .import(&ctx, &request(1, 10, Some("legacy")))
+            .await
+            .unwrap();
+        assert_eq!((response.scanned, response.already_indexed), (10, 0));
+        // The chunks of the manifest and the unreadable blob are not batches.
+        assert_eq!(response.skipped, 3);
+        let imported: Vec<_> = response
+            .imported
+            .iter()
+            .map(|entry| (entry.blob_id.as_str(), entry.batch_number))
+            .collect();
+        assert_eq!(imported, [("06", 10), ("0a", 11)]);
+        let entry = index.get("06").unwrap();
+        assert_eq!(
+            entry.payload_hash,
+            hex::encode(AttestationSvc::payload_hash(&[2, 4]))
+        );
+        assert_eq!(entry.metadata[IMPORTED_FROM_KEY], "6");
+
+        // The blobs already indexed are kept.
+        let response = svc.import(&ctx, &request(6, 12, None)).await.unwrap();
+        assert_eq!(response.imported.len(), 1);
+        assert_eq!(response.already_indexed, 2);
+
+        // A failed scan indexes nothing, a failed read keeps the blobs imported before it.
+        let response = svc.import(&ctx, &request(13, 16, None)).await.unwrap();
+        let failure = response.failure.unwrap();
+        assert_eq!((failure.height, failure.scanned), (15, false));
+        assert!(failure.retriable);
+        assert_eq!(response.scanned, 2);
+        let response = svc.import(&ctx, &request(12, 14, None)).await.unwrap();
+        let failure = response.failure.unwrap();
+        assert_eq!((failure.height, failure.scanned), (14, true));
+        assert_eq!(response.already_indexed, 1);
+
+        assert!(matches!(
+            svc.import(&ctx, &request(1, 4, Some("other"))).await,
+            Err(ImportError::UnreadableNamespace { .. })
+        ));
+        assert!(matches!(
+            svc.import(&ctx, &request(4, 1, None)).await,
+            Err(ImportError::InvalidRange { .. })
+        ));
+    }
+}
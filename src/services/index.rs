@@ -0,0 +1,535 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    config::DaBackend,
+    services::{
+        lifecycle::{Lifecycle, StopSignal, run_every},
+        sequence::SequenceSvc,
+    },
+    types::index::IndexEntry,
+};
+
+#[derive(Debug, Default)]
+struct Index {
+    entries: Vec<IndexEntry>,
+    positions: HashMap<String, usize>,
+    /// Position of the next entry returned by `sample`.
+    cursor: usize,
+}
+
+impl Index {
+    /// Inserts or updates an entry, returns true when it is new.
+    fn insert(&mut self, entry: IndexEntry) -> bool {
+        match self.positions.get(&entry.blob_id) {
+            Some(&position) => {
+                self.entries[position] = entry;
+                false
+            }
+            None => {
+                self.positions
+                    .insert(entry.blob_id.clone(), self.entries.len());
+                self.entries.push(entry);
+                true
+            }
+        }
+    }
+}
+
+/// Indexes the dispatched blobs by blob_id.
+///
+/// When a path is configured, the entries are appended to it as JSON lines and loaded back on
+/// startup, so the index outlives restarts.
+#[derive(Debug, Clone)]
+pub struct IndexSvc {
+    path: Option<PathBuf>,
+    index: Arc<RwLock<Index>>,
+    /// Whether the last entry was persisted.
+    persisted: Arc<AtomicBool>,
+    /// The length of the file loaded by `follow`.
+    loaded_len: Arc<Mutex<u64>>,
+}
+
+impl Default for IndexSvc {
+    fn default() -> Self {
+        Self {
+            path: None,
+            index: Arc::default(),
+            persisted: Arc::new(AtomicBool::new(true)),
+            loaded_len: Arc::default(),
+        }
+    }
+}
+
+impl IndexSvc {
+    pub fn new(path: Option<PathBuf>) -> anyhow::Result<Self> {
+        let svc = Self {
+            path,
+            ..Self::default()
+        };
+
+        let loaded = svc.follow()?;
+        if !loaded.is_empty() {
+            tracing::info!("Loaded {} dispatched blobs from the index", svc.len());
+        }
+        Ok(svc)
+    }
+
+    /// Loads the entries appended to the file since the last load, by another instance sharing
+    /// it. A line being written is loaded once complete. Returns the entries new to the index, the
+    /// others update the entries of the index.
+    pub fn follow(&self) -> anyhow::Result<Vec<IndexEntry>> {
+        let Some(path) = &self.path else {
+            return Ok(vec![]);
+        };
+        let mut loaded_len = self.loaded_len.lock().unwrap();
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+
+        // The file was truncated, the entries are loaded again.
+        if file.metadata()?.len() < *loaded_len {
+            *loaded_len = 0;
+        }
+        file.seek(SeekFrom::Start(*loaded_len))?;
+        let mut appended = vec![];
+        file.read_to_end(&mut appended)?;
+        let complete = appended
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |position| position + 1);
+
+        let mut index = self.index.write().unwrap();
+        let mut entries = vec![];
+        for line in String::from_utf8_lossy(&appended[..complete]).lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<IndexEntry>(line) {
+                Ok(entry) => {
+                    if index.insert(entry.clone()) {
+                        entries.push(entry);
+                    }
+                }
+                Err(err) => tracing::warn!("Skipping invalid index entry: {}", err),
+            }
+        }
+        *loaded_len += complete as u64;
+
+        Ok(entries)
+    }
+
+    /// Records a dispatched blob, a failure to persist it is logged and the entry is kept in memory.
+    pub fn record(&self, entry: IndexEntry) {
+        let mut index = self.index.write().unwrap();
+
+        if let Some(path) = &self.path
+            && let Err(err) = self.append(path, &entry)
+        {
+            tracing::error!(
+                "Failed to persist the index entry of {}: {}",
+                entry.blob_id,
+                err
+            );
+        }
+
+        index.insert(entry);
+    }
+
+    fn append(&self, path: &PathBuf, entry: &IndexEntry) -> anyhow::Result<()> {
+        let result = (|| {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+            anyhow::Ok(())
+        })();
+        self.persisted.store(result.is_ok(), Ordering::Relaxed);
+        result
+    }
+
+    /// Whether the index persists its entries, false once the last entry failed to be persisted.
+    pub fn is_healthy(&self) -> bool {
+        self.persisted.load(Ordering::Relaxed)
+    }
+
+    pub fn get(&self, blob_id: &str) -> Option<IndexEntry> {
+        let index = self.index.read().unwrap();
+        index
+            .positions
+            .get(blob_id)
+            .map(|&position| index.entries[position].clone())
+    }
+
+    /// Returns the last dispatch of a batch by the caller, re-dispatches included.
+    pub fn last_batch_dispatch(&self, caller: &str, batch_number: u32) -> Option<IndexEntry> {
+        self.index
+            .read()
+            .unwrap()
+            .entries
+            .iter()
+            .filter(|entry| entry.caller == caller && entry.batch_number == batch_number)
+            .max_by_key(|entry| entry.dispatched_at)
+            .cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.read().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the entries dispatched in `[from, to)`, in dispatch order.
+    pub fn range(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Vec<IndexEntry> {
+        self.index
+            .read()
+            .unwrap()
+            .entries
+            .iter()
+            .filter(|entry| from.is_none_or(|from| entry.dispatched_at >= from))
+            .filter(|entry| to.is_none_or(|to| entry.dispatched_at < to))
+            .cloned()
+            .collect()
+    }
+
+    /// Updates the confirmations of the unconfirmed blobs of `backend` from the chain head, returns
+    /// the blobs reaching `depth`. Only the confirmed blobs are persisted, the others are updated
+    /// again after a restart.
+    pub fn update_confirmations(
+        &self,
+        backend: DaBackend,
+        head: u64,
+        depth: u64,
+    ) -> Vec<IndexEntry> {
+        let mut index = self.index.write().unwrap();
+        let mut confirmed = vec![];
+
+        for entry in &mut index.entries {
+            let Some(height) = entry
+                .receipt
+                .as_ref()
+                .filter(|receipt| receipt.backend == backend)
+                .and_then(|receipt| receipt.height)
+            else {
+                continue;
+            };
+            if entry
+                .confirmations
+                .is_some_and(|confirmations| confirmations >= depth)
+            {
+                continue;
+            }
+
+            let confirmations = head.saturating_sub(height);
+            entry.confirmations = Some(confirmations);
+            if confirmations >= depth {
+                confirmed.push(entry.clone());
+            }
+        }
+
+        if let Some(path) = &self.path {
+            for entry in &confirmed {
+                if let Err(err) = self.append(path, entry) {
+                    tracing::error!(
+                        "Failed to persist the confirmations of {}: {}",
+                        entry.blob_id,
+                        err
+                    );
+                }
+            }
+        }
+
+        confirmed
+    }
+
+    /// Returns the confirmed blobs of `backend` not mirrored in the archive yet.
+    pub fn pending_archive(&self, backend: DaBackend, depth: u64) -> Vec<IndexEntry> {
+        self.index
+            .read()
+            .unwrap()
+            .entries
+            .iter()
+            .filter(|entry| entry.archive.is_none())
+            .filter(|entry| {
+                entry
+                    .receipt
+                    .as_ref()
+                    .is_some_and(|receipt| receipt.backend == backend)
+            })
+            .filter(|entry| {
+                entry
+                    .confirmations
+                    .is_some_and(|confirmations| confirmations >= depth)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Records the archive location of a blob.
+    pub fn set_archive(&self, blob_id: &str, location: String) {
+        let mut index = self.index.write().unwrap();
+        let Some(&position) = index.positions.get(blob_id) else {
+            return;
+        };
+        let entry = &mut index.entries[position];
+        entry.archive = Some(location);
+
+        if let Some(path) = &self.path
+            && let Err(err) = self.append(path, entry)
+        {
+            tracing::error!(
+                "Failed to persist the archive location of {}: {}",
+                blob_id,
+                err
+            );
+        }
+    }
+
+    /// Returns up to `count` entries, resuming after the last sampled one so that successive
+    /// samples cycle through the whole index.
+    pub fn sample(&self, count: usize) -> Vec<IndexEntry> {
+        let mut index = self.index.write().unwrap();
+        let len = index.entries.len();
+        if len == 0 {
+            return vec![];
+        }
+
+        let start = index.cursor % len;
+        let sample: Vec<IndexEntry> = (0..count.min(len))
+            .map(|offset| index.entries[(start + offset) % len].clone())
+            .collect();
+        index.cursor = (start + sample.len()) % len;
+        sample
+    }
+}
+
+/// How often a read-only replica loads the entries appended to the index file.
+pub const INDEX_FOLLOW_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Loads the dispatches of the writing instance into the index of a read-only replica sharing its
+/// index file.
+#[derive(Debug, Clone)]
+pub struct IndexFollower {
+    index: Arc<IndexSvc>,
+    sequence: Arc<SequenceSvc>,
+    interval: Duration,
+}
+
+impl IndexFollower {
+    pub fn new(index: Arc<IndexSvc>, sequence: Arc<SequenceSvc>, interval: Duration) -> Self {
+        Self {
+            index,
+            sequence,
+            interval,
+        }
+    }
+
+    pub async fn run(&self) {
+        match self.index.follow() {
+            Ok(entries) => {
+                for entry in entries {
+                    if entry.redispatch_of.is_none() {
+                        self.sequence.record(&entry.caller, entry.batch_number);
+                    }
+                }
+            }
+            Err(err) => tracing::error!("Failed to load the appended index entries: {}", err),
+        }
+    }
+}
+
+#[async_trait]
+impl Lifecycle for IndexFollower {
+    fn name(&self) -> &'static str {
+        "index_follower"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        run_every(self.interval, stop, || self.run()).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::types::dispatch::DispatchReceipt;
+
+    fn entry(blob_id: &str) -> IndexEntry {
+        IndexEntry {
+            blob_id: blob_id.to_string(),
+            batch_number: 1,
+            caller: "anonymous".to_string(),
+            size: 1,
+            payload_hash: String::new(),
+            dispatched_at: Utc::now(),
+            redispatch_of: None,
+            receipt: None,
+            confirmations: None,
+            archive: None,
+            metadata: BTreeMap::new(),
+            attestation: None,
+        }
+    }
+
+    #[test]
+    fn test_index_is_persisted() {
+        let path = std::env::temp_dir().join(format!("via-index-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let svc = IndexSvc::new(Some(path.clone())).unwrap();
+        svc.record(entry("a"));
+        svc.record(entry("b"));
+
+        let reloaded = IndexSvc::new(Some(path.clone())).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert_eq!(reloaded.get("b"), svc.get("b"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_appended_entries_are_followed() {
+        let path = std::env::temp_dir().join(format!("via-follow-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let writer = IndexSvc::new(Some(path.clone())).unwrap();
+        writer.record(entry("a"));
+        let reader = IndexSvc::new(Some(path.clone())).unwrap();
+        assert_eq!(reader.len(), 1);
+
+        writer.record(entry("b"));
+        // A line being written is not loaded yet.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"blob_id":"#).unwrap();
+        let followed = reader.follow().unwrap();
+        assert_eq!(followed.len(), 1);
+        assert_eq!(reader.get("b"), writer.get("b"));
+        assert!(reader.follow().unwrap().is_empty());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_persistence_is_unhealthy() {
+        let dir = std::env::temp_dir().join(format!("via-index-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // The entries can't be appended to a directory.
+        let svc = IndexSvc {
+            path: Some(dir.clone()),
+            ..IndexSvc::default()
+        };
+        assert!(svc.is_healthy());
+        svc.record(entry("a"));
+        assert!(!svc.is_healthy());
+        assert!(svc.get("a").is_some());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_sample_cycles_through_the_index() {
+        let svc = IndexSvc::new(None).unwrap();
+        assert!(svc.sample(2).is_empty());
+
+        for blob_id in ["a", "b", "c"] {
+            svc.record(entry(blob_id));
+        }
+
+        let ids = |sample: Vec<IndexEntry>| -> Vec<String> {
+            sample.into_iter().map(|entry| entry.blob_id).collect()
+        };
+        assert_eq!(ids(svc.sample(2)), ["a", "b"]);
+        assert_eq!(ids(svc.sample(2)), ["c", "a"]);
+        assert_eq!(ids(svc.sample(5)), ["b", "c", "a"]);
+    }
+
+    #[test]
+    fn test_range_filters_by_dispatch_time() {
+        let svc = IndexSvc::new(None).unwrap();
+        let start = Utc::now();
+        for (offset, blob_id) in ["a", "b", "c"].into_iter().enumerate() {
+            svc.record(IndexEntry {
+                dispatched_at: start + chrono::Duration::seconds(offset as i64),
+                ..entry(blob_id)
+            });
+        }
+
+        let range = svc.range(Some(start + chrono::Duration::seconds(1)), None);
+        assert_eq!(range.len(), 2);
+        assert_eq!(range[0].blob_id, "b");
+        assert_eq!(svc.range(None, Some(start)).len(), 0);
+
+        let row = IndexEntry {
+            caller: "a,\"b\"".to_string(),
+            ..range[0].clone()
+        }
+        .to_csv_row();
+        assert!(row.starts_with("1,b,\"a,\"\"b\"\"\",1,,"));
+    }
+
+    #[test]
+    fn test_confirmations_are_tracked_up_to_the_depth() {
+        let path = std::env::temp_dir().join(format!(
+            "via-index-confirmations-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let svc = IndexSvc::new(Some(path.clone())).unwrap();
+        for (blob_id, height, backend) in [
+            ("a", 10, DaBackend::Celestia),
+            ("b", 12, DaBackend::Celestia),
+            ("c", 10, DaBackend::InMemory),
+        ] {
+            svc.record(IndexEntry {
+                receipt: Some(DispatchReceipt {
+                    height: Some(height),
+                    commitment: String::new(),
+                    namespace: None,
+                    size: 1,
+                    backend,
+                    submitted_at: Utc::now(),
+                    fee: None,
+                    chunks: vec![],
+                }),
+                ..entry(blob_id)
+            });
+        }
+
+        let confirmed = svc.update_confirmations(DaBackend::Celestia, 12, 2);
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].blob_id, "a");
+        assert_eq!(svc.get("b").unwrap().confirmations, Some(0));
+        assert_eq!(svc.get("c").unwrap().confirmations, None);
+
+        // The confirmed blobs are no longer updated.
+        let confirmed = svc.update_confirmations(DaBackend::Celestia, 14, 2);
+        assert_eq!(confirmed[0].blob_id, "b");
+        assert_eq!(svc.get("a").unwrap().confirmations, Some(2));
+
+        let reloaded = IndexSvc::new(Some(path.clone())).unwrap();
+        assert_eq!(reloaded.get("b").unwrap().confirmations, Some(2));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    any::Any,
+    fmt,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures_util::FutureExt;
+use tokio::{sync::watch, task::JoinHandle, time::Instant};
+
+use crate::{
+    services::metrics::LIFECYCLE_METRICS,
+    types::lifecycle::{ComponentState, ComponentStatus},
+};
+
+/// Delay before the first restart of a failed component, doubled on every consecutive failure.
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// The time a component is given to stop on shutdown before it is aborted.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The stop signal of a component, set to true on shutdown.
+pub type StopSignal = watch::Receiver<bool>;
+
+/// `Lifecycle` is implemented by the background components supervised by the `Supervisor`.
+#[async_trait]
+pub trait Lifecycle: Send + Sync + fmt::Debug {
+    /// The name of the component in the logs, the metrics and the readiness.
+    fn name(&self) -> &'static str;
+
+    /// Runs the component until the stop signal. Returning `Ok` completes the component, an
+    /// error or a panic restarts it with a backoff.
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()>;
+
+    /// Releases the resources of the component once it stopped on shutdown.
+    async fn stop(&self) {}
+
+    /// Whether the component works as expected.
+    fn health(&self) -> bool {
+        true
+    }
+}
+
+/// Resolves once the stop signal is set.
+pub async fn stopped(stop: &mut StopSignal) {
+    // Never resolves if the supervisor is dropped without a shutdown.
+    if stop.wait_for(|stop| *stop).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Calls `tick` every `interval` until the stop signal, the first tick is immediate.
+pub async fn run_every<F, Fut>(interval: Duration, mut stop: StopSignal, mut tick: F)
+where
+    F: FnMut() -> Fut + Send,
+    Fut: Future<Output = ()> + Send,
+{
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => tick().await,
+            _ = stopped(&mut stop) => return,
+        }
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[derive(Debug)]
+struct Supervised {
+    component: Arc<dyn Lifecycle>,
+    stop: watch::Sender<bool>,
+    status: Arc<Mutex<ComponentStatus>>,
+    handle: JoinHandle<()>,
+}
+
+/// Starts the background components, restarts them with a backoff when they fail or panic, and
+/// stops them in the reverse order of their start on shutdown.
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    components: Mutex<Vec<Supervised>>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a component under supervision.
+    pub fn start(&self, component: Arc<dyn Lifecycle>) {
+        let (stop, stop_signal) = watch::channel(false);
+        let status = Arc::new(Mutex::new(ComponentStatus {
+            name: component.name().to_string(),
+            state: ComponentState::Running,
+            healthy: true,
+            restarts: 0,
+            last_error: None,
+        }));
+        tracing::info!("Starting {}", component.name());
+
+        let handle = tokio::spawn(Self::supervise(
+            component.clone(),
+            stop_signal,
+            status.clone(),
+        ));
+        self.components.lock().unwrap().push(Supervised {
+            component,
+            stop,
+            status,
+            handle,
+        });
+    }
+
+    async fn supervise(
+        component: Arc<dyn Lifecycle>,
+        mut stop: StopSignal,
+        status: Arc<Mutex<ComponentStatus>>,
+    ) {
+        let name = component.name();
+        let mut delay = RESTART_BASE_DELAY;
+
+        loop {
+            let started_at = Instant::now();
+            let error = match AssertUnwindSafe(component.start(stop.clone()))
+                .catch_unwind()
+                .await
+            {
+                Ok(Ok(())) => {
+                    status.lock().unwrap().state = ComponentState::Finished;
+                    return;
+                }
+                Ok(Err(err)) => err.to_string(),
+                Err(panic) => {
+                    LIFECYCLE_METRICS.panics[&name].inc();
+                    format!("panicked: {}", panic_message(panic.as_ref()))
+                }
+            };
+            if *stop.borrow() {
+                return;
+            }
+
+            // A component that ran for a while before failing is restarted promptly.
+            if started_at.elapsed() > RESTART_MAX_DELAY {
+                delay = RESTART_BASE_DELAY;
+            }
+            tracing::error!("{} failed, restarting in {:?}: {}", name, delay, error);
+            LIFECYCLE_METRICS.restarts[&name].inc();
+            {
+                let mut status = status.lock().unwrap();
+                status.state = ComponentState::Restarting;
+                status.restarts += 1;
+                status.last_error = Some(error);
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = stopped(&mut stop) => return,
+            }
+            delay = (delay * 2).min(RESTART_MAX_DELAY);
+            status.lock().unwrap().state = ComponentState::Running;
+        }
+    }
+
+    /// Returns the status of the components, in their start order.
+    pub fn status(&self) -> Vec<ComponentStatus> {
+        self.components
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|supervised| {
+                let mut status = supervised.status.lock().unwrap().clone();
+                status.healthy =
+                    status.state != ComponentState::Restarting && supervised.component.health();
+                status
+            })
+            .collect()
+    }
+
+    /// Stops the components in the reverse order of their start, the components not stopping
+    /// within `STOP_TIMEOUT` are aborted.
+    pub async fn shutdown(&self) {
+        let components = std::mem::take(&mut *self.components.lock().unwrap());
+
+        for mut supervised in components.into_iter().rev() {
+            let name = supervised.component.name();
+            tracing::info!("Stopping {}", name);
+
+            supervised.stop.send_replace(true);
+            if tokio::time::timeout(STOP_TIMEOUT, &mut supervised.handle)
+                .await
+                .is_err()
+            {
+                tracing::warn!(
+                    "{} didn't stop within {:?}, aborting it",
+                    name,
+                    STOP_TIMEOUT
+                );
+                supervised.handle.abort();
+            }
+            supervised.component.stop().await;
+            supervised.status.lock().unwrap().state = ComponentState::Stopped;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    /// Panics on its first start, then runs until stopped.
+    #[derive(Debug, Default)]
+    struct FlakyComponent {
+        starts: AtomicU32,
+        stopped: Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait]
+    impl Lifecycle for FlakyComponent {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn start(&self, mut stop: StopSignal) -> anyhow::Result<()> {
+            if self.starts.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("first start");
+            }
+            stopped(&mut stop).await;
+            Ok(())
+        }
+
+        async fn stop(&self) {
+            self.stopped.lock().unwrap().push("flaky");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicked_components_are_restarted_and_stopped() {
+        let supervisor = Supervisor::new();
+        let component = Arc::new(FlakyComponent::default());
+        supervisor.start(component.clone());
+
+        tokio::time::sleep(RESTART_BASE_DELAY / 2).await;
+        let status = &supervisor.status()[0];
+        assert_eq!(status.state, ComponentState::Restarting);
+        assert!(!status.healthy);
+        assert_eq!(status.last_error.as_deref(), Some("panicked: first start"));
+
+        tokio::time::sleep(RESTART_BASE_DELAY).await;
+        let status = &supervisor.status()[0];
+        assert_eq!(
+            (status.state, status.restarts),
+            (ComponentState::Running, 1)
+        );
+        assert_eq!(component.starts.load(Ordering::SeqCst), 2);
+
+        supervisor.shutdown().await;
+        assert_eq!(*component.stopped.lock().unwrap(), ["flaky"]);
+        assert!(supervisor.status().is_empty());
+    }
+}
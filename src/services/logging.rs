@@ -0,0 +1,344 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, RwLock},
+};
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use tracing::{
+    Event, Subscriber,
+    field::{Field, Visit},
+    span,
+};
+use tracing_subscriber::{
+    EnvFilter, Registry,
+    field::RecordFields,
+    fmt::{
+        self as tracing_fmt, FmtContext, FormatEvent, FormatFields, FormattedFields,
+        format::Writer, writer::BoxMakeWriter,
+    },
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    reload,
+    util::SubscriberInitExt,
+};
+
+use crate::config::{Config, LogFormat};
+
+/// Owns the log level of the global subscriber, which can be changed at runtime.
+#[derive(Debug)]
+pub struct LoggingSvc {
+    filter: reload::Handle<EnvFilter, Registry>,
+    level: RwLock<String>,
+}
+
+impl LoggingSvc {
+    /// Installs the global subscriber in the configured format, writing to stdout or to the
+    /// rotated log file.
+    pub fn init(config: &Config) -> anyhow::Result<Self> {
+        let (filter, handle) = reload::Layer::new(EnvFilter::try_new(&config.log_level)?);
+
+        let writer = match &config.log_file {
+            Some(path) => BoxMakeWriter::new(RotatingFile::open(
+                path.clone(),
+                config.log_file_max_bytes,
+                config.log_file_max_files,
+            )?),
+            None => BoxMakeWriter::new(io::stdout),
+        };
+        let (text, json) = match config.log_format {
+            LogFormat::Text => (
+                Some(
+                    tracing_fmt::layer()
+                        .with_ansi(config.log_file.is_none())
+                        .with_writer(writer),
+                ),
+                None,
+            ),
+            LogFormat::Json => (
+                None,
+                Some(
+                    tracing_fmt::layer()
+                        .fmt_fields(JsonFields)
+                        .event_format(JsonFormat)
+                        .with_writer(writer),
+                ),
+            ),
+        };
+
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(text)
+            .with(json)
+            .try_init()?;
+
+        Ok(Self {
+            filter: handle,
+            level: RwLock::new(config.log_level.clone()),
+        })
+    }
+
+    /// Returns the current log level directives.
+    pub fn level(&self) -> String {
+        self.level.read().unwrap().clone()
+    }
+
+    /// Replaces the log level directives, returns the previous ones.
+    pub fn set_level(&self, level: &str) -> anyhow::Result<String> {
+        anyhow::ensure!(!level.trim().is_empty(), "The log level is empty");
+        let filter = EnvFilter::try_new(level)?;
+
+        let mut current = self.level.write().unwrap();
+        self.filter.reload(filter)?;
+        tracing::info!("Changed the log level from {} to {}", current, level);
+
+        Ok(std::mem::replace(&mut *current, level.to_string()))
+    }
+}
+
+/// Collects the fields of an event or a span as JSON values.
+#[derive(Default)]
+struct FieldVisitor(Map<String, Value>);
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+}
+
+/// Formats the fields of the spans as a JSON object, read back by `JsonFormat`.
+struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = FieldVisitor::default();
+        fields.record(&mut visitor);
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &span::Record<'_>,
+    ) -> fmt::Result {
+        let mut visitor = FieldVisitor(serde_json::from_str(&current.fields).unwrap_or_default());
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+        Ok(())
+    }
+}
+
+/// Formats an event as a JSON line with its timestamp, level, target, message, fields and the
+/// spans it happened in, from the root.
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut fields = FieldVisitor::default();
+        event.record(&mut fields);
+
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_string(),
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::Micros, true)
+                .into(),
+        );
+        line.insert("level".to_string(), metadata.level().as_str().into());
+        line.insert("target".to_string(), metadata.target().into());
+        if let Some(message) = fields.0.remove("message") {
+            line.insert("message".to_string(), message);
+        }
+        if !fields.0.is_empty() {
+            line.insert("fields".to_string(), Value::Object(fields.0));
+        }
+
+        let spans: Vec<Value> = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let mut entry = Map::new();
+                if let Some(fields) = span.extensions().get::<FormattedFields<N>>()
+                    && let Ok(Value::Object(fields)) = serde_json::from_str(&fields.fields)
+                {
+                    entry.extend(fields);
+                }
+                entry.insert("name".to_string(), span.name().into());
+                Value::Object(entry)
+            })
+            .collect();
+        if !spans.is_empty() {
+            line.insert("spans".to_string(), spans.into());
+        }
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+#[derive(Debug)]
+struct OpenFile {
+    file: File,
+    size: u64,
+}
+
+impl OpenFile {
+    fn open(path: &Path, truncate: bool) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(!truncate)
+            .write(true)
+            .truncate(truncate)
+            .open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { file, size })
+    }
+}
+
+/// A log file rotated once it reaches `max_bytes`, the `max_files` previous files are kept as
+/// `<path>.1` (the most recent) to `<path>.<max_files>`.
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    current: Mutex<OpenFile>,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let current = Mutex::new(OpenFile::open(&path, false)?);
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            current,
+        })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    fn rotate(&self) -> io::Result<OpenFile> {
+        if self.max_files > 0 {
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated(index);
+                if from.exists() {
+                    fs::rename(from, self.rotated(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+        OpenFile::open(&self.path, true)
+    }
+}
+
+/// Every log line is written at once, so a line never spans two files.
+impl Write for &RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut current = self.current.lock().unwrap();
+        if current.size > 0 && current.size + buf.len() as u64 > self.max_bytes {
+            *current = self.rotate()?;
+        }
+        current.file.write_all(buf)?;
+        current.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.current.lock().unwrap().file.flush()
+    }
+}
+
+impl<'a> tracing_fmt::MakeWriter<'a> for RotatingFile {
+    type Writer = &'a RotatingFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_logs_are_rotated() {
+        let dir = std::env::temp_dir().join(format!("via-logs-{}", std::process::id()));
+        let path = dir.join("via.log");
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_fmt::layer()
+                .fmt_fields(JsonFields)
+                .event_format(JsonFormat)
+                .with_writer(RotatingFile::open(path.clone(), 300, 2).unwrap()),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", method = "GET");
+            span.in_scope(|| {
+                for batch in 0..4u32 {
+                    tracing::info!(batch, "Dispatched");
+                }
+            });
+        });
+
+        let line = fs::read_to_string(&path).unwrap();
+        let line: Value = serde_json::from_str(line.lines().last().unwrap()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["message"], "Dispatched");
+        assert_eq!(line["fields"]["batch"], 3);
+        assert_eq!(line["spans"][0]["name"], "request");
+        assert_eq!(line["spans"][0]["method"], "GET");
+
+        // Four lines of about 200 bytes went to four files, the oldest one was dropped.
+        let oldest = fs::read_to_string(dir.join("via.log.2")).unwrap();
+        let oldest: Value = serde_json::from_str(oldest.trim_end()).unwrap();
+        assert_eq!(oldest["fields"]["batch"], 1);
+        assert!(!dir.join("via.log.3").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::{Arc, RwLock};
+
+use chrono::Utc;
+
+use crate::{services::metrics::DA_METRICS, types::maintenance::MaintenanceStatus};
+
+/// Tracks the maintenance mode, while paused dispatches are rejected and reads keep working.
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceSvc {
+    status: Arc<RwLock<MaintenanceStatus>>,
+}
+
+impl MaintenanceSvc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.status.read().unwrap().paused
+    }
+
+    /// Pauses the dispatches, pausing again only updates the reason.
+    pub fn pause(&self, reason: Option<String>) -> MaintenanceStatus {
+        let status = {
+            let mut status = self.status.write().unwrap();
+            status.paused = true;
+            status.reason = reason;
+            status.since.get_or_insert_with(Utc::now);
+            status.clone()
+        };
+        DA_METRICS.dispatch_paused.set(1);
+        tracing::warn!("Dispatch paused for maintenance");
+
+        status
+    }
+
+    pub fn resume(&self) -> MaintenanceStatus {
+        *self.status.write().unwrap() = MaintenanceStatus::default();
+        DA_METRICS.dispatch_paused.set(0);
+        tracing::info!("Dispatch resumed");
+
+        MaintenanceStatus::default()
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_and_resume() {
+        let svc = MaintenanceSvc::new();
+        assert!(!svc.is_paused());
+
+        let first = svc.pause(None);
+        let second = svc.pause(Some("upgrade".to_string()));
+        assert!(svc.is_paused());
+        assert_eq!(second.reason.as_deref(), Some("upgrade"));
+        // Pausing again keeps the original start of the maintenance.
+        assert_eq!(first.since, second.since);
+
+        assert_eq!(svc.resume(), MaintenanceStatus::default());
+        assert!(!svc.is_paused());
+    }
+}
@@ -1,6 +1,13 @@
-use std::time::Duration;
+use std::{net::SocketAddr, time::Duration};
 
-use vise::{Buckets, Counter, Histogram, Metrics, Unit};
+use async_trait::async_trait;
+use vise::{
+    Buckets, Counter, EncodeLabelSet, Family, Format, Gauge, GaugeGuard, Histogram, LabeledFamily,
+    Metrics, MetricsCollection, Unit,
+};
+use vise_exporter::MetricsExporter;
+
+use crate::services::lifecycle::{Lifecycle, StopSignal, stopped};
 
 #[derive(Debug, Metrics)]
 #[metrics(prefix = "da")]
@@ -14,7 +21,387 @@ pub struct DaMetrics {
     /// Dispatch latency in seconds
     #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
     pub dispatch_latency: Histogram<Duration>,
+
+    /// Whether the dispatches are paused for maintenance (1) or not (0)
+    pub dispatch_paused: Gauge<u64>,
+
+    /// Number of retried DA calls per backend
+    #[metrics(labels = ["backend"])]
+    pub retries: LabeledFamily<&'static str, Counter>,
+
+    /// Number of dispatches waiting for a slot
+    pub queued_dispatches: Gauge<usize>,
+
+    /// Time the oldest dispatch waiting for a slot has been waiting
+    pub oldest_queued_dispatch_age: Gauge<Duration>,
+
+    /// Number of dispatches failed after waiting longer than the max wait for a slot
+    pub expired_queued_dispatches: Counter,
+
+    /// Current limit of the concurrent dispatches, lowered while the DA layer rate limits them
+    pub dispatch_concurrency_limit: Gauge<usize>,
+
+    /// Number of dispatches submitting to the DA layer
+    pub in_flight_dispatches: Gauge<usize>,
+
+    /// Latency of the chunk fetches of the chunked blobs in seconds
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
+    pub chunk_fetch_latency: Histogram<Duration>,
+
+    /// Number of failed chunk fetches
+    pub failed_chunk_fetches: Counter,
+
+    /// Latency of the chunk dispatches of the chunked blobs in seconds
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
+    pub chunk_dispatch_latency: Histogram<Duration>,
+
+    /// Number of failed chunk dispatches
+    pub chunk_dispatch_failures: Counter,
+
+    /// Earliest height the light node still serves the blobs of
+    pub earliest_available_height: Gauge<u64>,
+
+    /// Blob size limit of the app version of the DA network, in bytes
+    pub blob_size_limit: Gauge<u64>,
+
+    /// App version of the DA network
+    pub app_version: Gauge<u64>,
+
+    /// Number of dispatched blobs reaching the confirmation depth
+    pub confirmed_blobs: Counter,
+
+    /// Number of blob reads served by the archival node
+    pub archival_reads: Counter,
+
+    /// Number of blob reads served by a fallback node
+    pub fallback_reads: Counter,
+
+    /// Number of blobs from a fallback node not matching their commitment
+    pub fallback_mismatches: Counter,
+
+    /// Number of chunked blobs failed after some of their chunks were dispatched
+    pub partial_dispatches: Counter,
+
+    /// Number of blobs found on the DA layer and added to the index by the imports
+    pub imported_blobs: Counter,
+
+    /// Number of DA calls failed by the chaos settings
+    pub injected_faults: Counter,
+
+    /// Number of blobs mirrored into the archive
+    pub archived_blobs: Counter,
+
+    /// Number of failed mirrorings into the archive, retried on the next run
+    pub failed_archivings: Counter,
+
+    /// Number of pruned blob reads served by the archive
+    pub archive_reads: Counter,
+
+    /// Number of inclusion proofs served from the cache
+    pub proof_cache_hits: Counter,
+
+    /// Number of cached inclusion proofs generated again as the data root of their block changed
+    pub proof_cache_invalidations: Counter,
+
+    /// Number of shadow dispatches per result compared to the dispatch they shadow
+    #[metrics(labels = ["result"])]
+    pub shadow_dispatches: LabeledFamily<&'static str, Counter>,
+
+    /// Shadow dispatch latency in seconds
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
+    pub shadow_dispatch_latency: Histogram<Duration>,
+
+    /// Number of shadow blobs not read back as dispatched
+    pub shadow_readback_failures: Counter,
+
+    /// Number of DA node answers disagreeing with the read quorum
+    pub quorum_disagreements: Counter,
+
+    /// Number of blob reads without a read quorum
+    pub quorum_failures: Counter,
+
+    /// Time spent waiting for a dispatch slot in seconds
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
+    pub dispatch_queue_latency: Histogram<Duration>,
+
+    /// Time the CPU-bound work on the large payloads waits for a CPU slot in seconds
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
+    pub cpu_offload_wait: Histogram<Duration>,
+
+    /// Gas used by the submissions
+    pub gas_used: Counter,
+
+    /// Number of dispatches skipping batch numbers per caller
+    #[metrics(labels = ["caller"])]
+    pub batch_gaps: LabeledFamily<String, Counter>,
+
+    /// Number of dispatches of a batch lower than the previous one per caller
+    #[metrics(labels = ["caller"])]
+    pub batch_regressions: LabeledFamily<String, Counter>,
+
+    /// Number of batch numbers missing between the first and the last dispatched ones per caller
+    #[metrics(labels = ["caller"])]
+    pub missing_batches: LabeledFamily<String, Gauge<u64>>,
+
+    /// Fees paid for the submissions per denom
+    #[metrics(labels = ["denom"])]
+    pub fees_paid: LabeledFamily<String, Counter>,
+
+    /// Gas used per submission
+    #[metrics(buckets = Buckets::exponential(1_000.0..=100_000_000.0, 4.0))]
+    pub dispatch_gas_used: Histogram<u64>,
+
+    /// Fees paid within the window of the fee budget, in the smallest denom
+    pub fee_budget_spent: Gauge<u64>,
+
+    /// Number of dispatches rejected as the fee budget is exhausted
+    pub fee_budget_rejections: Counter,
+
+    /// Number of dispatches staged and not committed yet
+    pub staged_dispatches: Gauge<u64>,
+
+    /// Number of staged dispatches dropped as they were not committed in time
+    pub expired_staged_dispatches: Counter,
+
+    /// Number of dispatches of a batch locked or submitted by another replica
+    pub batch_lock_conflicts: Counter,
+
+    /// Number of failed calls to the batch lock store
+    pub batch_lock_errors: Counter,
+
+    /// Number of batch locks lost while the batch was being submitted
+    pub batch_lock_losses: Counter,
+
+    /// Fee paid per submission in the smallest denom
+    #[metrics(buckets = Buckets::exponential(100.0..=100_000_000.0, 4.0))]
+    pub dispatch_fee: Histogram<u64>,
 }
 
 #[vise::register]
 pub(crate) static DA_METRICS: vise::Global<DaMetrics> = vise::Global::new();
+
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "da_usage")]
+pub struct UsageMetrics {
+    /// Number of dispatch requests per caller
+    #[metrics(labels = ["caller"])]
+    pub dispatch_requests: LabeledFamily<String, Counter>,
+
+    /// Number of dispatched bytes per caller
+    #[metrics(labels = ["caller"])]
+    pub dispatched_bytes: LabeledFamily<String, Counter>,
+
+    /// Number of dispatch requests rejected by the monthly byte cap per caller
+    #[metrics(labels = ["caller"])]
+    pub rejected_requests: LabeledFamily<String, Counter>,
+
+    /// Number of failed dispatch requests per caller
+    #[metrics(labels = ["caller"])]
+    pub failed_dispatches: LabeledFamily<String, Counter>,
+
+    /// Gas used by the dispatches per caller
+    #[metrics(labels = ["caller"])]
+    pub gas_used: LabeledFamily<String, Counter>,
+
+    /// Fees paid for the dispatches per caller and denom
+    #[metrics(labels = ["caller", "denom"])]
+    pub fees_paid: LabeledFamily<(String, String), Counter, 2>,
+}
+
+#[vise::register]
+pub(crate) static USAGE_METRICS: vise::Global<UsageMetrics> = vise::Global::new();
+
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "webhook")]
+pub struct WebhookMetrics {
+    /// Number of delivery attempts, including the retries
+    pub delivery_attempts: Counter,
+
+    /// Number of events delivered per event kind
+    #[metrics(labels = ["event"])]
+    pub delivered_events: LabeledFamily<&'static str, Counter>,
+
+    /// Number of events dropped after the last retry per event kind
+    #[metrics(labels = ["event"])]
+    pub failed_events: LabeledFamily<&'static str, Counter>,
+
+    /// Delivery latency in seconds, including the retries
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
+    pub delivery_latency: Histogram<Duration>,
+
+    /// Number of submitted blobs waiting for the confirmation depth
+    pub pending_confirmations: Gauge<usize>,
+}
+
+#[vise::register]
+pub(crate) static WEBHOOK_METRICS: vise::Global<WebhookMetrics> = vise::Global::new();
+
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "lifecycle")]
+pub struct LifecycleMetrics {
+    /// Number of restarts of the failed background components per component
+    #[metrics(labels = ["component"])]
+    pub restarts: LabeledFamily<&'static str, Counter>,
+
+    /// Number of panics of the background components per component
+    #[metrics(labels = ["component"])]
+    pub panics: LabeledFamily<&'static str, Counter>,
+}
+
+#[vise::register]
+pub(crate) static LIFECYCLE_METRICS: vise::Global<LifecycleMetrics> = vise::Global::new();
+
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "health")]
+pub struct HealthMetrics {
+    /// Whether the DA node answers (1) or not (0)
+    pub celestia_connected: Gauge<u64>,
+
+    /// Whether the DA node caught up with the chain (1) or not (0)
+    pub celestia_synced: Gauge<u64>,
+
+    /// Whether the dispatch queue serves the dispatches (1) or they wait on the rate limiting (0)
+    pub queue_healthy: Gauge<u64>,
+
+    /// Whether the index persists its entries (1) or not (0)
+    pub index_db_healthy: Gauge<u64>,
+
+    /// Whether the clients of the DA backends are created (1) or the DA nodes were unreachable
+    /// since startup (0)
+    pub backends_initialized: Gauge<u64>,
+}
+
+#[vise::register]
+pub(crate) static HEALTH_METRICS: vise::Global<HealthMetrics> = vise::Global::new();
+
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "da_verification")]
+pub struct VerificationMetrics {
+    /// Number of dispatched blobs re-fetched from the DA layer
+    pub checked_blobs: Counter,
+
+    /// Number of re-fetched blobs that could not be retrieved
+    pub unavailable_blobs: Counter,
+
+    /// Number of re-fetched blobs that don't match the dispatched payload
+    pub mismatched_blobs: Counter,
+
+    /// Number of unavailable or mismatched blobs in the last run
+    pub last_run_failures: Gauge<usize>,
+
+    /// Number of blobs whose data root differs from the one committed on L1
+    pub onchain_mismatches: Counter,
+}
+
+#[vise::register]
+pub(crate) static VERIFICATION_METRICS: vise::Global<VerificationMetrics> = vise::Global::new();
+
+/// The labels of the HTTP metrics, `route` is the matched route template.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EncodeLabelSet)]
+pub struct RouteLabels {
+    pub method: String,
+    pub route: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EncodeLabelSet)]
+pub struct RouteStatusLabels {
+    pub method: String,
+    pub route: String,
+    pub status: u16,
+}
+
+#[derive(Debug, Metrics)]
+#[metrics(prefix = "http")]
+pub struct HttpMetrics {
+    /// Number of open HTTP connections
+    pub active_connections: Gauge<usize>,
+
+    /// Latency of the requests until the response head per route and status in seconds
+    #[metrics(buckets = Buckets::LATENCIES, unit = Unit::Seconds)]
+    pub request_latency: Family<RouteStatusLabels, Histogram<Duration>>,
+
+    /// Size of the request bodies per route in bytes
+    #[metrics(buckets = Buckets::exponential(64.0..=64.0 * 1024.0 * 1024.0, 4.0), unit = Unit::Bytes)]
+    pub request_size: Family<RouteLabels, Histogram<u64>>,
+
+    /// Size of the response bodies per route in bytes
+    #[metrics(buckets = Buckets::exponential(64.0..=64.0 * 1024.0 * 1024.0, 4.0), unit = Unit::Bytes)]
+    pub response_size: Family<RouteLabels, Histogram<u64>>,
+}
+
+#[vise::register]
+pub(crate) static HTTP_METRICS: vise::Global<HttpMetrics> = vise::Global::new();
+
+/// Counts an open HTTP connection until the returned guard is dropped.
+pub fn track_connection() -> GaugeGuard<usize> {
+    HTTP_METRICS.active_connections.inc_guard(1)
+}
+
+/// Encodes the metrics in the Prometheus text format, only the series labeled with the caller when
+/// one is given.
+pub fn encode_metrics(caller: Option<&str>) -> String {
+    let mut text = String::new();
+    MetricsCollection::default()
+        .collect()
+        .encode(&mut text, Format::Prometheus)
+        .expect("Writing to a String doesn't fail");
+    let Some(caller) = caller else {
+        return text;
+    };
+
+    // The HELP and TYPE lines of a metric are kept with its first series of the caller.
+    let label = format!(
+        "caller=\"{}\"",
+        caller
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    );
+    let mut filtered = String::new();
+    let mut comments = Vec::new();
+    for line in text.lines() {
+        if line.starts_with("# HELP") {
+            comments.clear();
+        }
+        if line.starts_with('#') {
+            comments.push(line);
+        } else if line.contains(&label) {
+            for comment in comments.drain(..) {
+                filtered.push_str(comment);
+                filtered.push('\n');
+            }
+            filtered.push_str(line);
+            filtered.push('\n');
+        }
+    }
+    filtered
+}
+
+/// Serves the metrics to Prometheus.
+#[derive(Debug)]
+pub struct MetricsExporterSvc {
+    address: SocketAddr,
+}
+
+impl MetricsExporterSvc {
+    pub fn new(address: SocketAddr) -> Self {
+        Self { address }
+    }
+}
+
+#[async_trait]
+impl Lifecycle for MetricsExporterSvc {
+    fn name(&self) -> &'static str {
+        "metrics_exporter"
+    }
+
+    async fn start(&self, mut stop: StopSignal) -> anyhow::Result<()> {
+        MetricsExporter::default()
+            .with_graceful_shutdown(async move {
+                stopped(&mut stop).await;
+            })
+            .start(self.address)
+            .await?;
+        Ok(())
+    }
+}
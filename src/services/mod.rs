@@ -1,3 +1,28 @@
+pub mod archive;
+pub mod attestation;
+pub mod batch_lock;
+pub mod confirmation;
+pub mod cpu;
 pub mod da;
+pub mod dispatch_queue;
+pub mod fee_budget;
 pub mod health_check;
+pub mod import;
+pub mod index;
+pub mod lifecycle;
+pub mod logging;
+pub mod maintenance;
 pub mod metrics;
+pub mod onchain;
+pub mod params_refresh;
+pub mod payload_cache;
+pub mod proof_cache;
+pub mod selftest;
+pub mod sequence;
+pub mod shadow;
+pub mod staging;
+pub mod transform;
+pub mod usage;
+pub mod verification;
+pub mod warmup;
+pub mod webhook;
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use crate::{
+    clients::{
+        da_clients::types::{CallContext, DAError},
+        data_root_sources::DataRootSource,
+    },
+    services::{metrics::VERIFICATION_METRICS, proof_cache::ProofCacheSvc},
+    types::verification::{OnchainStatus, OnchainVerifyResponse},
+};
+
+/// Checks the data root of the block of a blob against the one committed on L1, so a blob is
+/// known to be provable to the settlement layer and not only available on Celestia.
+#[derive(Debug, Clone)]
+pub struct OnchainVerificationSvc {
+    proofs: Arc<ProofCacheSvc>,
+    source: Arc<dyn DataRootSource>,
+}
+
+impl OnchainVerificationSvc {
+    pub fn new(proofs: Arc<ProofCacheSvc>, source: Arc<dyn DataRootSource>) -> Self {
+        Self { proofs, source }
+    }
+
+    /// Verifies a blob of the caller namespace, None when the blob is not found. The inclusion
+    /// proof of the blob leads to the data root of its block, checked against the extended
+    /// header of the block.
+    pub async fn verify(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<Option<OnchainVerifyResponse>, DAError> {
+        let Some(proof) = self
+            .proofs
+            .get_inclusion_proof(ctx, caller, blob_id)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let l1_data_root = ctx
+            .run(async {
+                self.source
+                    .data_root(proof.height)
+                    .await
+                    .map_err(|err| DAError::ConnectionError {
+                        message: format!("Error to get the data root committed on L1: {err}"),
+                    })
+            })
+            .await?;
+        let status = match &l1_data_root {
+            Some(l1_data_root) if l1_data_root.eq_ignore_ascii_case(&proof.data_root) => {
+                OnchainStatus::Provable
+            }
+            Some(l1_data_root) => {
+                tracing::error!(
+                    "The data root of block {} is {} on L1 but {} on DA",
+                    proof.height,
+                    l1_data_root,
+                    proof.data_root
+                );
+                VERIFICATION_METRICS.onchain_mismatches.inc();
+                OnchainStatus::Mismatch
+            }
+            None => OnchainStatus::Pending,
+        };
+
+        Ok(Some(OnchainVerifyResponse {
+            blob_id: blob_id.to_string(),
+            height: proof.height,
+            data_root: proof.data_root,
+            l1_data_root,
+            status,
+            provable: status == OnchainStatus::Provable,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{clients::da_clients::proving::ProvingClient, services::da::DaSvc};
+
+    /// The data roots committed on L1 by height.
+    #[derive(Debug)]
+    struct CommittedRoots(HashMap<u64, String>);
+
+    #[async_trait]
+    impl DataRootSource for CommittedRoots {
+        async fn data_root(&self, height: u64) -> anyhow::Result<Option<String>> {
+            Ok(self.0.get(&height).cloned())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blobs_are_provable_once_their_data_root_is_committed() {
+        let client = ProvingClient::default();
+        for height in 1..=3 {
+            client.set_data_root(height, &format!("0{height}"));
+        }
+        let svc = OnchainVerificationSvc::new(
+            Arc::new(
+                ProofCacheSvc::new(Arc::new(DaSvc::for_tests(Arc::new(client))), None).unwrap(),
+            ),
+            Arc::new(CommittedRoots(HashMap::from([
+                (1, "01".to_string()),
+                (2, "ff".to_string()),
+            ]))),
+        );
+
+        let ctx = CallContext::background();
+        for (blob_id, status) in [
+            ("1", Some(OnchainStatus::Provable)),
+            ("2", Some(OnchainStatus::Mismatch)),
+            // The block is not committed on L1 yet.
+            ("3", Some(OnchainStatus::Pending)),
+            ("missing", None),
+        ] {
+            let response = svc.verify(&ctx, "caller", blob_id).await.unwrap();
+            assert_eq!(response.map(|response| response.status), status);
+        }
+    }
+}
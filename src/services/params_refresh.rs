@@ -0,0 +1,58 @@
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{
+    clients::da_clients::celestia::CelestiaClient,
+    services::lifecycle::{Lifecycle, StopSignal, run_every},
+};
+
+/// Queries the network parameters of the Celestia clients every refresh interval, so the blob size
+/// limit follows the network upgrades between the dispatches.
+#[derive(Debug, Default)]
+pub struct ParamsRefreshSvc {
+    interval: Option<Duration>,
+    clients: Mutex<Vec<CelestiaClient>>,
+}
+
+impl ParamsRefreshSvc {
+    /// Creates the service, the parameters are not refreshed without an `interval`.
+    pub fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            clients: Mutex::default(),
+        }
+    }
+
+    /// Adds a client to refresh, once it is created.
+    pub fn add(&self, client: CelestiaClient) {
+        self.clients.lock().unwrap().push(client);
+    }
+
+    pub async fn run(&self) {
+        let clients = self.clients.lock().unwrap().clone();
+        for client in clients {
+            if let Err(err) = client.query_params().await {
+                tracing::warn!(
+                    "Failed to query the network parameters from {:?}: {}",
+                    client,
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Lifecycle for ParamsRefreshSvc {
+    fn name(&self) -> &'static str {
+        "da_params_refresh"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        if let Some(interval) = self.interval {
+            run_every(interval, stop, || self.run()).await;
+        }
+        Ok(())
+    }
+}
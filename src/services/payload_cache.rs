@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+/// Keeps the dispatched payloads on disk by blob_id, so they can be re-dispatched when the DA
+/// layer loses them.
+#[derive(Debug, Clone, Default)]
+pub struct PayloadCacheSvc {
+    dir: Option<PathBuf>,
+}
+
+impl PayloadCacheSvc {
+    pub fn new(dir: Option<PathBuf>) -> anyhow::Result<Self> {
+        if let Some(dir) = &dir {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(Self { dir })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Returns the cache file of the blob, None for blob_ids that are not hex.
+    fn path(&self, blob_id: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        if blob_id.is_empty() || !blob_id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(dir.join(blob_id))
+    }
+
+    /// Stores the payload of a dispatched blob, a failure is logged as the dispatch succeeded.
+    pub async fn put(&self, blob_id: &str, data: &[u8]) {
+        let Some(path) = self.path(blob_id) else {
+            return;
+        };
+        if let Err(err) = tokio::fs::write(&path, data).await {
+            tracing::error!("Failed to cache the payload of {}: {}", blob_id, err);
+        }
+    }
+
+    pub async fn get(&self, blob_id: &str) -> Option<Vec<u8>> {
+        tokio::fs::read(self.path(blob_id)?).await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_payloads_are_cached_by_blob_id() {
+        let dir = std::env::temp_dir().join(format!("via-cache-{}", std::process::id()));
+        let svc = PayloadCacheSvc::new(Some(dir.clone())).unwrap();
+
+        svc.put("abcd", b"payload").await;
+        assert_eq!(svc.get("abcd").await, Some(b"payload".to_vec()));
+        assert_eq!(svc.get("ef01").await, None);
+
+        // blob_ids are never used as paths unless they are hex.
+        svc.put("../escape", b"payload").await;
+        assert!(!dir.parent().unwrap().join("escape").exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{
+    clients::da_clients::types::{CallContext, DAError, InclusionProof},
+    services::{da::DaSvc, metrics::DA_METRICS},
+};
+
+/// Keeps the generated inclusion proofs on disk by blob_id, a proof takes several light node
+/// calls to generate. A cached proof is served once its data root is checked against the header
+/// of its block, and generated again when the root changed.
+#[derive(Debug, Clone)]
+pub struct ProofCacheSvc {
+    da_svc: Arc<DaSvc>,
+    dir: Option<PathBuf>,
+}
+
+impl ProofCacheSvc {
+    /// Creates the service, the proofs are generated on every request without `dir`.
+    pub fn new(da_svc: Arc<DaSvc>, dir: Option<PathBuf>) -> anyhow::Result<Self> {
+        if let Some(dir) = &dir {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(Self { da_svc, dir })
+    }
+
+    /// Returns the cache file of the proof, the proofs of the tenants are kept by namespace. None
+    /// for blob_ids that are not hex.
+    fn path(&self, caller: &str, blob_id: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        if blob_id.is_empty() || !blob_id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let file = format!("{blob_id}.json");
+        Some(match self.da_svc.tenant_namespace(caller) {
+            Some(namespace) => dir.join("tenants").join(hex::encode(namespace)).join(file),
+            None => dir.join(file),
+        })
+    }
+
+    /// Returns the cached proof of a blob, an unreadable cache file is ignored.
+    async fn load(path: &PathBuf) -> Option<InclusionProof> {
+        let bytes = tokio::fs::read(path).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Stores a generated proof, a failure is logged as the proof is served anyway.
+    async fn store(path: &PathBuf, proof: &InclusionProof) {
+        let result = async {
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(path, serde_json::to_vec(proof)?).await?;
+            anyhow::Ok(())
+        };
+        if let Err(err) = result.await {
+            tracing::error!("Failed to cache the proof of {}: {}", proof.blob_id, err);
+        }
+    }
+
+    /// Returns the inclusion proof of a blob in the caller namespace, from the cache while its
+    /// data root matches the header of its block.
+    pub async fn get_inclusion_proof(
+        &self,
+        ctx: &CallContext,
+        caller: &str,
+        blob_id: &str,
+    ) -> Result<Option<InclusionProof>, DAError> {
+        let path = self.path(caller, blob_id);
+
+        if let Some(path) = &path
+            && let Some(cached) = Self::load(path).await
+        {
+            let data_root = self.da_svc.data_root(ctx, caller, cached.height).await?;
+            if data_root == cached.data_root {
+                DA_METRICS.proof_cache_hits.inc();
+                return Ok(Some(cached));
+            }
+            tracing::warn!(
+                "The data root of the cached proof of {} changed from {} to {}, generating it again",
+                blob_id,
+                cached.data_root,
+                data_root
+            );
+            DA_METRICS.proof_cache_invalidations.inc();
+        }
+
+        let proof = self
+            .da_svc
+            .get_inclusion_proof(ctx, caller, blob_id)
+            .await?;
+        if let (Some(path), Some(proof)) = (&path, &proof) {
+            Self::store(path, proof).await;
+        }
+
+        Ok(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::da_clients::proving::ProvingClient;
+
+    #[tokio::test]
+    async fn test_cached_proofs_are_rechecked_against_the_header() {
+        let dir = std::env::temp_dir().join(format!("via-proofs-{}", std::process::id()));
+        let client = ProvingClient::default();
+        client.set_data_root(7, "01");
+        let da_svc = DaSvc::for_tests(Arc::new(client.clone()));
+        let svc = ProofCacheSvc::new(Arc::new(da_svc), Some(dir.clone())).unwrap();
+
+        let ctx = CallContext::background();
+        for _ in 0..2 {
+            let proof = svc.get_inclusion_proof(&ctx, "caller", "7").await;
+            assert_eq!(proof.unwrap().unwrap().data_root, "01");
+        }
+        assert_eq!(client.proofs(), 1);
+
+        // The block was reorged, the cached proof no longer holds.
+        client.set_data_root(7, "02");
+        let proof = svc.get_inclusion_proof(&ctx, "caller", "7").await;
+        assert_eq!(proof.unwrap().unwrap().data_root, "02");
+        assert_eq!(client.proofs(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::{
+    clients::da_clients::{DataAvailabilityClient, types::CallContext},
+    services::lifecycle::{Lifecycle, StopSignal},
+    types::selftest::SelftestResult,
+};
+
+/// Batch number of the canary blobs, never used by the sequencer.
+const CANARY_BATCH_NUMBER: u32 = u32::MAX;
+
+/// Checks that blobs can actually be dispatched and read back. A reachable node may still fail
+/// the submissions, e.g. with an empty wallet, which the ping doesn't detect.
+#[derive(Debug, Clone)]
+pub struct SelftestSvc {
+    da_client: Arc<dyn DataAvailabilityClient + Send + Sync>,
+    last_result: Arc<RwLock<Option<SelftestResult>>>,
+}
+
+impl SelftestSvc {
+    pub fn new(da_client: Arc<dyn DataAvailabilityClient + Send + Sync>) -> Self {
+        Self {
+            da_client,
+            last_result: Arc::default(),
+        }
+    }
+
+    /// Dispatches a canary blob, fetches its inclusion data and checks it matches.
+    pub async fn run(&self) -> SelftestResult {
+        let started_at = Instant::now();
+        let timestamp = Utc::now();
+        let canary = format!("via-core-ext/selftest/{}", timestamp.timestamp_millis()).into_bytes();
+
+        let (blob_id, outcome) = self.roundtrip(canary).await;
+        let result = SelftestResult {
+            success: outcome.is_ok(),
+            blob_id,
+            error: outcome.err().map(|err| err.to_string()),
+            latency_ms: started_at.elapsed().as_millis() as u64,
+            timestamp,
+        };
+
+        match &result.error {
+            None => tracing::info!("DA selftest succeeded in {}ms", result.latency_ms),
+            Some(err) => tracing::error!("DA selftest failed: {}", err),
+        }
+
+        *self.last_result.write().unwrap() = Some(result.clone());
+        result
+    }
+
+    async fn roundtrip(&self, canary: Vec<u8>) -> (Option<String>, anyhow::Result<()>) {
+        let blob_id = match self
+            .da_client
+            .dispatch_blob(
+                &CallContext::background(),
+                CANARY_BATCH_NUMBER,
+                canary.clone(),
+            )
+            .await
+        {
+            Ok(response) => response.blob_id,
+            Err(err) => return (None, Err(anyhow::anyhow!("Dispatch failed: {}", err))),
+        };
+
+        let outcome = match self
+            .da_client
+            .get_inclusion_data(&CallContext::background(), &blob_id)
+            .await
+        {
+            Ok(Some(inclusion)) if inclusion.data == canary => Ok(()),
+            Ok(Some(_)) => Err(anyhow::anyhow!("Inclusion data doesn't match the canary")),
+            Ok(None) => Err(anyhow::anyhow!("Canary blob not found after dispatch")),
+            Err(err) => Err(anyhow::anyhow!("Inclusion query failed: {}", err)),
+        };
+
+        (Some(blob_id), outcome)
+    }
+
+    /// Returns the result of the last run, if any.
+    pub fn last_result(&self) -> Option<SelftestResult> {
+        self.last_result.read().unwrap().clone()
+    }
+}
+
+/// Runs the selftest once on startup.
+#[async_trait]
+impl Lifecycle for SelftestSvc {
+    fn name(&self) -> &'static str {
+        "startup_selftest"
+    }
+
+    async fn start(&self, _stop: StopSignal) -> anyhow::Result<()> {
+        self.run().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::da_clients::in_memory::InMemoryClient;
+
+    #[tokio::test]
+    async fn test_selftest_roundtrip() {
+        let svc = SelftestSvc::new(Arc::new(InMemoryClient::new(1024)));
+        assert!(svc.last_result().is_none());
+
+        let result = svc.run().await;
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.blob_id.is_some());
+        assert_eq!(svc.last_result(), Some(result));
+    }
+}
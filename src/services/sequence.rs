@@ -0,0 +1,175 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    services::{index::IndexSvc, metrics::DA_METRICS},
+    types::sequence::{BatchGap, CallerSequence},
+};
+
+#[derive(Debug, Default)]
+struct Batches {
+    dispatched: BTreeSet<u32>,
+    /// The batch number of the previous dispatch.
+    previous: u32,
+    /// The number of batch numbers missing between the first and the last dispatched ones.
+    missing: u64,
+    regressions: u64,
+}
+
+impl Batches {
+    fn insert(&mut self, batch_number: u32) {
+        let (Some(&first), Some(&last)) = (self.dispatched.first(), self.dispatched.last()) else {
+            self.dispatched.insert(batch_number);
+            self.previous = batch_number;
+            return;
+        };
+
+        if self.dispatched.insert(batch_number) {
+            self.missing = if batch_number > last {
+                self.missing + (batch_number - last - 1) as u64
+            } else if batch_number < first {
+                self.missing + (first - batch_number - 1) as u64
+            } else {
+                self.missing - 1
+            };
+        }
+        self.previous = batch_number;
+    }
+
+    fn gaps(&self) -> Vec<BatchGap> {
+        self.dispatched
+            .iter()
+            .zip(self.dispatched.iter().skip(1))
+            .filter(|(previous, next)| *next - *previous > 1)
+            .map(|(previous, next)| BatchGap {
+                from: previous + 1,
+                to: next - 1,
+            })
+            .collect()
+    }
+}
+
+/// Tracks the batch numbers dispatched by each caller, the dispatches skipping batch numbers or
+/// going back to a lower one are reported in the logs and the metrics. A sequencer skipping a
+/// batch shows up as a gap before the batch is needed on L1.
+#[derive(Debug, Clone, Default)]
+pub struct SequenceSvc {
+    callers: Arc<Mutex<HashMap<String, Batches>>>,
+}
+
+impl SequenceSvc {
+    /// Creates the service from the dispatches of the index, in their dispatch order.
+    pub fn new(index: &IndexSvc) -> Self {
+        let mut callers: HashMap<String, Batches> = HashMap::new();
+        for entry in index.range(None, None) {
+            if entry.redispatch_of.is_none() {
+                callers
+                    .entry(entry.caller)
+                    .or_default()
+                    .insert(entry.batch_number);
+            }
+        }
+        for (caller, batches) in &callers {
+            DA_METRICS.missing_batches[caller].set(batches.missing);
+        }
+
+        Self {
+            callers: Arc::new(Mutex::new(callers)),
+        }
+    }
+
+    /// Records a dispatch of the caller. Dispatching the previous batch again, e.g. on a retry,
+    /// is neither a gap nor a regression.
+    pub fn record(&self, caller: &str, batch_number: u32) {
+        let mut callers = self.callers.lock().unwrap();
+        let batches = callers.entry(caller.to_string()).or_default();
+
+        if let Some(&last) = batches.dispatched.last()
+            && let Some(next) = last.checked_add(1)
+            && batch_number > next
+        {
+            tracing::warn!(
+                "Batches {} to {} of {} were skipped, {} dispatched after {}",
+                next,
+                batch_number - 1,
+                caller,
+                batch_number,
+                last
+            );
+            DA_METRICS.batch_gaps[&caller.to_string()].inc();
+        }
+        if !batches.dispatched.is_empty() && batch_number < batches.previous {
+            tracing::warn!(
+                "Batch {} of {} dispatched after batch {}",
+                batch_number,
+                caller,
+                batches.previous
+            );
+            batches.regressions += 1;
+            DA_METRICS.batch_regressions[&caller.to_string()].inc();
+        }
+
+        batches.insert(batch_number);
+        DA_METRICS.missing_batches[&caller.to_string()].set(batches.missing);
+    }
+
+    /// Returns the sequences of all the callers, sorted by caller.
+    pub fn sequences(&self) -> Vec<CallerSequence> {
+        let callers = self.callers.lock().unwrap();
+        let mut sequences: Vec<CallerSequence> = callers
+            .iter()
+            .filter_map(|(caller, batches)| {
+                Some(CallerSequence {
+                    caller: caller.clone(),
+                    first: *batches.dispatched.first()?,
+                    last: *batches.dispatched.last()?,
+                    gaps: batches.gaps(),
+                    regressions: batches.regressions,
+                })
+            })
+            .collect();
+        sequences.sort_by(|a, b| a.caller.cmp(&b.caller));
+        sequences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaps_and_regressions_are_detected() {
+        let svc = SequenceSvc::default();
+        for batch_number in [1, 2, 2, 5, 9, 4] {
+            svc.record("a", batch_number);
+        }
+        svc.record("b", 7);
+
+        let sequences = svc.sequences();
+        assert_eq!(sequences.len(), 2);
+        assert_eq!((sequences[0].first, sequences[0].last), (1, 9));
+        assert_eq!(
+            sequences[0].gaps,
+            vec![BatchGap { from: 3, to: 3 }, BatchGap { from: 6, to: 8 }]
+        );
+        assert_eq!(sequences[0].regressions, 1);
+        assert!(sequences[1].gaps.is_empty());
+
+        let batches = &svc.callers.lock().unwrap()["a"];
+        assert_eq!(batches.missing, 4);
+    }
+
+    #[test]
+    fn test_last_batch_number_does_not_overflow() {
+        let svc = SequenceSvc::default();
+        for batch_number in [u32::MAX - 1, u32::MAX, u32::MAX, 0] {
+            svc.record("a", batch_number);
+        }
+
+        let sequences = svc.sequences();
+        assert_eq!((sequences[0].first, sequences[0].last), (0, u32::MAX));
+        assert_eq!(sequences[0].regressions, 1);
+    }
+}
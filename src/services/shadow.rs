@@ -0,0 +1,140 @@
+use std::{sync::Arc, time::Duration};
+
+use rand::Rng;
+use tokio::{task::JoinHandle, time::Instant};
+
+use crate::{
+    clients::da_clients::{DataAvailabilityClient, types::CallContext},
+    services::{da::DaSvc, metrics::DA_METRICS},
+};
+
+/// The time a shadow dispatch and its read back are given, they never hold a request.
+const SHADOW_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The result of a shadow dispatch compared to the dispatch it shadows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowResult {
+    BothSucceeded,
+    /// The shadow dispatch failed, or its blob was not read back as dispatched.
+    CanaryFailed,
+    PrimaryFailed,
+    BothFailed,
+}
+
+impl ShadowResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BothSucceeded => "both_succeeded",
+            Self::CanaryFailed => "canary_failed",
+            Self::PrimaryFailed => "primary_failed",
+            Self::BothFailed => "both_failed",
+        }
+    }
+}
+
+/// Submits a share of the dispatches to a canary backend or namespace as well, to validate it on
+/// the production traffic. The results are compared in the metrics, never returned to the callers.
+#[derive(Debug, Clone, Default)]
+pub struct ShadowSvc {
+    client: Option<Arc<dyn DataAvailabilityClient + Send + Sync>>,
+    percent: u8,
+}
+
+impl ShadowSvc {
+    /// Creates the service, the dispatches are not shadowed without a canary `client`.
+    pub fn new(client: Option<Arc<dyn DataAvailabilityClient + Send + Sync>>, percent: u8) -> Self {
+        Self { client, percent }
+    }
+
+    /// Whether to shadow a dispatch, true for `percent` of them.
+    pub fn sample(&self) -> bool {
+        self.client.is_some() && rand::thread_rng().gen_range(0..100) < self.percent
+    }
+
+    pub fn blob_size_limit(&self) -> Option<usize> {
+        self.client.as_ref()?.blob_size_limit()
+    }
+
+    /// Dispatches the payload to the canary in the background and reads it back, the result is
+    /// compared to the outcome of the shadowed dispatch.
+    pub fn dispatch(
+        &self,
+        batch_number: u32,
+        data: Vec<u8>,
+        chunk_size: Option<usize>,
+        primary_succeeded: bool,
+    ) -> Option<JoinHandle<ShadowResult>> {
+        let client = self.client.clone()?;
+
+        Some(tokio::spawn(async move {
+            let ctx = CallContext::with_timeout(SHADOW_TIMEOUT);
+            let start = Instant::now();
+            let canary_succeeded =
+                match DaSvc::submit(&ctx, &client, batch_number, data.clone(), chunk_size, &[])
+                    .await
+                {
+                    Ok(response) => {
+                        DA_METRICS.shadow_dispatch_latency.observe(start.elapsed());
+                        Self::read_back(&ctx, &client, &response.blob_id, &data).await
+                    }
+                    Err(err) => {
+                        tracing::warn!("Shadow dispatch of batch {} failed: {}", batch_number, err);
+                        false
+                    }
+                };
+
+            let result = match (primary_succeeded, canary_succeeded) {
+                (true, true) => ShadowResult::BothSucceeded,
+                (true, false) => ShadowResult::CanaryFailed,
+                (false, true) => ShadowResult::PrimaryFailed,
+                (false, false) => ShadowResult::BothFailed,
+            };
+            DA_METRICS.shadow_dispatches[&result.as_str()].inc();
+            result
+        }))
+    }
+
+    /// Whether the canary serves the blob as dispatched.
+    async fn read_back(
+        ctx: &CallContext,
+        client: &Arc<dyn DataAvailabilityClient + Send + Sync>,
+        blob_id: &str,
+        data: &[u8],
+    ) -> bool {
+        let reason = match client.get_inclusion_data(ctx, blob_id).await {
+            Ok(Some(inclusion)) if inclusion.data == data => return true,
+            Ok(Some(inclusion)) => format!(
+                "{} bytes read back, {} dispatched",
+                inclusion.data.len(),
+                data.len()
+            ),
+            Ok(None) => "not found".to_string(),
+            Err(err) => err.to_string(),
+        };
+
+        tracing::warn!("Shadow blob {} was not read back: {}", blob_id, reason);
+        DA_METRICS.shadow_readback_failures.inc();
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::da_clients::in_memory::InMemoryClient;
+
+    #[tokio::test]
+    async fn test_shadow_dispatches_are_compared() {
+        let client = InMemoryClient::new(4);
+        let svc = ShadowSvc::new(Some(client.namespaced("canary").unwrap()), 100);
+        assert!(svc.sample());
+        assert!(!ShadowSvc::new(None, 100).sample());
+
+        // The chunked blob is read back whole.
+        let result = svc.dispatch(1, b"pubdata".to_vec(), Some(4), false);
+        assert_eq!(result.unwrap().await.unwrap(), ShadowResult::PrimaryFailed);
+
+        let result = svc.dispatch(2, b"pubdata".to_vec(), None, true);
+        assert_eq!(result.unwrap().await.unwrap(), ShadowResult::BothSucceeded);
+    }
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use crate::{
+    services::{da::PreparedBlob, metrics::DA_METRICS},
+    types::dispatch::DispatchPriority,
+};
+
+/// `StagedDispatch` is a dispatch ready to be submitted, staged by `POST /da/prepare` until its
+/// commit.
+#[derive(Debug, Clone)]
+pub struct StagedDispatch {
+    pub caller: String,
+    pub batch_number: u32,
+    pub priority: DispatchPriority,
+    pub webhook_url: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+    /// The size and the hash (hex) of the payload of the caller.
+    pub size: u64,
+    pub payload_hash: String,
+    /// The payload of the caller, kept when the payload cache is enabled.
+    pub payload: Option<Vec<u8>>,
+    pub prepared: PreparedBlob,
+}
+
+/// Keeps the staged dispatches in memory until they are committed or expire, so the callers can
+/// upload the payload early and trigger the paid submission later.
+#[derive(Debug, Clone)]
+pub struct StagingSvc {
+    ttl: Duration,
+    /// The staged dispatches by staging id, with their expiry.
+    staged: Arc<Mutex<HashMap<String, (Instant, StagedDispatch)>>>,
+}
+
+impl StagingSvc {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            staged: Arc::default(),
+        }
+    }
+
+    /// Drops the expired dispatches.
+    fn prune(&self, staged: &mut HashMap<String, (Instant, StagedDispatch)>) {
+        let now = Instant::now();
+        let before = staged.len();
+        staged.retain(|_, (expires_at, _)| *expires_at > now);
+        DA_METRICS
+            .expired_staged_dispatches
+            .inc_by((before - staged.len()) as u64);
+        DA_METRICS.staged_dispatches.set(staged.len() as u64);
+    }
+
+    /// Stages a dispatch, returns its staging id and how long it can be committed.
+    pub fn stage(&self, dispatch: StagedDispatch) -> (String, Duration) {
+        let staging_id = hex::encode(rand::random::<[u8; 16]>());
+
+        let mut staged = self.staged.lock().unwrap();
+        staged.insert(staging_id.clone(), (Instant::now() + self.ttl, dispatch));
+        self.prune(&mut staged);
+
+        (staging_id, self.ttl)
+    }
+
+    /// Takes out a staged dispatch of the caller with its expiry, None when it is unknown, expired
+    /// or staged by another caller. A failed commit puts it back with `restore`.
+    pub fn take(&self, caller: &str, staging_id: &str) -> Option<(StagedDispatch, Instant)> {
+        let mut staged = self.staged.lock().unwrap();
+        self.prune(&mut staged);
+
+        if staged.get(staging_id)?.1.caller != caller {
+            return None;
+        }
+        let (expires_at, dispatch) = staged.remove(staging_id)?;
+        DA_METRICS.staged_dispatches.set(staged.len() as u64);
+        Some((dispatch, expires_at))
+    }
+
+    /// Stages a taken dispatch again under its staging id, until its original expiry.
+    pub fn restore(&self, staging_id: &str, dispatch: StagedDispatch, expires_at: Instant) {
+        let mut staged = self.staged.lock().unwrap();
+        staged.insert(staging_id.to_string(), (expires_at, dispatch));
+        self.prune(&mut staged);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dispatch(caller: &str) -> StagedDispatch {
+        StagedDispatch {
+            caller: caller.to_string(),
+            batch_number: 1,
+            priority: DispatchPriority::Normal,
+            webhook_url: None,
+            metadata: BTreeMap::new(),
+            size: 0,
+            payload_hash: String::new(),
+            payload: None,
+            prepared: PreparedBlob::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_staged_dispatches_are_committed_once_by_their_caller() {
+        let svc = StagingSvc::new(Duration::from_secs(60));
+        let (staging_id, _) = svc.stage(dispatch("a"));
+
+        assert!(svc.take("b", &staging_id).is_none());
+        let (staged, expires_at) = svc.take("a", &staging_id).unwrap();
+        assert!(svc.take("a", &staging_id).is_none());
+
+        // The commit failed, the dispatch can be committed again.
+        svc.restore(&staging_id, staged, expires_at);
+        assert!(svc.take("a", &staging_id).is_some());
+
+        // Expired before its commit.
+        svc.restore(&staging_id, dispatch("a"), Instant::now());
+        assert!(svc.take("a", &staging_id).is_none());
+    }
+}
@@ -0,0 +1,280 @@
+use std::sync::Arc;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{
+    clients::{da_clients::types::DAError, key_providers::KeyProvider},
+    config::PayloadTransform,
+    services::cpu::offload,
+    types::envelope::{AppliedTransform, BlobEnvelope},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const TAG_LEN: usize = 32;
+
+/// Applies the configured transforms to the dispatched payloads, and reverses the transforms
+/// recorded in the envelope of the retrieved ones.
+#[derive(Debug, Clone)]
+pub struct TransformSvc {
+    transforms: Vec<PayloadTransform>,
+    key_provider: Arc<dyn KeyProvider>,
+    compression_level: i32,
+    chunk_size: Option<usize>,
+}
+
+impl TransformSvc {
+    pub fn new(
+        transforms: Vec<PayloadTransform>,
+        key_provider: Arc<dyn KeyProvider>,
+        compression_level: i32,
+        chunk_size: Option<usize>,
+    ) -> Self {
+        Self {
+            transforms,
+            key_provider,
+            compression_level,
+            chunk_size,
+        }
+    }
+
+    /// The size of the chunks of the chunked blobs, None without the chunk transform. Capped by
+    /// the blob size limit of the backend.
+    pub fn chunk_size(&self, blob_size_limit: Option<usize>) -> Option<usize> {
+        if !self.transforms.contains(&PayloadTransform::Chunk) {
+            return None;
+        }
+        [self.chunk_size, blob_size_limit]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Applies the compress and encrypt transforms, the payload is not enveloped without them.
+    pub async fn encode(&self, data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let mut envelope = BlobEnvelope {
+            transforms: vec![],
+            data,
+        };
+
+        for transform in &self.transforms {
+            match transform {
+                PayloadTransform::Compress => {
+                    let (data, level) = (envelope.data, self.compression_level);
+                    envelope.data =
+                        offload(data.len(), move || zstd::encode_all(&data[..], level)).await?;
+                    envelope.transforms.push(AppliedTransform::Zstd);
+                }
+                PayloadTransform::Encrypt => {
+                    let key = self.key_provider.encryption_key().await?;
+                    let nonce = rand::random();
+                    let data = envelope.data;
+                    envelope.data =
+                        offload(data.len(), move || encrypt(&key.key, &nonce, data)).await;
+                    envelope.transforms.push(AppliedTransform::HmacSha256Ctr {
+                        key_id: key.key_id,
+                        nonce,
+                    });
+                }
+                // Applied on dispatch, the clients reassemble the chunked blobs.
+                PayloadTransform::Chunk => {}
+            }
+        }
+
+        if envelope.transforms.is_empty() {
+            return Ok(envelope.data);
+        }
+        Ok(envelope.to_bytes())
+    }
+
+    /// Reverses the transforms recorded in the envelope of a payload, the payloads without an
+    /// envelope are returned as is.
+    pub async fn decode(&self, blob_id: &str, data: Vec<u8>) -> Result<Vec<u8>, DAError> {
+        let mismatch = |reason: String| DAError::IntegrityMismatch {
+            blob_id: blob_id.to_string(),
+            reason,
+        };
+        let Some(envelope) = BlobEnvelope::from_bytes(&data)
+            .map_err(|err| mismatch(format!("Invalid envelope: {err}")))?
+        else {
+            return Ok(data);
+        };
+
+        let mut data = envelope.data;
+        for transform in envelope.transforms.iter().rev() {
+            data = match transform {
+                AppliedTransform::Zstd => offload(data.len(), move || zstd::decode_all(&data[..]))
+                    .await
+                    .map_err(|err| mismatch(format!("Failed to decompress: {err}")))?,
+                AppliedTransform::HmacSha256Ctr { key_id, nonce } => {
+                    let key = self.key_provider.encryption_key_by_id(key_id).await?;
+                    let nonce = *nonce;
+                    offload(data.len(), move || decrypt(&key.key, &nonce, data))
+                        .await
+                        .ok_or_else(|| {
+                            mismatch(format!("Failed to authenticate with key [{key_id}]"))
+                        })?
+                }
+            };
+        }
+
+        Ok(data)
+    }
+}
+
+/// Derives the key of a purpose from the encryption key, so the keystream and the tag use
+/// different keys.
+fn derive_key(key: &[u8; 32], purpose: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(purpose);
+    HmacSha256::new_from_slice(&mac.finalize().into_bytes()).expect("HMAC accepts keys of any size")
+}
+
+/// XORs the data with the keystream, the HMACs of the nonce and the block index.
+fn apply_keystream(key: &HmacSha256, nonce: &[u8; 16], data: &mut [u8]) {
+    for (index, block) in data.chunks_mut(TAG_LEN).enumerate() {
+        let mut mac = key.clone();
+        mac.update(nonce);
+        mac.update(&(index as u64).to_be_bytes());
+        for (byte, key) in block.iter_mut().zip(mac.finalize().into_bytes()) {
+            *byte ^= key;
+        }
+    }
+}
+
+fn tag(key: &[u8; 32], nonce: &[u8; 16], ciphertext: &[u8]) -> HmacSha256 {
+    let mut mac = derive_key(key, b"via-envelope-authentication");
+    mac.update(nonce);
+    mac.update(ciphertext);
+    mac
+}
+
+/// Encrypts then authenticates the data, the tag is appended to the ciphertext.
+fn encrypt(key: &[u8; 32], nonce: &[u8; 16], mut data: Vec<u8>) -> Vec<u8> {
+    apply_keystream(
+        &derive_key(key, b"via-envelope-encryption"),
+        nonce,
+        &mut data,
+    );
+    let tag = tag(key, nonce, &data).finalize().into_bytes();
+    data.extend_from_slice(&tag);
+    data
+}
+
+/// Decrypts the data once its tag is verified, None when the verification fails.
+fn decrypt(key: &[u8; 32], nonce: &[u8; 16], mut data: Vec<u8>) -> Option<Vec<u8>> {
+    let tag_at = data.len().checked_sub(TAG_LEN)?;
+    let expected = data.split_off(tag_at);
+    tag(key, nonce, &data).verify_slice(&expected).ok()?;
+
+    apply_keystream(
+        &derive_key(key, b"via-envelope-encryption"),
+        nonce,
+        &mut data,
+    );
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clients::{
+            da_clients::{in_memory::InMemoryClient, types::CallContext},
+            key_providers::local::LocalKeyProvider,
+        },
+        services::da::DaSvc,
+        types::dispatch::DispatchPriority,
+    };
+
+    fn transform_svc(transforms: Vec<PayloadTransform>, keys: &[(&str, u8)]) -> TransformSvc {
+        let keys = keys
+            .iter()
+            .map(|(key_id, key)| (key_id.to_string(), [*key; 32]))
+            .collect();
+        TransformSvc::new(
+            transforms,
+            Arc::new(LocalKeyProvider::new(None, keys)),
+            3,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_transformed_payloads_are_self_describing() {
+        let ctx = CallContext::background();
+        let dispatched = transform_svc(
+            vec![
+                PayloadTransform::Compress,
+                PayloadTransform::Encrypt,
+                PayloadTransform::Chunk,
+            ],
+            &[("key-1", 1)],
+        );
+        let client = Arc::new(InMemoryClient::new(64));
+        let da_svc = |transforms| DaSvc::for_tests(client.clone()).with_transforms(transforms);
+
+        // Random bytes don't compress, the envelope spans several chunks.
+        let payload: Vec<u8> = (0..300).map(|_| rand::random()).collect();
+        let blob_id = da_svc(Arc::new(dispatched))
+            .dispatch_blob(
+                &ctx,
+                "caller",
+                1,
+                None,
+                payload.clone(),
+                DispatchPriority::Normal,
+            )
+            .await
+            .unwrap()
+            .blob_id;
+
+        // The key was rotated and the transforms dropped since the dispatch.
+        let retrieved = da_svc(Arc::new(transform_svc(
+            vec![],
+            &[("key-2", 2), ("key-1", 1)],
+        )));
+        let data = retrieved
+            .get_inclusion_data(&ctx, "caller", &blob_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.data, payload);
+        let range = retrieved
+            .get_inclusion_range(&ctx, "caller", &blob_id, 100..200)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(range.data, payload[100..200]);
+        assert_eq!(range.size, Some(300));
+
+        let unknown_key = da_svc(Arc::new(transform_svc(vec![], &[("key-2", 2)])));
+        assert!(matches!(
+            unknown_key
+                .get_inclusion_data(&ctx, "caller", &blob_id)
+                .await,
+            Err(DAError::Internal(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tampered_ciphertexts_are_rejected() {
+        let svc = transform_svc(
+            vec![PayloadTransform::Compress, PayloadTransform::Encrypt],
+            &[("key-1", 1)],
+        );
+        let mut envelope = svc.encode(b"pubdata".repeat(10)).await.unwrap();
+        assert_eq!(
+            svc.decode("blob", envelope.clone()).await.unwrap(),
+            b"pubdata".repeat(10)
+        );
+
+        *envelope.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            svc.decode("blob", envelope).await,
+            Err(DAError::IntegrityMismatch { .. })
+        ));
+        assert_eq!(svc.decode("blob", b"raw".to_vec()).await.unwrap(), b"raw");
+    }
+}
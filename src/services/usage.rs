@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::{
+    services::{
+        lifecycle::{Lifecycle, StopSignal, run_every},
+        metrics::USAGE_METRICS,
+    },
+    types::{dispatch::DispatchReceipt, usage::CallerUsage},
+};
+
+/// `UsageError` is returned when a dispatch would exceed the caller monthly byte cap.
+#[derive(Debug, thiserror::Error)]
+#[error("Monthly byte cap exceeded for {caller}: {used} + {requested} > {cap} bytes")]
+pub struct UsageError {
+    pub caller: String,
+    pub used: u64,
+    pub requested: u64,
+    pub cap: u64,
+}
+
+/// `UsageReservation` is the bytes reserved for a dispatch in the monthly cap of its month.
+#[derive(Debug)]
+pub struct UsageReservation {
+    caller: String,
+    bytes: u64,
+    month: String,
+}
+
+/// Tracks the dispatched bytes and requests per caller and enforces the monthly byte cap.
+///
+/// Bytes are reserved before the dispatch, so concurrent requests can't overrun the cap, and
+/// released when the dispatch fails.
+///
+/// When a snapshot path is configured, the usage is written to it every snapshot interval and on
+/// shutdown, and loaded back on startup, so the monthly caps outlive restarts. The reservations of
+/// the dispatches in progress at a crash stay counted.
+#[derive(Debug, Clone)]
+pub struct UsageSvc {
+    monthly_byte_cap: Option<u64>,
+    caller_byte_caps: HashMap<String, u64>,
+    usage: Arc<Mutex<HashMap<String, CallerUsage>>>,
+    snapshot: Option<(PathBuf, Duration)>,
+    /// Whether the last snapshot was written.
+    persisted: Arc<AtomicBool>,
+}
+
+impl Default for UsageSvc {
+    fn default() -> Self {
+        Self {
+            monthly_byte_cap: None,
+            caller_byte_caps: HashMap::new(),
+            usage: Arc::default(),
+            snapshot: None,
+            persisted: Arc::new(AtomicBool::new(true)),
+        }
+    }
+}
+
+impl UsageSvc {
+    /// Creates the service, `caller_byte_caps` override the monthly byte cap of some callers.
+    pub fn new(monthly_byte_cap: Option<u64>, caller_byte_caps: Vec<(String, u64)>) -> Self {
+        Self {
+            monthly_byte_cap,
+            caller_byte_caps: caller_byte_caps.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Persists the usage to a snapshot at `path` every `interval`, the usage of the snapshot is
+    /// loaded when it exists.
+    pub fn with_snapshot(self, path: PathBuf, interval: Duration) -> anyhow::Result<Self> {
+        let loaded = match fs::read(&path) {
+            Ok(snapshot) => serde_json::from_slice::<Vec<CallerUsage>>(&snapshot)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err.into()),
+        };
+        if !loaded.is_empty() {
+            tracing::info!("Loaded the usage of {} callers", loaded.len());
+        }
+        {
+            let mut usage = self.usage.lock().unwrap();
+            for mut caller_usage in loaded {
+                // The caps follow the configuration, not the snapshot.
+                caller_usage.monthly_byte_cap = self.byte_cap(&caller_usage.caller);
+                usage.insert(caller_usage.caller.clone(), caller_usage);
+            }
+        }
+
+        Ok(Self {
+            snapshot: Some((path, interval)),
+            ..self
+        })
+    }
+
+    /// Writes the usage to the snapshot, replacing the previous one at once.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some((path, _)) = &self.snapshot else {
+            return Ok(());
+        };
+        let result = (|| {
+            let snapshot = serde_json::to_vec(&self.usage())?;
+            let tmp = path.with_extension("tmp");
+            fs::write(&tmp, snapshot)?;
+            fs::rename(&tmp, path)?;
+            anyhow::Ok(())
+        })();
+        self.persisted.store(result.is_ok(), Ordering::Relaxed);
+        result
+    }
+
+    fn byte_cap(&self, caller: &str) -> Option<u64> {
+        self.caller_byte_caps
+            .get(caller)
+            .copied()
+            .or(self.monthly_byte_cap)
+    }
+
+    fn current_month() -> String {
+        Utc::now().format("%Y-%m").to_string()
+    }
+
+    fn new_usage(&self, caller: &str) -> CallerUsage {
+        CallerUsage {
+            caller: caller.to_string(),
+            monthly_byte_cap: self.byte_cap(caller),
+            ..Default::default()
+        }
+    }
+
+    /// Resets the monthly bytes of a usage from a past month.
+    fn in_month(mut usage: CallerUsage, month: &str) -> CallerUsage {
+        if usage.month != month {
+            usage.month = month.to_string();
+            usage.month_bytes = 0;
+        }
+        usage
+    }
+
+    /// Reserves `bytes` of the caller monthly cap for a dispatch.
+    pub fn reserve(&self, caller: &str, bytes: u64) -> Result<UsageReservation, UsageError> {
+        self.reserve_in_month(caller, bytes, &Self::current_month())
+    }
+
+    fn reserve_in_month(
+        &self,
+        caller: &str,
+        bytes: u64,
+        month: &str,
+    ) -> Result<UsageReservation, UsageError> {
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage
+            .entry(caller.to_string())
+            .or_insert_with(|| self.new_usage(caller));
+
+        if entry.month != month {
+            entry.month = month.to_string();
+            entry.month_bytes = 0;
+        }
+
+        if let Some(cap) = entry.monthly_byte_cap
+            && entry.month_bytes.saturating_add(bytes) > cap
+        {
+            USAGE_METRICS.rejected_requests[&caller.to_string()].inc();
+            return Err(UsageError {
+                caller: caller.to_string(),
+                used: entry.month_bytes,
+                requested: bytes,
+                cap,
+            });
+        }
+
+        entry.month_bytes += bytes;
+        Ok(UsageReservation {
+            caller: caller.to_string(),
+            bytes,
+            month: month.to_string(),
+        })
+    }
+
+    /// Releases a reservation after a failed dispatch. The bytes of a reservation made in a past
+    /// month were already reset with the monthly bytes.
+    pub fn release(&self, reservation: UsageReservation) {
+        if let Some(entry) = self.usage.lock().unwrap().get_mut(&reservation.caller)
+            && entry.month == reservation.month
+        {
+            entry.month_bytes = entry.month_bytes.saturating_sub(reservation.bytes);
+        }
+    }
+
+    /// Records a successful dispatch of a reserved amount of bytes, with the fees of its receipt.
+    pub fn record_dispatch(&self, caller: &str, bytes: u64, receipt: Option<&DispatchReceipt>) {
+        let fees: Vec<_> = receipt
+            .into_iter()
+            .flat_map(DispatchReceipt::fees)
+            .collect();
+        if let Some(entry) = self.usage.lock().unwrap().get_mut(caller) {
+            entry.requests += 1;
+            entry.bytes += bytes;
+            for fee in &fees {
+                entry.gas_used += fee.gas_used;
+                if let (Some(amount), Some(denom)) = (fee.amount, &fee.denom) {
+                    *entry.fees.entry(denom.clone()).or_default() += amount;
+                }
+            }
+        }
+
+        let caller = caller.to_string();
+        USAGE_METRICS.dispatch_requests[&caller].inc();
+        USAGE_METRICS.dispatched_bytes[&caller].inc_by(bytes);
+        for fee in fees {
+            USAGE_METRICS.gas_used[&caller].inc_by(fee.gas_used);
+            if let (Some(amount), Some(denom)) = (fee.amount, &fee.denom) {
+                USAGE_METRICS.fees_paid[&(caller.clone(), denom.clone())].inc_by(amount);
+            }
+        }
+    }
+
+    /// Records a failed dispatch, its reservation is released separately.
+    pub fn record_failure(&self, caller: &str) {
+        self.usage
+            .lock()
+            .unwrap()
+            .entry(caller.to_string())
+            .or_insert_with(|| self.new_usage(caller))
+            .failures += 1;
+        USAGE_METRICS.failed_dispatches[&caller.to_string()].inc();
+    }
+
+    /// Returns the usage of a caller, empty before its first dispatch.
+    pub fn caller_usage(&self, caller: &str) -> CallerUsage {
+        let usage = self.usage.lock().unwrap().get(caller).cloned();
+        Self::in_month(
+            usage.unwrap_or_else(|| self.new_usage(caller)),
+            &Self::current_month(),
+        )
+    }
+
+    /// Returns the usage of all the callers, sorted by caller.
+    pub fn usage(&self) -> Vec<CallerUsage> {
+        let month = Self::current_month();
+        let mut callers: Vec<CallerUsage> = self
+            .usage
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .map(|usage| Self::in_month(usage, &month))
+            .collect();
+        callers.sort_by(|a, b| a.caller.cmp(&b.caller));
+        callers
+    }
+}
+
+#[async_trait]
+impl Lifecycle for UsageSvc {
+    fn name(&self) -> &'static str {
+        "usage_snapshot"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        let Some((path, interval)) = &self.snapshot else {
+            return Ok(());
+        };
+        let save = || async {
+            if let Err(err) = self.save() {
+                tracing::error!("Failed to write the usage snapshot {:?}: {}", path, err);
+            }
+        };
+        run_every(*interval, stop, save).await;
+        // The usage since the last snapshot is written on shutdown.
+        save().await;
+        Ok(())
+    }
+
+    fn health(&self) -> bool {
+        self.persisted.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::DaBackend, types::dispatch::DispatchFee};
+
+    #[test]
+    fn test_usage_is_tracked_per_caller() {
+        let svc = UsageSvc::new(None, vec![]);
+
+        for (caller, bytes) in [("a", 10), ("b", 5), ("a", 20)] {
+            svc.reserve(caller, bytes).unwrap();
+            svc.record_dispatch(caller, bytes, None);
+        }
+        let reservation = svc.reserve("b", 100).unwrap();
+        svc.release(reservation);
+
+        let usage = svc.usage();
+        assert_eq!(usage.len(), 2);
+        assert_eq!((usage[0].requests, usage[0].bytes), (2, 30));
+        assert_eq!((usage[1].requests, usage[1].bytes), (1, 5));
+        assert_eq!(usage[1].month_bytes, 5);
+    }
+
+    #[test]
+    fn test_failures_and_fees_are_tracked_per_caller() {
+        let svc = UsageSvc::new(None, vec![]);
+        let receipt = DispatchReceipt {
+            height: Some(1),
+            commitment: String::new(),
+            namespace: None,
+            size: 10,
+            backend: DaBackend::Celestia,
+            submitted_at: Utc::now(),
+            fee: Some(DispatchFee {
+                tx_hash: String::new(),
+                gas_wanted: 200,
+                gas_used: 100,
+                amount: Some(25),
+                denom: Some("utia".to_string()),
+            }),
+            chunks: vec![],
+        };
+
+        svc.reserve("a", 10).unwrap();
+        svc.record_dispatch("a", 10, Some(&receipt));
+        svc.reserve("a", 10).unwrap();
+        svc.record_dispatch("a", 10, Some(&receipt));
+        let reservation = svc.reserve("a", 10).unwrap();
+        svc.release(reservation);
+        svc.record_failure("a");
+
+        let usage = svc.caller_usage("a");
+        assert_eq!((usage.requests, usage.failures), (2, 1));
+        assert_eq!((usage.month_bytes, usage.gas_used), (20, 200));
+        assert_eq!(usage.fees.get("utia"), Some(&50));
+
+        // A tenant without dispatches has an empty usage.
+        assert_eq!(svc.caller_usage("b").requests, 0);
+    }
+
+    #[test]
+    fn test_monthly_cap_is_enforced_and_reset() {
+        let svc = UsageSvc::new(Some(100), vec![("c".to_string(), 10)]);
+
+        svc.reserve_in_month("a", 60, "2025-01").unwrap();
+        let err = svc.reserve_in_month("a", 50, "2025-01").unwrap_err();
+        assert_eq!((err.used, err.requested, err.cap), (60, 50, 100));
+
+        // Other callers have their own cap.
+        svc.reserve_in_month("b", 100, "2025-01").unwrap();
+
+        // Callers can have their own cap.
+        assert!(svc.reserve_in_month("c", 20, "2025-01").is_err());
+
+        // The cap is reset on a new month.
+        svc.reserve_in_month("a", 100, "2025-02").unwrap();
+    }
+
+    #[test]
+    fn test_reservations_of_a_past_month_are_not_released() {
+        let svc = UsageSvc::new(Some(100), vec![]);
+
+        let reservation = svc.reserve_in_month("a", 60, "2025-01").unwrap();
+        svc.reserve_in_month("a", 80, "2025-02").unwrap();
+        // The dispatch reserved in January fails in February.
+        svc.release(reservation);
+        let err = svc.reserve_in_month("a", 30, "2025-02").unwrap_err();
+        assert_eq!((err.used, err.requested), (80, 30));
+    }
+
+    #[test]
+    fn test_usage_is_persisted() {
+        let path = std::env::temp_dir().join(format!("via-usage-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let interval = Duration::from_secs(60);
+
+        let svc = UsageSvc::new(Some(100), vec![])
+            .with_snapshot(path.clone(), interval)
+            .unwrap();
+        svc.reserve("a", 60).unwrap();
+        svc.record_dispatch("a", 60, None);
+        svc.save().unwrap();
+
+        // The cap follows the configuration of the new instance.
+        let reloaded = UsageSvc::new(Some(200), vec![])
+            .with_snapshot(path.clone(), interval)
+            .unwrap();
+        let usage = reloaded.caller_usage("a");
+        assert_eq!((usage.requests, usage.month_bytes), (1, 60));
+        assert_eq!(usage.monthly_byte_cap, Some(200));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::{
+    clients::da_clients::types::{CallContext, DAError},
+    services::{
+        attestation::AttestationSvc,
+        da::DaSvc,
+        index::IndexSvc,
+        lifecycle::{Lifecycle, StopSignal, run_every},
+        metrics::VERIFICATION_METRICS,
+    },
+    types::verification::VerificationReport,
+};
+
+/// Periodically re-fetches a sample of the dispatched blobs from the DA layer, to get continuous
+/// evidence that the published data remains available.
+#[derive(Debug, Clone)]
+pub struct VerificationSvc {
+    da_svc: Arc<DaSvc>,
+    index: Arc<IndexSvc>,
+    sample_size: usize,
+    interval: Option<Duration>,
+    last_report: Arc<RwLock<Option<VerificationReport>>>,
+}
+
+impl VerificationSvc {
+    /// Creates the service, the blobs are only re-verified on demand without an `interval`.
+    pub fn new(
+        da_svc: Arc<DaSvc>,
+        index: Arc<IndexSvc>,
+        sample_size: usize,
+        interval: Option<Duration>,
+    ) -> Self {
+        Self {
+            da_svc,
+            index,
+            sample_size,
+            interval,
+            last_report: Arc::default(),
+        }
+    }
+
+    /// Re-fetches a sample of the indexed blobs and checks their payload hash.
+    pub async fn run(&self) -> VerificationReport {
+        let mut report = VerificationReport::default();
+
+        for entry in self.index.sample(self.sample_size) {
+            report.checked += 1;
+            VERIFICATION_METRICS.checked_blobs.inc();
+
+            match self
+                .da_svc
+                .get_inclusion_data(&CallContext::background(), &entry.caller, &entry.blob_id)
+                .await
+            {
+                Ok(Some(data))
+                    if hex::encode(AttestationSvc::payload_hash(&data.data))
+                        == entry.payload_hash => {}
+                Ok(Some(_)) => {
+                    tracing::error!(
+                        "Blob {} of batch {} doesn't match the dispatched payload",
+                        entry.blob_id,
+                        entry.batch_number
+                    );
+                    VERIFICATION_METRICS.mismatched_blobs.inc();
+                    report.mismatched.push(entry.blob_id);
+                }
+                Ok(None) => {
+                    tracing::error!(
+                        "Blob {} of batch {} is no longer retrievable",
+                        entry.blob_id,
+                        entry.batch_number
+                    );
+                    VERIFICATION_METRICS.unavailable_blobs.inc();
+                    report.unavailable.push(entry.blob_id);
+                }
+                Err(DAError::Pruned { .. }) => {
+                    tracing::debug!(
+                        "Blob {} of batch {} is pruned by the DA node",
+                        entry.blob_id,
+                        entry.batch_number
+                    );
+                    report.pruned.push(entry.blob_id);
+                }
+                Err(err) => {
+                    tracing::error!(
+                        "Blob {} of batch {} could not be retrieved: {}",
+                        entry.blob_id,
+                        entry.batch_number,
+                        err
+                    );
+                    VERIFICATION_METRICS.unavailable_blobs.inc();
+                    report.unavailable.push(entry.blob_id);
+                }
+            }
+        }
+
+        VERIFICATION_METRICS
+            .last_run_failures
+            .set(report.unavailable.len() + report.mismatched.len());
+        report.finished_at = Some(Utc::now());
+        *self.last_report.write().unwrap() = Some(report.clone());
+
+        report
+    }
+
+    /// Returns the report of the last run, if any.
+    pub fn last_report(&self) -> Option<VerificationReport> {
+        self.last_report.read().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Lifecycle for VerificationSvc {
+    fn name(&self) -> &'static str {
+        "verification"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        if let Some(interval) = self.interval {
+            run_every(interval, stop, || async {
+                self.run().await;
+            })
+            .await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        clients::da_clients::in_memory::InMemoryClient,
+        types::{dispatch::DispatchPriority, index::IndexEntry},
+    };
+
+    #[tokio::test]
+    async fn test_unretrievable_blobs_are_reported() {
+        let da_svc = Arc::new(
+            DaSvc::for_tests(Arc::new(InMemoryClient::new(1024)))
+                .with_tenants(&[("tenant", "tenant")]),
+        );
+        let index = Arc::new(IndexSvc::new(None).unwrap());
+
+        let dispatch = |caller: &'static str, data: &'static [u8]| {
+            let da_svc = da_svc.clone();
+            async move {
+                da_svc
+                    .dispatch_blob(
+                        &CallContext::background(),
+                        caller,
+                        1,
+                        None,
+                        data.to_vec(),
+                        DispatchPriority::Normal,
+                    )
+                    .await
+                    .unwrap()
+                    .blob_id
+            }
+        };
+        let available = dispatch("anonymous", b"a").await;
+        let altered = dispatch("anonymous", b"b").await;
+        // Tenant blobs are read from the tenant namespace.
+        let tenant = dispatch("tenant", b"t").await;
+        for (caller, blob_id, payload) in [
+            ("anonymous", available, b"a"),
+            ("anonymous", altered.clone(), b"c"),
+            ("anonymous", "missing".to_string(), b"d"),
+            ("tenant", tenant, b"t"),
+        ] {
+            index.record(IndexEntry {
+                blob_id,
+                batch_number: 1,
+                caller: caller.to_string(),
+                size: 1,
+                payload_hash: hex::encode(AttestationSvc::payload_hash(payload)),
+                dispatched_at: Utc::now(),
+                redispatch_of: None,
+                receipt: None,
+                confirmations: None,
+                archive: None,
+                metadata: BTreeMap::new(),
+                attestation: None,
+            });
+        }
+
+        let svc = VerificationSvc::new(da_svc, index, 10, None);
+        let report = svc.run().await;
+
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.mismatched, [altered]);
+        assert_eq!(report.unavailable, ["missing"]);
+        assert_eq!(svc.last_report(), Some(report));
+    }
+}
@@ -0,0 +1,81 @@
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{
+    clients::da_clients::lazy::LazyClient,
+    services::{
+        lifecycle::{Lifecycle, StopSignal, stopped},
+        metrics::HEALTH_METRICS,
+    },
+};
+
+/// Delay before the second attempt to create the clients, doubled on every failed attempt.
+const WARMUP_BASE_DELAY: Duration = Duration::from_secs(1);
+
+const WARMUP_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Creates the clients of the DA backends in the background from startup, until they are all
+/// created. The service serves meanwhile, with the DA layer reported as unreachable.
+#[derive(Debug, Default)]
+pub struct WarmupSvc {
+    clients: Mutex<Vec<LazyClient>>,
+}
+
+impl WarmupSvc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a client to create.
+    pub fn add(&self, client: LazyClient) {
+        self.clients.lock().unwrap().push(client);
+    }
+
+    /// Whether all the clients are created.
+    pub fn is_warm(&self) -> bool {
+        let clients = self.clients.lock().unwrap();
+        clients.iter().all(LazyClient::is_initialized)
+    }
+
+    /// Tries to create the clients not created yet, returns whether they are all created.
+    pub async fn warm_up(&self) -> bool {
+        let clients = self.clients.lock().unwrap().clone();
+        let mut warm = true;
+        for client in clients.iter().filter(|client| !client.is_initialized()) {
+            if let Err(err) = client.init().await {
+                tracing::warn!(
+                    "The {} backend is unreachable: {:#}",
+                    client.backend().as_str(),
+                    err
+                );
+                warm = false;
+            }
+        }
+        HEALTH_METRICS.backends_initialized.set(warm as u64);
+        warm
+    }
+}
+
+#[async_trait]
+impl Lifecycle for WarmupSvc {
+    fn name(&self) -> &'static str {
+        "da_warmup"
+    }
+
+    async fn start(&self, mut stop: StopSignal) -> anyhow::Result<()> {
+        let mut delay = WARMUP_BASE_DELAY;
+        while !self.warm_up().await {
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = stopped(&mut stop) => return Ok(()),
+            }
+            delay = (delay * 2).min(WARMUP_MAX_DELAY);
+        }
+        Ok(())
+    }
+
+    fn health(&self) -> bool {
+        self.is_warm()
+    }
+}
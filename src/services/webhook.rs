@@ -0,0 +1,357 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::time::Instant;
+
+use crate::{
+    clients::da_clients::{DataAvailabilityClient, types::CallContext},
+    services::{
+        lifecycle::{Lifecycle, StopSignal, run_every},
+        metrics::WEBHOOK_METRICS,
+    },
+    types::webhook::{WebhookEvent, WebhookEventKind},
+};
+
+/// Header carrying the hex HMAC-SHA256 of the body, keyed by the webhook secret.
+pub const SIGNATURE_HEADER: &str = "X-Via-Signature";
+
+/// Header carrying the event kind.
+pub const EVENT_HEADER: &str = "X-Via-Event";
+
+/// Interval between two checks of the pending confirmations.
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Delay before the first retry of a delivery, doubled on every retry.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns the hex HMAC-SHA256 of the body.
+pub fn sign_payload(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[derive(Debug, Clone)]
+struct PendingConfirmation {
+    blob_id: String,
+    batch_number: u32,
+    urls: Vec<String>,
+}
+
+/// Delivers the dispatch lifecycle events to the configured webhooks and to the webhook of the
+/// request, if any.
+///
+/// Events are POSTed as JSON signed with the shared secret, webhooks are disabled when no secret
+/// is configured. Deliveries are retried with an exponential backoff and run in the background so
+/// they never delay the dispatches.
+#[derive(Debug, Clone)]
+pub struct WebhookSvc {
+    http: reqwest::Client,
+    da_client: Arc<dyn DataAvailabilityClient + Send + Sync>,
+    urls: Vec<String>,
+    secret: Option<String>,
+    confirmation_depth: u64,
+    max_attempts: u32,
+    pending: Arc<Mutex<Vec<PendingConfirmation>>>,
+}
+
+impl WebhookSvc {
+    pub fn new(
+        da_client: Arc<dyn DataAvailabilityClient + Send + Sync>,
+        urls: Vec<String>,
+        secret: Option<String>,
+        confirmation_depth: u64,
+        max_attempts: u32,
+    ) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()
+                .expect("Failed to build the webhook HTTP client"),
+            da_client,
+            urls,
+            secret,
+            confirmation_depth,
+            max_attempts: max_attempts.max(1),
+            pending: Arc::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Checks a webhook url passed with a dispatch request.
+    pub fn validate_url(&self, url: &str) -> anyhow::Result<()> {
+        if !self.is_enabled() {
+            anyhow::bail!("Webhooks are not enabled");
+        }
+
+        let url = reqwest::Url::parse(url)?;
+        if !matches!(url.scheme(), "http" | "https") {
+            anyhow::bail!("Webhook url must be http or https");
+        }
+
+        Ok(())
+    }
+
+    fn targets(&self, url: Option<String>) -> Vec<String> {
+        if !self.is_enabled() {
+            return vec![];
+        }
+
+        let mut urls = self.urls.clone();
+        if let Some(url) = url
+            && !urls.contains(&url)
+        {
+            urls.push(url);
+        }
+        urls
+    }
+
+    /// Notifies a submitted blob and waits for its confirmation.
+    pub fn on_submitted(&self, blob_id: &str, batch_number: u32, url: Option<String>) {
+        let urls = self.targets(url);
+        if urls.is_empty() {
+            return;
+        }
+
+        self.deliver_all(
+            &urls,
+            WebhookEvent {
+                event: WebhookEventKind::Submitted,
+                batch_number,
+                blob_id: Some(blob_id.to_string()),
+                confirmations: None,
+                error: None,
+                timestamp: Utc::now(),
+            },
+        );
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.push(PendingConfirmation {
+            blob_id: blob_id.to_string(),
+            batch_number,
+            urls,
+        });
+        WEBHOOK_METRICS.pending_confirmations.set(pending.len());
+    }
+
+    /// Notifies a failed dispatch.
+    pub fn on_failed(&self, batch_number: u32, error: &str, url: Option<String>) {
+        let urls = self.targets(url);
+        if urls.is_empty() {
+            return;
+        }
+
+        self.deliver_all(
+            &urls,
+            WebhookEvent {
+                event: WebhookEventKind::Failed,
+                batch_number,
+                blob_id: None,
+                confirmations: None,
+                error: Some(error.to_string()),
+                timestamp: Utc::now(),
+            },
+        );
+    }
+
+    async fn check_confirmations(&self) {
+        let pending = self.pending.lock().unwrap().clone();
+
+        for blob in pending {
+            let confirmations = match self
+                .da_client
+                .confirmations(&CallContext::background(), &blob.blob_id)
+                .await
+            {
+                Ok(Some(confirmations)) if confirmations >= self.confirmation_depth => {
+                    confirmations
+                }
+                Ok(_) => continue,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to get the confirmations of {}: {}",
+                        blob.blob_id,
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            self.deliver_all(
+                &blob.urls,
+                WebhookEvent {
+                    event: WebhookEventKind::Confirmed,
+                    batch_number: blob.batch_number,
+                    blob_id: Some(blob.blob_id.clone()),
+                    confirmations: Some(confirmations),
+                    error: None,
+                    timestamp: Utc::now(),
+                },
+            );
+
+            let mut pending = self.pending.lock().unwrap();
+            pending.retain(|candidate| candidate.blob_id != blob.blob_id);
+            WEBHOOK_METRICS.pending_confirmations.set(pending.len());
+        }
+    }
+
+    fn deliver_all(&self, urls: &[String], event: WebhookEvent) {
+        let Some(secret) = &self.secret else {
+            return;
+        };
+        let body = serde_json::to_vec(&event).expect("Failed to serialize the webhook event");
+        let signature = sign_payload(secret.as_bytes(), &body);
+
+        for url in urls {
+            let svc = self.clone();
+            let url = url.clone();
+            let body = body.clone();
+            let signature = signature.clone();
+            tokio::spawn(async move {
+                svc.deliver(&url, event.event, body, &signature).await;
+            });
+        }
+    }
+
+    async fn deliver(&self, url: &str, event: WebhookEventKind, body: Vec<u8>, signature: &str) {
+        let start = Instant::now();
+
+        for attempt in 0..self.max_attempts {
+            if attempt > 0 {
+                tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1)).await;
+            }
+            WEBHOOK_METRICS.delivery_attempts.inc();
+
+            let result = self
+                .http
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, format!("sha256={}", signature))
+                .header(EVENT_HEADER, event.as_str())
+                .body(body.clone())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match result {
+                Ok(_) => {
+                    WEBHOOK_METRICS.delivered_events[&event.as_str()].inc();
+                    WEBHOOK_METRICS.delivery_latency.observe(start.elapsed());
+                    return;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Webhook {} delivery to {} failed (attempt {}/{}): {}",
+                        event.as_str(),
+                        url,
+                        attempt + 1,
+                        self.max_attempts,
+                        err
+                    );
+                }
+            }
+        }
+
+        WEBHOOK_METRICS.failed_events[&event.as_str()].inc();
+        tracing::error!("Dropped the webhook {} event for {}", event.as_str(), url);
+    }
+}
+
+/// Periodically notifies the submitted blobs that reached the confirmation depth.
+#[async_trait]
+impl Lifecycle for WebhookSvc {
+    fn name(&self) -> &'static str {
+        "webhook_confirmations"
+    }
+
+    async fn start(&self, stop: StopSignal) -> anyhow::Result<()> {
+        run_every(CONFIRMATION_POLL_INTERVAL, stop, || {
+            self.check_confirmations()
+        })
+        .await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Json, Router, extract::State, http::HeaderMap, routing::post};
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::clients::da_clients::in_memory::InMemoryClient;
+
+    const SECRET: &str = "secret";
+
+    async fn start_receiver() -> (String, mpsc::UnboundedReceiver<(WebhookEvent, String)>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app = Router::new()
+            .route(
+                "/",
+                post(
+                    |State(sender): State<mpsc::UnboundedSender<(WebhookEvent, String)>>,
+                     headers: HeaderMap,
+                     Json(event): Json<WebhookEvent>| async move {
+                        let signature = headers[SIGNATURE_HEADER].to_str().unwrap().to_string();
+                        sender.send((event, signature)).unwrap();
+                    },
+                ),
+            )
+            .with_state(sender);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_events_are_delivered_signed() {
+        let (url, mut receiver) = start_receiver().await;
+        let da_client = Arc::new(InMemoryClient::new(1024));
+        let svc = WebhookSvc::new(da_client.clone(), vec![], Some(SECRET.to_string()), 1, 1);
+
+        let blob_id = da_client
+            .dispatch_blob(&CallContext::background(), 1, vec![1])
+            .await
+            .unwrap()
+            .blob_id;
+        svc.on_submitted(&blob_id, 1, Some(url));
+
+        let (event, signature) = receiver.recv().await.unwrap();
+        assert_eq!(event.event, WebhookEventKind::Submitted);
+        assert_eq!(event.blob_id.as_deref(), Some(blob_id.as_str()));
+        let body = serde_json::to_vec(&event).unwrap();
+        assert_eq!(
+            signature,
+            format!("sha256={}", sign_payload(SECRET.as_bytes(), &body))
+        );
+
+        svc.check_confirmations().await;
+        let (event, _) = receiver.recv().await.unwrap();
+        assert_eq!(event.event, WebhookEventKind::Confirmed);
+        assert!(svc.pending.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_webhooks_require_a_secret() {
+        let da_client = Arc::new(InMemoryClient::new(1024));
+        let svc = WebhookSvc::new(da_client.clone(), vec![], None, 1, 1);
+        assert!(svc.validate_url("http://localhost/hook").is_err());
+
+        let svc = WebhookSvc::new(da_client, vec![], Some(SECRET.to_string()), 1, 1);
+        assert!(svc.validate_url("http://localhost/hook").is_ok());
+        assert!(svc.validate_url("ftp://localhost/hook").is_err());
+    }
+}
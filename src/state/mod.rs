@@ -1,18 +1,74 @@
 use std::sync::Arc;
 
+use anyhow::Context;
 use axum::{
-    Router,
+    Router, middleware,
     routing::{get, post},
 };
 
+use tower_http::{compression::CompressionLayer, decompression::RequestDecompressionLayer};
+
 use crate::{
-    clients::da_clients::make_da_client,
-    config::Config,
+    clients::{
+        da_clients::{
+            DataAvailabilityClient, chaos::ChaosClient, make_shadow_da_client,
+            make_switchable_da_client, switchable::SwitchableClient,
+        },
+        data_root_sources::make_data_root_source,
+        key_providers::make_key_provider,
+        lock_stores::make_lock_store,
+    },
+    config::{Config, PayloadTransform},
     handlers::{
-        da::{dispatch_handler, inclusion_handler},
-        health_check::health_check_handler,
+        admin::{
+            backend_handler, chaos_handler, export_handler, gaps_handler, import_handler,
+            log_level_handler, metrics_handler, pause_handler, resume_handler, selftest_handler,
+            set_chaos_handler, set_log_level_handler, stats_handler, switch_backend_handler,
+            tenant_metrics_handler, usage_handler, verification_handler,
+        },
+        attestation::{attestation_handler, keys_handler},
+        da::{
+            batch_footprint_handler, chunk_handler, chunk_manifest_handler, commit_handler,
+            dispatch_handler, inclusion_by_commitment_handler, inclusion_handler, prepare_handler,
+            proof_handler, raw_blob_handler, redispatch_handler, status_handler, verify_handler,
+            verify_onchain_handler,
+        },
+        health_check::{health_check_handler, readiness_handler},
+    },
+    middlewares::{
+        auth::{ApiKeys, api_key_middleware},
+        call_context::call_context_middleware,
+        ip_allowlist::{IpAllowlist, ip_allowlist_middleware},
+        metrics::http_metrics_middleware,
+    },
+    services::{
+        archive::{ArchiveStore, ArchiveSvc},
+        attestation::AttestationSvc,
+        batch_lock::BatchLockSvc,
+        confirmation::ConfirmationSvc,
+        da::DaSvc,
+        fee_budget::FeeBudgetSvc,
+        health_check::HealthCheckSvc,
+        import::ImportSvc,
+        index::{INDEX_FOLLOW_INTERVAL, IndexFollower, IndexSvc},
+        lifecycle::Supervisor,
+        logging::LoggingSvc,
+        maintenance::MaintenanceSvc,
+        metrics::MetricsExporterSvc,
+        onchain::OnchainVerificationSvc,
+        params_refresh::ParamsRefreshSvc,
+        payload_cache::PayloadCacheSvc,
+        proof_cache::ProofCacheSvc,
+        selftest::SelftestSvc,
+        sequence::SequenceSvc,
+        shadow::ShadowSvc,
+        staging::StagingSvc,
+        transform::TransformSvc,
+        usage::UsageSvc,
+        verification::VerificationSvc,
+        warmup::WarmupSvc,
+        webhook::WebhookSvc,
     },
-    services::{da::DaSvc, health_check::HealthCheckSvc},
 };
 
 #[derive(Clone)]
@@ -20,28 +76,379 @@ pub struct AppState {
     pub config: Config,
     pub health_check: HealthCheckSvc,
     pub da_svc: Arc<DaSvc>,
+    pub da_backends: Arc<SwitchableClient>,
+    /// The faults injected into the DA calls, when the chaos mode is enabled.
+    pub chaos: Option<Arc<ChaosClient>>,
+    pub attestation_svc: Arc<AttestationSvc>,
+    pub usage_svc: Arc<UsageSvc>,
+    pub fee_budget: Arc<FeeBudgetSvc>,
+    pub staging: Arc<StagingSvc>,
+    pub batch_locks: Arc<BatchLockSvc>,
+    pub maintenance: Arc<MaintenanceSvc>,
+    pub selftest: Arc<SelftestSvc>,
+    pub webhooks: Arc<WebhookSvc>,
+    pub index: Arc<IndexSvc>,
+    pub sequence: Arc<SequenceSvc>,
+    pub import: Arc<ImportSvc>,
+    pub payload_cache: Arc<PayloadCacheSvc>,
+    pub proofs: Arc<ProofCacheSvc>,
+    /// The verification of the blobs against the data roots committed on L1, when a source is
+    /// configured.
+    pub onchain: Option<Arc<OnchainVerificationSvc>>,
+    pub verification: Arc<VerificationSvc>,
+    pub confirmations: Arc<ConfirmationSvc>,
+    pub supervisor: Arc<Supervisor>,
+    pub logging: Arc<LoggingSvc>,
+    pub api_keys: Arc<ApiKeys>,
+    pub da_allowlist: Arc<IpAllowlist>,
+    pub admin_allowlist: Arc<IpAllowlist>,
 }
 
 impl AppState {
-    pub async fn new(config: Config) -> anyhow::Result<Self> {
-        let da_client = make_da_client(config.clone()).await?;
+    pub async fn new(config: Config, logging: Arc<LoggingSvc>) -> anyhow::Result<Self> {
+        // The clients are created by the warm-up, the service starts while the DA node is down.
+        let warmup = Arc::new(WarmupSvc::new());
+        let params_refresh = Arc::new(ParamsRefreshSvc::new(config.da_params_refresh_interval));
+        let da_backends = Arc::new(make_switchable_da_client(
+            &config,
+            &warmup,
+            &params_refresh,
+        )?);
+        let chaos = config.chaos_enabled.then(|| {
+            tracing::warn!("Chaos mode enabled, faults can be injected into the DA calls");
+            Arc::new(ChaosClient::new(da_backends.clone()))
+        });
+        let da_client: Arc<dyn DataAvailabilityClient + Send + Sync> = match &chaos {
+            Some(chaos) => chaos.clone(),
+            None => da_backends.clone(),
+        };
+
+        let key_provider = make_key_provider(&config)?;
+        if config.read_only {
+            tracing::info!("Read-only mode, the dispatch routes are disabled");
+            if key_provider.can_sign() {
+                tracing::warn!("The signing key is not used in read-only mode");
+            }
+        } else if key_provider.can_sign() {
+            tracing::info!("Dispatch attestations enabled with {:?}", key_provider);
+        }
+
+        // The background components are stopped in the reverse order, the metrics last.
+        let supervisor = Arc::new(Supervisor::new());
+        supervisor.start(Arc::new(MetricsExporterSvc::new(
+            config.metrics_address.parse()?,
+        )));
+        supervisor.start(warmup.clone());
+        if config.da_params_refresh_interval.is_some() {
+            supervisor.start(params_refresh.clone());
+        }
 
         // Services
-        let health_check = HealthCheckSvc::new(da_client.clone());
-        let da_svc = Arc::new(DaSvc::new(da_client));
+        let maintenance = Arc::new(MaintenanceSvc::new());
+        let selftest = Arc::new(SelftestSvc::new(da_client.clone()));
+        let transform_svc = Arc::new(TransformSvc::new(
+            config.payload_transforms.clone(),
+            key_provider.clone(),
+            config.payload_compression_level,
+            config.payload_chunk_size,
+        ));
+        if config
+            .payload_transforms
+            .contains(&PayloadTransform::Encrypt)
+        {
+            key_provider
+                .encryption_key()
+                .await
+                .context("The encrypt transform requires an encryption key")?;
+        }
+        if !config.payload_transforms.is_empty() {
+            tracing::info!("Payload transforms: {:?}", config.payload_transforms);
+        }
+        let archive = Arc::new(ArchiveStore::new(config.archive_url.as_deref())?);
+        let shadow_svc = Arc::new(ShadowSvc::new(
+            make_shadow_da_client(&config, &warmup, &params_refresh)?,
+            config.shadow_percent,
+        ));
+        if config.shadow_percent > 0 {
+            tracing::info!(
+                "Shadowing {}% of the dispatches to {:?} in namespace {:?}",
+                config.shadow_percent,
+                config.shadow_backend.unwrap_or(config.da_backend),
+                config.shadow_namespace
+            );
+        }
+        let attestation_svc = Arc::new(AttestationSvc::new(key_provider));
+        let da_svc = Arc::new(
+            DaSvc::new(
+                da_client.clone(),
+                attestation_svc.clone(),
+                transform_svc,
+                archive.clone(),
+                shadow_svc,
+                config.da_max_concurrent_dispatches,
+                &config.tenant_namespaces,
+            )?
+            .with_namespace_routes(&config.namespace_routes)?
+            .with_queue_compaction(
+                config.da_queue_compaction_interval,
+                config.da_queue_max_wait,
+            ),
+        );
+        supervisor.start(Arc::new(da_svc.queue().clone()));
+        let mut usage_svc = UsageSvc::new(
+            config.usage_monthly_byte_cap,
+            config.usage_caller_byte_caps.clone(),
+        );
+        if let Some(path) = &config.usage_path {
+            usage_svc = usage_svc.with_snapshot(path.clone(), config.usage_snapshot_interval)?;
+        }
+        let usage_svc = Arc::new(usage_svc);
+        if config.usage_path.is_some() {
+            supervisor.start(usage_svc.clone());
+        }
+
+        let fee_budget = Arc::new(FeeBudgetSvc::new(
+            config.fee_budget,
+            config.fee_budget_window,
+        ));
+        let staging = Arc::new(StagingSvc::new(config.staging_ttl));
+        let lock_store = make_lock_store(&config)?;
+        if let Some(store) = &lock_store {
+            tracing::info!("Coordinating the batch submissions through {:?}", store);
+        }
+        let batch_locks = Arc::new(BatchLockSvc::new(
+            lock_store,
+            config.batch_lock_ttl,
+            config.batch_lock_retention,
+        ));
+
+        let index = Arc::new(IndexSvc::new(config.index_path.clone())?);
+        let sequence = Arc::new(SequenceSvc::new(&index));
+        if config.read_only && config.index_path.is_some() {
+            supervisor.start(Arc::new(IndexFollower::new(
+                index.clone(),
+                sequence.clone(),
+                INDEX_FOLLOW_INTERVAL,
+            )));
+        }
+        let import = Arc::new(ImportSvc::new(
+            da_svc.clone(),
+            index.clone(),
+            sequence.clone(),
+        ));
+        let payload_cache = Arc::new(PayloadCacheSvc::new(config.payload_cache_dir.clone())?);
+        let health_check = HealthCheckSvc::new(
+            da_client.clone(),
+            da_svc.clone(),
+            index.clone(),
+            maintenance.clone(),
+            selftest.clone(),
+            supervisor.clone(),
+        );
+        supervisor.start(Arc::new(health_check.clone()));
+        let proofs = Arc::new(ProofCacheSvc::new(
+            da_svc.clone(),
+            config.proof_cache_dir.clone(),
+        )?);
+        let onchain = make_data_root_source(&config).map(|source| {
+            tracing::info!(
+                "Verifying the blobs against the data roots on L1 from {:?}",
+                source
+            );
+            Arc::new(OnchainVerificationSvc::new(proofs.clone(), source))
+        });
+        let verification = Arc::new(VerificationSvc::new(
+            da_svc.clone(),
+            index.clone(),
+            config.verification_sample_size,
+            config.verification_interval,
+        ));
+        if config.verification_interval.is_some() {
+            supervisor.start(verification.clone());
+        }
+        let confirmations = Arc::new(ConfirmationSvc::new(
+            da_backends.clone(),
+            index.clone(),
+            config.confirmation_depth,
+            config.confirmation_poll_interval,
+        ));
+        // The read-only replicas don't write the index.
+        if config.confirmation_poll_interval.is_some() && !config.read_only {
+            supervisor.start(confirmations.clone());
+        }
+        if archive.is_enabled() && !config.read_only {
+            tracing::info!("Mirroring the confirmed blobs into the archive");
+            supervisor.start(Arc::new(ArchiveSvc::new(
+                da_svc.clone(),
+                index.clone(),
+                config.confirmation_depth,
+                config.archive_interval,
+            )));
+        }
+        let webhooks = Arc::new(WebhookSvc::new(
+            da_client,
+            config.webhook_urls.clone(),
+            config.webhook_secret.clone(),
+            config.webhook_confirmation_depth,
+            config.webhook_max_attempts,
+        ));
+        if webhooks.is_enabled() {
+            supervisor.start(webhooks.clone());
+        } else if !config.webhook_urls.is_empty() {
+            tracing::warn!("VIA_WEBHOOK_SECRET is not set, the webhooks are disabled");
+        }
+
+        if config.selftest_on_startup {
+            supervisor.start(selftest.clone());
+        }
+
+        // Middlewares
+        let api_keys = Arc::new(
+            ApiKeys::new(config.api_keys.clone()).with_jwt_secret(config.jwt_secret.clone()),
+        );
+        if !api_keys.is_enabled() {
+            tracing::warn!("No API keys configured, the /da routes are not authenticated");
+        }
+        let da_allowlist = Arc::new(IpAllowlist::new(
+            config.da_allowed_cidrs.clone(),
+            config.trusted_proxies.clone(),
+        ));
+        let admin_allowlist = Arc::new(IpAllowlist::strict(
+            config.admin_allowed_cidrs.clone(),
+            config.trusted_proxies.clone(),
+        ));
 
         Ok(Self {
             config,
             da_svc,
+            da_backends,
+            chaos,
+            attestation_svc,
+            usage_svc,
+            fee_budget,
+            staging,
+            batch_locks,
+            maintenance,
+            selftest,
+            webhooks,
+            index,
+            sequence,
+            import,
+            payload_cache,
+            proofs,
+            onchain,
+            verification,
+            confirmations,
+            supervisor,
+            logging,
             health_check,
+            api_keys,
+            da_allowlist,
+            admin_allowlist,
         })
     }
 
     pub fn into_router(self) -> Router {
-        Router::new()
-            .route("/da/dispatch", post(dispatch_handler))
+        let mut da_router = Router::new()
+            .route("/da/inclusion", get(inclusion_by_commitment_handler))
             .route("/da/inclusion/:blob_id", get(inclusion_handler))
+            .route("/da/inclusion/:blob_id/chunks", get(chunk_manifest_handler))
+            .route("/da/status/:blob_id", get(status_handler))
+            .route("/da/proof/:blob_id", get(proof_handler))
+            .route("/da/batch/:batch_number", get(batch_footprint_handler))
+            .route("/da/verify", post(verify_handler));
+        if self.onchain.is_some() {
+            da_router = da_router.route("/da/verify_onchain/:blob_id", get(verify_onchain_handler));
+        }
+        if !self.config.read_only {
+            da_router = da_router
+                .route("/da/dispatch", post(dispatch_handler))
+                .route("/da/prepare", post(prepare_handler))
+                .route("/da/commit/:staging_id", post(commit_handler))
+                .route("/da/redispatch/:blob_id", post(redispatch_handler));
+        }
+        let da_router = da_router
+            // Hex pubdata compresses well, the responses are compressed per the Accept-Encoding
+            // and the request bodies can be sent compressed with a Content-Encoding.
+            .layer(CompressionLayer::new())
+            .layer(RequestDecompressionLayer::new())
+            .route("/da/attestation/:blob_id", get(attestation_handler))
+            // Not compressed, so the Content-Length and the byte ranges refer to the payload.
+            .route("/da/blob/:blob_id/raw", get(raw_blob_handler))
+            .route("/da/inclusion/:blob_id/chunks/:n", get(chunk_handler))
+            .layer(middleware::from_fn_with_state(
+                self.config.request_timeout,
+                call_context_middleware,
+            ))
+            .layer(middleware::from_fn_with_state(
+                self.api_keys.clone(),
+                api_key_middleware,
+            ))
+            .layer(middleware::from_fn_with_state(
+                self.da_allowlist.clone(),
+                ip_allowlist_middleware,
+            ));
+
+        let mut admin_router = Router::new()
+            .route("/admin/usage", get(usage_handler))
+            .route(
+                "/admin/backend",
+                get(backend_handler).post(switch_backend_handler),
+            )
+            .route(
+                "/admin/log_level",
+                get(log_level_handler).put(set_log_level_handler),
+            )
+            .route("/admin/verification", get(verification_handler))
+            .route("/admin/stats", get(stats_handler))
+            .route("/admin/export", get(export_handler))
+            .route("/admin/gaps", get(gaps_handler))
+            .route("/admin/metrics", get(metrics_handler));
+        if self.config.chaos_enabled {
+            admin_router =
+                admin_router.route("/admin/chaos", get(chaos_handler).put(set_chaos_handler));
+        }
+        if !self.config.read_only {
+            admin_router = admin_router
+                .route("/admin/import", post(import_handler))
+                .route("/admin/pause", post(pause_handler))
+                .route("/admin/resume", post(resume_handler))
+                .route("/admin/selftest", post(selftest_handler));
+        }
+        let admin_router = admin_router
+            .layer(middleware::from_fn_with_state(
+                self.config.request_timeout,
+                call_context_middleware,
+            ))
+            .layer(middleware::from_fn_with_state(
+                self.admin_allowlist.clone(),
+                ip_allowlist_middleware,
+            ));
+
+        // Served to the tenants outside the admin allowlist, each one authenticated by its API key.
+        let mut tenant_router = Router::new();
+        if !self.config.tenant_namespaces.is_empty() {
+            tenant_router = tenant_router
+                .route("/admin/usage/:tenant/metrics", get(tenant_metrics_handler))
+                .layer(middleware::from_fn_with_state(
+                    self.api_keys.clone(),
+                    api_key_middleware,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    self.da_allowlist.clone(),
+                    ip_allowlist_middleware,
+                ));
+        }
+
+        Router::new()
+            .merge(da_router)
+            .merge(admin_router)
+            .merge(tenant_router)
+            // Public, the verifiers of the attestations don't hold an API key.
+            .route("/da/keys", get(keys_handler))
             .route("/health", get(health_check_handler))
+            .route("/health/ready", get(readiness_handler))
+            .layer(middleware::from_fn(http_metrics_middleware))
             .with_state(self.into())
     }
 }
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{DaBackend, RetryPolicy};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchBackendRequest {
+    pub backend: DaBackend,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendResponse {
+    /// The active DA backend.
+    pub active: DaBackend,
+    /// The backend that was active before the switch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<DaBackend>,
+    /// The backends that can be switched to.
+    pub available: Vec<DaBackend>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLevelRequest {
+    /// The log level directives, in the `RUST_LOG` syntax.
+    pub level: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLevelResponse {
+    /// The current log level directives.
+    pub level: String,
+    /// The log level directives before the change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+/// `ChaosError` is the error of the DA calls failed by the chaos settings.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChaosError {
+    /// A connection error, retried by the callers.
+    #[default]
+    Connection,
+    RateLimited,
+    Internal,
+}
+
+/// `ChaosSettings` are the faults injected into the DA calls, for the staging game days.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ChaosSettings {
+    /// The delay added to the DA calls.
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// The share of the DA calls failing, from 0 to 1.
+    #[serde(default)]
+    pub error_rate: f64,
+    #[serde(default)]
+    pub error: ChaosError,
+}
+
+impl ChaosSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.error_rate) {
+            return Err("error_rate must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChaosResponse {
+    /// The faults injected from now on.
+    pub settings: ChaosSettings,
+    /// The faults injected before the change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<ChaosSettings>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendStats {
+    pub backend: DaBackend,
+    /// The effective retry policy of the backend.
+    pub retry_policy: RetryPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsResponse {
+    /// The active DA backend.
+    pub active_backend: DaBackend,
+    pub backends: Vec<BackendStats>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Parquet,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsQuery {
+    /// Only the metrics of this caller.
+    pub tenant: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportQuery {
+    /// Only the blobs dispatched at or after this time (RFC 3339).
+    pub from: Option<DateTime<Utc>>,
+    /// Only the blobs dispatched before this time (RFC 3339).
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::AttestationKeyType;
+
+/// Domain separator prepended to every attestation signing message.
+const ATTESTATION_DOMAIN: &[u8] = b"via-core-ext/dispatch-attestation/v1";
+
+/// `Attestation` is a signed statement that this gateway published a batch to the DA layer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Attestation {
+    /// The blob_id returned by the DA layer.
+    pub blob_id: String,
+    /// The batch number the blob was dispatched for.
+    pub batch_number: u32,
+    /// The SHA-256 hash of the dispatched payload (hex).
+    pub payload_hash: String,
+    /// The unix timestamp (seconds) of the dispatch.
+    pub timestamp: u64,
+    /// The id of the signing key, stays stable for a key across rotations of the active key.
+    pub key_id: String,
+    /// The type of the signing key.
+    pub key_type: AttestationKeyType,
+    /// The public key of the signer (hex).
+    pub public_key: String,
+    /// The signature over `signing_message` (hex).
+    pub signature: String,
+}
+
+/// `Jwk` is a public attestation key in the JSON Web Key format.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Jwk {
+    pub kid: String,
+    /// "OKP" for ed25519, "EC" for secp256k1.
+    pub kty: String,
+    pub crv: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    /// The base64url encoded public key, or its x coordinate for secp256k1.
+    pub x: String,
+    /// The base64url encoded y coordinate of a secp256k1 public key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+    /// The unix timestamp (seconds) the key signs from, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_from: Option<u64>,
+    /// Whether the key signs the new attestations.
+    pub active: bool,
+}
+
+/// `JwkSet` is the response of `GET /da/keys`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl Attestation {
+    /// Returns the bytes covered by the signature:
+    /// `domain | len(blob_id) (4 bytes) | blob_id | batch_number (4 bytes) | payload_hash (32 bytes) | timestamp (8 bytes)`,
+    /// integers are big-endian.
+    pub fn signing_message(
+        blob_id: &str,
+        batch_number: u32,
+        payload_hash: &[u8; 32],
+        timestamp: u64,
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(ATTESTATION_DOMAIN.len() + blob_id.len() + 48);
+        message.extend_from_slice(ATTESTATION_DOMAIN);
+        message.extend_from_slice(&(blob_id.len() as u32).to_be_bytes());
+        message.extend_from_slice(blob_id.as_bytes());
+        message.extend_from_slice(&batch_number.to_be_bytes());
+        message.extend_from_slice(payload_hash);
+        message.extend_from_slice(&timestamp.to_be_bytes());
+        message
+    }
+}
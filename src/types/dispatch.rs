@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{clients::da_clients::types::DAError, config::DaBackend};
+
+/// The priority of a dispatch, when the dispatch queue is saturated the higher priorities are
+/// served first.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum DispatchPriority {
+    /// e.g. archival mirrors
+    Low,
+    #[default]
+    Normal,
+    /// e.g. commit batches
+    High,
+}
+
+/// `DispatchReceipt` is the metadata of a submitted blob, so callers don't have to parse the
+/// blob_id.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DispatchReceipt {
+    /// The DA height the blob was included at, for the backends with a chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// The commitment of the blob (hex).
+    pub commitment: String,
+    /// The namespace of the blob (hex), if the backend has namespaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// The blob size in bytes.
+    pub size: u64,
+    pub backend: DaBackend,
+    pub submitted_at: DateTime<Utc>,
+    /// What the DA layer charged for the submission, for the backends with fees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<DispatchFee>,
+    /// The chunks of a chunked blob in order, the receipt being the one of their manifest.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<ChunkReceipt>,
+}
+
+impl DispatchReceipt {
+    /// The fees of the submissions of the blob, the manifest and the chunks of a chunked blob.
+    pub fn fees(&self) -> impl Iterator<Item = &DispatchFee> {
+        self.fee
+            .iter()
+            .chain(self.chunks.iter().filter_map(|chunk| chunk.fee.as_ref()))
+    }
+}
+
+/// `ChunkReceipt` is the submission metadata of a chunk of a chunked blob.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChunkReceipt {
+    pub blob_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    pub commitment: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<DispatchFee>,
+}
+
+impl ChunkReceipt {
+    pub fn new(blob_id: String, receipt: DispatchReceipt) -> Self {
+        Self {
+            blob_id,
+            height: receipt.height,
+            commitment: receipt.commitment,
+            size: receipt.size,
+            fee: receipt.fee,
+        }
+    }
+}
+
+/// `PartialDispatchReport` describes the dispatch of a chunked blob that failed after some of its
+/// chunks were dispatched. The dispatch is resumed with the failed chunks only, the succeeded ones
+/// are not paid for again.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PartialDispatchReport {
+    pub batch_number: u32,
+    /// The number of chunks of the blob and their size in bytes, the last one can be smaller.
+    pub chunks: usize,
+    pub chunk_size: usize,
+    pub succeeded: Vec<DispatchedChunk>,
+    pub failed: Vec<FailedChunk>,
+    /// Whether all the failures can be retried.
+    pub retriable: bool,
+    /// The staged dispatch resuming from the succeeded chunks, committed with
+    /// `POST /da/commit/:staging_id` until `expires_at`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub staging_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// `DispatchedChunk` is a chunk dispatched before its blob failed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DispatchedChunk {
+    pub index: usize,
+    pub blob_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<DispatchReceipt>,
+}
+
+/// `FailedChunk` is a chunk of a blob, or the manifest of the chunks, that failed to dispatch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FailedChunk {
+    /// The index of the chunk, None for the manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    /// The machine-readable class of the error, see `DAError::code`.
+    pub code: String,
+    pub message: String,
+    pub retriable: bool,
+}
+
+impl FailedChunk {
+    pub fn new(index: Option<usize>, err: &DAError) -> Self {
+        Self {
+            index,
+            code: err.code().to_string(),
+            message: err.to_string(),
+            retriable: err.is_retriable(),
+        }
+    }
+}
+
+/// `FeeEstimate` is the expected gas and fee of a dispatch, the fee is priced at the gas price of
+/// the last dispatch and unknown before the first one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FeeEstimate {
+    pub gas: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denom: Option<String>,
+}
+
+/// `DispatchFee` is the gas and fee of the transaction that submitted a blob.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DispatchFee {
+    pub tx_hash: String,
+    pub gas_wanted: u64,
+    pub gas_used: u64,
+    /// The fee paid in `denom`, if the transaction reported it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denom: Option<String>,
+}
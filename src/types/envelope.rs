@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// The prefix of the enveloped payloads, followed by the envelope version.
+pub const ENVELOPE_MAGIC: &[u8; 6] = b"VIAENV";
+
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// The length of the prefix telling an enveloped payload from a raw one.
+pub const ENVELOPE_HEADER_LEN: usize = ENVELOPE_MAGIC.len() + 1;
+
+/// A transform applied to a payload, with what is needed to reverse it. The variants are encoded
+/// by their index, new ones are appended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AppliedTransform {
+    Zstd,
+    /// Encrypted with a HMAC-SHA256 keystream and authenticated with a HMAC-SHA256 tag appended
+    /// to the ciphertext, both derived from the encryption key.
+    HmacSha256Ctr {
+        key_id: String,
+        nonce: [u8; 16],
+    },
+}
+
+/// `BlobEnvelope` is a transformed payload with the transforms applied to it, in order, so it is
+/// decoded whatever the transforms configured at the time of its retrieval.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobEnvelope {
+    pub transforms: Vec<AppliedTransform>,
+    pub data: Vec<u8>,
+}
+
+impl BlobEnvelope {
+    /// Whether the payload is enveloped, the payloads dispatched without transforms are not.
+    pub fn is_enveloped(bytes: &[u8]) -> bool {
+        bytes.starts_with(ENVELOPE_MAGIC)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = ENVELOPE_MAGIC.to_vec();
+        bytes.push(ENVELOPE_VERSION);
+        bincode::serialize_into(&mut bytes, self).expect("Failed to serialize BlobEnvelope");
+        bytes
+    }
+
+    /// Parses an enveloped payload, None when the payload is not enveloped.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Option<Self>> {
+        if !Self::is_enveloped(bytes) {
+            return Ok(None);
+        }
+        let version = bytes.get(ENVELOPE_MAGIC.len()).copied();
+        anyhow::ensure!(
+            version == Some(ENVELOPE_VERSION),
+            "Unsupported envelope version {:?}",
+            version
+        );
+
+        Ok(Some(bincode::deserialize(&bytes[ENVELOPE_HEADER_LEN..])?))
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// The dispatches are paused for maintenance.
+pub const MAINTENANCE_ERROR_CODE: &str = "MAINTENANCE";
+
+/// The fees paid over the window of the fee budget exhausted it.
+pub const FEE_BUDGET_ERROR_CODE: &str = "FEE_BUDGET_EXHAUSTED";
+
+/// Another replica is submitting the batch.
+pub const BATCH_LOCKED_ERROR_CODE: &str = "BATCH_LOCKED";
+
+/// The batch lock store is unreachable, the dispatches are rejected as they can't be coordinated.
+pub const BATCH_LOCK_UNAVAILABLE_ERROR_CODE: &str = "BATCH_LOCK_UNAVAILABLE";
+
+/// `ErrorResponse` is the JSON body of the errors that carry a machine-readable code.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ErrorResponse {
+    pub code: String,
+    pub message: String,
+}
+
+impl ErrorResponse {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
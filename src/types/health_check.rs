@@ -1,12 +1,46 @@
 use serde::{Deserialize, Serialize};
 
+use crate::types::{
+    lifecycle::ComponentStatus, maintenance::MaintenanceStatus, selftest::SelftestResult,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceStatus {
     pub status: bool,
     pub message: String,
 }
 
+/// The health of the subsystems, exported as the `health_*` gauges as well.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SubsystemHealth {
+    /// Whether the DA node answers.
+    pub celestia_connected: bool,
+    /// Whether the DA node caught up with the chain, true for the backends without sync.
+    pub celestia_synced: bool,
+    /// Whether the dispatch queue serves the dispatches.
+    pub queue_healthy: bool,
+    /// Whether the index persists its entries.
+    pub index_db_healthy: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthCheckResponse {
     pub da: ServiceStatus,
+    /// The result of the last dispatch roundtrip selftest, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selftest: Option<SelftestResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    /// Whether the DA is reachable, the last selftest, if any, succeeded and the background
+    /// components are healthy. Dispatches may still be paused for maintenance.
+    pub ready: bool,
+    pub da: ServiceStatus,
+    pub maintenance: MaintenanceStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selftest: Option<SelftestResult>,
+    /// The status of the supervised background components.
+    pub components: Vec<ComponentStatus>,
+    pub subsystems: SubsystemHealth,
 }
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{clients::da_clients::types::DAError, types::index::IndexEntry};
+
+/// The metadata key tagging the index entries of the imported blobs with the height they were
+/// found at.
+pub const IMPORTED_FROM_KEY: &str = "imported_from";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRequest {
+    /// The caller the blobs are indexed for, they are read from its namespaces.
+    pub caller: String,
+    /// The first DA height scanned.
+    pub from_height: u64,
+    /// The last DA height scanned, included.
+    pub to_height: u64,
+    /// The namespace scanned, the one of the caller dispatches by default. It must be a namespace
+    /// the blobs of the caller are read from, its tenant namespace or a namespace route.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// The batch number of the first blob found, the next ones are numbered in order.
+    pub first_batch_number: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportResponse {
+    /// The number of heights scanned.
+    pub scanned: u64,
+    /// The entries of the blobs added to the index, in order.
+    pub imported: Vec<IndexEntry>,
+    /// The number of blobs found that were already indexed.
+    pub already_indexed: usize,
+    /// The number of blobs found that are not batches: the chunks of the chunked blobs and the
+    /// blobs that can't be decoded.
+    pub skipped: usize,
+    /// The failure that stopped the import, the blobs imported before it are kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<ImportFailure>,
+}
+
+/// `ImportFailure` is the DA call that failed at `height`. The import is resumed by running it
+/// again over the same range, with the first batch number following the imported ones.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportFailure {
+    pub height: u64,
+    /// Whether the range was scanned whole, the failure happened while indexing its blobs.
+    pub scanned: bool,
+    /// The machine-readable class of the error, see `DAError::code`.
+    pub code: String,
+    pub message: String,
+    pub retriable: bool,
+}
+
+impl ImportFailure {
+    pub fn new(height: u64, scanned: bool, err: &DAError) -> Self {
+        Self {
+            height,
+            scanned,
+            code: err.code().to_string(),
+            message: err.to_string(),
+            retriable: err.is_retriable(),
+        }
+    }
+}
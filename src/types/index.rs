@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    attestation::Attestation,
+    dispatch::{DispatchFee, DispatchReceipt},
+    parquet::{ParquetColumn, ParquetValues, write_parquet},
+};
+
+/// `IndexEntry` records a successful dispatch.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub blob_id: String,
+    pub batch_number: u32,
+    pub caller: String,
+    /// The payload size in bytes.
+    pub size: u64,
+    /// The SHA-256 of the payload (hex).
+    pub payload_hash: String,
+    pub dispatched_at: DateTime<Utc>,
+    /// The blob_id this blob re-dispatches, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redispatch_of: Option<String>,
+    /// The submission metadata returned by the DA client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<DispatchReceipt>,
+    /// The number of blocks built on top of the blob, tracked in the background up to the
+    /// confirmation depth.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    /// The location of the copy of the blob mirrored in the archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<String>,
+    /// The key/value tags attached by the caller, never sent to the DA layer.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// The attestation issued for the dispatch, when attestations are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Attestation>,
+}
+
+/// Quotes a CSV field when needed.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl IndexEntry {
+    /// The header of the CSV export, matching `to_csv_row`.
+    pub const CSV_HEADER: &str = "batch_number,blob_id,caller,size,height,commitment,namespace,backend,tx_hash,gas_used,fee,fee_denom,payload_hash,dispatched_at,submitted_at,redispatch_of,archive,metadata\n";
+
+    /// Returns the entry as a CSV row, the unknown fields are empty.
+    pub fn to_csv_row(&self) -> String {
+        let receipt = self.receipt.as_ref();
+        let fee = receipt.and_then(|receipt| receipt.fee.as_ref());
+        let fields = [
+            self.batch_number.to_string(),
+            self.blob_id.clone(),
+            self.caller.clone(),
+            self.size.to_string(),
+            receipt
+                .and_then(|receipt| receipt.height)
+                .map(|height| height.to_string())
+                .unwrap_or_default(),
+            receipt
+                .map(|receipt| receipt.commitment.clone())
+                .unwrap_or_default(),
+            receipt
+                .and_then(|receipt| receipt.namespace.clone())
+                .unwrap_or_default(),
+            receipt
+                .map(|receipt| receipt.backend.as_str().to_string())
+                .unwrap_or_default(),
+            fee.map(|fee| fee.tx_hash.clone()).unwrap_or_default(),
+            fee.map(|fee| fee.gas_used.to_string()).unwrap_or_default(),
+            fee.and_then(|fee| fee.amount)
+                .map(|amount| amount.to_string())
+                .unwrap_or_default(),
+            fee.and_then(|fee| fee.denom.clone()).unwrap_or_default(),
+            self.payload_hash.clone(),
+            self.dispatched_at.to_rfc3339(),
+            receipt
+                .map(|receipt| receipt.submitted_at.to_rfc3339())
+                .unwrap_or_default(),
+            self.redispatch_of.clone().unwrap_or_default(),
+            self.archive.clone().unwrap_or_default(),
+            // The tags as a JSON object, empty without tags.
+            if self.metadata.is_empty() {
+                String::new()
+            } else {
+                serde_json::to_string(&self.metadata).unwrap_or_default()
+            },
+        ];
+
+        let mut row = fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        row.push('\n');
+        row
+    }
+
+    fn fee(&self) -> Option<&DispatchFee> {
+        self.receipt
+            .as_ref()
+            .and_then(|receipt| receipt.fee.as_ref())
+    }
+
+    /// Returns the entries as a Parquet file with the columns of the CSV export, the unknown
+    /// fields are null.
+    pub fn to_parquet(entries: &[Self]) -> Vec<u8> {
+        let int64 = |name, value: &dyn Fn(&Self) -> Option<i64>| ParquetColumn {
+            name,
+            values: ParquetValues::Int64(entries.iter().map(value).collect()),
+        };
+        let text = |name, value: &dyn Fn(&Self) -> Option<String>| ParquetColumn {
+            name,
+            values: ParquetValues::Text(entries.iter().map(value).collect()),
+        };
+        let timestamp = |name, value: &dyn Fn(&Self) -> Option<DateTime<Utc>>| ParquetColumn {
+            name,
+            values: ParquetValues::Timestamp(
+                entries
+                    .iter()
+                    .map(|entry| value(entry).map(|time| time.timestamp_millis()))
+                    .collect(),
+            ),
+        };
+
+        write_parquet(&[
+            int64("batch_number", &|entry| Some(entry.batch_number.into())),
+            text("blob_id", &|entry| Some(entry.blob_id.clone())),
+            text("caller", &|entry| Some(entry.caller.clone())),
+            int64("size", &|entry| i64::try_from(entry.size).ok()),
+            int64("height", &|entry| {
+                entry
+                    .receipt
+                    .as_ref()?
+                    .height
+                    .and_then(|height| height.try_into().ok())
+            }),
+            text("commitment", &|entry| {
+                Some(entry.receipt.as_ref()?.commitment.clone())
+            }),
+            text("namespace", &|entry| {
+                entry.receipt.as_ref()?.namespace.clone()
+            }),
+            text("backend", &|entry| {
+                Some(entry.receipt.as_ref()?.backend.as_str().to_string())
+            }),
+            text("tx_hash", &|entry| Some(entry.fee()?.tx_hash.clone())),
+            int64("gas_used", &|entry| entry.fee()?.gas_used.try_into().ok()),
+            int64("fee", &|entry| entry.fee()?.amount?.try_into().ok()),
+            text("fee_denom", &|entry| entry.fee()?.denom.clone()),
+            text("payload_hash", &|entry| Some(entry.payload_hash.clone())),
+            timestamp("dispatched_at", &|entry| Some(entry.dispatched_at)),
+            timestamp("submitted_at", &|entry| {
+                Some(entry.receipt.as_ref()?.submitted_at)
+            }),
+            text("redispatch_of", &|entry| entry.redispatch_of.clone()),
+            text("archive", &|entry| entry.archive.clone()),
+            // The tags as a JSON object, null without tags.
+            text("metadata", &|entry| {
+                (!entry.metadata.is_empty())
+                    .then(|| serde_json::to_string(&entry.metadata).unwrap_or_default())
+            }),
+        ])
+    }
+}
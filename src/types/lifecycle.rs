@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// The state of a supervised background component.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentState {
+    Running,
+    /// The component failed or panicked and waits for its restart.
+    Restarting,
+    /// The component completed its work, e.g. a startup check.
+    Finished,
+    /// The component was stopped on shutdown.
+    Stopped,
+}
+
+/// The status of a supervised background component.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ComponentStatus {
+    pub name: String,
+    pub state: ComponentState,
+    /// Whether the component reports itself healthy and is not restarting.
+    pub healthy: bool,
+    pub restarts: u64,
+    /// The error of the last failure, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
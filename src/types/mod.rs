@@ -1 +1,18 @@
+pub mod admin;
+pub mod attestation;
+pub mod dispatch;
+pub mod envelope;
+pub mod error;
 pub mod health_check;
+pub mod import;
+pub mod index;
+pub mod lifecycle;
+pub mod maintenance;
+pub mod parquet;
+pub mod routing;
+pub mod selftest;
+pub mod sequence;
+pub mod staging;
+pub mod usage;
+pub mod verification;
+pub mod webhook;
@@ -0,0 +1,310 @@
+/// The magic bytes at the start and the end of a Parquet file.
+const MAGIC: &[u8] = b"PAR1";
+
+/// The Parquet physical types used by the columns.
+const TYPE_INT64: i32 = 2;
+const TYPE_BYTE_ARRAY: i32 = 6;
+
+/// The Parquet converted types used by the columns.
+const CONVERTED_UTF8: i32 = 0;
+const CONVERTED_TIMESTAMP_MILLIS: i32 = 9;
+
+const REPETITION_OPTIONAL: i32 = 1;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_UNCOMPRESSED: i32 = 0;
+const PAGE_DATA: i32 = 0;
+
+/// The Thrift compact protocol types of the fields.
+const THRIFT_I32: u8 = 5;
+const THRIFT_I64: u8 = 6;
+const THRIFT_BINARY: u8 = 8;
+const THRIFT_LIST: u8 = 9;
+const THRIFT_STRUCT: u8 = 12;
+
+/// The values of a column, None for the nulls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParquetValues {
+    Int64(Vec<Option<i64>>),
+    Text(Vec<Option<String>>),
+    /// Milliseconds since the Unix epoch.
+    Timestamp(Vec<Option<i64>>),
+}
+
+impl ParquetValues {
+    fn len(&self) -> usize {
+        match self {
+            Self::Int64(values) | Self::Timestamp(values) => values.len(),
+            Self::Text(values) => values.len(),
+        }
+    }
+
+    fn physical_type(&self) -> i32 {
+        match self {
+            Self::Int64(_) | Self::Timestamp(_) => TYPE_INT64,
+            Self::Text(_) => TYPE_BYTE_ARRAY,
+        }
+    }
+
+    fn converted_type(&self) -> Option<i32> {
+        match self {
+            Self::Int64(_) => None,
+            Self::Text(_) => Some(CONVERTED_UTF8),
+            Self::Timestamp(_) => Some(CONVERTED_TIMESTAMP_MILLIS),
+        }
+    }
+
+    /// The definition levels, 1 for the values and 0 for the nulls.
+    fn definition_levels(&self) -> Vec<u8> {
+        match self {
+            Self::Int64(values) | Self::Timestamp(values) => {
+                values.iter().map(|v| u8::from(v.is_some())).collect()
+            }
+            Self::Text(values) => values.iter().map(|v| u8::from(v.is_some())).collect(),
+        }
+    }
+
+    /// The values PLAIN encoded, without the nulls.
+    fn plain(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Self::Int64(values) | Self::Timestamp(values) => {
+                for value in values.iter().flatten() {
+                    buf.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            Self::Text(values) => {
+                for value in values.iter().flatten() {
+                    buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                    buf.extend_from_slice(value.as_bytes());
+                }
+            }
+        }
+        buf
+    }
+}
+
+/// `ParquetColumn` is a named column of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParquetColumn {
+    pub name: &'static str,
+    pub values: ParquetValues,
+}
+
+/// Encodes the definition levels of bit width 1 as RLE runs, prefixed by their length.
+fn encode_levels(levels: &[u8]) -> Vec<u8> {
+    let mut runs = Vec::new();
+    for run in levels.chunk_by(|a, b| a == b) {
+        write_varint(&mut runs, (run.len() as u64) << 1);
+        runs.push(run[0]);
+    }
+    let mut buf = (runs.len() as u32).to_le_bytes().to_vec();
+    buf.extend_from_slice(&runs);
+    buf
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Writes the structs of the Thrift compact protocol, the field ids are delta encoded within each
+/// struct.
+#[derive(Debug, Default)]
+struct CompactWriter {
+    buf: Vec<u8>,
+    last_ids: Vec<i16>,
+    last_id: i16,
+}
+
+impl CompactWriter {
+    fn field(&mut self, id: i16, kind: u8) {
+        let delta = id - self.last_id;
+        if (1..=15).contains(&delta) {
+            self.buf.push(((delta as u8) << 4) | kind);
+        } else {
+            self.buf.push(kind);
+            write_varint(&mut self.buf, ((id << 1) ^ (id >> 15)) as u16 as u64);
+        }
+        self.last_id = id;
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, THRIFT_I32);
+        write_varint(&mut self.buf, ((value << 1) ^ (value >> 31)) as u32 as u64);
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, THRIFT_I64);
+        write_varint(&mut self.buf, ((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, THRIFT_BINARY);
+        self.raw_binary(value);
+    }
+
+    fn raw_binary(&mut self, value: &[u8]) {
+        write_varint(&mut self.buf, value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.field(id, THRIFT_LIST);
+        if len < 15 {
+            self.buf.push(((len as u8) << 4) | kind);
+        } else {
+            self.buf.push(0xf0 | kind);
+            write_varint(&mut self.buf, len as u64);
+        }
+    }
+
+    /// Starts a struct, as the field `id` or as an element of a list without one.
+    fn begin(&mut self, id: Option<i16>) {
+        if let Some(id) = id {
+            self.field(id, THRIFT_STRUCT);
+        }
+        self.last_ids.push(self.last_id);
+        self.last_id = 0;
+    }
+
+    fn end(&mut self) {
+        self.buf.push(0);
+        self.last_id = self.last_ids.pop().unwrap_or_default();
+    }
+}
+
+/// Metadata of a written column chunk.
+struct ChunkMeta {
+    offset: usize,
+    size: usize,
+}
+
+/// Writes the columns, all of the same length, as a Parquet file of one row group with one
+/// uncompressed PLAIN data page per column, every column optional. The metadata is encoded with
+/// the Thrift compact protocol, per
+/// https://github.com/apache/parquet-format/blob/master/src/main/thrift/parquet.thrift.
+pub fn write_parquet(columns: &[ParquetColumn]) -> Vec<u8> {
+    let num_rows = columns.first().map_or(0, |column| column.values.len());
+    let mut file = MAGIC.to_vec();
+
+    let mut chunks = Vec::new();
+    if num_rows > 0 {
+        for column in columns {
+            let mut page = encode_levels(&column.values.definition_levels());
+            page.extend_from_slice(&column.values.plain());
+
+            let mut header = CompactWriter::default();
+            header.i32(1, PAGE_DATA);
+            header.i32(2, page.len() as i32);
+            header.i32(3, page.len() as i32);
+            header.begin(Some(5));
+            header.i32(1, num_rows as i32);
+            header.i32(2, ENCODING_PLAIN);
+            header.i32(3, ENCODING_RLE);
+            header.i32(4, ENCODING_RLE);
+            header.end();
+            header.end();
+
+            chunks.push(ChunkMeta {
+                offset: file.len(),
+                size: header.buf.len() + page.len(),
+            });
+            file.extend_from_slice(&header.buf);
+            file.extend_from_slice(&page);
+        }
+    }
+
+    let mut meta = CompactWriter::default();
+    meta.begin(None);
+    meta.i32(1, 1);
+    meta.list(2, THRIFT_STRUCT, columns.len() + 1);
+    meta.begin(None);
+    meta.binary(4, b"schema");
+    meta.i32(5, columns.len() as i32);
+    meta.end();
+    for column in columns {
+        meta.begin(None);
+        meta.i32(1, column.values.physical_type());
+        meta.i32(3, REPETITION_OPTIONAL);
+        meta.binary(4, column.name.as_bytes());
+        if let Some(converted_type) = column.values.converted_type() {
+            meta.i32(6, converted_type);
+        }
+        meta.end();
+    }
+    meta.i64(3, num_rows as i64);
+    meta.list(4, THRIFT_STRUCT, usize::from(!chunks.is_empty()));
+    if !chunks.is_empty() {
+        meta.begin(None);
+        meta.list(1, THRIFT_STRUCT, columns.len());
+        for (column, chunk) in columns.iter().zip(&chunks) {
+            meta.begin(None);
+            meta.i64(2, chunk.offset as i64);
+            meta.begin(Some(3));
+            meta.i32(1, column.values.physical_type());
+            meta.list(2, THRIFT_I32, 2);
+            write_varint(&mut meta.buf, (ENCODING_PLAIN << 1) as u64);
+            write_varint(&mut meta.buf, (ENCODING_RLE << 1) as u64);
+            meta.list(3, THRIFT_BINARY, 1);
+            meta.raw_binary(column.name.as_bytes());
+            meta.i32(4, CODEC_UNCOMPRESSED);
+            meta.i64(5, num_rows as i64);
+            meta.i64(6, chunk.size as i64);
+            meta.i64(7, chunk.size as i64);
+            meta.i64(9, chunk.offset as i64);
+            meta.end();
+            meta.end();
+        }
+        meta.i64(2, chunks.iter().map(|chunk| chunk.size as i64).sum());
+        meta.i64(3, num_rows as i64);
+        meta.end();
+    }
+    meta.binary(
+        6,
+        concat!("via-core-ext ", env!("CARGO_PKG_VERSION")).as_bytes(),
+    );
+    meta.end();
+
+    file.extend_from_slice(&meta.buf);
+    file.extend_from_slice(&(meta.buf.len() as u32).to_le_bytes());
+    file.extend_from_slice(MAGIC);
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_are_written_as_plain_pages_with_rle_levels() {
+        let file = write_parquet(&[
+            ParquetColumn {
+                name: "height",
+                values: ParquetValues::Int64(vec![Some(1), None, Some(2)]),
+            },
+            ParquetColumn {
+                name: "caller",
+                values: ParquetValues::Text(vec![Some("a".to_string()), None, None]),
+            },
+        ]);
+
+        assert_eq!(&file[..4], MAGIC);
+        assert_eq!(&file[file.len() - 4..], MAGIC);
+        let footer_len =
+            u32::from_le_bytes(file[file.len() - 8..file.len() - 4].try_into().unwrap());
+        assert!((footer_len as usize) < file.len() - 12);
+
+        // The runs of the definition levels, then the values without the nulls.
+        let mut height = vec![6, 0, 0, 0, 2, 1, 2, 0, 2, 1];
+        height.extend_from_slice(&1i64.to_le_bytes());
+        height.extend_from_slice(&2i64.to_le_bytes());
+        let caller = [4, 0, 0, 0, 2, 1, 4, 0, 1, 0, 0, 0, b'a'];
+        for page in [&height[..], &caller[..]] {
+            assert!(file.windows(page.len()).any(|window| window == page));
+        }
+    }
+}
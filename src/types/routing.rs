@@ -0,0 +1,66 @@
+use anyhow::{Context, anyhow};
+
+/// The metadata key of the protocol version of a dispatch, matched by the `protocol_version`
+/// routes.
+pub const PROTOCOL_VERSION_KEY: &str = "protocol_version";
+
+/// `RouteSelector` is the dispatches a namespace route applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteSelector {
+    /// The batches from `from` to `to`, bounds included, without end when `to` is None.
+    Batches { from: u32, to: Option<u32> },
+    /// The dispatches tagged with this protocol version in their metadata.
+    ProtocolVersion(String),
+}
+
+/// `NamespaceRoute` publishes the selected dispatches under another namespace, e.g. the batches
+/// after a protocol upgrade.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceRoute {
+    pub selector: RouteSelector,
+    pub namespace: String,
+}
+
+impl NamespaceRoute {
+    /// Parses a route from its selector, `batches=<from>-[<to>]` or `protocol_version=<version>`.
+    pub fn parse(selector: &str, namespace: &str) -> anyhow::Result<Self> {
+        let selector = match selector.split_once('=') {
+            Some(("batches", range)) => {
+                let (from, to) = range
+                    .split_once('-')
+                    .ok_or_else(|| anyhow!("Invalid batch range {}, expected from-[to]", range))?;
+                let from = from.trim().parse().context("Invalid batch range start")?;
+                let to = match to.trim() {
+                    "" => None,
+                    to => Some(to.parse().context("Invalid batch range end")?),
+                };
+                if to.is_some_and(|to| to < from) {
+                    anyhow::bail!("Empty batch range {}", range);
+                }
+                RouteSelector::Batches { from, to }
+            }
+            Some(("protocol_version", version)) if !version.trim().is_empty() => {
+                RouteSelector::ProtocolVersion(version.trim().to_string())
+            }
+            _ => anyhow::bail!(
+                "Invalid route {}, expected batches=<from>-[<to>] or protocol_version=<version>",
+                selector
+            ),
+        };
+
+        Ok(Self {
+            selector,
+            namespace: namespace.to_string(),
+        })
+    }
+
+    /// Whether the route applies to a dispatch of the batch with the protocol version.
+    pub fn matches(&self, batch_number: u32, protocol_version: Option<&str>) -> bool {
+        match &self.selector {
+            RouteSelector::Batches { from, to } => {
+                batch_number >= *from && to.is_none_or(|to| batch_number <= to)
+            }
+            RouteSelector::ProtocolVersion(version) => protocol_version == Some(version.as_str()),
+        }
+    }
+}
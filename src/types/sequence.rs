@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// `BatchGap` is a range of batch numbers never dispatched, bounds included.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BatchGap {
+    pub from: u32,
+    pub to: u32,
+}
+
+/// `CallerSequence` is the sequence of the batch numbers dispatched by a caller.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CallerSequence {
+    pub caller: String,
+    /// The lowest and highest dispatched batch numbers.
+    pub first: u32,
+    pub last: u32,
+    /// The batch numbers missing between the first and the last.
+    pub gaps: Vec<BatchGap>,
+    /// Number of dispatches of a batch lower than the previous one since startup.
+    pub regressions: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GapsResponse {
+    pub callers: Vec<CallerSequence>,
+}
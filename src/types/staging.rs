@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::types::dispatch::FeeEstimate;
+
+/// `PrepareResponse` describes a staged dispatch, committed with `POST /da/commit/:staging_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrepareResponse {
+    pub staging_id: String,
+    /// The staged dispatch is dropped when not committed by then.
+    pub expires_at: DateTime<Utc>,
+    /// The size of the payload and of the blob it is dispatched as, once transformed.
+    pub size: u64,
+    pub blob_size: u64,
+    /// The sizes of the chunks of a chunked blob, a manifest of their blob_ids is dispatched
+    /// too. Empty when dispatched as one blob.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<u64>,
+    /// None for the backends without fees.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<FeeEstimate>,
+}
@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// `CallerUsage` is the dispatch usage of a caller, since startup or since the first snapshot when
+/// the usage is persisted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CallerUsage {
+    pub caller: String,
+    /// Number of successful dispatch requests.
+    pub requests: u64,
+    /// Number of dispatched bytes.
+    pub bytes: u64,
+    /// Number of failed dispatch requests.
+    #[serde(default)]
+    pub failures: u64,
+    /// Gas used by the dispatches, for the backends with fees.
+    #[serde(default)]
+    pub gas_used: u64,
+    /// Fees paid for the dispatches per denom, as reported by the transactions.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fees: BTreeMap<String, u64>,
+    /// The current billing month (YYYY-MM).
+    pub month: String,
+    /// Number of dispatched bytes in the current month.
+    pub month_bytes: u64,
+    /// The monthly byte cap, if any.
+    pub monthly_byte_cap: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageResponse {
+    pub callers: Vec<CallerUsage>,
+}
+
+/// `TenantMetricsResponse` is the usage of a single tenant, shared with its rollup team.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantMetricsResponse {
+    pub namespace: String,
+    #[serde(flatten)]
+    pub usage: CallerUsage,
+}
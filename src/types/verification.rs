@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::types::attestation::Attestation;
+
+/// `VerificationReport` is the result of a re-verification run of the dispatched blobs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VerificationReport {
+    /// Number of blobs re-fetched from the DA layer.
+    pub checked: usize,
+    /// The blobs that could not be retrieved.
+    pub unavailable: Vec<String>,
+    /// The blobs retrieved with a payload different from the dispatched one.
+    pub mismatched: Vec<String>,
+    /// The blobs older than the retention of the DA node, not counted as failures.
+    #[serde(default)]
+    pub pruned: Vec<String>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// `VerifyRequest` checks a blob against the expected payload hash or the payload itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyRequest {
+    pub blob_id: String,
+    /// The expected SHA-256 of the payload (hex).
+    #[serde(default)]
+    pub expected_hash: Option<String>,
+    /// The expected payload (hex).
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VerifyResponse {
+    /// Whether the payload on DA matches the expected one.
+    pub verified: bool,
+    pub blob_id: String,
+    pub expected_hash: String,
+    /// The SHA-256 of the payload fetched from DA (hex).
+    pub payload_hash: String,
+    pub size: usize,
+    /// The Celestia block height including the blob.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    /// The Celestia commitment hash of the blob (hex).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+    /// The batch number of the dispatch, when indexed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_number: Option<u32>,
+    /// The attestation of the dispatch, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<Attestation>,
+}
+
+/// Whether a blob is provable on L1.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnchainStatus {
+    /// The data root of the block of the blob is committed on L1.
+    Provable,
+    /// The block of the blob is not committed on L1 yet.
+    Pending,
+    /// The data root committed on L1 differs from the one of the block.
+    Mismatch,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OnchainVerifyResponse {
+    pub blob_id: String,
+    /// The Celestia block height including the blob.
+    pub height: u64,
+    /// The data root of the block, from its extended header (hex).
+    pub data_root: String,
+    /// The data root committed on L1 for the block (hex).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub l1_data_root: Option<String>,
+    pub status: OnchainStatus,
+    /// Whether the inclusion proof of the blob can be verified on L1.
+    pub provable: bool,
+}
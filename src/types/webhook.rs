@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEventKind {
+    /// The blob was submitted to the DA layer.
+    Submitted,
+    /// The blob reached the configured confirmation depth.
+    Confirmed,
+    /// The dispatch failed and won't be retried.
+    Failed,
+}
+
+impl WebhookEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventKind::Submitted => "submitted",
+            WebhookEventKind::Confirmed => "confirmed",
+            WebhookEventKind::Failed => "failed",
+        }
+    }
+}
+
+/// The body of the webhook POSTs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WebhookEvent {
+    pub event: WebhookEventKind,
+    pub batch_number: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_id: Option<String>,
+    /// The number of blocks on top of the blob, set on confirmed events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
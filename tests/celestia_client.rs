@@ -0,0 +1,519 @@
+mod common;
+
+use std::time::Duration;
+
+use celestia_types::{Commitment, nmt::Namespace};
+use common::mock_celestia::{
+    MOCK_BASE_GAS, MOCK_GAS_PER_BLOB_BYTE, MOCK_GAS_PRICE_PER_1000, MockCelestiaNode, MockRpcError,
+};
+use via_core_ext::clients::da_clients::{
+    DataAvailabilityClient,
+    blob_id::BlobIdCodec,
+    celestia::{CelestiaBlobId, CelestiaClient},
+    types::{CallContext, DAError, InclusionData, ViaDaBlob, serialize_blob_ids},
+};
+
+const AUTH_TOKEN: &str = "test-token";
+const BLOB_SIZE_LIMIT: usize = 1024 * 1024;
+
+async fn new_client(node: &MockCelestiaNode) -> CelestiaClient {
+    CelestiaClient::new(node.url(), AUTH_TOKEN.to_string(), BLOB_SIZE_LIMIT)
+        .await
+        .unwrap()
+}
+
+fn ctx() -> CallContext {
+    CallContext::background()
+}
+
+fn split_blob_id(blob_id: &str) -> (u64, Commitment) {
+    let id = CelestiaBlobId::decode(blob_id).unwrap();
+    (id.height, Commitment::new(id.commitment))
+}
+
+#[tokio::test]
+async fn test_dispatch_and_retrieve_blob() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    let data = b"hello celestia".to_vec();
+    let response = client.dispatch_blob(&ctx(), 1, data.clone()).await.unwrap();
+
+    // blob_id = [version (1 byte) | block_height (8 bytes) | commitment (32 bytes)]
+    assert_eq!(response.blob_id.len(), 2 * 41);
+    let (height, commitment) = split_blob_id(&response.blob_id);
+    assert_eq!(height, node.height());
+    let stored = node.blob(height, &commitment).unwrap();
+    assert_eq!(stored.data, data);
+
+    let receipt = response.receipt.unwrap();
+    assert_eq!(receipt.height, Some(height));
+    assert_eq!(receipt.commitment, hex::encode(commitment.hash()));
+    assert_eq!(receipt.size, data.len() as u64);
+
+    let fee = receipt.fee.unwrap();
+    let gas_used = MOCK_BASE_GAS + data.len() as u64 * MOCK_GAS_PER_BLOB_BYTE;
+    assert_eq!(fee.tx_hash, format!("{height:064X}"));
+    assert_eq!(fee.gas_used, gas_used);
+    assert!(fee.gas_wanted >= fee.gas_used);
+    assert_eq!(fee.amount, Some(gas_used * MOCK_GAS_PRICE_PER_1000 / 1000));
+    assert_eq!(fee.denom.as_deref(), Some("utia"));
+
+    let inclusion = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap();
+    assert_eq!(inclusion, Some(InclusionData { data }));
+}
+
+#[tokio::test]
+async fn test_retrieve_chunked_blob() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    let chunks = [b"first chunk ".to_vec(), b"second chunk".to_vec()];
+    let mut chunk_ids = vec![];
+    for chunk in &chunks {
+        let chunk_blob = ViaDaBlob::new(1, chunk.clone()).to_bytes();
+        let response = client.dispatch_blob(&ctx(), 1, chunk_blob).await.unwrap();
+        chunk_ids.push(response.blob_id);
+    }
+
+    let manifest = ViaDaBlob::new(chunks.len(), serialize_blob_ids(&chunk_ids).unwrap());
+    let response = client
+        .dispatch_blob(&ctx(), 1, manifest.to_bytes())
+        .await
+        .unwrap();
+    assert_eq!(node.blob_count(), 3);
+
+    let inclusion = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let expected: Vec<u8> = chunks
+        .iter()
+        .flat_map(|chunk| ViaDaBlob::new(1, chunk.clone()).to_bytes())
+        .collect();
+    assert_eq!(inclusion.data, expected);
+}
+
+#[tokio::test]
+async fn test_submit_error_is_retriable() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    node.fail_next(
+        "state.SubmitPayForBlob",
+        MockRpcError::handler("insufficient funds"),
+    );
+
+    let error = client
+        .dispatch_blob(&ctx(), 1, b"data".to_vec())
+        .await
+        .unwrap_err();
+    assert!(error.is_retriable());
+    assert!(matches!(error, DAError::SubmitFailed { .. }));
+    assert!(error.to_string().contains("insufficient funds"));
+    assert_eq!(node.blob_count(), 0);
+
+    // The next submission goes through.
+    client
+        .dispatch_blob(&ctx(), 1, b"data".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(node.calls("state.SubmitPayForBlob"), 2);
+}
+
+#[tokio::test]
+async fn test_rate_limits_carry_the_requested_backoff() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    node.fail_next("state.SubmitPayForBlob", MockRpcError::rate_limited(3));
+
+    let error = client
+        .dispatch_blob(&ctx(), 1, b"data".to_vec())
+        .await
+        .unwrap_err();
+    assert!(error.is_retriable());
+    assert!(matches!(
+        error,
+        DAError::RateLimited { retry_after: Some(retry_after), .. } if retry_after == Duration::from_secs(3)
+    ));
+}
+
+#[tokio::test]
+async fn test_missing_blob_returns_none() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    let response = client
+        .dispatch_blob(&ctx(), 1, b"data".to_vec())
+        .await
+        .unwrap();
+    node.fail_next("blob.Get", MockRpcError::blob_not_found());
+
+    let inclusion = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap();
+    assert!(inclusion.is_none());
+
+    // Other node errors are still errors.
+    node.fail_next(
+        "blob.Get",
+        MockRpcError::handler("header: syncing in progress"),
+    );
+    assert!(
+        client
+            .get_inclusion_data(&ctx(), &response.blob_id)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_missing_chunk_is_an_integrity_error() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    let chunk = client
+        .dispatch_blob(&ctx(), 1, b"chunk".to_vec())
+        .await
+        .unwrap();
+    let missing = CelestiaBlobId {
+        height: node.height(),
+        commitment: [7u8; 32],
+    }
+    .encode();
+    let manifest = ViaDaBlob::new(2, serialize_blob_ids(&[chunk.blob_id, missing]).unwrap());
+    let response = client
+        .dispatch_blob(&ctx(), 1, manifest.to_bytes())
+        .await
+        .unwrap();
+
+    let error = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DAError::IntegrityMismatch { .. }));
+}
+
+#[tokio::test]
+async fn test_invalid_auth_token_is_rejected() {
+    let node = MockCelestiaNode::start_with_auth_token(AUTH_TOKEN).await;
+
+    assert!(
+        CelestiaClient::new(node.url(), "wrong-token".to_string(), BLOB_SIZE_LIMIT)
+            .await
+            .is_err()
+    );
+    assert!(
+        CelestiaClient::new(node.url(), AUTH_TOKEN.to_string(), BLOB_SIZE_LIMIT)
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn test_reconnects_after_node_restart() {
+    let mut node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    let data = b"survives restart".to_vec();
+    let response = client.dispatch_blob(&ctx(), 1, data.clone()).await.unwrap();
+    assert!(client.ping().await.unwrap());
+
+    node.stop().await;
+    assert!(!client.ping().await.unwrap());
+    assert!(
+        client
+            .get_inclusion_data(&ctx(), &response.blob_id)
+            .await
+            .is_err()
+    );
+
+    node.restart().await;
+    assert!(client.ping().await.unwrap());
+    let inclusion = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap();
+    assert_eq!(inclusion, Some(InclusionData { data }));
+}
+
+#[tokio::test]
+async fn test_confirmations_follow_the_chain_head() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    let first = client
+        .dispatch_blob(&ctx(), 1, b"first".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(
+        client.confirmations(&ctx(), &first.blob_id).await.unwrap(),
+        Some(0)
+    );
+
+    // Every submission produces a new block.
+    client
+        .dispatch_blob(&ctx(), 2, b"second".to_vec())
+        .await
+        .unwrap();
+    client
+        .dispatch_blob(&ctx(), 3, b"third".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(
+        client.confirmations(&ctx(), &first.blob_id).await.unwrap(),
+        Some(2)
+    );
+}
+
+#[tokio::test]
+async fn test_blob_id_from_height_and_commitment() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+
+    let response = client
+        .dispatch_blob(&ctx(), 1, b"lookup".to_vec())
+        .await
+        .unwrap();
+    let (height, commitment) = split_blob_id(&response.blob_id);
+
+    let blob_id = CelestiaBlobId {
+        height,
+        commitment: *commitment.hash(),
+    }
+    .encode();
+    assert_eq!(blob_id, response.blob_id);
+}
+
+#[tokio::test]
+async fn test_blob_size_limit_follows_the_app_version_of_the_network() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node)
+        .await
+        .with_params_refresh(Duration::from_secs(60))
+        .await;
+    let tenant = client.namespaced("tenant").unwrap();
+
+    // The mock network runs app version 1, its square holds blobs of up to ~7.9 MB.
+    let limit = client.blob_size_limit().unwrap();
+    assert!(limit > BLOB_SIZE_LIMIT && limit < 8 * 1024 * 1024);
+    assert_eq!(tenant.blob_size_limit(), Some(limit));
+
+    let data = vec![7; BLOB_SIZE_LIMIT + 1];
+    assert!(client.dispatch_blob(&ctx(), 1, data).await.is_ok());
+    // The parameters are not queried again within the refresh interval.
+    assert_eq!(node.calls("header.NetworkHead"), 1);
+}
+
+#[tokio::test]
+async fn test_failed_params_queries_are_not_retried_by_every_dispatch() {
+    let node = MockCelestiaNode::start().await;
+    node.fail_next(
+        "header.NetworkHead",
+        MockRpcError::handler("node is syncing"),
+    );
+    let client = new_client(&node)
+        .await
+        .with_params_refresh(Duration::from_secs(60))
+        .await;
+    assert_eq!(client.blob_size_limit(), Some(BLOB_SIZE_LIMIT));
+
+    for batch_number in 1..=3 {
+        let data = b"pubdata".to_vec();
+        assert!(
+            client
+                .dispatch_blob(&ctx(), batch_number, data)
+                .await
+                .is_ok()
+        );
+    }
+    assert_eq!(node.calls("header.NetworkHead"), 1);
+}
+
+#[tokio::test]
+async fn test_blobs_not_found_are_only_read_from_the_fallback_nodes_when_missed() {
+    let fallback = MockCelestiaNode::start().await;
+    let data = b"fallback blob".to_vec();
+    let response = new_client(&fallback)
+        .await
+        .dispatch_blob(&ctx(), 1, data.clone())
+        .await
+        .unwrap();
+    let (height, _) = split_blob_id(&response.blob_id);
+
+    // The light node didn't sync the height of the blob yet.
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node)
+        .await
+        .with_fallback_nodes(&[fallback.url()])
+        .await
+        .unwrap();
+    assert!(node.height() < height);
+    let inclusion_data = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap();
+    assert_eq!(inclusion_data, Some(InclusionData { data }));
+    assert_eq!(fallback.calls("blob.Get"), 1);
+
+    // The synced light node doesn't have the blob.
+    client
+        .dispatch_blob(&ctx(), 2, b"pubdata".to_vec())
+        .await
+        .unwrap();
+    assert!(node.height() >= height);
+    let inclusion_data = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap();
+    assert_eq!(inclusion_data, None);
+    assert_eq!(fallback.calls("blob.Get"), 1);
+}
+
+#[tokio::test]
+async fn test_blobs_of_a_namespace_are_scanned_by_height() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+    let tenant = client.namespaced("tenant").unwrap();
+
+    // Submitted by a previous system, in the namespace of the tenant.
+    let data = b"legacy blob".to_vec();
+    let response = tenant.dispatch_blob(&ctx(), 1, data.clone()).await.unwrap();
+    let (height, _) = split_blob_id(&response.blob_id);
+
+    let blobs = tenant.scan_blobs(&ctx(), height).await.unwrap();
+    assert_eq!(blobs.len(), 1);
+    assert_eq!(blobs[0].blob_id, response.blob_id);
+    assert_eq!(blobs[0].data, data);
+    assert_eq!(blobs[0].receipt.height, Some(height));
+    assert_eq!(blobs[0].receipt.size, data.len() as u64);
+
+    // The scanned blob_ids read the blobs like the dispatched ones.
+    let inclusion = tenant
+        .get_inclusion_data(&ctx(), &blobs[0].blob_id)
+        .await
+        .unwrap();
+    assert_eq!(inclusion, Some(InclusionData { data }));
+
+    assert!(client.scan_blobs(&ctx(), height).await.unwrap().is_empty());
+    assert!(tenant.scan_blobs(&ctx(), 1).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_namespaced_client_is_isolated() {
+    let node = MockCelestiaNode::start().await;
+    let client = new_client(&node).await;
+    let tenant = client.namespaced("tenant").unwrap();
+
+    let data = b"tenant blob".to_vec();
+    let response = tenant.dispatch_blob(&ctx(), 1, data.clone()).await.unwrap();
+
+    let (height, commitment) = split_blob_id(&response.blob_id);
+    let stored = node.blob(height, &commitment).unwrap();
+    assert_eq!(stored.namespace, Namespace::new_v0(b"tenant").unwrap());
+
+    let inclusion = tenant
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap();
+    assert_eq!(inclusion, Some(InclusionData { data }));
+    assert!(
+        client
+            .get_inclusion_data(&ctx(), &response.blob_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_blobs_out_of_the_sampling_window_are_pruned() {
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    let node = MockCelestiaNode::start_with_chain_age(10 * DAY, DAY).await;
+    let client = new_client(&node).await.with_sampling_window(7 * DAY);
+
+    let old = client
+        .dispatch_blob(&ctx(), 1, b"old blob".to_vec())
+        .await
+        .unwrap();
+    node.produce_blocks_now();
+    let recent = client
+        .dispatch_blob(&ctx(), 2, b"recent blob".to_vec())
+        .await
+        .unwrap();
+    let (recent_height, _) = split_blob_id(&recent.blob_id);
+
+    assert_eq!(
+        client.earliest_available_height().await.unwrap(),
+        Some(recent_height)
+    );
+    let err = client
+        .get_inclusion_data(&ctx(), &old.blob_id)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        DAError::Pruned { earliest_available_height, .. } if earliest_available_height == recent_height
+    ));
+    assert!(
+        client
+            .get_inclusion_data(&ctx(), &recent.blob_id)
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    // The mock node keeps every blob, so it stands in for an archival node.
+    let client = client
+        .with_archival_node(&node.url(), Some(AUTH_TOKEN))
+        .await
+        .unwrap();
+    let inclusion = client
+        .get_inclusion_data(&ctx(), &old.blob_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        inclusion,
+        Some(InclusionData {
+            data: b"old blob".to_vec()
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_blobs_expire_after_the_sampling_window() {
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    let node = MockCelestiaNode::start_with_chain_age(2 * DAY, DAY).await;
+    let client = new_client(&node).await.with_sampling_window(7 * DAY);
+    assert_eq!(client.retention(), Some(7 * DAY));
+
+    let response = client
+        .dispatch_blob(&ctx(), 1, b"blob".to_vec())
+        .await
+        .unwrap();
+    let expires_at = client
+        .expiry_for(&ctx(), &response.blob_id)
+        .await
+        .unwrap()
+        .unwrap();
+    // Included two days ago at most, in the chain time of the mock node.
+    let remaining = (expires_at - chrono::Utc::now()).to_std().unwrap();
+    assert!(remaining > 4 * DAY && remaining <= 7 * DAY);
+
+    // The archival node keeps the blobs forever.
+    let client = client
+        .with_archival_node(&node.url(), Some(AUTH_TOKEN))
+        .await
+        .unwrap();
+    assert_eq!(client.retention(), None);
+    assert_eq!(
+        client.expiry_for(&ctx(), &response.blob_id).await.unwrap(),
+        None
+    );
+}
@@ -0,0 +1,123 @@
+//! The scenarios of the Celestia client against a local devnet, ignored by default as they need
+//! docker: `cargo test --test celestia_devnet -- --ignored`.
+
+mod common;
+
+use common::devnet::CelestiaDevnet;
+use via_core_ext::clients::da_clients::{
+    DataAvailabilityClient,
+    blob_id::BlobIdCodec,
+    celestia::{
+        CelestiaBlobId, CelestiaClient, DEFAULT_APP_VERSION, celestia_namespace, share_commitment,
+    },
+    types::{CallContext, InclusionData, ViaDaBlob, serialize_blob_ids},
+};
+
+const BLOB_SIZE_LIMIT: usize = 1024 * 1024;
+
+async fn new_client(devnet: &CelestiaDevnet) -> CelestiaClient {
+    CelestiaClient::new(devnet.url(), devnet.auth_token(), BLOB_SIZE_LIMIT)
+        .await
+        .unwrap()
+}
+
+fn ctx() -> CallContext {
+    CallContext::background()
+}
+
+#[tokio::test]
+#[ignore = "needs docker"]
+async fn test_dispatched_blobs_are_included() {
+    let devnet = CelestiaDevnet::start().await;
+    let client = new_client(&devnet).await;
+
+    let data = b"hello devnet".to_vec();
+    let response = client.dispatch_blob(&ctx(), 1, data.clone()).await.unwrap();
+
+    // The blob_id commits to the data in the VIA namespace.
+    let id = CelestiaBlobId::decode(&response.blob_id).unwrap();
+    let namespace = celestia_namespace(None).unwrap();
+    assert_eq!(
+        id.commitment,
+        share_commitment(namespace, &data, DEFAULT_APP_VERSION).unwrap()
+    );
+    let receipt = response.receipt.unwrap();
+    assert_eq!(receipt.height, Some(id.height));
+    assert_eq!(receipt.commitment, hex::encode(id.commitment));
+    assert!(receipt.fee.unwrap().gas_used > 0);
+
+    let inclusion = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap();
+    assert_eq!(inclusion, Some(InclusionData { data }));
+    assert!(
+        client
+            .confirmations(&ctx(), &response.blob_id)
+            .await
+            .unwrap()
+            .is_some()
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs docker"]
+async fn test_inclusion_proofs_match_the_data_root() {
+    let devnet = CelestiaDevnet::start().await;
+    let client = new_client(&devnet).await;
+
+    let response = client
+        .dispatch_blob(&ctx(), 1, vec![7; 4096])
+        .await
+        .unwrap();
+    let id = CelestiaBlobId::decode(&response.blob_id).unwrap();
+
+    let proof = client
+        .get_inclusion_proof(&ctx(), &response.blob_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(proof.height, id.height);
+    assert_eq!(proof.commitment, hex::encode(id.commitment));
+    assert_eq!(
+        proof.data_root,
+        client.data_root(&ctx(), id.height).await.unwrap()
+    );
+    assert!(
+        proof
+            .proofs
+            .as_array()
+            .is_some_and(|proofs| !proofs.is_empty())
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs docker"]
+async fn test_chunked_blobs_are_reassembled() {
+    let devnet = CelestiaDevnet::start().await;
+    let client = new_client(&devnet).await;
+
+    let chunks = [vec![1; 1000], vec![2; 1000], vec![3; 500]];
+    let mut chunk_ids = vec![];
+    for chunk in &chunks {
+        let chunk_blob = ViaDaBlob::new(1, chunk.clone()).to_bytes();
+        let response = client.dispatch_blob(&ctx(), 1, chunk_blob).await.unwrap();
+        chunk_ids.push(response.blob_id);
+    }
+    let manifest = ViaDaBlob::new(chunks.len(), serialize_blob_ids(&chunk_ids).unwrap());
+    let response = client
+        .dispatch_blob(&ctx(), 1, manifest.to_bytes())
+        .await
+        .unwrap();
+
+    let inclusion = client
+        .get_inclusion_data(&ctx(), &response.blob_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let expected: Vec<u8> = chunks
+        .iter()
+        .flat_map(|chunk| ViaDaBlob::new(1, chunk.clone()).to_bytes())
+        .collect();
+    assert_eq!(inclusion.data, expected);
+}
@@ -0,0 +1,141 @@
+use std::{
+    env,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use via_core_ext::clients::da_clients::{DataAvailabilityClient, celestia::CelestiaClient};
+
+/// The single container devnet of a Celestia validator and a bridge node, overridden with
+/// `VIA_TEST_DEVNET_IMAGE`.
+const DEVNET_IMAGE: &str = "ghcr.io/rollkit/local-celestia-devnet:v0.13.1";
+
+/// The RPC port of the bridge node within the container.
+const BRIDGE_RPC_PORT: u16 = 26658;
+
+/// The store of the bridge node within the container, the admin auth token is issued from it.
+const BRIDGE_NODE_STORE: &str = "/home/celestia/bridge";
+
+/// The devnet produces its first blocks within this timeout.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+
+fn docker(args: &[&str]) -> String {
+    let output = Command::new("docker")
+        .args(args)
+        .output()
+        .expect("Failed to run docker");
+    assert!(
+        output.status.success(),
+        "docker {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// A local Celestia devnet started in docker and removed on drop. `VIA_TEST_DEVNET_URL` and
+/// `VIA_TEST_DEVNET_AUTH_TOKEN` use a running devnet instead.
+pub struct CelestiaDevnet {
+    url: String,
+    auth_token: String,
+    container_id: Option<String>,
+}
+
+impl CelestiaDevnet {
+    pub async fn start() -> Self {
+        let mut devnet = match env::var("VIA_TEST_DEVNET_URL") {
+            Ok(url) => Self {
+                url,
+                auth_token: env::var("VIA_TEST_DEVNET_AUTH_TOKEN").unwrap_or_default(),
+                container_id: None,
+            },
+            Err(_) => Self::run_container(),
+        };
+        devnet.wait_for_blocks().await;
+        devnet
+    }
+
+    fn run_container() -> Self {
+        let image = env::var("VIA_TEST_DEVNET_IMAGE").unwrap_or_else(|_| DEVNET_IMAGE.to_string());
+        let container_id = docker(&[
+            "run",
+            "-d",
+            "--rm",
+            "-p",
+            &format!("127.0.0.1::{BRIDGE_RPC_PORT}"),
+            &image,
+        ]);
+        // The container is removed on drop from here on.
+        let mut devnet = Self {
+            url: String::new(),
+            auth_token: String::new(),
+            container_id: Some(container_id.clone()),
+        };
+
+        let address = docker(&["port", &container_id, &BRIDGE_RPC_PORT.to_string()]);
+        devnet.url = format!("http://{}", address.lines().next().unwrap());
+        devnet
+    }
+
+    /// Issues an admin auth token from the bridge node store, once it is initialized.
+    fn issue_auth_token(container_id: &str) -> Option<String> {
+        let output = Command::new("docker")
+            .args(["exec", container_id, "celestia", "bridge", "auth", "admin"])
+            .args(["--node.store", BRIDGE_NODE_STORE])
+            .output()
+            .expect("Failed to run docker");
+        let stdout = String::from_utf8(output.stdout).ok()?;
+        let token = stdout.trim().lines().last()?;
+        output.status.success().then(|| token.to_string())
+    }
+
+    /// Waits until the bridge node serves the RPC and the validator produced a few blocks.
+    async fn wait_for_blocks(&mut self) {
+        let started_at = Instant::now();
+        loop {
+            if let Some(container_id) = &self.container_id
+                && self.auth_token.is_empty()
+                && let Some(auth_token) = Self::issue_auth_token(container_id)
+            {
+                self.auth_token = auth_token;
+            }
+
+            // The token of a running devnet is optional, e.g. with the auth skipped.
+            let issued = self.container_id.is_none() || !self.auth_token.is_empty();
+            if issued
+                && let Ok(client) =
+                    CelestiaClient::new(self.url.clone(), self.auth_token.clone(), 1).await
+                && let Ok(Some(height)) = client.head_height().await
+                && height > 2
+            {
+                return;
+            }
+
+            assert!(
+                started_at.elapsed() < STARTUP_TIMEOUT,
+                "The devnet at {} didn't produce blocks",
+                self.url
+            );
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn auth_token(&self) -> String {
+        self.auth_token.clone()
+    }
+}
+
+impl Drop for CelestiaDevnet {
+    fn drop(&mut self) {
+        if let Some(container_id) = self.container_id.take() {
+            Command::new("docker")
+                .args(["rm", "-f", &container_id])
+                .output()
+                .ok();
+        }
+    }
+}
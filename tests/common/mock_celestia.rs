@@ -0,0 +1,409 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use celestia_types::{
+    AppVersion, Blob, Commitment, ExtendedHeader, blob::RawBlob, nmt::Namespace,
+    test_utils::ExtendedHeaderGenerator,
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::{net::TcpListener, sync::oneshot, task::JoinHandle};
+
+/// Peer id returned by `p2p.Info`, any valid libp2p peer id works for the client.
+const MOCK_PEER_ID: &str = "12D3KooWL8z3KARAYJcmExhDsGwKbjChKeGaJpFPENyADdxmEHzw";
+
+/// Error code used by celestia-node (go-jsonrpc) for errors returned by the API handlers.
+const HANDLER_ERROR_CODE: i64 = 1;
+
+/// Gas charged by the mock node for a PayForBlob transaction, plus `MOCK_GAS_PER_BLOB_BYTE` per byte.
+pub const MOCK_BASE_GAS: u64 = 65_000;
+pub const MOCK_GAS_PER_BLOB_BYTE: u64 = 8;
+
+/// Gas price of the mock node, in utia per 1000 gas.
+pub const MOCK_GAS_PRICE_PER_1000: u64 = 2;
+
+/// A JSON-RPC error returned by the mock node.
+#[derive(Debug, Clone)]
+pub struct MockRpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl MockRpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// The error of a throttled request, as returned by the RPC gateways in front of the nodes.
+    pub fn rate_limited(retry_after_secs: u64) -> Self {
+        Self {
+            data: Some(json!({ "retry_after": retry_after_secs })),
+            ..Self::new(429, "rate limit exceeded")
+        }
+    }
+
+    /// The error returned by `blob.Get` when there is no blob for the commitment at the height.
+    pub fn blob_not_found() -> Self {
+        Self::new(HANDLER_ERROR_CODE, "blob: not found")
+    }
+
+    /// A generic handler error, e.g. an out of funds wallet on submit.
+    pub fn handler(message: impl Into<String>) -> Self {
+        Self::new(HANDLER_ERROR_CODE, message)
+    }
+}
+
+#[derive(Debug)]
+struct MockState {
+    auth_token: Option<String>,
+    headers: ExtendedHeaderGenerator,
+    head: ExtendedHeader,
+    /// The headers from genesis to the head.
+    chain: Vec<ExtendedHeader>,
+    blobs: HashMap<BlobKey, Blob>,
+    injected_errors: HashMap<String, VecDeque<MockRpcError>>,
+    calls: HashMap<String, usize>,
+}
+
+impl MockState {
+    fn new(auth_token: Option<String>) -> Self {
+        Self::with_headers(auth_token, ExtendedHeaderGenerator::new())
+    }
+
+    fn with_headers(auth_token: Option<String>, mut headers: ExtendedHeaderGenerator) -> Self {
+        let head = headers.next();
+
+        Self {
+            auth_token,
+            headers,
+            chain: vec![head.clone()],
+            head,
+            blobs: HashMap::new(),
+            injected_errors: HashMap::new(),
+            calls: HashMap::new(),
+        }
+    }
+
+    fn height(&self) -> u64 {
+        self.head.height().value()
+    }
+
+    fn produce_block(&mut self) -> u64 {
+        self.head = self.headers.next();
+        self.chain.push(self.head.clone());
+        self.height()
+    }
+}
+
+/// Blobs are keyed by height, namespace bytes and commitment hash.
+type BlobKey = (u64, Vec<u8>, [u8; 32]);
+
+fn blob_key(height: u64, namespace: &Namespace, commitment: &Commitment) -> BlobKey {
+    (height, namespace.as_bytes().to_vec(), *commitment.hash())
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// An embeddable mock of a Celestia light node.
+///
+/// Implements the subset of the JSON-RPC API used by `CelestiaClient` over HTTP:
+/// `p2p.Info`, `header.NetworkHead`, `header.SyncState`, `header.GetByHeight`,
+/// `state.SubmitPayForBlob`, `blob.Get` and `blob.GetAll`. Every submission produces a new block,
+/// errors can be injected per method and the node can be stopped and restarted on the same
+/// address while keeping its state, to exercise reconnections.
+pub struct MockCelestiaNode {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    server: Option<(oneshot::Sender<()>, JoinHandle<()>)>,
+}
+
+impl MockCelestiaNode {
+    /// Starts a mock node on a random local port that accepts any auth token.
+    pub async fn start() -> Self {
+        Self::start_with_state(MockState::new(None)).await
+    }
+
+    /// Starts a mock node that rejects requests without the given bearer token.
+    pub async fn start_with_auth_token(auth_token: &str) -> Self {
+        Self::start_with_state(MockState::new(Some(auth_token.to_string()))).await
+    }
+
+    /// Starts a mock node whose genesis is `age` old, the next blocks are `block_time` apart until
+    /// `produce_blocks_now` is called.
+    pub async fn start_with_chain_age(age: Duration, block_time: Duration) -> Self {
+        let now = ExtendedHeaderGenerator::new().next().time();
+        let mut headers = ExtendedHeaderGenerator::new();
+        headers.set_time(now.checked_sub(age).unwrap(), block_time);
+
+        Self::start_with_state(MockState::with_headers(None, headers)).await
+    }
+
+    async fn start_with_state(state: MockState) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut node = Self {
+            addr,
+            state: Arc::new(Mutex::new(state)),
+            server: None,
+        };
+        node.serve(listener);
+        node
+    }
+
+    fn serve(&mut self, listener: TcpListener) {
+        let app = Router::new()
+            .route("/", post(rpc_handler))
+            .with_state(self.state.clone());
+
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    shutdown_receiver.await.ok();
+                })
+                .await
+                .unwrap();
+        });
+
+        self.server = Some((shutdown_sender, handle));
+    }
+
+    /// The HTTP url to pass to `CelestiaClient::new`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Stops serving requests, the stored blobs and chain head are kept.
+    pub async fn stop(&mut self) {
+        if let Some((shutdown_sender, handle)) = self.server.take() {
+            shutdown_sender.send(()).ok();
+            handle.await.unwrap();
+        }
+    }
+
+    /// Serves again on the same address after a `stop`.
+    pub async fn restart(&mut self) {
+        self.stop().await;
+        let listener = TcpListener::bind(self.addr).await.unwrap();
+        self.serve(listener);
+    }
+
+    /// Makes the next call of `method` (e.g. `state.SubmitPayForBlob`) fail with `error`.
+    pub fn fail_next(&self, method: &str, error: MockRpcError) {
+        self.state
+            .lock()
+            .unwrap()
+            .injected_errors
+            .entry(method.to_string())
+            .or_default()
+            .push_back(error);
+    }
+
+    /// Returns how many times `method` was called, including failed calls.
+    pub fn calls(&self, method: &str) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .calls
+            .get(method)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Makes the next blocks carry the current time.
+    pub fn produce_blocks_now(&self) {
+        self.state.lock().unwrap().headers.reset_time();
+    }
+
+    /// Returns the current chain head height.
+    pub fn height(&self) -> u64 {
+        self.state.lock().unwrap().height()
+    }
+
+    /// Returns the number of blobs stored by the node.
+    pub fn blob_count(&self) -> usize {
+        self.state.lock().unwrap().blobs.len()
+    }
+
+    /// Returns the blob stored at `height` with `commitment`, in any namespace.
+    pub fn blob(&self, height: u64, commitment: &Commitment) -> Option<Blob> {
+        self.state
+            .lock()
+            .unwrap()
+            .blobs
+            .iter()
+            .find(|((h, _, c), _)| *h == height && c == commitment.hash())
+            .map(|(_, blob)| blob.clone())
+    }
+}
+
+impl Drop for MockCelestiaNode {
+    fn drop(&mut self) {
+        if let Some((shutdown_sender, _)) = self.server.take() {
+            shutdown_sender.send(()).ok();
+        }
+    }
+}
+
+async fn rpc_handler(
+    State(state): State<Arc<Mutex<MockState>>>,
+    headers: HeaderMap,
+    Json(request): Json<RpcRequest>,
+) -> Response {
+    let mut state = state.lock().unwrap();
+
+    if let Some(token) = &state.auth_token {
+        let expected = format!("Bearer {token}");
+        let authorized = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value == expected);
+        if !authorized {
+            return (StatusCode::UNAUTHORIZED, "unauthorized").into_response();
+        }
+    }
+
+    *state.calls.entry(request.method.clone()).or_default() += 1;
+
+    let injected = state
+        .injected_errors
+        .get_mut(&request.method)
+        .and_then(VecDeque::pop_front);
+
+    let result = match injected {
+        Some(error) => Err(error),
+        None => dispatch(&mut state, &request.method, request.params),
+    };
+
+    let body = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": request.id,
+            "error": { "code": error.code, "message": error.message, "data": error.data },
+        }),
+    };
+
+    Json(body).into_response()
+}
+
+fn dispatch(state: &mut MockState, method: &str, params: Value) -> Result<Value, MockRpcError> {
+    match method {
+        "p2p.Info" => Ok(json!({ "ID": MOCK_PEER_ID, "Addrs": [] })),
+        "header.NetworkHead" => Ok(serde_json::to_value(&state.head).unwrap()),
+        // The node is synced up to its head.
+        "header.SyncState" => Ok(json!({
+            "id": 1,
+            "height": state.height(),
+            "from_height": 1,
+            "to_height": state.height(),
+            "from_hash": state.chain[0].hash(),
+            "to_hash": state.head.hash(),
+            "start": state.chain[0].time(),
+            "end": state.head.time(),
+        })),
+        "header.GetByHeight" => {
+            let (height,): (u64,) = parse_params(params)?;
+
+            state
+                .chain
+                .get((height as usize).wrapping_sub(1))
+                .map(|header| serde_json::to_value(header).unwrap())
+                .ok_or_else(|| MockRpcError::handler("header: not found"))
+        }
+        "state.SubmitPayForBlob" => {
+            let (blobs, _tx_config): (Vec<Value>, Value) = parse_params(params)?;
+            if blobs.is_empty() {
+                return Err(MockRpcError::handler("blob: no blobs provided"));
+            }
+
+            let height = state.produce_block();
+            let mut gas_used = MOCK_BASE_GAS;
+            for mut blob in blobs {
+                // The client omits the empty signer, which the Go node defaults.
+                if let Some(blob) = blob.as_object_mut() {
+                    blob.entry("signer").or_insert_with(|| json!(""));
+                }
+                let blob: RawBlob = parse_params(blob)?;
+                let blob = Blob::from_raw(blob, AppVersion::V5)
+                    .map_err(|error| MockRpcError::handler(error.to_string()))?;
+                gas_used += blob.data.len() as u64 * MOCK_GAS_PER_BLOB_BYTE;
+                let key = blob_key(height, &blob.namespace, &blob.commitment);
+                state.blobs.insert(key, blob);
+            }
+            let fee = gas_used * MOCK_GAS_PRICE_PER_1000 / 1000;
+
+            Ok(json!({
+                "height": height,
+                "txhash": format!("{height:064X}"),
+                "code": 0,
+                "gas_wanted": gas_used + gas_used / 10,
+                "gas_used": gas_used,
+                "events": [{
+                    "type": "tx",
+                    "attributes": [{ "key": "fee", "value": format!("{fee}utia"), "index": true }],
+                }],
+            }))
+        }
+        "blob.Get" => {
+            let (height, namespace, commitment): (u64, Namespace, Commitment) =
+                parse_params(params)?;
+
+            state
+                .blobs
+                .get(&blob_key(height, &namespace, &commitment))
+                .map(|blob| serde_json::to_value(blob).unwrap())
+                .ok_or_else(MockRpcError::blob_not_found)
+        }
+        "blob.GetAll" => {
+            let (height, namespaces): (u64, Vec<Namespace>) = parse_params(params)?;
+
+            let blobs: Vec<&Blob> = state
+                .blobs
+                .iter()
+                .filter(|((h, namespace, _), _)| {
+                    *h == height && namespaces.iter().any(|n| n.as_bytes() == namespace)
+                })
+                .map(|(_, blob)| blob)
+                .collect();
+            // The node returns null for a height without blobs in the namespaces.
+            if blobs.is_empty() {
+                Ok(Value::Null)
+            } else {
+                Ok(serde_json::to_value(blobs).unwrap())
+            }
+        }
+        other => Err(MockRpcError::new(
+            -32601,
+            format!("method '{other}' not found"),
+        )),
+    }
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, MockRpcError> {
+    serde_json::from_value(params)
+        .map_err(|error| MockRpcError::new(-32602, format!("invalid params: {error}")))
+}
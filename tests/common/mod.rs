@@ -0,0 +1,4 @@
+#![allow(dead_code)]
+
+pub mod devnet;
+pub mod mock_celestia;